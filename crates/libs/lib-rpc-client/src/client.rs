@@ -0,0 +1,573 @@
+use crate::types::*;
+use crate::{ClientError, Error, Result};
+use reqwest::cookie::{CookieStore, Jar};
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION};
+use reqwest::{Client, Url};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+const AUTH_TOKEN: &str = "auth-token";
+const JSONRPC_VERSION: &str = "2.0";
+
+const PATH_LOGIN: &str = "/api/login";
+const PATH_LOGOFF: &str = "/api/logoff";
+const PATH_RPC: &str = "/api/rpc";
+
+pub struct RpcClient {
+	base_url: Url,
+	http: Client,
+	cookie_jar: Arc<Jar>,
+	next_rpc_id: AtomicU64,
+}
+
+// region:    --- Constructors & Auth
+impl RpcClient {
+	/// A client authenticated by the `auth-token` cookie, once `login` succeeded.
+	pub fn new(base_url: &str) -> Result<Self> {
+		Self::build(base_url, None)
+	}
+
+	/// A client authenticated by a bearer token
+	/// (e.g., the `auth_token` of a logged in client).
+	pub fn with_bearer_token(base_url: &str, token: &str) -> Result<Self> {
+		Self::build(base_url, Some(token))
+	}
+
+	fn build(base_url: &str, bearer_token: Option<&str>) -> Result<Self> {
+		let base_url = Url::parse(base_url)
+			.map_err(|_| Error::InvalidBaseUrl(base_url.to_string()))?;
+
+		let mut headers = HeaderMap::new();
+		if let Some(token) = bearer_token {
+			let auth_value = HeaderValue::from_str(&format!("Bearer {token}"))
+				.map_err(|_| Error::AuthTokenInvalid)?;
+			headers.insert(AUTHORIZATION, auth_value);
+		}
+
+		let cookie_jar = Arc::new(Jar::default());
+		let http = Client::builder()
+			.cookie_provider(cookie_jar.clone())
+			.default_headers(headers)
+			.build()?;
+
+		Ok(Self {
+			base_url,
+			http,
+			cookie_jar,
+			next_rpc_id: AtomicU64::new(1),
+		})
+	}
+
+	pub async fn login(&self, username: &str, pwd: &str) -> Result<()> {
+		let body = json!({
+			"username": username,
+			"pwd": pwd
+		});
+		self.post(PATH_LOGIN, &body).await?;
+
+		Ok(())
+	}
+
+	pub async fn logoff(&self) -> Result<()> {
+		self.post(PATH_LOGOFF, &json!({ "logoff": true })).await?;
+
+		Ok(())
+	}
+
+	/// The current auth token (i.e., the `auth-token` cookie, renewed by each call).
+	pub fn auth_token(&self) -> Result<String> {
+		let cookies = self
+			.cookie_jar
+			.cookies(&self.base_url)
+			.ok_or(Error::AuthTokenNotFound)?;
+		let cookies = cookies.to_str().map_err(|_| Error::AuthTokenNotFound)?;
+
+		cookies
+			.split("; ")
+			.find_map(|cookie| cookie.strip_prefix(AUTH_TOKEN)?.strip_prefix('='))
+			.map(str::to_string)
+			.ok_or(Error::AuthTokenNotFound)
+	}
+}
+// endregion: --- Constructors & Auth
+
+// region:    --- Rpc Call
+impl RpcClient {
+	/// Call the `method` rpc method with its params (None for the methods without).
+	///
+	/// Note: The typed functions below (e.g., `create_task`) should be preferred.
+	pub async fn call<P, R>(&self, method: &str, params: Option<P>) -> Result<R>
+	where
+		P: Serialize,
+		R: DeserializeOwned,
+	{
+		let rpc_id = self.next_rpc_id.fetch_add(1, Ordering::Relaxed);
+		let body = json!({
+			"jsonrpc": JSONRPC_VERSION,
+			"id": rpc_id,
+			"method": method,
+			"params": params,
+		});
+		let result = self.post(PATH_RPC, &body).await?;
+
+		Ok(serde_json::from_value(result)?)
+	}
+
+	/// Post the json `body`, returning the `result` of the response
+	/// (or the typed error of its `error`).
+	async fn post(&self, path: &str, body: &Value) -> Result<Value> {
+		let url = self
+			.base_url
+			.join(path)
+			.map_err(|_| Error::InvalidBaseUrl(self.base_url.to_string()))?;
+
+		let res: Value = self.http.post(url).json(body).send().await?.json().await?;
+
+		if let Some(error) = res.get("error") {
+			let RpcErrorBody {
+				code,
+				message,
+				data,
+			} = serde_json::from_value(error.clone())
+				.map_err(|_| Error::RpcInvalidResponse(res.to_string()))?;
+
+			return Err(Error::Rpc {
+				code,
+				client_error: client_error(message, data.detail),
+				req_uuid: data.req_uuid,
+			});
+		}
+
+		res.get("result")
+			.cloned()
+			.ok_or_else(|| Error::RpcInvalidResponse(res.to_string()))
+	}
+}
+
+#[derive(Deserialize)]
+struct RpcErrorBody {
+	code: i64,
+	message: String,
+	#[serde(default)]
+	data: RpcErrorData,
+}
+
+#[derive(Default, Deserialize)]
+struct RpcErrorData {
+	req_uuid: Option<String>,
+	detail: Option<Value>,
+}
+
+/// The `ClientError` from the error `message` (its variant) and `detail`.
+fn client_error(message: String, detail: Option<Value>) -> ClientError {
+	let mut client_error = json!({ "message": message });
+	if let Some(detail) = detail.filter(|detail| !detail.is_null()) {
+		client_error["detail"] = detail;
+	}
+
+	serde_json::from_value(client_error).unwrap_or(ClientError::UNKNOWN)
+}
+// endregion: --- Rpc Call
+
+// region:    --- Task Methods
+impl RpcClient {
+	pub async fn create_task(&self, task_c: TaskForCreate) -> Result<Task> {
+		self.call("create_task", Some(json!({ "data": task_c })))
+			.await
+	}
+
+	pub async fn list_tasks(&self) -> Result<Vec<TaskListItem>> {
+		self.call("list_tasks", None::<()>).await
+	}
+
+	pub async fn list_tasks_by_filter(
+		&self,
+		filter: TaskFilter,
+		list_options: Option<ListOptions>,
+	) -> Result<Vec<TaskListItem>> {
+		let params = json!({
+			"filter": filter,
+			"list_options": list_options,
+		});
+		self.call("list_tasks", Some(params)).await
+	}
+
+	/// A page of the tasks, the next one being listed with its `next_cursor`
+	/// as the `list_options.cursor` (and the same filter and order).
+	pub async fn list_tasks_page(
+		&self,
+		filter: TaskFilter,
+		list_options: Option<ListOptions>,
+	) -> Result<ListPage<TaskListItem>> {
+		let params = json!({
+			"filter": filter,
+			"list_options": list_options,
+		});
+		self.call("list_tasks_page", Some(params)).await
+	}
+
+	/// Note: `expected_version` for the optimistic concurrency
+	///       (i.e., `VERSION_CONFLICT` when the task has been updated since).
+	pub async fn update_task(
+		&self,
+		id: i64,
+		task_u: TaskForUpdate,
+		expected_version: Option<i64>,
+	) -> Result<Task> {
+		let params = json!({
+			"id": id,
+			"data": task_u,
+			"expected_version": expected_version,
+		});
+		self.call("update_task", Some(params)).await
+	}
+
+	pub async fn create_tasks(
+		&self,
+		tasks_c: Vec<TaskForCreate>,
+	) -> Result<Vec<BulkItemResult<i64>>> {
+		self.call("create_tasks", Some(json!({ "data": tasks_c })))
+			.await
+	}
+
+	/// Update the `ids` tasks with the same `task_u`.
+	pub async fn update_tasks(
+		&self,
+		ids: &[i64],
+		task_u: TaskForUpdate,
+	) -> Result<Vec<BulkItemResult<i64>>> {
+		let params = json!({
+			"ids": ids,
+			"data": task_u,
+		});
+		self.call("update_tasks", Some(params)).await
+	}
+
+	pub async fn delete_tasks(
+		&self,
+		ids: &[i64],
+	) -> Result<Vec<BulkItemResult<i64>>> {
+		self.call("delete_tasks", Some(json!({ "ids": ids }))).await
+	}
+
+	/// Note: `expected_version` as for `update_task`.
+	pub async fn move_task(
+		&self,
+		id: i64,
+		task_move: TaskMove,
+		expected_version: Option<i64>,
+	) -> Result<Task> {
+		let params = json!({
+			"id": id,
+			"data": task_move,
+			"expected_version": expected_version,
+		});
+		self.call("move_task", Some(params)).await
+	}
+
+	pub async fn delete_task(&self, id: i64) -> Result<Task> {
+		self.call("delete_task", Some(json!({ "id": id }))).await
+	}
+
+	pub async fn list_trash(&self) -> Result<Vec<Task>> {
+		self.call("list_trash", None::<()>).await
+	}
+
+	pub async fn restore_task(&self, id: i64) -> Result<Task> {
+		self.call("restore_task", Some(json!({ "id": id }))).await
+	}
+
+	pub async fn purge_task(&self, id: i64) -> Result<i64> {
+		self.call("purge_task", Some(json!({ "id": id }))).await
+	}
+
+	pub async fn list_task_revisions(&self, id: i64) -> Result<Vec<Revision>> {
+		self.call("list_task_revisions", Some(json!({ "id": id })))
+			.await
+	}
+
+	/// Note: `to_revision_id` None for the diff with the current task.
+	pub async fn diff_task_revisions(
+		&self,
+		id: i64,
+		from_revision_id: i64,
+		to_revision_id: Option<i64>,
+	) -> Result<Vec<FieldDiff>> {
+		let params = json!({
+			"id": id,
+			"from_revision_id": from_revision_id,
+			"to_revision_id": to_revision_id,
+		});
+		self.call("diff_task_revisions", Some(params)).await
+	}
+
+	pub async fn revert_task(&self, id: i64, revision_id: i64) -> Result<Task> {
+		let params = json!({
+			"id": id,
+			"revision_id": revision_id,
+		});
+		self.call("revert_task", Some(params)).await
+	}
+}
+// endregion: --- Task Methods
+
+// region:    --- Comment Methods
+impl RpcClient {
+	pub async fn create_comment(
+		&self,
+		comment_c: CommentForCreate,
+	) -> Result<Comment> {
+		self.call("create_comment", Some(json!({ "data": comment_c })))
+			.await
+	}
+
+	pub async fn list_comments(
+		&self,
+		task_id: i64,
+		list_options: Option<ListOptions>,
+	) -> Result<Vec<Comment>> {
+		let params = json!({
+			"task_id": task_id,
+			"list_options": list_options,
+		});
+		self.call("list_comments", Some(params)).await
+	}
+
+	/// A page of the comments of a task, the next one being listed with its
+	/// `next_cursor` as the `list_options.cursor`.
+	pub async fn list_comments_page(
+		&self,
+		task_id: i64,
+		list_options: Option<ListOptions>,
+	) -> Result<ListPage<Comment>> {
+		let params = json!({
+			"task_id": task_id,
+			"list_options": list_options,
+		});
+		self.call("list_comments_page", Some(params)).await
+	}
+
+	pub async fn update_comment(
+		&self,
+		id: i64,
+		comment_u: CommentForUpdate,
+	) -> Result<Comment> {
+		let params = json!({
+			"id": id,
+			"data": comment_u,
+		});
+		self.call("update_comment", Some(params)).await
+	}
+
+	pub async fn delete_comment(&self, id: i64) -> Result<Comment> {
+		self.call("delete_comment", Some(json!({ "id": id }))).await
+	}
+}
+// endregion: --- Comment Methods
+
+// region:    --- Webhook Methods
+impl RpcClient {
+	pub async fn create_webhook(
+		&self,
+		webhook_c: WebhookForCreate,
+	) -> Result<WebhookWithSecret> {
+		self.call("create_webhook", Some(json!({ "data": webhook_c })))
+			.await
+	}
+
+	pub async fn list_webhooks(&self) -> Result<Vec<Webhook>> {
+		self.call("list_webhooks", None::<()>).await
+	}
+
+	pub async fn update_webhook(
+		&self,
+		id: i64,
+		webhook_u: WebhookForUpdate,
+	) -> Result<Webhook> {
+		let params = json!({
+			"id": id,
+			"data": webhook_u,
+		});
+		self.call("update_webhook", Some(params)).await
+	}
+
+	pub async fn delete_webhook(&self, id: i64) -> Result<Webhook> {
+		self.call("delete_webhook", Some(json!({ "id": id }))).await
+	}
+
+	/// The delivery attempts of a webhook, newest first.
+	pub async fn list_webhook_deliveries(
+		&self,
+		webhook_id: i64,
+		list_options: Option<ListOptions>,
+	) -> Result<Vec<WebhookDelivery>> {
+		let params = json!({
+			"webhook_id": webhook_id,
+			"list_options": list_options,
+		});
+		self.call("list_webhook_deliveries", Some(params)).await
+	}
+
+	/// A page of the delivery attempts of a webhook, newest first
+	/// (see `list_comments_page` for the next page).
+	pub async fn list_webhook_deliveries_page(
+		&self,
+		webhook_id: i64,
+		list_options: Option<ListOptions>,
+	) -> Result<ListPage<WebhookDelivery>> {
+		let params = json!({
+			"webhook_id": webhook_id,
+			"list_options": list_options,
+		});
+		self.call("list_webhook_deliveries_page", Some(params))
+			.await
+	}
+
+	/// Deliver a `webhook.test` event now, returning the delivery attempt.
+	pub async fn send_test_webhook(&self, id: i64) -> Result<WebhookDelivery> {
+		self.call("send_test_webhook", Some(json!({ "id": id })))
+			.await
+	}
+}
+// endregion: --- Webhook Methods
+
+// region:    --- Notification Methods
+impl RpcClient {
+	/// The notifications of the user, newest first.
+	pub async fn list_notifications(
+		&self,
+		unread_only: bool,
+		list_options: Option<ListOptions>,
+	) -> Result<Vec<Notification>> {
+		let params = json!({
+			"unread_only": unread_only,
+			"list_options": list_options,
+		});
+		self.call("list_notifications", Some(params)).await
+	}
+
+	/// A page of the notifications of the user, newest first
+	/// (see `list_comments_page` for the next page).
+	pub async fn list_notifications_page(
+		&self,
+		unread_only: bool,
+		list_options: Option<ListOptions>,
+	) -> Result<ListPage<Notification>> {
+		let params = json!({
+			"unread_only": unread_only,
+			"list_options": list_options,
+		});
+		self.call("list_notifications_page", Some(params)).await
+	}
+
+	pub async fn count_unread_notifications(&self) -> Result<i64> {
+		self.call("count_unread_notifications", None::<()>).await
+	}
+
+	pub async fn mark_notification_read(&self, id: i64) -> Result<Notification> {
+		self.call("mark_notification_read", Some(json!({ "id": id })))
+			.await
+	}
+
+	/// Returns the number of the notifications marked as read.
+	pub async fn mark_all_notifications_read(&self) -> Result<u64> {
+		self.call("mark_all_notifications_read", None::<()>).await
+	}
+
+	pub async fn list_notification_prefs(&self) -> Result<Vec<NotificationPref>> {
+		self.call("list_notification_prefs", None::<()>).await
+	}
+
+	/// Enable or disable a kind of notifications, returning all the preferences.
+	pub async fn set_notification_pref(
+		&self,
+		pref: NotificationPref,
+	) -> Result<Vec<NotificationPref>> {
+		self.call("set_notification_pref", Some(pref)).await
+	}
+}
+// endregion: --- Notification Methods
+
+// region:    --- Search Methods
+impl RpcClient {
+	/// Full-text search of the tasks and comments (or only of the `entities`),
+	/// best ranked first. The last query word matches as a prefix.
+	pub async fn search(
+		&self,
+		query: &str,
+		entities: Option<Vec<SearchEntity>>,
+		list_options: Option<ListOptions>,
+	) -> Result<Vec<SearchHit>> {
+		let params = json!({
+			"query": query,
+			"entities": entities,
+			"list_options": list_options,
+		});
+		self.call("search", Some(params)).await
+	}
+}
+// endregion: --- Search Methods
+
+// region:    --- Saved View Methods
+impl RpcClient {
+	pub async fn create_saved_view(
+		&self,
+		saved_view_c: SavedViewForCreate,
+	) -> Result<SavedView> {
+		self.call("create_saved_view", Some(json!({ "data": saved_view_c })))
+			.await
+	}
+
+	pub async fn get_saved_view(&self, id: i64) -> Result<SavedView> {
+		self.call("get_saved_view", Some(json!({ "id": id }))).await
+	}
+
+	/// The views of the user, and the shared ones, by name.
+	pub async fn list_saved_views(&self) -> Result<Vec<SavedView>> {
+		self.call("list_saved_views", None::<()>).await
+	}
+
+	pub async fn update_saved_view(
+		&self,
+		id: i64,
+		saved_view_u: SavedViewForUpdate,
+	) -> Result<SavedView> {
+		let params = json!({
+			"id": id,
+			"data": saved_view_u,
+		});
+		self.call("update_saved_view", Some(params)).await
+	}
+
+	pub async fn delete_saved_view(&self, id: i64) -> Result<SavedView> {
+		self.call("delete_saved_view", Some(json!({ "id": id })))
+			.await
+	}
+
+	/// The tasks of a saved view, the `list_options` fields overriding the
+	/// view ones (e.g., to page through the view).
+	pub async fn list_tasks_by_view(
+		&self,
+		view_id: i64,
+		list_options: Option<ListOptions>,
+	) -> Result<Vec<TaskListItem>> {
+		let params = json!({
+			"view_id": view_id,
+			"list_options": list_options,
+		});
+		self.call("list_tasks_by_view", Some(params)).await
+	}
+}
+// endregion: --- Saved View Methods
+
+// region:    --- Admin Methods
+impl RpcClient {
+	/// The crons last run status (for an admin user only).
+	pub async fn list_cron_runs(&self) -> Result<Vec<CronRun>> {
+		self.call("list_cron_runs", None::<()>).await
+	}
+}
+// endregion: --- Admin Methods
@@ -0,0 +1,101 @@
+use derive_more::From;
+use serde::Deserialize;
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[derive(Debug, From)]
+pub enum Error {
+	// -- Rpc
+	/// The error response of the app (e.g., `ENTITY_NOT_FOUND`).
+	Rpc {
+		code: i64,
+		client_error: ClientError,
+		req_uuid: Option<String>,
+	},
+	/// A response being neither a result nor an error.
+	RpcInvalidResponse(String),
+
+	// -- Auth
+	AuthTokenNotFound,
+	AuthTokenInvalid,
+	InvalidBaseUrl(String),
+
+	// -- Externals
+	#[from]
+	Reqwest(reqwest::Error),
+	#[from]
+	SerdeJson(serde_json::Error),
+}
+
+impl Error {
+	/// The `ClientError` of an `Error::Rpc`.
+	pub fn client_error(&self) -> Option<&ClientError> {
+		match self {
+			Error::Rpc { client_error, .. } => Some(client_error),
+			_ => None,
+		}
+	}
+}
+
+// region:    --- Error Boilerplate
+impl core::fmt::Display for Error {
+	fn fmt(
+		&self,
+		fmt: &mut core::fmt::Formatter,
+	) -> core::result::Result<(), core::fmt::Error> {
+		write!(fmt, "{self:?}")
+	}
+}
+
+impl std::error::Error for Error {}
+// endregion: --- Error Boilerplate
+
+// region:    --- Client Error
+
+/// The app `ClientError`, from the `error.message` and `error.data.detail`
+/// of the JSON-RPC error responses.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "message", content = "detail")]
+#[allow(non_camel_case_types)]
+pub enum ClientError {
+	RPC_PARSE_ERROR,
+	RPC_INVALID_REQUEST,
+	RPC_METHOD_NOT_FOUND {
+		rpc_method: String,
+	},
+	RPC_INVALID_PARAMS {
+		rpc_method: String,
+	},
+
+	INVALID_INPUT(String),
+
+	LOGIN_FAIL,
+	NO_AUTH,
+	ENTITY_NOT_FOUND {
+		entity: String,
+		id: i64,
+	},
+	ACCESS_DENIED {
+		entity: String,
+		id: i64,
+	},
+	ADMIN_REQUIRED,
+	VERSION_CONFLICT {
+		entity: String,
+		id: i64,
+		current_version: i64,
+	},
+	NAME_TAKEN {
+		name: String,
+	},
+	INVALID_RRULE(String),
+	INVALID_RANK,
+
+	SERVICE_ERROR,
+
+	/// A `ClientError` added to the app after this client.
+	#[serde(other)]
+	UNKNOWN,
+}
+
+// endregion: --- Client Error
@@ -0,0 +1,27 @@
+//! Typed client of the rust-web-app RPC API (`/api/rpc`), for the Rust services
+//! calling the app.
+//!
+//! ```ignore
+//! let client = RpcClient::new("http://localhost:8080")?;
+//! client.login("demo1", "welcome").await?;
+//!
+//! let task = client.create_task(TaskForCreate::new("Buy milk")).await?;
+//! ```
+//!
+//! Authentication is either by the `auth-token` cookie (set by `login`), or by
+//! a bearer token (see `RpcClient::with_bearer_token` and `RpcClient::auth_token`).
+//!
+//! The failed calls return `Error::Rpc`, with the typed `ClientError` of the app.
+//!
+
+// region:    --- Modules
+
+mod client;
+mod error;
+mod types;
+
+pub use self::client::RpcClient;
+pub use self::error::{ClientError, Error, Result};
+pub use self::types::*;
+
+// endregion: --- Modules
@@ -0,0 +1,356 @@
+//! The params and results of the RPC methods
+//! (the client side of the app `model` and `web::rpc` types).
+
+use crate::error::ClientError;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use time::OffsetDateTime;
+
+// region:    --- Task Types
+#[derive(Debug, Clone, Deserialize)]
+pub struct Task {
+	pub id: i64,
+	pub title: String,
+	pub done: bool,
+	pub rank: String,
+	#[serde(with = "time::serde::rfc3339::option", default)]
+	pub due_date: Option<OffsetDateTime>,
+	pub rrule: Option<String>,
+	pub assignee_id: Option<i64>,
+	pub version: i64,
+}
+
+/// A `Task` as returned by `list_tasks`, with its comment count.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TaskListItem {
+	#[serde(flatten)]
+	pub task: Task,
+	pub comment_count: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TaskForCreate {
+	pub title: String,
+	#[serde(
+		with = "time::serde::rfc3339::option",
+		skip_serializing_if = "Option::is_none"
+	)]
+	pub due_date: Option<OffsetDateTime>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub rrule: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub assignee_id: Option<i64>,
+}
+
+impl TaskForCreate {
+	pub fn new(title: impl Into<String>) -> Self {
+		Self {
+			title: title.into(),
+			..Default::default()
+		}
+	}
+}
+
+/// Note: The `None` fields are not sent, so not updated.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TaskForUpdate {
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub title: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub done: Option<bool>,
+	#[serde(
+		with = "time::serde::rfc3339::option",
+		skip_serializing_if = "Option::is_none"
+	)]
+	pub due_date: Option<OffsetDateTime>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub rrule: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub assignee_id: Option<i64>,
+}
+
+/// Where to move a task, relative to another task of the list.
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskMove {
+	Before(i64),
+	After(i64),
+}
+
+/// The `list_tasks_by_filter` filter (all the given conditions apply).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TaskFilter {
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub done: Option<bool>,
+	/// Case insensitive substring of the title.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub title_contains: Option<String>,
+	#[serde(
+		with = "time::serde::rfc3339::option",
+		skip_serializing_if = "Option::is_none",
+		default
+	)]
+	pub due_before: Option<OffsetDateTime>,
+	#[serde(
+		with = "time::serde::rfc3339::option",
+		skip_serializing_if = "Option::is_none",
+		default
+	)]
+	pub due_after: Option<OffsetDateTime>,
+}
+// endregion: --- Task Types
+
+// region:    --- Comment Types
+#[derive(Debug, Clone, Deserialize)]
+pub struct Comment {
+	pub id: i64,
+	pub task_id: i64,
+	pub author_id: i64,
+
+	pub body: String,
+
+	#[serde(with = "time::serde::rfc3339")]
+	pub ctime: OffsetDateTime,
+	#[serde(with = "time::serde::rfc3339::option", default)]
+	pub mtime: Option<OffsetDateTime>, // None if never edited.
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CommentForCreate {
+	pub task_id: i64,
+	pub body: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CommentForUpdate {
+	pub body: String,
+}
+// endregion: --- Comment Types
+
+// region:    --- Revision Types
+#[derive(Debug, Clone, Deserialize)]
+pub struct Revision {
+	pub id: i64,
+	pub entity: String,
+	pub entity_id: i64,
+
+	pub actor_id: i64,
+	#[serde(with = "time::serde::rfc3339")]
+	pub ctime: OffsetDateTime,
+
+	/// The entity row before the update.
+	pub data: Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct FieldDiff {
+	pub field: String,
+	pub from: Value,
+	pub to: Value,
+}
+// endregion: --- Revision Types
+
+// region:    --- Webhook Types
+#[derive(Debug, Clone, Deserialize)]
+pub struct Webhook {
+	pub id: i64,
+	pub owner_id: i64,
+	pub url: String,
+	pub events: Vec<String>,
+	pub active: bool,
+
+	#[serde(with = "time::serde::rfc3339")]
+	pub ctime: OffsetDateTime,
+	#[serde(with = "time::serde::rfc3339::option", default)]
+	pub mtime: Option<OffsetDateTime>,
+}
+
+/// The created webhook, with its signing secret (only returned on create).
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookWithSecret {
+	#[serde(flatten)]
+	pub webhook: Webhook,
+	pub secret: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WebhookForCreate {
+	pub url: String,
+	/// The event types (e.g., `task.created`, `task.*`, or `*` for all).
+	pub events: Vec<String>,
+}
+
+/// Note: The `None` fields are not sent, so not updated.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct WebhookForUpdate {
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub url: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub events: Option<Vec<String>>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub active: Option<bool>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookDelivery {
+	pub id: i64,
+	pub webhook_id: i64,
+	pub delivery_id: String,
+	pub event: String,
+	pub attempt: i32,
+
+	pub status_code: Option<i32>,
+	pub error: Option<String>,
+	pub duration_ms: i32,
+
+	#[serde(with = "time::serde::rfc3339")]
+	pub ctime: OffsetDateTime,
+}
+// endregion: --- Webhook Types
+
+// region:    --- Notification Types
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+	TaskAssigned,
+	TaskCommented,
+	TaskDueSoon,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Notification {
+	pub id: i64,
+	pub recipient_id: i64,
+	pub kind: NotificationKind,
+	/// Kind specific (e.g., `{"task_id": 1000, "title": "..."}`).
+	pub payload: Value,
+	pub read: bool,
+
+	#[serde(with = "time::serde::rfc3339")]
+	pub ctime: OffsetDateTime,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationPref {
+	pub kind: NotificationKind,
+	pub enabled: bool,
+}
+// endregion: --- Notification Types
+
+// region:    --- Search Types
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchEntity {
+	Task,
+	Comment,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SearchHit {
+	pub entity: SearchEntity,
+	pub id: i64,
+	pub task_id: i64,
+	pub task_title: String,
+	pub rank: f32,
+	/// HTML escaped excerpt, with the matches in `<mark>` elements.
+	pub snippet: String,
+}
+// endregion: --- Search Types
+
+// region:    --- Saved View Types
+/// The tasks list of a saved view (the `list_tasks_by_filter` params).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SavedViewQuery {
+	pub filter: Option<TaskFilter>,
+	pub list_options: Option<ListOptions>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SavedView {
+	pub id: i64,
+	pub owner_id: i64,
+	pub name: String,
+	pub query: SavedViewQuery,
+	pub shared: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SavedViewForCreate {
+	pub name: String,
+	pub query: SavedViewQuery,
+	/// Visible to all the users (but only changed by its owner).
+	pub shared: bool,
+}
+
+/// Note: The `None` fields are not sent, so not updated.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SavedViewForUpdate {
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub name: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub query: Option<SavedViewQuery>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub shared: Option<bool>,
+}
+// endregion: --- Saved View Types
+
+// region:    --- Admin Types
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CronStatus {
+	Running,
+	Ok,
+	Failed,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CronRun {
+	pub name: String,
+	pub schedule: String,
+	#[serde(with = "time::serde::rfc3339::option", default)]
+	pub next_run_at: Option<OffsetDateTime>,
+
+	pub last_status: Option<CronStatus>,
+	#[serde(with = "time::serde::rfc3339::option", default)]
+	pub last_started_at: Option<OffsetDateTime>,
+	#[serde(with = "time::serde::rfc3339::option", default)]
+	pub last_finished_at: Option<OffsetDateTime>,
+	pub last_message: Option<String>,
+}
+// endregion: --- Admin Types
+
+// region:    --- Common Types
+
+/// Pagination and order options for the list methods.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ListOptions {
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub limit: Option<i64>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub offset: Option<i64>,
+	/// The order (e.g., `["!due_date", "title"]`, `!` for descending).
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub order_bys: Option<Vec<String>>,
+	/// The `next_cursor` of the previous `ListPage` (`offset` then being ignored).
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub cursor: Option<String>,
+}
+
+/// A page of a cursor paginated list (e.g., `list_tasks_page`).
+#[derive(Debug, Clone, Deserialize)]
+pub struct ListPage<T> {
+	pub items: Vec<T>,
+	/// The `ListOptions::cursor` of the next page (None for the last page).
+	pub next_cursor: Option<String>,
+	pub has_more: bool,
+}
+
+/// The per item result of the bulk methods (e.g., `create_tasks`).
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkItemResult<T> {
+	Ok(T),
+	Error(ClientError),
+}
+
+// endregion: --- Common Types
@@ -21,6 +21,7 @@ async fn main() -> Result<()> {
 	let req_create_task = hc.do_post(
 		"/api/rpc",
 		json!({
+			"jsonrpc": "2.0",
 			"id": 1,
 			"method": "create_task",
 			"params": {
@@ -35,13 +36,15 @@ async fn main() -> Result<()> {
 	let req_update_task = hc.do_post(
 		"/api/rpc",
 		json!({
+			"jsonrpc": "2.0",
 			"id": 1,
 			"method": "update_task",
 			"params": {
 				"id": 1000, // Hardcode the task id.
 				"data": {
 					"title": "task BB"
-				}
+				},
+				"expected_version": 0
 			}
 		}),
 	);
@@ -50,6 +53,7 @@ async fn main() -> Result<()> {
 	let req_delete_task = hc.do_post(
 		"/api/rpc",
 		json!({
+			"jsonrpc": "2.0",
 			"id": 1,
 			"method": "delete_task",
 			"params": {
@@ -62,12 +66,37 @@ async fn main() -> Result<()> {
 	let req_list_tasks = hc.do_post(
 		"/api/rpc",
 		json!({
+			"jsonrpc": "2.0",
 			"id": 1,
 			"method": "list_tasks"
 		}),
 	);
 	req_list_tasks.await?.print().await?;
 
+	// Batch, with a notification (no response entry).
+	let req_batch = hc.do_post(
+		"/api/rpc",
+		json!([
+			{
+				"jsonrpc": "2.0",
+				"id": 2,
+				"method": "create_task",
+				"params": { "data": { "title": "task CC" } }
+			},
+			{
+				"jsonrpc": "2.0",
+				"method": "delete_task",
+				"params": { "id": 1000 }
+			},
+			{
+				"jsonrpc": "2.0",
+				"id": 3,
+				"method": "list_tasks"
+			}
+		]),
+	);
+	req_batch.await?.print().await?;
+
 	let req_logoff = hc.do_post(
 		"/api/logoff",
 		json!({
@@ -1,10 +1,17 @@
 ---- Base app schema
 
+-- The text search configuration of the full-text search (e.g., 'english'),
+-- as set for the app connections (see `SERVICE_SEARCH_CONFIG`).
+CREATE FUNCTION search_config() RETURNS regconfig LANGUAGE sql STABLE AS $$
+  SELECT COALESCE(NULLIF(current_setting('app.search_config', true), ''), 'english')::regconfig
+$$;
+
 -- User
 CREATE TABLE "user" (
   id BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
 
   username varchar(128) NOT NULL UNIQUE,
+  is_admin bool NOT NULL DEFAULT false,
 
   -- Auth
   pwd varchar(256),
@@ -17,5 +24,216 @@ CREATE TABLE "user" (
 CREATE TABLE task (
   id BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
 
-  title varchar(256) NOT NULL
-);
\ No newline at end of file
+  title varchar(256) NOT NULL,
+  done bool NOT NULL DEFAULT false,
+
+  -- Manual ordering key (see utils::rank), compared byte-wise.
+  rank varchar(128) COLLATE "C" NOT NULL,
+
+  due_date timestamp with time zone,
+
+  -- Recurrence rule (iCalendar RRULE subset, e.g., "FREQ=WEEKLY")
+  rrule varchar(256),
+
+  assignee_id BIGINT REFERENCES "user"(id) ON DELETE SET NULL,
+
+  -- Optimistic concurrency (incremented on each update)
+  version BIGINT NOT NULL DEFAULT 0,
+
+  -- Soft delete (in trash when not null)
+  deleted_at timestamp with time zone,
+
+  -- Full-text search (maintained by the trigger below)
+  search_vec tsvector
+);
+
+CREATE INDEX task_rank_idx ON task (rank);
+
+-- Lock (LOCK TABLE) serializing the task appends (see TaskBmc::create), as the
+-- concurrent reads of the last rank conflict otherwise (SERIALIZABLE).
+CREATE TABLE task_append_lock ();
+CREATE INDEX task_search_vec_idx ON task USING gin (search_vec);
+
+CREATE FUNCTION task_search_vec() RETURNS trigger LANGUAGE plpgsql AS $$
+BEGIN
+  NEW.search_vec := setweight(to_tsvector(search_config(), NEW.title), 'A');
+  RETURN NEW;
+END $$;
+
+CREATE TRIGGER task_search_vec BEFORE INSERT OR UPDATE OF title ON task
+  FOR EACH ROW EXECUTE FUNCTION task_search_vec();
+
+-- Comment
+CREATE TABLE comment (
+  id BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+
+  task_id BIGINT NOT NULL REFERENCES task(id) ON DELETE CASCADE,
+  author_id BIGINT NOT NULL,
+
+  body text NOT NULL,
+
+  -- Timestamps
+  ctime timestamp with time zone NOT NULL DEFAULT now(),
+  mtime timestamp with time zone,
+
+  -- Full-text search (maintained by the trigger below)
+  search_vec tsvector
+);
+
+CREATE INDEX comment_task_id_idx ON comment (task_id);
+CREATE INDEX comment_search_vec_idx ON comment USING gin (search_vec);
+
+CREATE FUNCTION comment_search_vec() RETURNS trigger LANGUAGE plpgsql AS $$
+BEGIN
+  NEW.search_vec := setweight(to_tsvector(search_config(), NEW.body), 'C');
+  RETURN NEW;
+END $$;
+
+CREATE TRIGGER comment_search_vec BEFORE INSERT OR UPDATE OF body ON comment
+  FOR EACH ROW EXECUTE FUNCTION comment_search_vec();
+
+
+-- Revision (history of the versioned entities)
+CREATE TABLE revision (
+  id BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+
+  entity varchar(64) NOT NULL, -- Table name (e.g., 'task')
+  entity_id BIGINT NOT NULL,
+
+  actor_id BIGINT NOT NULL,
+  ctime timestamp with time zone NOT NULL DEFAULT now(),
+
+  -- Entity row snapshot, before the update.
+  data jsonb NOT NULL
+);
+
+CREATE INDEX revision_entity_idx ON revision (entity, entity_id);
+
+-- Change Event (the entity change events, id being their sequence number)
+CREATE TYPE change_kind AS ENUM ('created', 'updated', 'deleted', 'restored');
+
+CREATE TABLE change_event (
+  id BIGINT GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+
+  entity varchar(64) NOT NULL, -- Table name (e.g., 'task')
+  entity_id BIGINT NOT NULL,
+  kind change_kind NOT NULL,
+
+  actor_id BIGINT NOT NULL,
+  ctime timestamp with time zone NOT NULL DEFAULT now()
+);
+
+
+-- Job (the background jobs queue)
+CREATE TYPE job_status AS ENUM ('pending', 'running', 'done', 'dead');
+
+CREATE TABLE job (
+  id BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+
+  kind varchar(64) NOT NULL, -- Job type (e.g., 'send_mail')
+  payload jsonb NOT NULL,
+  status job_status NOT NULL DEFAULT 'pending',
+
+  run_at timestamp with time zone NOT NULL DEFAULT now(),
+  attempts integer NOT NULL DEFAULT 0,
+  max_attempts integer NOT NULL,
+  last_error text,
+
+  -- Timestamps
+  ctime timestamp with time zone NOT NULL DEFAULT now(),
+  mtime timestamp with time zone
+);
+
+CREATE INDEX job_due_idx ON job (run_at) WHERE status IN ('pending', 'running');
+
+
+-- Webhook (the endpoints the change events are delivered to, see model::webhook)
+CREATE TABLE webhook (
+  id BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+
+  owner_id BIGINT NOT NULL REFERENCES "user"(id) ON DELETE CASCADE,
+  url varchar(2048) NOT NULL,
+  -- Delivered event types (e.g., 'task.created', 'task.*', or '*')
+  events varchar(64)[] NOT NULL,
+  secret varchar(128) NOT NULL, -- HMAC-SHA256 signing key
+  active bool NOT NULL DEFAULT true,
+
+  -- Timestamps
+  ctime timestamp with time zone NOT NULL DEFAULT now(),
+  mtime timestamp with time zone
+);
+
+-- Webhook Delivery (the log of the delivery attempts)
+CREATE TABLE webhook_delivery (
+  id BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+
+  webhook_id BIGINT NOT NULL REFERENCES webhook(id) ON DELETE CASCADE,
+  delivery_id uuid NOT NULL, -- Same for the attempts of a delivery
+  event varchar(64) NOT NULL,
+  attempt integer NOT NULL,
+
+  status_code integer, -- None when no response
+  error text,
+  duration_ms integer NOT NULL,
+
+  ctime timestamp with time zone NOT NULL DEFAULT now()
+);
+
+CREATE INDEX webhook_delivery_webhook_id_idx ON webhook_delivery (webhook_id);
+CREATE INDEX webhook_delivery_delivery_id_idx ON webhook_delivery (delivery_id);
+
+-- Notification (the in-app notifications of the users, see model::notification)
+CREATE TYPE notification_kind AS ENUM ('task_assigned', 'task_commented', 'task_due_soon');
+
+CREATE TABLE notification (
+  id BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+
+  recipient_id BIGINT NOT NULL REFERENCES "user"(id) ON DELETE CASCADE,
+  kind notification_kind NOT NULL,
+  payload jsonb NOT NULL, -- Kind specific (e.g., {"task_id": 1000, "title": "..."})
+  read bool NOT NULL DEFAULT false,
+
+  ctime timestamp with time zone NOT NULL DEFAULT now()
+);
+
+CREATE INDEX notification_recipient_id_idx ON notification (recipient_id, id);
+
+-- Notification Pref (per user and kind, the kinds without one being enabled)
+CREATE TABLE notification_pref (
+  user_id BIGINT NOT NULL REFERENCES "user"(id) ON DELETE CASCADE,
+  kind notification_kind NOT NULL,
+  enabled bool NOT NULL,
+
+  PRIMARY KEY (user_id, kind)
+);
+
+-- Saved View (the named tasks list definitions, see model::saved_view)
+CREATE TABLE saved_view (
+  id BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+
+  owner_id BIGINT NOT NULL REFERENCES "user"(id) ON DELETE CASCADE,
+  name varchar(128) NOT NULL,
+  -- The `list_tasks` params (e.g., {"filter": {"done": false}, "list_options": {...}})
+  query jsonb NOT NULL,
+  shared bool NOT NULL DEFAULT false, -- Visible to all the users when true
+
+  -- Timestamps
+  ctime timestamp with time zone NOT NULL DEFAULT now(),
+  mtime timestamp with time zone,
+
+  UNIQUE (owner_id, name)
+);
+
+-- Cron Run (the last run of each cron, see model::cron)
+CREATE TYPE cron_status AS ENUM ('running', 'ok', 'failed');
+
+CREATE TABLE cron_run (
+  name varchar(64) PRIMARY KEY, -- Cron name (e.g., 'purge_trash')
+  schedule varchar(128) NOT NULL, -- Cron expression (e.g., '0 3 * * *')
+  next_run_at timestamp with time zone,
+
+  last_status cron_status,
+  last_started_at timestamp with time zone,
+  last_finished_at timestamp with time zone,
+  last_message text -- Run summary or error
+);
@@ -1,2 +1,2 @@
 -- User demo1
-INSERT INTO "user" (username) VALUES ('demo1');
\ No newline at end of file
+INSERT INTO "user" (username, is_admin) VALUES ('demo1', true);
\ No newline at end of file
@@ -82,8 +82,8 @@ async fn pexec(db: &Db, file: &Path) -> Result<(), sqlx::Error> {
 	// -- Read the file.
 	let content = fs::read_to_string(file)?;
 
-	// FIXME: Make the split more sql proof.
-	let sqls: Vec<&str> = content.split(';').collect();
+	// FIXME: Make the split more sql proof (e.g., `;` in string literals).
+	let sqls = split_sql(&content);
 
 	for sql in sqls {
 		sqlx::query(sql).execute(db).await?;
@@ -92,6 +92,32 @@ async fn pexec(db: &Db, file: &Path) -> Result<(), sqlx::Error> {
 	Ok(())
 }
 
+/// Split the sql content on `;`, except in the `$$` quoted bodies
+/// (e.g., of the plpgsql functions).
+fn split_sql(content: &str) -> Vec<&str> {
+	let mut sqls = Vec::new();
+	let mut start = 0;
+	let mut in_dollar_quote = false;
+	let mut rest = content;
+
+	while let Some(idx) = rest.find(['$', ';']) {
+		let offset = content.len() - rest.len() + idx;
+		if rest[idx..].starts_with("$$") {
+			in_dollar_quote = !in_dollar_quote;
+			rest = &rest[idx + 2..];
+			continue;
+		}
+		if !in_dollar_quote && rest[idx..].starts_with(';') {
+			sqls.push(&content[start..offset]);
+			start = offset + 1;
+		}
+		rest = &rest[idx + 1..];
+	}
+	sqls.push(&content[start..]);
+
+	sqls
+}
+
 async fn new_db_pool(db_con_url: &str) -> Result<Db, sqlx::Error> {
 	PgPoolOptions::new()
 		.max_connections(1)
@@ -3,7 +3,9 @@
 mod dev_db;
 
 use crate::ctx::Ctx;
+use crate::model::comment::{Comment, CommentBmc, CommentForCreate};
 use crate::model::task::{Task, TaskBmc, TaskForCreate};
+use crate::model::user::{UserBmc, UserForCreate};
 use crate::model::{self, ModelManager};
 use tokio::sync::OnceCell;
 use tracing::info;
@@ -50,6 +52,7 @@ pub async fn seed_tasks(
 			mm,
 			TaskForCreate {
 				title: title.to_string(),
+				..Default::default()
 			},
 		)
 		.await?;
@@ -60,3 +63,65 @@ pub async fn seed_tasks(
 
 	Ok(tasks)
 }
+
+pub async fn seed_comments(
+	ctx: &Ctx,
+	mm: &ModelManager,
+	task_id: i64,
+	bodies: &[&str],
+) -> model::Result<Vec<Comment>> {
+	let mut comments = Vec::new();
+
+	for body in bodies {
+		let id = CommentBmc::create(
+			ctx,
+			mm,
+			CommentForCreate {
+				task_id,
+				body: body.to_string(),
+			},
+		)
+		.await?;
+		let comment = CommentBmc::get(ctx, mm, id).await?;
+
+		comments.push(comment);
+	}
+
+	Ok(comments)
+}
+
+/// Seed users (with the "welcome" pwd) and return their ids.
+pub async fn seed_users(
+	ctx: &Ctx,
+	mm: &ModelManager,
+	usernames: &[&str],
+) -> model::Result<Vec<i64>> {
+	let mut ids = Vec::new();
+
+	for username in usernames {
+		let id = UserBmc::create(
+			ctx,
+			mm,
+			UserForCreate {
+				username: username.to_string(),
+				pwd_clear: "welcome".to_string(),
+			},
+		)
+		.await?;
+
+		ids.push(id);
+	}
+
+	Ok(ids)
+}
+
+/// Delete the users (e.g., seeded by `seed_users`), with their cascading rows.
+pub async fn clean_users(
+	ctx: &Ctx,
+	mm: &ModelManager,
+	ids: &[i64],
+) -> model::Result<()> {
+	UserBmc::delete_many(ctx, mm, ids).await?;
+
+	Ok(())
+}
@@ -1,6 +1,7 @@
 pub type Result<T> = core::result::Result<T, Error>;
 
 #[derive(Debug)]
+#[allow(dead_code)] // Variant data only read through Debug.
 pub enum Error {
 	MissingEnv(&'static str),
 	WrongFormat(&'static str),
@@ -11,6 +11,9 @@ use std::sync::OnceLock;
 
 // endregion: --- Modules
 
+/// The max of the duration configs (100 years).
+const DURATION_SEC_MAX: f64 = 100.0 * 365.25 * 24.0 * 3600.0;
+
 pub fn config() -> &'static Config {
 	static INSTANCE: OnceLock<Config> = OnceLock::new();
 
@@ -31,6 +34,17 @@ pub struct Config {
 
 	// -- Db
 	pub DB_URL: String,
+	pub TRASH_RETENTION_SEC: f64,
+	/// The Postgres text search configuration (e.g., `english`, `simple`).
+	pub SEARCH_CONFIG: String,
+
+	// -- Jobs
+	pub JOB_WORKERS: usize,
+
+	// -- Webhooks
+	/// The webhook url hosts allowed to resolve to non-public addresses
+	/// (e.g., `127.0.0.1` for local endpoints).
+	pub WEBHOOK_ALLOWED_HOSTS: Vec<String>,
 
 	// -- Web
 	pub WEB_FOLDER: String,
@@ -47,6 +61,16 @@ impl Config {
 
 			// -- Db
 			DB_URL: get_env("SERVICE_DB_URL")?,
+			TRASH_RETENTION_SEC: get_env_duration_sec(
+				"SERVICE_TRASH_RETENTION_SEC",
+			)?,
+			SEARCH_CONFIG: get_env("SERVICE_SEARCH_CONFIG")?,
+
+			// -- Jobs
+			JOB_WORKERS: get_env_parse("SERVICE_JOB_WORKERS")?,
+
+			// -- Webhooks
+			WEBHOOK_ALLOWED_HOSTS: get_env_list("SERVICE_WEBHOOK_ALLOWED_HOSTS")?,
 
 			// -- Web
 			WEB_FOLDER: get_env("SERVICE_WEB_FOLDER")?,
@@ -63,6 +87,28 @@ fn get_env_parse<T: FromStr>(name: &'static str) -> Result<T> {
 	val.parse::<T>().map_err(|_| Error::WrongFormat(name))
 }
 
+/// Seconds, finite and between 0 and `DURATION_SEC_MAX`
+/// (so that `time::Duration::seconds_f64` and the date arithmetic cannot panic).
+fn get_env_duration_sec(name: &'static str) -> Result<f64> {
+	let sec: f64 = get_env_parse(name)?;
+	if !(0.0..=DURATION_SEC_MAX).contains(&sec) {
+		return Err(Error::WrongFormat(name));
+	}
+
+	Ok(sec)
+}
+
+/// Comma separated values (empty for none).
+fn get_env_list(name: &'static str) -> Result<Vec<String>> {
+	let val = get_env(name)?;
+	Ok(val
+		.split(',')
+		.map(str::trim)
+		.filter(|v| !v.is_empty())
+		.map(String::from)
+		.collect())
+}
+
 fn get_env_b64u_as_u8s(name: &'static str) -> Result<Vec<u8>> {
 	b64u_decode(&get_env(name)?).map_err(|_| Error::WrongFormat(name))
 }
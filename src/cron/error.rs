@@ -0,0 +1,26 @@
+use serde::Serialize;
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[derive(Debug, Serialize)]
+pub enum Error {
+	// -- Parsing
+	FieldCount(usize),
+	UnknownMacro(String),
+	InvalidValue { field: &'static str, value: String },
+	OutOfRange { field: &'static str, value: u32 },
+	InvalidStep { field: &'static str, value: String },
+}
+
+// region:    --- Error Boilerplate
+impl core::fmt::Display for Error {
+	fn fmt(
+		&self,
+		fmt: &mut core::fmt::Formatter,
+	) -> core::result::Result<(), core::fmt::Error> {
+		write!(fmt, "{self:?}")
+	}
+}
+
+impl std::error::Error for Error {}
+// endregion: --- Error Boilerplate
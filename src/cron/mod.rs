@@ -0,0 +1,405 @@
+//! Cron expressions, following the classic five fields crontab format, in UTC.
+//!
+//! Format: `minute hour day-of-month month day-of-week`
+//!
+//! - Each field is a list (`,`) of `*`, a value, or a range (`a-b`), each optionally
+//!   with a step (e.g., `*/15`, `1-5/2`, `10/20` as `10-max/20`).
+//! - Months and week days also take their three letters names
+//!   (e.g., `JAN`, `MON-FRI`), Sunday being `0` (or `7`).
+//! - The `@yearly` (`@annually`), `@monthly`, `@weekly`, `@daily` (`@midnight`)
+//!   and `@hourly` macros.
+//!
+//! Notes:
+//!
+//! - As in Vixie cron, when both the day of month and the day of week are
+//!   restricted (i.e., not starting with `*`), a day matching either one matches.
+//!
+
+// region:    --- Modules
+
+mod error;
+
+pub use self::error::{Error, Result};
+
+use std::fmt::Display;
+use std::str::FromStr;
+use time::{Date, Duration, Month, OffsetDateTime, Time, UtcOffset};
+
+// endregion: --- Modules
+
+/// Max number of steps scanned for the next match
+/// (guard for the expressions never matching, e.g., `0 0 30 2 *`).
+const MAX_STEPS: u32 = 100_000;
+
+// region:    --- CronExpr
+
+/// String format: `*/15 9-17 * * MON-FRI`
+#[derive(Debug, Clone, PartialEq)]
+pub struct CronExpr {
+	source: String,
+	// Bit sets of the matching values.
+	minutes: u64,
+	hours: u64,
+	days: u64,
+	months: u64,
+	weekdays: u64, // Bit 0 is Sunday.
+	days_any: bool,
+	weekdays_any: bool,
+}
+
+struct FieldSpec {
+	name: &'static str,
+	min: u32,
+	max: u32,
+	/// Names of the values from `min`.
+	names: &'static [&'static str],
+}
+
+const MINUTE: FieldSpec = FieldSpec {
+	name: "minute",
+	min: 0,
+	max: 59,
+	names: &[],
+};
+const HOUR: FieldSpec = FieldSpec {
+	name: "hour",
+	min: 0,
+	max: 23,
+	names: &[],
+};
+const DAY: FieldSpec = FieldSpec {
+	name: "day-of-month",
+	min: 1,
+	max: 31,
+	names: &[],
+};
+const MONTH: FieldSpec = FieldSpec {
+	name: "month",
+	min: 1,
+	max: 12,
+	names: &[
+		"JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV",
+		"DEC",
+	],
+};
+const WEEKDAY: FieldSpec = FieldSpec {
+	name: "day-of-week",
+	min: 0,
+	max: 7,
+	names: &["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"],
+};
+
+impl FromStr for CronExpr {
+	type Err = Error;
+
+	fn from_str(cron_str: &str) -> std::result::Result<Self, Self::Err> {
+		let source = cron_str.split_whitespace().collect::<Vec<_>>().join(" ");
+
+		let expanded = match source.strip_prefix('@') {
+			Some(name) => match name.to_ascii_lowercase().as_str() {
+				"yearly" | "annually" => "0 0 1 1 *",
+				"monthly" => "0 0 1 * *",
+				"weekly" => "0 0 * * 0",
+				"daily" | "midnight" => "0 0 * * *",
+				"hourly" => "0 * * * *",
+				_ => return Err(Error::UnknownMacro(source)),
+			},
+			None => source.as_str(),
+		};
+
+		let fields: Vec<&str> = expanded.split(' ').collect();
+		let [minute, hour, day, month, weekday] = fields[..] else {
+			return Err(Error::FieldCount(fields.len()));
+		};
+
+		let mut weekdays = parse_field(&WEEKDAY, weekday)?;
+		// Sunday as 7.
+		if weekdays & (1 << 7) != 0 {
+			weekdays = (weekdays & !(1 << 7)) | 1;
+		}
+
+		Ok(Self {
+			minutes: parse_field(&MINUTE, minute)?,
+			hours: parse_field(&HOUR, hour)?,
+			days: parse_field(&DAY, day)?,
+			months: parse_field(&MONTH, month)?,
+			weekdays,
+			days_any: day.starts_with('*'),
+			weekdays_any: weekday.starts_with('*'),
+			source,
+		})
+	}
+}
+
+impl Display for CronExpr {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}", self.source)
+	}
+}
+
+// endregion: --- CronExpr
+
+// region:    --- Matching
+
+impl CronExpr {
+	/// Returns the first matching minute strictly after `after` (in UTC).
+	///
+	/// Returns `None` when the expression never matches.
+	pub fn next_after(&self, after: OffsetDateTime) -> Option<OffsetDateTime> {
+		let after = after.to_offset(UtcOffset::UTC);
+		let mut t = after
+			.replace_time(Time::from_hms(after.hour(), after.minute(), 0).ok()?)
+			+ Duration::minutes(1);
+
+		for _ in 0..MAX_STEPS {
+			if !has_bit(self.months, t.month() as u32) {
+				t = t
+					.replace_time(Time::MIDNIGHT)
+					.replace_date(first_of_next_month(t.date())?);
+			} else if !self.day_matches(t.date()) {
+				t = t.replace_time(Time::MIDNIGHT) + Duration::days(1);
+			} else if !has_bit(self.hours, t.hour() as u32) {
+				t = t.replace_time(Time::from_hms(t.hour(), 0, 0).ok()?)
+					+ Duration::hours(1);
+			} else if !has_bit(self.minutes, t.minute() as u32) {
+				t += Duration::minutes(1);
+			} else {
+				return Some(t);
+			}
+		}
+
+		None
+	}
+
+	fn day_matches(&self, date: Date) -> bool {
+		let day = has_bit(self.days, date.day() as u32);
+		let weekday = has_bit(
+			self.weekdays,
+			date.weekday().number_days_from_sunday() as u32,
+		);
+
+		if self.days_any || self.weekdays_any {
+			day && weekday
+		} else {
+			day || weekday
+		}
+	}
+}
+
+fn has_bit(bits: u64, value: u32) -> bool {
+	bits & (1 << value) != 0
+}
+
+fn first_of_next_month(date: Date) -> Option<Date> {
+	let (year, month) = match date.month() {
+		Month::December => (date.year() + 1, Month::January),
+		month => (date.year(), month.next()),
+	};
+
+	Date::from_calendar_date(year, month, 1).ok()
+}
+
+// endregion: --- Matching
+
+// region:    --- Parsing Helpers
+
+/// Returns the bit set of the values of the field.
+fn parse_field(spec: &FieldSpec, field: &str) -> Result<u64> {
+	let mut bits = 0;
+
+	for item in field.split(',') {
+		let (range, step) = match item.split_once('/') {
+			Some((range, step)) => {
+				let step = step
+					.parse::<u32>()
+					.ok()
+					.filter(|step| *step > 0)
+					.ok_or_else(|| Error::InvalidStep {
+						field: spec.name,
+						value: item.to_string(),
+					})?;
+				(range, Some(step))
+			}
+			None => (item, None),
+		};
+
+		let (start, end) = match range.split_once('-') {
+			_ if range == "*" => (spec.min, spec.max),
+			Some((start, end)) => {
+				(parse_value(spec, start)?, parse_value(spec, end)?)
+			}
+			// `a/step` runs up to the max.
+			None if step.is_some() => (parse_value(spec, range)?, spec.max),
+			None => {
+				let value = parse_value(spec, range)?;
+				(value, value)
+			}
+		};
+		if start > end {
+			return Err(Error::InvalidValue {
+				field: spec.name,
+				value: item.to_string(),
+			});
+		}
+
+		for value in (start..=end).step_by(step.unwrap_or(1) as usize) {
+			bits |= 1 << value;
+		}
+	}
+
+	Ok(bits)
+}
+
+fn parse_value(spec: &FieldSpec, value: &str) -> Result<u32> {
+	let parsed = match value.parse::<u32>() {
+		Ok(num) => num,
+		Err(_) => spec
+			.names
+			.iter()
+			.position(|name| name.eq_ignore_ascii_case(value))
+			.map(|idx| spec.min + idx as u32)
+			.ok_or_else(|| Error::InvalidValue {
+				field: spec.name,
+				value: value.to_string(),
+			})?,
+	};
+
+	if (spec.min..=spec.max).contains(&parsed) {
+		Ok(parsed)
+	} else {
+		Err(Error::OutOfRange {
+			field: spec.name,
+			value: parsed,
+		})
+	}
+}
+
+// endregion: --- Parsing Helpers
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::utils::time::{format_time, parse_utc};
+	use anyhow::{Context, Result};
+
+	fn fx_next_n(expr: &CronExpr, after: &str, n: usize) -> Result<Vec<String>> {
+		let mut after = parse_utc(after)?;
+		let mut nexts = Vec::new();
+		for _ in 0..n {
+			after = expr.next_after(after).context("Should have a next")?;
+			nexts.push(format_time(after));
+		}
+
+		Ok(nexts)
+	}
+
+	#[test]
+	fn test_cron_from_str_ok() -> Result<()> {
+		// -- Fixtures
+		let fx_cases = &[
+			("*/15  9-17 * * MON-FRI", "*/15 9-17 * * MON-FRI"),
+			("0 0 1,15 jan-mar/2 *", "0 0 1,15 jan-mar/2 *"),
+			("@daily", "@daily"),
+			("5/20 * * * 7", "5/20 * * * 7"),
+		];
+
+		// -- Exec & Check
+		for (fx_str, fx_display) in fx_cases {
+			let expr: CronExpr = fx_str.parse()?;
+			assert_eq!(expr.to_string(), *fx_display);
+		}
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_cron_from_str_err() -> Result<()> {
+		// -- Fixtures
+		let fx_cases = &[
+			"* * * *",
+			"* * * * * *",
+			"@every_minute",
+			"60 * * * *",
+			"* 24 * * *",
+			"* * 0 * *",
+			"* * * 13 *",
+			"* * * * 8",
+			"*/0 * * * *",
+			"5-1 * * * *",
+			"* * * FOO *",
+		];
+
+		// -- Exec & Check
+		for fx_case in fx_cases {
+			let res = fx_case.parse::<CronExpr>();
+			assert!(res.is_err(), "Should have failed for '{fx_case}'");
+		}
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_next_after_steps_and_ranges() -> Result<()> {
+		// -- Fixtures
+		// 2024-01-05 is a Friday.
+		let expr: CronExpr = "*/20 17-18 * * MON-FRI".parse()?;
+
+		// -- Exec
+		let nexts = fx_next_n(&expr, "2024-01-05T17:35:10Z", 5)?;
+
+		// -- Check
+		assert_eq!(
+			nexts,
+			&[
+				"2024-01-05T17:40:00Z",
+				"2024-01-05T18:00:00Z",
+				"2024-01-05T18:20:00Z",
+				"2024-01-05T18:40:00Z",
+				"2024-01-08T17:00:00Z",
+			]
+		);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_next_after_day_or_weekday() -> Result<()> {
+		// -- Fixtures
+		// The 1st, or the Sundays (as 7).
+		let expr: CronExpr = "30 3 1 * 7".parse()?;
+
+		// -- Exec
+		let nexts = fx_next_n(&expr, "2024-01-29T00:00:00Z", 3)?;
+
+		// -- Check
+		assert_eq!(
+			nexts,
+			&[
+				"2024-02-01T03:30:00Z",
+				"2024-02-04T03:30:00Z",
+				"2024-02-11T03:30:00Z",
+			]
+		);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_next_after_leap_day_and_never() -> Result<()> {
+		// -- Fixtures
+		let leap_day: CronExpr = "0 0 29 2 *".parse()?;
+		let never: CronExpr = "0 0 30 2 *".parse()?;
+		let fx_after = parse_utc("2024-03-01T00:00:00Z")?;
+
+		// -- Exec & Check
+		let next = leap_day
+			.next_after(fx_after)
+			.context("Should have a next")?;
+		assert_eq!(format_time(next), "2028-02-29T00:00:00Z");
+		assert_eq!(never.next_after(fx_after), None);
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
@@ -8,6 +8,13 @@ pub enum Error {
 	// -- Modules
 	#[from]
 	Model(model::Error),
+
+	// -- CLI
+	CliUnknownCommand(String),
+
+	// -- Externals
+	#[from]
+	Io(std::io::Error),
 }
 
 // region:    --- Error Boilerplate
@@ -3,11 +3,13 @@
 // region:    --- Modules
 
 mod config;
+mod cron;
 mod ctx;
 mod error;
 mod log;
 mod model;
 mod pwd;
+mod recurrence;
 mod token;
 mod utils;
 mod web;
@@ -18,12 +20,8 @@ pub use self::error::{Error, Result};
 pub use config::config;
 
 use crate::model::ModelManager;
-use crate::web::mw_auth::{mw_ctx_require, mw_ctx_resolve};
-use crate::web::mw_res_map::mw_reponse_map;
-use crate::web::{routes_login, routes_static, rpc};
-use axum::{middleware, Router};
+use crate::web::rpc;
 use std::net::SocketAddr;
-use tower_cookies::CookieManagerLayer;
 use tracing::info;
 use tracing_subscriber::EnvFilter;
 
@@ -37,23 +35,24 @@ async fn main() -> Result<()> {
 		.with_env_filter(EnvFilter::from_default_env())
 		.init();
 
+	// -- CLI Commands (e.g., `cargo run -- export-ts`)
+	let args: Vec<String> = std::env::args().skip(1).collect();
+	if let Some(command) = args.first() {
+		return run_command(command, &args[1..]);
+	}
+
 	// -- FOR DEV ONLY
 	_dev_utils::init_dev().await;
 
 	// Initialize ModelManager.
 	let mm = ModelManager::new().await?;
 
-	// -- Define Routes
-	let routes_rpc =
-		rpc::routes(mm.clone()).route_layer(middleware::from_fn(mw_ctx_require));
+	// -- Start Background Tasks
+	model::cron::app_crons().start(mm.clone());
+	model::job::app_jobs().start_workers(mm.clone(), config().JOB_WORKERS);
 
-	let routes_all = Router::new()
-		.merge(routes_login::routes(mm.clone()))
-		.nest("/api", routes_rpc)
-		.layer(middleware::map_response(mw_reponse_map))
-		.layer(middleware::from_fn_with_state(mm.clone(), mw_ctx_resolve))
-		.layer(CookieManagerLayer::new())
-		.fallback_service(routes_static::serve_dir());
+	// -- Define Routes
+	let routes_all = web::routes_all(mm);
 
 	// region:    --- Start Server
 	let addr = SocketAddr::from(([127, 0, 0, 1], 8080));
@@ -66,3 +65,20 @@ async fn main() -> Result<()> {
 
 	Ok(())
 }
+
+fn run_command(command: &str, args: &[String]) -> Result<()> {
+	match command {
+		// Export the TypeScript rpc client (to `rpc::TS_CLIENT_PATH` by default).
+		"export-ts" => {
+			let path = args
+				.first()
+				.map(String::as_str)
+				.unwrap_or(rpc::TS_CLIENT_PATH);
+			rpc::export_ts_client(path)?;
+			info!("{:<12} - {path}", "EXPORTED");
+
+			Ok(())
+		}
+		_ => Err(Error::CliUnknownCommand(command.to_string())),
+	}
+}
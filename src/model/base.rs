@@ -1,29 +1,143 @@
 use crate::ctx::Ctx;
+use crate::model::cursor::ListCursor;
+use crate::model::event::{Change, ChangeKind};
+use crate::model::revision::RevisionBmc;
 use crate::model::ModelManager;
 use crate::model::{Error, Result};
-use sqlb::HasFields;
+use crate::utils::time::now_utc;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlb::{Field, HasFields, Raw, SelectSqlBuilder, SqlBuilder, Whereable};
 use sqlx::postgres::PgRow;
-use sqlx::FromRow;
+use sqlx::types::Json;
+use sqlx::{FromRow, Row};
+use time::OffsetDateTime;
+
+const LIST_LIMIT_DEFAULT: i64 = 300;
+const LIST_LIMIT_MAX: i64 = 1000;
+
+/// The `base::list_page` column of the row sort keys (for the next cursor).
+const LIST_PAGE_KEYS: &str = "list_page_keys";
 
 pub trait DbBmc {
 	const TABLE: &'static str;
+
+	/// The `base::list` order (sqlb format, e.g., `!ctime` for descending).
+	const LIST_ORDER_BYS: &'static [&'static str] = &["id"];
+
+	/// The columns the lists can be ordered by (see `ListOptions::order_bys`).
+	const LIST_ORDERABLE: &'static [&'static str] = &["id"];
+
+	/// When true, the table has a `deleted_at` column, and `base::delete`
+	/// only moves the rows to the trash (see `base::restore` and `base::purge`).
+	const SOFT_DELETE: bool = false;
+
+	/// When true, the table has a `version` column incremented by each
+	/// `base::update` (see its `expected_version`), and each update records
+	/// the previous row state in the `revision` table (see `RevisionBmc`).
+	const VERSIONED: bool = false;
+
+	/// When true, the `base` writes emit the change events of the entities
+	/// (see the `event` module).
+	const CHANGE_EVENTS: bool = false;
+}
+
+pub(in crate::model) const DELETED_AT: &str = "deleted_at";
+const VERSION: &str = "version";
+
+// region:    --- ListOptions
+
+/// Pagination and order options for the list functions.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct ListOptions {
+	pub limit: Option<i64>,
+	pub offset: Option<i64>,
+	/// The order, by the orderable columns of the list (e.g., `["!due_date", "title"]`,
+	/// `!` for descending). The default order of the list when absent.
+	pub order_bys: Option<Vec<String>>,
+	/// The `next_cursor` of the previous page, for the lists paginated by keyset
+	/// (see `base::list_page`, the `offset` then being ignored).
+	/// Rejected by the other lists.
+	pub cursor: Option<String>,
 }
 
-pub async fn create<MC, E>(_ctx: &Ctx, mm: &ModelManager, data: E) -> Result<i64>
+impl ListOptions {
+	/// Fails with `Error::ListCursorUnsupported` when given a cursor,
+	/// for the lists not paginated by keyset (i.e., by `limit_offset` only).
+	pub fn check_no_cursor(&self) -> Result<()> {
+		match self.cursor {
+			Some(_) => Err(Error::ListCursorUnsupported),
+			None => Ok(()),
+		}
+	}
+
+	/// Returns the `(limit, offset)` to apply,
+	/// with the limit defaulted and capped to `LIST_LIMIT_MAX`.
+	pub fn limit_offset(&self) -> (i64, i64) {
+		let limit = self
+			.limit
+			.unwrap_or(LIST_LIMIT_DEFAULT)
+			.clamp(1, LIST_LIMIT_MAX);
+		let offset = self.offset.unwrap_or(0).max(0);
+
+		(limit, offset)
+	}
+
+	/// Returns the order bys to apply (sqlb format), `MC::LIST_ORDER_BYS` when
+	/// absent, failing for the columns not in `MC::LIST_ORDERABLE`.
+	pub fn order_bys<MC>(&self) -> Result<Vec<&str>>
+	where
+		MC: DbBmc,
+	{
+		let Some(order_bys) = self.order_bys.as_ref().filter(|o| !o.is_empty())
+		else {
+			return Ok(MC::LIST_ORDER_BYS.to_vec());
+		};
+
+		order_bys
+			.iter()
+			.map(|order_by| {
+				let column = order_by.strip_prefix('!').unwrap_or(order_by);
+				if MC::LIST_ORDERABLE.contains(&column) {
+					Ok(order_by.as_str())
+				} else {
+					Err(Error::ListOrderByInvalid {
+						entity: MC::TABLE,
+						order_by: order_by.to_string(),
+					})
+				}
+			})
+			.collect()
+	}
+}
+
+/// A page of a keyset paginated list (see `base::list_page`).
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ListPage<E> {
+	pub items: Vec<E>,
+	/// The `ListOptions::cursor` of the next page (None for the last page).
+	pub next_cursor: Option<String>,
+	pub has_more: bool,
+}
+
+// endregion: --- ListOptions
+
+pub async fn create<MC, E>(ctx: &Ctx, mm: &ModelManager, data: E) -> Result<i64>
 where
 	MC: DbBmc,
 	E: HasFields,
 {
-	let db = mm.db();
-
 	let fields = data.not_none_fields();
 	let (id,) = sqlb::insert()
 		.table(MC::TABLE)
 		.data(fields)
 		.returning(&["id"])
-		.fetch_one::<_, (i64,)>(db)
+		.fetch_one::<_, (i64,)>(&mut *mm.db().await?)
 		.await?;
 
+	emit_changes::<MC>(ctx, mm, &[id], ChangeKind::Created).await?;
+
 	Ok(id)
 }
 
@@ -33,13 +147,12 @@ where
 	E: for<'r> FromRow<'r, PgRow> + Unpin + Send,
 	E: HasFields,
 {
-	let db = mm.db();
-
-	let entity: E = sqlb::select()
+	let sb = sqlb::select()
 		.table(MC::TABLE)
 		.columns(E::field_names())
-		.and_where("id", "=", id)
-		.fetch_optional(db)
+		.and_where("id", "=", id);
+	let entity: E = and_where_not_deleted::<MC, _>(sb)
+		.fetch_optional(&mut *mm.db().await?)
 		.await?
 		.ok_or(Error::EntityNotFound {
 			entity: MC::TABLE,
@@ -55,36 +168,532 @@ where
 	E: for<'r> FromRow<'r, PgRow> + Unpin + Send,
 	E: HasFields,
 {
-	let db = mm.db();
-
-	let entities: Vec<E> = sqlb::select()
+	let sb = sqlb::select()
 		.table(MC::TABLE)
 		.columns(E::field_names())
-		.order_by("id")
-		.fetch_all(db)
+		.order_bys(MC::LIST_ORDER_BYS);
+	let entities: Vec<E> = and_where_not_deleted::<MC, _>(sb)
+		.fetch_all(&mut *mm.db().await?)
 		.await?;
 
 	Ok(entities)
 }
 
-pub async fn update<MC, E>(
+/// List a page of the entities matching the where clauses of `sb` (e.g.,
+/// `sqlb::select()` for all of them), in the `ListOptions` order.
+///
+/// Keyset pagination: The page after the `list_options.cursor` is made of the
+/// rows after the sort keys of the cursor (the last row of the previous page),
+/// so no row is skipped or repeated with the concurrent inserts and deletes.
+/// (`list_options.offset` only applies to the first page, i.e., without cursor)
+///
+/// Notes:
+///   - The order bys get the `id` tie-break (unless already ordered by `id`),
+///     so that the sort keys are unique.
+///   - The NULLs are ordered as by Postgres (last when ascending, first when
+///     descending), the comparisons of the keys following that order.
+pub async fn list_page<'a, MC, E>(
+	ctx: &Ctx,
+	mm: &ModelManager,
+	sb: SelectSqlBuilder<'a>,
+	list_options: Option<ListOptions>,
+) -> Result<ListPage<E>>
+where
+	MC: DbBmc,
+	E: for<'r> FromRow<'r, PgRow> + Unpin + Send,
+	E: HasFields,
+{
+	list_page_columns::<MC, E>(ctx, mm, sb, E::field_names(), list_options).await
+}
+
+/// Same as `list_page`, selecting the `columns` of the entities
+/// (for the entities not sqlb `Fields`, e.g., with a json column).
+pub async fn list_page_columns<'a, MC, E>(
 	_ctx: &Ctx,
 	mm: &ModelManager,
+	sb: SelectSqlBuilder<'a>,
+	columns: &[&'a str],
+	list_options: Option<ListOptions>,
+) -> Result<ListPage<E>>
+where
+	MC: DbBmc,
+	E: for<'r> FromRow<'r, PgRow> + Unpin + Send,
+{
+	let list_options = list_options.unwrap_or_default();
+	let (limit, offset) = list_options.limit_offset();
+	let mut order_bys = list_options.order_bys::<MC>()?;
+	if !order_bys.iter().any(|o| o.trim_start_matches('!') == "id") {
+		order_bys.push("id");
+	}
+	let cursor = list_options
+		.cursor
+		.as_deref()
+		.map(|cursor| ListCursor::decode(cursor, MC::TABLE, &order_bys))
+		.transpose()?;
+
+	// -- The sort keys, as (column, descending).
+	let keys: Vec<(&str, bool)> = order_bys
+		.iter()
+		.map(|order_by| match order_by.strip_prefix('!') {
+			Some(column) => (column, true),
+			None => (*order_by, false),
+		})
+		.collect();
+
+	// -- The filtered rows (with the key columns), as the `l` subquery.
+	let mut columns = columns.to_vec();
+	for (column, _) in keys.iter() {
+		if !columns.contains(column) {
+			columns.push(column);
+		}
+	}
+	let sb = and_where_not_deleted::<MC, _>(sb.table(MC::TABLE).columns(&columns));
+
+	// -- Build the sql.
+	// Note: The cursor keys are cast to the table columns types by
+	//       `jsonb_populate_record` (bound after the `sb` values).
+	let keys_json: Vec<String> = keys
+		.iter()
+		.map(|(column, _)| format!(r#"'{column}', l."{column}""#))
+		.collect();
+	let order_sql: Vec<String> = keys
+		.iter()
+		.map(|(column, desc)| {
+			format!(r#"l."{column}"{}"#, if *desc { " DESC" } else { "" })
+		})
+		.collect();
+	let mut sql = format!(
+		r#"SELECT l.*, jsonb_build_object({}) AS {LIST_PAGE_KEYS} FROM ({}) AS l"#,
+		keys_json.join(", "),
+		sb.sql()
+	);
+	if cursor.is_some() {
+		sql.push_str(&format!(
+			r#", jsonb_populate_record(NULL::"{}", ${}) AS c WHERE {}"#,
+			MC::TABLE,
+			sb.vals().count() + 1,
+			keyset_after_sql(&keys)
+		));
+	}
+	sql.push_str(&format!(
+		" ORDER BY {} LIMIT {}",
+		order_sql.join(", "),
+		limit + 1
+	));
+	if cursor.is_none() {
+		sql.push_str(&format!(" OFFSET {offset}"));
+	}
+
+	// -- Bind & Exec.
+	let mut query = sqlx::query(&sql);
+	for val in sb.vals() {
+		query = val.bind_query(query);
+	}
+	if let Some(cursor) = cursor {
+		query = query.bind(Json(cursor.keys));
+	}
+	let mut rows = query.fetch_all(&mut *mm.db().await?).await?;
+
+	// -- The page, and the cursor of its last row (if more rows).
+	let has_more = rows.len() as i64 > limit;
+	rows.truncate(limit as usize);
+	let next_cursor = match rows.last() {
+		Some(last_row) if has_more => {
+			let Json(keys): Json<Value> = last_row.try_get(LIST_PAGE_KEYS)?;
+			let cursor = ListCursor {
+				entity: MC::TABLE.to_string(),
+				order_bys: order_bys.iter().map(|o| o.to_string()).collect(),
+				keys,
+			};
+			Some(cursor.encode()?)
+		}
+		_ => None,
+	};
+	let items = rows
+		.iter()
+		.map(E::from_row)
+		.collect::<core::result::Result<_, _>>()?;
+
+	Ok(ListPage {
+		items,
+		next_cursor,
+		has_more,
+	})
+}
+
+/// The sql condition of the `l` rows after the `c` cursor row, in the order
+/// of the sort `keys` (i.e., greater on a key, and equal on the keys before).
+fn keyset_after_sql(keys: &[(&str, bool)]) -> String {
+	let afters: Vec<String> = (0..keys.len())
+		.map(|idx| {
+			let mut conds: Vec<String> = keys[..idx]
+				.iter()
+				.map(|(column, _)| {
+					format!(r#"l."{column}" IS NOT DISTINCT FROM c."{column}""#)
+				})
+				.collect();
+			let after = match keys[idx] {
+				(column, false) => format!(
+					r#"(l."{column}" > c."{column}" OR (l."{column}" IS NULL AND c."{column}" IS NOT NULL))"#
+				),
+				(column, true) => format!(
+					r#"(l."{column}" < c."{column}" OR (l."{column}" IS NOT NULL AND c."{column}" IS NULL))"#
+				),
+			};
+			conds.push(after);
+			format!("({})", conds.join(" AND "))
+		})
+		.collect();
+
+	format!("({})", afters.join(" OR "))
+}
+
+/// Update the entity with the not none fields of `data`.
+///
+/// When `MC::VERSIONED`, the version is incremented, and when `expected_version`
+/// is given and is not the current version, fails with `Error::VersionConflict`.
+/// (`expected_version` is ignored for the non versioned entities)
+pub async fn update<MC, E>(
+	ctx: &Ctx,
+	mm: &ModelManager,
 	id: i64,
 	data: E,
+	expected_version: Option<i64>,
 ) -> Result<()>
 where
 	MC: DbBmc,
 	E: HasFields,
 {
-	let db = mm.db();
+	update_fields::<MC>(ctx, mm, id, data.not_none_fields(), expected_version).await
+}
 
-	let fields = data.not_none_fields();
+/// Update the entity with the given `fields` (see `update`), e.g., with the
+/// `all_fields` of the data to set its none fields to null.
+pub async fn update_fields<MC>(
+	ctx: &Ctx,
+	mm: &ModelManager,
+	id: i64,
+	fields: Vec<Field<'_>>,
+	expected_version: Option<i64>,
+) -> Result<()>
+where
+	MC: DbBmc,
+{
+	let mut fields = fields;
+	let mut sb = sqlb::update().table(MC::TABLE).and_where("id", "=", id);
+
+	if MC::VERSIONED {
+		// -- Check the version before recording the revision.
+		let version = get_version::<MC>(mm, id).await?;
+		check_version::<MC>(id, expected_version, version)?;
+
+		RevisionBmc::record::<MC>(ctx, mm, &[id]).await?;
+
+		// Note: Matching the read version, so a concurrent update in between
+		//       fails as a conflict rather than being overwritten.
+		sb = sb.and_where(VERSION, "=", version);
+		fields.push((VERSION, Raw("version + 1")).into());
+	}
+
+	let sb = sb.data(fields);
+	let count = and_where_not_deleted::<MC, _>(sb)
+		.exec(&mut *mm.db().await?)
+		.await?;
+
+	if count > 0 {
+		emit_changes::<MC>(ctx, mm, &[id], ChangeKind::Updated).await?;
+		return Ok(());
+	}
+
+	if MC::VERSIONED {
+		let version = get_version::<MC>(mm, id).await?;
+		Err(Error::VersionConflict {
+			entity: MC::TABLE,
+			id,
+			expected: expected_version,
+			current: version,
+		})
+	} else {
+		Err(Error::EntityNotFound {
+			entity: MC::TABLE,
+			id,
+		})
+	}
+}
+
+/// Delete the entity, or move it to the trash if `MC::SOFT_DELETE`.
+pub async fn delete<MC>(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()>
+where
+	MC: DbBmc,
+{
+	let count = if MC::SOFT_DELETE {
+		sqlb::update()
+			.table(MC::TABLE)
+			.and_where("id", "=", id)
+			.and_where(DELETED_AT, "IS NOT DISTINCT FROM", None::<OffsetDateTime>)
+			.data(vec![(DELETED_AT, now_utc()).into()])
+			.exec(&mut *mm.db().await?)
+			.await?
+	} else {
+		sqlb::delete()
+			.table(MC::TABLE)
+			.and_where("id", "=", id)
+			.exec(&mut *mm.db().await?)
+			.await?
+	};
+
+	if count == 0 {
+		Err(Error::EntityNotFound {
+			entity: MC::TABLE,
+			id,
+		})
+	} else {
+		emit_changes::<MC>(ctx, mm, &[id], ChangeKind::Deleted).await?;
+		Ok(())
+	}
+}
+
+// region:    --- Bulk
+
+/// Max items of a bulk operation (e.g., `create_many` binds all the fields
+/// of all the items, and Postgres allows at most 65,535 bind parameters).
+pub const BULK_MAX_ITEMS: usize = 1_000;
+
+/// Fails with `Error::BulkTooManyItems` above `BULK_MAX_ITEMS`.
+pub(in crate::model) fn check_bulk_count(count: usize) -> Result<()> {
+	if count > BULK_MAX_ITEMS {
+		return Err(Error::BulkTooManyItems {
+			max: BULK_MAX_ITEMS,
+			count,
+		});
+	}
+
+	Ok(())
+}
+
+/// Insert all the entities in one statement, returning their ids (in order).
+///
+/// Note: All the `E` fields are inserted (None as NULL rather than the column
+///       default), so that all the rows have the same columns.
+pub async fn create_many<MC, E>(
+	ctx: &Ctx,
+	mm: &ModelManager,
+	data: Vec<E>,
+) -> Result<Vec<i64>>
+where
+	MC: DbBmc,
+	E: HasFields,
+{
+	check_bulk_count(data.len())?;
+
+	let rows: Vec<Vec<Field>> = data.into_iter().map(|e| e.all_fields()).collect();
+	let Some(first_row) = rows.first() else {
+		return Ok(Vec::new());
+	};
+
+	// -- Build the sql.
+	let columns: Vec<String> = first_row
+		.iter()
+		.map(|f| format!(r#""{}""#, f.name))
+		.collect();
+	let mut idx = 0;
+	let values: Vec<String> = rows
+		.iter()
+		.map(|row| {
+			let params: Vec<String> = row
+				.iter()
+				.map(|_| {
+					idx += 1;
+					format!("${idx}")
+				})
+				.collect();
+			format!("({})", params.join(", "))
+		})
+		.collect();
+	let sql = format!(
+		r#"INSERT INTO "{}" ({}) VALUES {} RETURNING id"#,
+		MC::TABLE,
+		columns.join(", "),
+		values.join(", ")
+	);
+
+	// -- Bind & Exec.
+	let mut query = sqlx::query(&sql);
+	for field in rows.iter().flatten() {
+		query = field.value.bind_query(query);
+	}
+	let rows = query.fetch_all(&mut *mm.db().await?).await?;
+
+	let ids: Vec<i64> = rows
+		.iter()
+		.map(|row| row.try_get("id"))
+		.collect::<core::result::Result<_, _>>()?;
+
+	emit_changes::<MC>(ctx, mm, &ids, ChangeKind::Created).await?;
+
+	Ok(ids)
+}
+
+/// Update the entities of `ids` with the same (not none) fields of `data`,
+/// in one statement. Returns the updated ids (missing ones are skipped).
+///
+/// When `MC::VERSIONED`, the versions are incremented and the revisions recorded
+/// (no `expected_version` here, see `base::update` for it).
+pub async fn update_many<MC, E>(
+	ctx: &Ctx,
+	mm: &ModelManager,
+	ids: &[i64],
+	data: E,
+) -> Result<Vec<i64>>
+where
+	MC: DbBmc,
+	E: HasFields,
+{
+	check_bulk_count(ids.len())?;
+
+	let mut fields = data.not_none_fields();
+
+	if MC::VERSIONED {
+		RevisionBmc::record::<MC>(ctx, mm, ids).await?;
+		fields.push((VERSION, Raw("version + 1")).into());
+	}
+
+	// -- Build the sql.
+	let mut idx = 0;
+	let sets: Vec<String> = fields
+		.iter()
+		.map(|f| match f.value.raw() {
+			Some(raw) => format!(r#""{}" = {raw}"#, f.name),
+			None => {
+				idx += 1;
+				format!(r#""{}" = ${idx}"#, f.name)
+			}
+		})
+		.collect();
+	let sql = format!(
+		r#"UPDATE "{}" SET {} WHERE id = ANY(${}){} RETURNING id"#,
+		MC::TABLE,
+		sets.join(", "),
+		idx + 1,
+		and_not_deleted_sql::<MC>()
+	);
+
+	// -- Bind & Exec.
+	// Note: The `Raw` values do not bind anything.
+	let mut query = sqlx::query(&sql);
+	for field in fields.iter() {
+		query = field.value.bind_query(query);
+	}
+	let query = query.bind(ids);
+	let rows = query.fetch_all(&mut *mm.db().await?).await?;
+
+	let ids: Vec<i64> = rows
+		.iter()
+		.map(|row| row.try_get("id"))
+		.collect::<core::result::Result<_, _>>()?;
+
+	emit_changes::<MC>(ctx, mm, &ids, ChangeKind::Updated).await?;
+
+	Ok(ids)
+}
+
+/// Delete the entities of `ids` (or move them to the trash if `MC::SOFT_DELETE`),
+/// in one statement. Returns the deleted ids (missing ones are skipped).
+pub async fn delete_many<MC>(
+	ctx: &Ctx,
+	mm: &ModelManager,
+	ids: &[i64],
+) -> Result<Vec<i64>>
+where
+	MC: DbBmc,
+{
+	check_bulk_count(ids.len())?;
+
+	let ids: Vec<(i64,)> = if MC::SOFT_DELETE {
+		let sql = format!(
+			r#"UPDATE "{}" SET {DELETED_AT} = $1 WHERE id = ANY($2){} RETURNING id"#,
+			MC::TABLE,
+			and_not_deleted_sql::<MC>()
+		);
+		sqlx::query_as(&sql)
+			.bind(now_utc())
+			.bind(ids)
+			.fetch_all(&mut *mm.db().await?)
+			.await?
+	} else {
+		let sql = format!(
+			r#"DELETE FROM "{}" WHERE id = ANY($1) RETURNING id"#,
+			MC::TABLE
+		);
+		sqlx::query_as(&sql)
+			.bind(ids)
+			.fetch_all(&mut *mm.db().await?)
+			.await?
+	};
+
+	let ids: Vec<i64> = ids.into_iter().map(|(id,)| id).collect();
+
+	emit_changes::<MC>(ctx, mm, &ids, ChangeKind::Deleted).await?;
+
+	Ok(ids)
+}
+
+/// Returns the per id results of a bulk operation, from the ids it applied to
+/// (`Error::EntityNotFound` for the others).
+pub(in crate::model) fn results_by_ids<MC>(
+	ids: &[i64],
+	done_ids: &[i64],
+) -> Vec<Result<i64>>
+where
+	MC: DbBmc,
+{
+	ids.iter()
+		.map(|&id| {
+			if done_ids.contains(&id) {
+				Ok(id)
+			} else {
+				Err(Error::EntityNotFound {
+					entity: MC::TABLE,
+					id,
+				})
+			}
+		})
+		.collect()
+}
+
+// endregion: --- Bulk
+
+// region:    --- Trash (for MC::SOFT_DELETE)
+
+/// List the (soft) deleted entities, most recently deleted first.
+pub async fn list_deleted<MC, E>(_ctx: &Ctx, mm: &ModelManager) -> Result<Vec<E>>
+where
+	MC: DbBmc,
+	E: for<'r> FromRow<'r, PgRow> + Unpin + Send,
+	E: HasFields,
+{
+	let entities: Vec<E> = sqlb::select()
+		.table(MC::TABLE)
+		.columns(E::field_names())
+		.and_where(DELETED_AT, "IS DISTINCT FROM", None::<OffsetDateTime>)
+		.order_bys(&["!deleted_at", "id"])
+		.fetch_all(&mut *mm.db().await?)
+		.await?;
+
+	Ok(entities)
+}
+
+/// Restore a (soft) deleted entity from the trash.
+pub async fn restore<MC>(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()>
+where
+	MC: DbBmc,
+{
 	let count = sqlb::update()
 		.table(MC::TABLE)
 		.and_where("id", "=", id)
-		.data(fields)
-		.exec(db)
+		.and_where(DELETED_AT, "IS DISTINCT FROM", None::<OffsetDateTime>)
+		.data(vec![(DELETED_AT, None::<OffsetDateTime>).into()])
+		.exec(&mut *mm.db().await?)
 		.await?;
 
 	if count == 0 {
@@ -93,20 +702,21 @@ where
 			id,
 		})
 	} else {
+		emit_changes::<MC>(ctx, mm, &[id], ChangeKind::Restored).await?;
 		Ok(())
 	}
 }
 
-pub async fn delete<MC>(_ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()>
+/// Permanently delete an entity that is in the trash.
+pub async fn purge<MC>(_ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()>
 where
 	MC: DbBmc,
 {
-	let db = mm.db();
-
 	let count = sqlb::delete()
 		.table(MC::TABLE)
 		.and_where("id", "=", id)
-		.exec(db)
+		.and_where(DELETED_AT, "IS DISTINCT FROM", None::<OffsetDateTime>)
+		.exec(&mut *mm.db().await?)
 		.await?;
 
 	if count == 0 {
@@ -118,3 +728,127 @@ where
 		Ok(())
 	}
 }
+
+/// Permanently delete the entities deleted before `deleted_before`.
+/// Returns the number of purged entities.
+pub async fn purge_deleted_before<MC>(
+	_ctx: &Ctx,
+	mm: &ModelManager,
+	deleted_before: OffsetDateTime,
+) -> Result<u64>
+where
+	MC: DbBmc,
+{
+	let count = sqlb::delete()
+		.table(MC::TABLE)
+		.and_where(DELETED_AT, "<", deleted_before)
+		.exec(&mut *mm.db().await?)
+		.await?;
+
+	Ok(count)
+}
+
+// region:    --- Change Events (for MC::CHANGE_EVENTS)
+
+/// Emit the change events of the entities of `ids` (when `MC::CHANGE_EVENTS`).
+///
+/// Note: The purges emit no events, as only the trashed entities are purged.
+pub(in crate::model) async fn emit_changes<MC>(
+	ctx: &Ctx,
+	mm: &ModelManager,
+	ids: &[i64],
+	kind: ChangeKind,
+) -> Result<()>
+where
+	MC: DbBmc,
+{
+	if !MC::CHANGE_EVENTS || ids.is_empty() {
+		return Ok(());
+	}
+
+	let changes = ids
+		.iter()
+		.map(|&id| Change {
+			entity: MC::TABLE,
+			id,
+			kind,
+			actor_id: ctx.user_id(),
+		})
+		.collect();
+	mm.emit_changes(changes).await
+}
+
+// endregion: --- Change Events (for MC::CHANGE_EVENTS)
+
+// region:    --- Version (for MC::VERSIONED)
+
+/// Returns the current version of the entity (EntityNotFound if not found).
+async fn get_version<MC>(mm: &ModelManager, id: i64) -> Result<i64>
+where
+	MC: DbBmc,
+{
+	let sb = sqlb::select()
+		.table(MC::TABLE)
+		.columns(&[VERSION])
+		.and_where("id", "=", id);
+	let (version,) = and_where_not_deleted::<MC, _>(sb)
+		.fetch_optional::<_, (i64,)>(&mut *mm.db().await?)
+		.await?
+		.ok_or(Error::EntityNotFound {
+			entity: MC::TABLE,
+			id,
+		})?;
+
+	Ok(version)
+}
+
+pub(in crate::model) fn check_version<MC>(
+	id: i64,
+	expected: Option<i64>,
+	current: i64,
+) -> Result<()>
+where
+	MC: DbBmc,
+{
+	match expected {
+		Some(expected) if expected != current => Err(Error::VersionConflict {
+			entity: MC::TABLE,
+			id,
+			expected: Some(expected),
+			current,
+		}),
+		_ => Ok(()),
+	}
+}
+
+// endregion: --- Version (for MC::VERSIONED)
+
+/// Restrict the query to the rows not in the trash (when `MC::SOFT_DELETE`).
+///
+/// Note: sqlb where clauses always bind a value, hence the
+///       `IS NOT DISTINCT FROM NULL` rather than `IS NULL`.
+pub(in crate::model) fn and_where_not_deleted<'a, MC, W>(sb: W) -> W
+where
+	MC: DbBmc,
+	W: Whereable<'a>,
+{
+	if MC::SOFT_DELETE {
+		sb.and_where(DELETED_AT, "IS NOT DISTINCT FROM", None::<OffsetDateTime>)
+	} else {
+		sb
+	}
+}
+
+/// The raw sql condition of `and_where_not_deleted` (for the raw sql queries).
+pub(in crate::model) fn and_not_deleted_sql<MC>() -> &'static str
+where
+	MC: DbBmc,
+{
+	if MC::SOFT_DELETE {
+		" AND deleted_at IS NULL"
+	} else {
+		""
+	}
+}
+
+// endregion: --- Trash (for MC::SOFT_DELETE)
@@ -0,0 +1,426 @@
+use crate::ctx::Ctx;
+use crate::model::base::{self, DbBmc, ListOptions, ListPage};
+use crate::model::notification::NotificationBmc;
+use crate::model::task::TaskBmc;
+use crate::model::ModelManager;
+use crate::model::{Error, Result};
+use crate::utils::time::{now_utc, Rfc3339, Rfc3339Schema};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use sqlb::Fields;
+use sqlx::FromRow;
+use std::collections::HashMap;
+use time::OffsetDateTime;
+
+// region:    --- Comment Types
+#[serde_as]
+#[derive(Debug, Clone, Fields, FromRow, Serialize, JsonSchema)]
+pub struct Comment {
+	pub id: i64,
+	pub task_id: i64,
+	pub author_id: i64,
+
+	pub body: String,
+
+	#[serde_as(as = "Rfc3339")]
+	#[schemars(with = "Rfc3339Schema")]
+	pub ctime: OffsetDateTime,
+	#[serde_as(as = "Option<Rfc3339>")]
+	#[schemars(with = "Option<Rfc3339Schema>")]
+	pub mtime: Option<OffsetDateTime>, // None if never edited.
+}
+
+#[derive(Clone, Deserialize, JsonSchema)]
+pub struct CommentForCreate {
+	pub task_id: i64,
+	pub body: String,
+}
+
+#[derive(Fields)]
+struct CommentForInsert {
+	task_id: i64,
+	author_id: i64,
+	body: String,
+}
+
+#[derive(Clone, Deserialize, JsonSchema)]
+pub struct CommentForUpdate {
+	pub body: String,
+}
+
+#[derive(Fields)]
+struct CommentForEdit {
+	body: String,
+	mtime: OffsetDateTime,
+}
+// endregion: --- Comment Types
+
+// region:    --- CommentBmc
+pub struct CommentBmc;
+
+impl DbBmc for CommentBmc {
+	const TABLE: &'static str = "comment";
+	const CHANGE_EVENTS: bool = true;
+}
+
+impl CommentBmc {
+	/// Create a comment on a task, authored by the ctx user.
+	pub async fn create(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		comment_c: CommentForCreate,
+	) -> Result<i64> {
+		let CommentForCreate { task_id, body } = comment_c;
+
+		// Make sure the task exists (proper EntityNotFound rather than FK error).
+		let task = TaskBmc::get(ctx, mm, task_id).await?;
+
+		let comment_fi = CommentForInsert {
+			task_id,
+			author_id: ctx.user_id(),
+			body,
+		};
+
+		let id = base::create::<Self, _>(ctx, mm, comment_fi).await?;
+
+		NotificationBmc::notify_task_commented(ctx, mm, &task, id).await?;
+
+		Ok(id)
+	}
+
+	pub async fn get(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<Comment> {
+		base::get::<Self, _>(ctx, mm, id).await
+	}
+
+	/// List the comments of a task, oldest first
+	/// (without cursor, see `list_page_by_task` for the cursors).
+	pub async fn list_by_task(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		task_id: i64,
+		list_options: Option<ListOptions>,
+	) -> Result<Vec<Comment>> {
+		if let Some(list_options) = &list_options {
+			list_options.check_no_cursor()?;
+		}
+		let page = Self::list_page_by_task(ctx, mm, task_id, list_options).await?;
+
+		Ok(page.items)
+	}
+
+	/// List a page of the comments of a task, oldest first
+	/// (see `base::list_page` for the cursors).
+	pub async fn list_page_by_task(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		task_id: i64,
+		list_options: Option<ListOptions>,
+	) -> Result<ListPage<Comment>> {
+		let sb = sqlb::select().and_where("task_id", "=", task_id);
+
+		base::list_page::<Self, _>(ctx, mm, sb, list_options).await
+	}
+
+	/// Returns the number of comments per task id, in one query.
+	/// (task ids without comments are absent from the map)
+	pub async fn count_by_task_ids(
+		_ctx: &Ctx,
+		mm: &ModelManager,
+		task_ids: &[i64],
+	) -> Result<HashMap<i64, i64>> {
+		let counts: Vec<(i64, i64)> = sqlx::query_as(
+			"SELECT task_id, count(*) FROM comment \
+			 WHERE task_id = ANY($1) GROUP BY task_id",
+		)
+		.bind(task_ids)
+		.fetch_all(&mut *mm.db().await?)
+		.await?;
+
+		Ok(counts.into_iter().collect())
+	}
+
+	/// Edit the comment body. Only the author can edit its comment.
+	pub async fn update(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		id: i64,
+		comment_u: CommentForUpdate,
+	) -> Result<()> {
+		Self::check_author(ctx, mm, id).await?;
+
+		let comment_fe = CommentForEdit {
+			body: comment_u.body,
+			mtime: now_utc(),
+		};
+
+		base::update::<Self, _>(ctx, mm, id, comment_fe, None).await
+	}
+
+	/// Delete the comment. Only the author can delete its comment.
+	pub async fn delete(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
+		Self::check_author(ctx, mm, id).await?;
+
+		base::delete::<Self>(ctx, mm, id).await
+	}
+
+	/// Fails with `Error::AccessDenied` when the ctx user is not the author.
+	async fn check_author(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
+		let comment = Self::get(ctx, mm, id).await?;
+		if comment.author_id != ctx.user_id() {
+			return Err(Error::AccessDenied {
+				entity: Self::TABLE,
+				id,
+			});
+		}
+
+		Ok(())
+	}
+}
+// endregion: --- CommentBmc
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::_dev_utils;
+	use anyhow::Result;
+	use serial_test::serial;
+
+	#[serial]
+	#[tokio::test]
+	async fn test_create_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_body = "test_create_ok comment body";
+		let fx_task = _dev_utils::seed_tasks(&ctx, &mm, &["test_create_ok task"])
+			.await?
+			.remove(0);
+
+		// -- Exec
+		let id = CommentBmc::create(
+			&ctx,
+			&mm,
+			CommentForCreate {
+				task_id: fx_task.id,
+				body: fx_body.to_string(),
+			},
+		)
+		.await?;
+
+		// -- Check
+		let comment = CommentBmc::get(&ctx, &mm, id).await?;
+		assert_eq!(comment.body, fx_body);
+		assert_eq!(comment.task_id, fx_task.id);
+		assert_eq!(comment.author_id, ctx.user_id());
+		assert!(comment.mtime.is_none(), "mtime should be None");
+
+		// -- Clean
+		TaskBmc::delete(&ctx, &mm, fx_task.id).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_create_err_task_not_found() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+
+		// -- Exec
+		let res = CommentBmc::create(
+			&ctx,
+			&mm,
+			CommentForCreate {
+				task_id: 100,
+				body: "test_create_err_task_not_found".to_string(),
+			},
+		)
+		.await;
+
+		// -- Check
+		assert!(
+			matches!(
+				res,
+				Err(Error::EntityNotFound {
+					entity: "task",
+					id: 100
+				})
+			),
+			"EntityNotFound not matching"
+		);
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_list_by_task_ok_paginated() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_task =
+			_dev_utils::seed_tasks(&ctx, &mm, &["test_list_by_task_ok task"])
+				.await?
+				.remove(0);
+		let fx_bodies = &["comment 01", "comment 02", "comment 03"];
+		_dev_utils::seed_comments(&ctx, &mm, fx_task.id, fx_bodies).await?;
+
+		// -- Exec
+		let page = CommentBmc::list_by_task(
+			&ctx,
+			&mm,
+			fx_task.id,
+			Some(ListOptions {
+				limit: Some(2),
+				offset: Some(1),
+				..Default::default()
+			}),
+		)
+		.await?;
+
+		// -- Check
+		let bodies: Vec<&str> = page.iter().map(|c| c.body.as_str()).collect();
+		assert_eq!(bodies, &["comment 02", "comment 03"]);
+
+		let counts =
+			CommentBmc::count_by_task_ids(&ctx, &mm, &[fx_task.id, 100]).await?;
+		assert_eq!(counts.get(&fx_task.id), Some(&3));
+		assert_eq!(counts.get(&100), None);
+
+		// -- Clean
+		TaskBmc::delete(&ctx, &mm, fx_task.id).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_update_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_body_new = "test_update_ok - comment - new";
+		let fx_task = _dev_utils::seed_tasks(&ctx, &mm, &["test_update_ok task"])
+			.await?
+			.remove(0);
+		let fx_comment =
+			_dev_utils::seed_comments(&ctx, &mm, fx_task.id, &["comment"])
+				.await?
+				.remove(0);
+
+		// -- Exec
+		CommentBmc::update(
+			&ctx,
+			&mm,
+			fx_comment.id,
+			CommentForUpdate {
+				body: fx_body_new.to_string(),
+			},
+		)
+		.await?;
+
+		// -- Check
+		let comment = CommentBmc::get(&ctx, &mm, fx_comment.id).await?;
+		assert_eq!(comment.body, fx_body_new);
+		assert!(comment.mtime.is_some(), "mtime should be set");
+
+		// -- Clean
+		TaskBmc::delete(&ctx, &mm, fx_task.id).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_update_delete_err_not_author() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let root_ctx = Ctx::root_ctx();
+		let fx_user_id = _dev_utils::seed_users(
+			&root_ctx,
+			&mm,
+			&["test_update_delete_err_not_author"],
+		)
+		.await?
+		.remove(0);
+		let user_ctx = Ctx::new(fx_user_id)?;
+		let fx_task = _dev_utils::seed_tasks(&root_ctx, &mm, &["task"])
+			.await?
+			.remove(0);
+		let fx_comment =
+			_dev_utils::seed_comments(&root_ctx, &mm, fx_task.id, &["comment"])
+				.await?
+				.remove(0);
+
+		// -- Exec
+		let update_res = CommentBmc::update(
+			&user_ctx,
+			&mm,
+			fx_comment.id,
+			CommentForUpdate {
+				body: "not mine".to_string(),
+			},
+		)
+		.await;
+		let delete_res = CommentBmc::delete(&user_ctx, &mm, fx_comment.id).await;
+
+		// -- Check
+		for res in [update_res, delete_res] {
+			assert!(
+				matches!(
+					res,
+					Err(Error::AccessDenied {
+						entity: "comment",
+						..
+					})
+				),
+				"AccessDenied not matching"
+			);
+		}
+		let comment = CommentBmc::get(&root_ctx, &mm, fx_comment.id).await?;
+		assert_eq!(comment.body, "comment");
+
+		// -- Clean
+		TaskBmc::delete(&root_ctx, &mm, fx_task.id).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_purge_task_cascade() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_task = _dev_utils::seed_tasks(&ctx, &mm, &["task"])
+			.await?
+			.remove(0);
+		let fx_comment =
+			_dev_utils::seed_comments(&ctx, &mm, fx_task.id, &["comment"])
+				.await?
+				.remove(0);
+
+		// -- Exec
+		TaskBmc::delete(&ctx, &mm, fx_task.id).await?;
+		TaskBmc::purge(&ctx, &mm, fx_task.id).await?;
+
+		// -- Check
+		let res = CommentBmc::get(&ctx, &mm, fx_comment.id).await;
+		assert!(
+			matches!(
+				res,
+				Err(Error::EntityNotFound {
+					entity: "comment",
+					..
+				})
+			),
+			"EntityNotFound not matching"
+		);
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
@@ -0,0 +1,56 @@
+//! The maintenance crons, purging the old rows of the append only tables.
+
+use crate::ctx::Ctx;
+use crate::model::cron::Cron;
+use crate::model::event::ChangeEventBmc;
+use crate::model::job::{JobBmc, JobError};
+use crate::model::ModelManager;
+use async_trait::async_trait;
+use time::{Duration, OffsetDateTime};
+
+/// Retention of the change events (i.e., how long a client can resume from).
+const CHANGE_EVENT_RETENTION: Duration = Duration::days(7);
+
+/// Retention of the `done` and `dead` jobs (e.g., for the dead ones inspection).
+const FINISHED_JOB_RETENTION: Duration = Duration::days(30);
+
+pub struct PurgeChangeEvents;
+
+#[async_trait]
+impl Cron for PurgeChangeEvents {
+	const NAME: &'static str = "purge_change_events";
+	const SCHEDULE: &'static str = "15 3 * * *";
+
+	async fn run(
+		&self,
+		mm: &ModelManager,
+		now: OffsetDateTime,
+	) -> core::result::Result<String, JobError> {
+		let ctime_before = now - CHANGE_EVENT_RETENTION;
+		let count =
+			ChangeEventBmc::purge_before(&Ctx::root_ctx(), mm, ctime_before).await?;
+
+		Ok(format!("{count} change event(s) purged"))
+	}
+}
+
+pub struct PurgeFinishedJobs;
+
+#[async_trait]
+impl Cron for PurgeFinishedJobs {
+	const NAME: &'static str = "purge_finished_jobs";
+	const SCHEDULE: &'static str = "30 3 * * *";
+
+	async fn run(
+		&self,
+		mm: &ModelManager,
+		now: OffsetDateTime,
+	) -> core::result::Result<String, JobError> {
+		let mtime_before = now - FINISHED_JOB_RETENTION;
+		let count =
+			JobBmc::purge_finished_before(&Ctx::root_ctx(), mm, mtime_before)
+				.await?;
+
+		Ok(format!("{count} job(s) purged"))
+	}
+}
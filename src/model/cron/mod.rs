@@ -0,0 +1,169 @@
+//! Scheduled (cron) background tasks (e.g., purging the trash), defined in code
+//! with a cron expression (see `crate::cron`), and run by the scheduler
+//! (see `scheduler`).
+//!
+//! Design:
+//!
+//! - A cron implements `Cron` (its `NAME`, `SCHEDULE`, and its `run`), and is
+//!   registered in the `Crons` of the scheduler (see `app_crons`).
+//! - All the app instances run the scheduler, but a due cron only runs on one of
+//!   them at a time, under a Postgres advisory lock.
+//! - The last run of each cron (and its next due time) is recorded in the
+//!   `cron_run` table (see `CronRunBmc`).
+//! - The scheduler takes its time from a `Clock`, so the tests can run the due
+//!   crons of a fake clock (see `Crons::run_due`).
+//!
+
+// region:    --- Modules
+
+mod maintenance;
+mod scheduler;
+
+pub use self::scheduler::Crons;
+
+use crate::ctx::Ctx;
+use crate::model::job::JobError;
+use crate::model::notification::DueSoonReminders;
+use crate::model::trash::PurgeTrash;
+use crate::model::user::UserBmc;
+use crate::model::{ModelManager, Result};
+use crate::utils::time::{Rfc3339, Rfc3339Schema};
+use async_trait::async_trait;
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_with::serde_as;
+use sqlx::FromRow;
+use time::OffsetDateTime;
+
+// endregion: --- Modules
+
+/// The crons of the app (i.e., run by the scheduler started from `main`).
+pub fn app_crons() -> Crons {
+	Crons::new()
+		.register(PurgeTrash)
+		.register(maintenance::PurgeChangeEvents)
+		.register(maintenance::PurgeFinishedJobs)
+		.register(DueSoonReminders)
+}
+
+// region:    --- Cron Types
+
+/// A scheduled background task.
+#[async_trait]
+pub trait Cron: Send + Sync + 'static {
+	/// The `cron_run.name` of this cron (e.g., `purge_trash`), unique per cron.
+	const NAME: &'static str;
+
+	/// When the cron is due (e.g., `0 3 * * *`), in UTC.
+	const SCHEDULE: &'static str;
+
+	/// Run as of `now` (of the scheduler clock), returning the summary of the run
+	/// (e.g., `3 task(s) purged`).
+	async fn run(
+		&self,
+		mm: &ModelManager,
+		now: OffsetDateTime,
+	) -> core::result::Result<String, JobError>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "cron_status", rename_all = "snake_case")]
+pub enum CronStatus {
+	Running,
+	Ok,
+	Failed,
+}
+
+/// The last run status of a cron.
+#[serde_as]
+#[derive(Debug, Clone, FromRow, Serialize, JsonSchema)]
+pub struct CronRun {
+	pub name: String,
+	pub schedule: String,
+	/// None when the schedule never matches (anymore).
+	#[serde_as(as = "Option<Rfc3339>")]
+	#[schemars(with = "Option<Rfc3339Schema>")]
+	pub next_run_at: Option<OffsetDateTime>,
+
+	/// None before the first run.
+	pub last_status: Option<CronStatus>,
+	#[serde_as(as = "Option<Rfc3339>")]
+	#[schemars(with = "Option<Rfc3339Schema>")]
+	pub last_started_at: Option<OffsetDateTime>,
+	#[serde_as(as = "Option<Rfc3339>")]
+	#[schemars(with = "Option<Rfc3339Schema>")]
+	pub last_finished_at: Option<OffsetDateTime>,
+	/// The run summary, or its error.
+	pub last_message: Option<String>,
+}
+
+// endregion: --- Cron Types
+
+// region:    --- CronRunBmc
+
+pub struct CronRunBmc;
+
+impl CronRunBmc {
+	/// The crons last run status (admin only).
+	pub async fn list(ctx: &Ctx, mm: &ModelManager) -> Result<Vec<CronRun>> {
+		UserBmc::require_admin(ctx, mm).await?;
+
+		Self::list_unchecked(mm).await
+	}
+
+	async fn list_unchecked(mm: &ModelManager) -> Result<Vec<CronRun>> {
+		let cron_runs = sqlx::query_as(
+			"SELECT name, schedule, next_run_at, last_status, last_started_at,
+			        last_finished_at, last_message
+			 FROM cron_run ORDER BY name",
+		)
+		.fetch_all(&mut *mm.db().await?)
+		.await?;
+
+		Ok(cron_runs)
+	}
+}
+
+// endregion: --- CronRunBmc
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::_dev_utils;
+	use crate::model::Error;
+	use anyhow::Result;
+	use serial_test::serial;
+
+	#[serial]
+	#[tokio::test]
+	async fn test_list_err_admin_required() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let root_ctx = Ctx::root_ctx();
+		let user_id = _dev_utils::seed_users(
+			&root_ctx,
+			&mm,
+			&["test_list_err_admin_required-user-01"],
+		)
+		.await?[0];
+		let ctx = Ctx::new(user_id)?;
+
+		// -- Exec
+		let res = CronRunBmc::list(&ctx, &mm).await;
+
+		// -- Check
+		assert!(
+			matches!(res, Err(Error::AdminRequired { user_id: id }) if id == user_id),
+			"Should have been AdminRequired, was {res:?}"
+		);
+		CronRunBmc::list(&root_ctx, &mm).await?;
+
+		// -- Clean
+		_dev_utils::clean_users(&root_ctx, &mm, &[user_id]).await?;
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
@@ -0,0 +1,390 @@
+//! The cron scheduler, running the due crons of all the registered ones.
+//!
+//! A due cron runs under a session advisory lock (on a connection of its own,
+//! so that the cron can use the pool), the `cron_run` row being claimed again under
+//! the lock (so that a cron just run by another instance does not run twice).
+//!
+//! The missed runs (e.g., while no instance was up) are not caught up, the next
+//! due time being the next one after the run.
+//!
+
+use crate::config;
+use crate::cron::CronExpr;
+use crate::model::cron::{Cron, CronStatus};
+use crate::model::job::JobError;
+use crate::model::{ModelManager, Result};
+use crate::utils::time::{Clock, SystemClock};
+use async_trait::async_trait;
+use sqlx::{Connection, PgConnection};
+use std::sync::Arc;
+use std::time::Duration;
+use time::OffsetDateTime;
+use tracing::{debug, error};
+
+/// Wait between two looks for the due crons.
+const TICK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Run time after which a cron run is deemed failed.
+const RUN_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+
+/// The first key of the cron advisory locks (the second being the cron name hash).
+const CRON_LOCK_CLASS: i32 = 0x0c50;
+
+/// The crons run by the scheduler.
+#[derive(Clone, Default)]
+pub struct Crons {
+	crons: Vec<ScheduledCron>,
+}
+
+#[derive(Clone)]
+struct ScheduledCron {
+	name: &'static str,
+	expr: CronExpr,
+	runner: Arc<dyn CronRunner>,
+}
+
+impl Crons {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Panics when `C::SCHEDULE` is not a valid cron expression.
+	pub fn register<C: Cron>(mut self, cron: C) -> Self {
+		let expr = C::SCHEDULE.parse().unwrap_or_else(|ex| {
+			panic!("Invalid cron schedule '{}' - {ex:?}", C::SCHEDULE)
+		});
+
+		self.crons.push(ScheduledCron {
+			name: C::NAME,
+			expr,
+			runner: Arc::new(cron),
+		});
+		self
+	}
+
+	/// Start the scheduler, running the due crons until the process ends.
+	pub fn start(self, mm: ModelManager) {
+		tokio::spawn(async move {
+			loop {
+				if let Err(ex) = self.run_due(&mm, &SystemClock).await {
+					error!("{:<12} - run_due - {ex:?}", "CRONS");
+				}
+
+				tokio::time::sleep(TICK_INTERVAL).await;
+			}
+		});
+	}
+
+	/// Run the crons due per the `clock` (but the ones running on another instance),
+	/// returning the names of the ones run.
+	pub async fn run_due(
+		&self,
+		mm: &ModelManager,
+		clock: &dyn Clock,
+	) -> Result<Vec<&'static str>> {
+		let now = clock.now_utc();
+		let mut lock_conn: Option<PgConnection> = None;
+		let mut ran = Vec::new();
+
+		for cron in self.crons.iter() {
+			let next_run_at = schedule(mm, cron, now).await?;
+			if !next_run_at.is_some_and(|next_run_at| next_run_at <= now) {
+				continue;
+			}
+
+			let lock_conn = match lock_conn.as_mut() {
+				Some(lock_conn) => lock_conn,
+				None => {
+					lock_conn.insert(PgConnection::connect(&config().DB_URL).await?)
+				}
+			};
+			if run_locked(mm, lock_conn, cron, clock).await? {
+				ran.push(cron.name);
+			}
+		}
+
+		Ok(ran)
+	}
+}
+
+/// Returns the next due time of the cron, (re)scheduling it when new, or when its
+/// schedule changed.
+async fn schedule(
+	mm: &ModelManager,
+	cron: &ScheduledCron,
+	now: OffsetDateTime,
+) -> Result<Option<OffsetDateTime>> {
+	let schedule = cron.expr.to_string();
+
+	let current: Option<(String, Option<OffsetDateTime>)> =
+		sqlx::query_as("SELECT schedule, next_run_at FROM cron_run WHERE name = $1")
+			.bind(cron.name)
+			.fetch_optional(&mut *mm.db().await?)
+			.await?;
+	if let Some((current_schedule, next_run_at)) = current {
+		if current_schedule == schedule {
+			return Ok(next_run_at);
+		}
+	}
+
+	let (next_run_at,) = sqlx::query_as(
+		"INSERT INTO cron_run (name, schedule, next_run_at) VALUES ($1, $2, $3)
+		 ON CONFLICT (name) DO UPDATE
+		 SET schedule = EXCLUDED.schedule, next_run_at = EXCLUDED.next_run_at
+		 RETURNING next_run_at",
+	)
+	.bind(cron.name)
+	.bind(schedule)
+	.bind(cron.expr.next_after(now))
+	.fetch_one(&mut *mm.db().await?)
+	.await?;
+
+	Ok(next_run_at)
+}
+
+/// Run the cron if not locked by another instance, and still due once locked.
+async fn run_locked(
+	mm: &ModelManager,
+	lock_conn: &mut PgConnection,
+	cron: &ScheduledCron,
+	clock: &dyn Clock,
+) -> Result<bool> {
+	let (locked,): (bool,) =
+		sqlx::query_as("SELECT pg_try_advisory_lock($1, hashtext($2))")
+			.bind(CRON_LOCK_CLASS)
+			.bind(cron.name)
+			.fetch_one(&mut *lock_conn)
+			.await?;
+	if !locked {
+		debug!(
+			"{:<12} - {} running on another instance",
+			"CRONS", cron.name
+		);
+		return Ok(false);
+	}
+
+	let res = run_claimed(mm, cron, clock).await;
+
+	sqlx::query("SELECT pg_advisory_unlock($1, hashtext($2))")
+		.bind(CRON_LOCK_CLASS)
+		.bind(cron.name)
+		.execute(&mut *lock_conn)
+		.await?;
+
+	res
+}
+
+/// Claim the cron run (when still due), run it, and record its status.
+async fn run_claimed(
+	mm: &ModelManager,
+	cron: &ScheduledCron,
+	clock: &dyn Clock,
+) -> Result<bool> {
+	let now = clock.now_utc();
+
+	let claimed = sqlx::query(
+		"UPDATE cron_run SET last_status = 'running', last_started_at = $2
+		 WHERE name = $1 AND next_run_at <= $2",
+	)
+	.bind(cron.name)
+	.bind(now)
+	.execute(&mut *mm.db().await?)
+	.await?
+	.rows_affected()
+		== 1;
+	if !claimed {
+		return Ok(false);
+	}
+	debug!("{:<12} - run {}", "CRONS", cron.name);
+
+	let res = tokio::time::timeout(RUN_TIMEOUT, cron.runner.run(mm, now))
+		.await
+		.unwrap_or_else(|_| Err(JobError("Run timed out".to_string())));
+	let (status, message) = match res {
+		Ok(summary) => (CronStatus::Ok, summary),
+		Err(JobError(ex)) => {
+			error!("{:<12} - {} failed - {ex}", "CRONS", cron.name);
+			(CronStatus::Failed, ex)
+		}
+	};
+
+	let finished_at = clock.now_utc();
+	sqlx::query(
+		"UPDATE cron_run SET
+			last_status = $2, last_finished_at = $3, last_message = $4,
+			next_run_at = $5
+		 WHERE name = $1",
+	)
+	.bind(cron.name)
+	.bind(status)
+	.bind(finished_at)
+	.bind(message)
+	.bind(cron.expr.next_after(finished_at))
+	.execute(&mut *mm.db().await?)
+	.await?;
+
+	Ok(true)
+}
+
+// region:    --- CronRunner
+
+/// The `Cron::run` of a registered cron.
+#[async_trait]
+trait CronRunner: Send + Sync {
+	async fn run(
+		&self,
+		mm: &ModelManager,
+		now: OffsetDateTime,
+	) -> core::result::Result<String, JobError>;
+}
+
+#[async_trait]
+impl<C: Cron> CronRunner for C {
+	async fn run(
+		&self,
+		mm: &ModelManager,
+		now: OffsetDateTime,
+	) -> core::result::Result<String, JobError> {
+		Cron::run(self, mm, now).await
+	}
+}
+
+// endregion: --- CronRunner
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::_dev_utils;
+	use crate::model::cron::CronRunBmc;
+	use crate::utils::time::{parse_utc, FakeClock};
+	use anyhow::{Context, Result};
+	use serial_test::serial;
+	use std::sync::atomic::{AtomicUsize, Ordering};
+
+	/// Counts its runs, failing the even ones.
+	#[derive(Clone, Default)]
+	struct FxCountCron(Arc<AtomicUsize>);
+
+	#[async_trait]
+	impl Cron for FxCountCron {
+		const NAME: &'static str = "fx_count";
+		const SCHEDULE: &'static str = "0 */6 * * *";
+
+		async fn run(
+			&self,
+			_mm: &ModelManager,
+			_now: OffsetDateTime,
+		) -> core::result::Result<String, JobError> {
+			let count = self.0.fetch_add(1, Ordering::SeqCst) + 1;
+			if count % 2 == 0 {
+				Err(JobError(format!("fx failure {count}")))
+			} else {
+				Ok(format!("fx run {count}"))
+			}
+		}
+	}
+
+	async fn fx_clean_cron_runs(mm: &ModelManager) -> Result<()> {
+		sqlx::query("DELETE FROM cron_run WHERE name LIKE 'fx_%'")
+			.execute(&mut *mm.db().await?)
+			.await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_run_due_fake_clock_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		fx_clean_cron_runs(&mm).await?;
+		let fx_cron = FxCountCron::default();
+		let crons = Crons::new().register(fx_cron.clone());
+		let clock = FakeClock::new(parse_utc("2024-01-01T05:00:00Z")?);
+
+		// -- Exec & Check
+		// Scheduled at 06:00.
+		assert!(crons.run_due(&mm, &clock).await?.is_empty());
+
+		clock.advance(time::Duration::minutes(59));
+		assert!(crons.run_due(&mm, &clock).await?.is_empty());
+
+		clock.advance(time::Duration::minutes(1));
+		assert_eq!(crons.run_due(&mm, &clock).await?, vec!["fx_count"]);
+		// Next at 12:00.
+		assert!(crons.run_due(&mm, &clock).await?.is_empty());
+		let fx_run = fx_find_run(&mm).await?;
+		assert_eq!(fx_run.last_status, Some(CronStatus::Ok));
+		assert_eq!(fx_run.last_message.as_deref(), Some("fx run 1"));
+		assert_eq!(fx_run.next_run_at, Some(parse_utc("2024-01-01T12:00:00Z")?));
+
+		// Missed runs not caught up.
+		clock.advance(time::Duration::hours(13));
+		assert_eq!(crons.run_due(&mm, &clock).await?, vec!["fx_count"]);
+		let fx_run = fx_find_run(&mm).await?;
+		assert_eq!(fx_run.last_status, Some(CronStatus::Failed));
+		assert_eq!(fx_run.last_message.as_deref(), Some("fx failure 2"));
+		assert_eq!(fx_run.next_run_at, Some(parse_utc("2024-01-02T00:00:00Z")?));
+		assert_eq!(fx_cron.0.load(Ordering::SeqCst), 2);
+
+		// -- Clean
+		fx_clean_cron_runs(&mm).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_run_due_skip_locked() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		fx_clean_cron_runs(&mm).await?;
+		let fx_cron = FxCountCron::default();
+		let crons = Crons::new().register(fx_cron.clone());
+		let clock = FakeClock::new(parse_utc("2024-01-01T05:00:00Z")?);
+		crons.run_due(&mm, &clock).await?;
+		clock.advance(time::Duration::hours(1));
+		// As another instance running the cron.
+		let mut other_conn = PgConnection::connect(&config().DB_URL).await?;
+		sqlx::query("SELECT pg_advisory_lock($1, hashtext($2))")
+			.bind(CRON_LOCK_CLASS)
+			.bind("fx_count")
+			.execute(&mut other_conn)
+			.await?;
+
+		// -- Exec & Check
+		assert!(crons.run_due(&mm, &clock).await?.is_empty());
+
+		other_conn.close().await?;
+		assert_eq!(crons.run_due(&mm, &clock).await?, vec!["fx_count"]);
+		assert_eq!(fx_cron.0.load(Ordering::SeqCst), 1);
+
+		// -- Clean
+		fx_clean_cron_runs(&mm).await?;
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_app_crons_ok() -> Result<()> {
+		// -- Exec & Check
+		// Schedules valid (or panics), names unique.
+		let crons = crate::model::cron::app_crons();
+		let mut names: Vec<_> = crons.crons.iter().map(|c| c.name).collect();
+		names.sort();
+		names.dedup();
+		assert_eq!(names.len(), crons.crons.len());
+
+		Ok(())
+	}
+
+	async fn fx_find_run(mm: &ModelManager) -> Result<crate::model::cron::CronRun> {
+		CronRunBmc::list_unchecked(mm)
+			.await?
+			.into_iter()
+			.find(|run| run.name == "fx_count")
+			.context("Should have the fx_count run")
+	}
+}
+// endregion: --- Tests
@@ -0,0 +1,119 @@
+//! The opaque cursors of the keyset paginated lists (see `base::list_page`).
+//!
+//! String format: `payload_b64u.sign_b64u`, the payload being the json of the
+//! list entity, its order bys, and the sort keys of the last row of the page.
+//!
+//! The cursors are signed (HMAC-SHA256 with the `TOKEN_KEY`), so the clients
+//! cannot forge the sort keys bound in the list queries.
+//!
+
+use crate::config;
+use crate::model::{Error, Result};
+use crate::utils::b64::{b64u_decode, b64u_encode};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::Sha256;
+
+/// Prefixed to the signed content, so a cursor signature cannot be taken
+/// for another `TOKEN_KEY` signature.
+const SIGN_CONTEXT: &[u8] = b"list-cursor.";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(in crate::model) struct ListCursor {
+	pub entity: String,
+	/// The order bys of the list (sqlb format, with the `id` tie-break).
+	pub order_bys: Vec<String>,
+	/// The sort keys of the last row, by column name.
+	pub keys: Value,
+}
+
+impl ListCursor {
+	pub fn encode(&self) -> Result<String> {
+		let payload_b64u = b64u_encode(serde_json::to_vec(self)?);
+		let sign_b64u = b64u_encode(sign(&payload_b64u)?.finalize().into_bytes());
+
+		Ok(format!("{payload_b64u}.{sign_b64u}"))
+	}
+
+	/// Decode the cursor of a list, failing with `Error::ListCursorInvalid`
+	/// when badly signed, or not of this entity and order bys.
+	pub fn decode(
+		cursor: &str,
+		entity: &'static str,
+		order_bys: &[&str],
+	) -> Result<Self> {
+		let invalid = || Error::ListCursorInvalid { entity };
+
+		let (payload_b64u, sign_b64u) =
+			cursor.split_once('.').ok_or_else(invalid)?;
+		let sign_bytes = b64u_decode(sign_b64u).map_err(|_| invalid())?;
+		sign(payload_b64u)?
+			.verify_slice(&sign_bytes)
+			.map_err(|_| invalid())?;
+
+		let payload = b64u_decode(payload_b64u).map_err(|_| invalid())?;
+		let list_cursor: ListCursor =
+			serde_json::from_slice(&payload).map_err(|_| invalid())?;
+
+		if list_cursor.entity != entity || list_cursor.order_bys != order_bys {
+			return Err(invalid());
+		}
+
+		Ok(list_cursor)
+	}
+}
+
+fn sign(payload_b64u: &str) -> Result<Hmac<Sha256>> {
+	let mut hmac_sha256 = Hmac::<Sha256>::new_from_slice(&config().TOKEN_KEY)
+		.map_err(|_| Error::ListCursorSignFail)?;
+	hmac_sha256.update(SIGN_CONTEXT);
+	hmac_sha256.update(payload_b64u.as_bytes());
+
+	Ok(hmac_sha256)
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use anyhow::Result;
+	use serde_json::json;
+
+	#[test]
+	fn test_cursor_encode_decode_ok_and_tampered() -> Result<()> {
+		// -- Fixtures
+		let fx_order_bys = &["!due_date", "id"];
+		let fx_cursor = ListCursor {
+			entity: "task".to_string(),
+			order_bys: fx_order_bys.iter().map(|o| o.to_string()).collect(),
+			keys: json!({"due_date": null, "id": 1000}),
+		};
+
+		// -- Exec
+		let cursor = fx_cursor.encode()?;
+		let decoded = ListCursor::decode(&cursor, "task", fx_order_bys)?;
+		let other_order_res = ListCursor::decode(&cursor, "task", &["id"]);
+		let (_, sign_b64u) = cursor.split_once('.').unwrap();
+		let forged_payload = b64u_encode(
+			json!({"entity": "task", "order_bys": fx_order_bys, "keys": {"id": 1}})
+				.to_string(),
+		);
+		let forged_res = ListCursor::decode(
+			&format!("{forged_payload}.{sign_b64u}"),
+			"task",
+			fx_order_bys,
+		);
+
+		// -- Check
+		assert_eq!(decoded, fx_cursor);
+		assert!(matches!(
+			other_order_res,
+			Err(Error::ListCursorInvalid { .. })
+		));
+		assert!(matches!(forged_res, Err(Error::ListCursorInvalid { .. })));
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
@@ -1,5 +1,7 @@
 use crate::model::store;
 use crate::pwd;
+use crate::recurrence;
+use crate::utils::rank;
 use derive_more::From;
 use serde::Serialize;
 use serde_with::{serde_as, DisplayFromStr};
@@ -13,16 +15,75 @@ pub enum Error {
 		entity: &'static str,
 		id: i64,
 	},
+	AccessDenied {
+		entity: &'static str,
+		id: i64,
+	},
+	AdminRequired {
+		user_id: i64,
+	},
+	/// Not an orderable column of the entity list (see `ListOptions::order_bys`).
+	ListOrderByInvalid {
+		entity: &'static str,
+		order_by: String,
+	},
+	/// Not a (signed) cursor of the entity list with these order bys
+	/// (see `base::list_page`).
+	ListCursorInvalid {
+		entity: &'static str,
+	},
+	ListCursorSignFail,
+	/// A cursor for a list not paginated by keyset (see `ListOptions::cursor`).
+	ListCursorUnsupported,
+	/// More items than `base::BULK_MAX_ITEMS` for a bulk operation.
+	BulkTooManyItems {
+		max: usize,
+		count: usize,
+	},
+	/// A bulk insert returning another count of ids than of items.
+	BulkCountMismatch {
+		expected: usize,
+		count: usize,
+	},
+	/// The entity was updated since the `expected` version was read.
+	VersionConflict {
+		entity: &'static str,
+		id: i64,
+		expected: Option<i64>,
+		current: i64,
+	},
+
+	// -- Transaction
+	TxnAlreadyStarted,
+	TxnNotStarted,
+
+	// -- Events
+	EventListenerFail(String),
+
+	// -- Webhooks
+	WebhookInvalidUrl(String),
+	WebhookInvalidEvent(String),
+	WebhookSignFail,
+
+	// -- Saved Views
+	SavedViewInvalidName(String),
+	SavedViewNameTaken(String),
 
 	// -- Modules
 	#[from]
 	Pwd(pwd::Error),
 	#[from]
+	Recurrence(recurrence::Error),
+	#[from]
+	Rank(#[serde_as(as = "DisplayFromStr")] rank::Error),
+	#[from]
 	Store(store::Error),
 
 	// -- Externals
 	#[from]
 	Sqlx(#[serde_as(as = "DisplayFromStr")] sqlx::Error),
+	#[from]
+	SerdeJson(#[serde_as(as = "DisplayFromStr")] serde_json::Error),
 }
 
 // region:    --- Error Boilerplate
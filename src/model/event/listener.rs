@@ -0,0 +1,262 @@
+//! The LISTEN connection of an `EventHub`, broadcasting the change events notified
+//! by all the app instances (its own included).
+//!
+//! The connection runs on its own thread (and runtime), so it lasts as long as the
+//! hub (i.e., the last clone of its `ModelManager`), whatever the runtime the
+//! `ModelManager` was created on. It only holds a `WeakEventHub`, and stops once
+//! the hub is dropped.
+//!
+//! When the connection is lost, it reconnects (after `RECONNECT_DELAY`), then sends
+//! the events persisted in the meantime, the hub skipping the ones already sent.
+//!
+
+use crate::config;
+use crate::model::event::{
+	ChangeEvent, EventHub, WeakEventHub, NOTIFY_CHANNEL, RECENT_EVENTS_MAX,
+};
+use crate::model::{Error, Result};
+use sqlx::postgres::{PgConnectOptions, PgListener, PgPoolOptions};
+use std::str::FromStr;
+use std::thread;
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tracing::{debug, error};
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(2);
+
+/// The `application_name` of the LISTEN connections.
+const LISTENER_APP_NAME: &str = "event-listener";
+
+/// Start the LISTEN connection of the hub, returning once listening
+/// (and stopped once the hub is dropped).
+pub(super) async fn start(hub: &EventHub) -> Result<()> {
+	let (ready_tx, ready_rx) = oneshot::channel();
+	let weak_hub = hub.downgrade();
+	let mut stopped = hub.stopped();
+
+	let rt = tokio::runtime::Builder::new_current_thread()
+		.enable_all()
+		.build()
+		.map_err(|ex| Error::EventListenerFail(ex.to_string()))?;
+	thread::Builder::new()
+		.name("event-listener".to_string())
+		.spawn(move || {
+			rt.block_on(async {
+				tokio::select! {
+					_ = listen_loop(weak_hub, ready_tx) => (),
+					// Only fails (i.e., closed) once the hub is dropped.
+					_ = stopped.changed() => (),
+				}
+			});
+			debug!("{:<12} - listener stopped", "EVENTS");
+		})
+		.map_err(|ex| Error::EventListenerFail(ex.to_string()))?;
+
+	ready_rx
+		.await
+		.map_err(|ex| Error::EventListenerFail(ex.to_string()))?
+}
+
+/// Listen (again) until the connection fails, but for the first connection,
+/// which fails the `start`, until the hub is dropped.
+async fn listen_loop(hub: WeakEventHub, ready_tx: oneshot::Sender<Result<()>>) {
+	let mut ready_tx = Some(ready_tx);
+
+	loop {
+		let res = listen(&hub, &mut ready_tx).await;
+
+		match (res, ready_tx.take()) {
+			(Err(ex), Some(ready_tx)) => {
+				let _ = ready_tx.send(Err(ex));
+				return;
+			}
+			(Err(ex), None) => {
+				error!("{:<12} - listen fail (reconnecting) - {ex:?}", "EVENTS")
+			}
+			// The hub dropped.
+			(Ok(()), _) => return,
+		}
+
+		tokio::time::sleep(RECONNECT_DELAY).await;
+	}
+}
+
+/// Listen until the connection fails (an error), or the hub is dropped (Ok).
+async fn listen(
+	hub: &WeakEventHub,
+	ready_tx: &mut Option<oneshot::Sender<Result<()>>>,
+) -> Result<()> {
+	let mut listener = connect().await?;
+	listener.listen(NOTIFY_CHANNEL).await?;
+	// Note: The hub is only upgraded while used, so that it can be dropped.
+	match hub.upgrade() {
+		Some(hub) => catch_up(&hub, &mut listener).await?,
+		None => return Ok(()),
+	}
+
+	debug!("{:<12} - listening '{NOTIFY_CHANNEL}'", "EVENTS");
+	if let Some(ready_tx) = ready_tx.take() {
+		let _ = ready_tx.send(Ok(()));
+	}
+
+	loop {
+		let notification = listener.try_recv().await?;
+		let Some(hub) = hub.upgrade() else {
+			return Ok(());
+		};
+
+		match notification {
+			Some(notification) => {
+				match serde_json::from_str::<ChangeEvent>(notification.payload()) {
+					Ok(event) => hub.send(vec![event]),
+					Err(ex) => {
+						error!("{:<12} - invalid notification - {ex}", "EVENTS")
+					}
+				}
+			}
+			// Connection lost (reconnected by the next query), the events notified
+			// in the meantime are sent from the persisted ones.
+			None => {
+				debug!("{:<12} - connection lost", "EVENTS");
+				catch_up(&hub, &mut listener).await?;
+			}
+		}
+	}
+}
+
+/// New listener, on a pool of one connection (as `PgListener::connect`),
+/// named `LISTENER_APP_NAME`.
+async fn connect() -> Result<PgListener> {
+	let options = PgConnectOptions::from_str(&config().DB_URL)?
+		.application_name(LISTENER_APP_NAME);
+	let pool = PgPoolOptions::new()
+		.max_connections(1)
+		.max_lifetime(None)
+		.idle_timeout(None)
+		.connect_with(options)
+		.await?;
+
+	let mut listener = PgListener::connect_with(&pool).await?;
+	listener.ignore_pool_close_event(true);
+
+	Ok(listener)
+}
+
+/// Send the events persisted after the hub `last_seq`.
+async fn catch_up(hub: &EventHub, listener: &mut PgListener) -> Result<()> {
+	loop {
+		let events: Vec<ChangeEvent> = sqlx::query_as(
+			"SELECT id AS seq, entity, entity_id AS id, kind, actor_id
+			 FROM change_event WHERE id > $1 ORDER BY id LIMIT $2",
+		)
+		.bind(hub.last_seq())
+		.bind(RECENT_EVENTS_MAX as i64)
+		.fetch_all(&mut *listener)
+		.await?;
+
+		let caught_up = events.len() < RECENT_EVENTS_MAX;
+		hub.send(events);
+		if caught_up {
+			return Ok(());
+		}
+	}
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::_dev_utils;
+	use crate::ctx::Ctx;
+	use crate::model::event::tests::fx_next_event;
+	use crate::model::event::ChangeKind;
+	use crate::model::task::{TaskBmc, TaskForCreate};
+	use crate::model::ModelManager;
+	use anyhow::Result;
+	use serial_test::serial;
+
+	#[serial]
+	#[tokio::test]
+	async fn test_listen_two_model_managers_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		_dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		// As two app instances.
+		let mm_1 = ModelManager::new().await?;
+		let mm_2 = ModelManager::new().await?;
+		let mut events_rx_1 = mm_1.events().subscribe();
+		let mut events_rx_2 = mm_2.events().subscribe();
+		let fx_task_c = TaskForCreate {
+			title: "test_listen_two_model_managers_ok".to_string(),
+			..Default::default()
+		};
+
+		// -- Exec & Check
+		// Changed on one, received by both.
+		let id = TaskBmc::create(&ctx, &mm_1, fx_task_c).await?;
+		let event_1 = fx_next_event(&mut events_rx_1, &[id]).await?;
+		let event_2 = fx_next_event(&mut events_rx_2, &[id]).await?;
+		assert_eq!(event_1.kind, ChangeKind::Created);
+		assert_eq!(event_2.seq, event_1.seq);
+		assert_eq!(mm_2.events().last_seq(), event_1.seq);
+
+		// Listen connections lost, changed, received once reconnected.
+		sqlx::query(
+			"SELECT pg_terminate_backend(pid) FROM pg_stat_activity
+			 WHERE application_name = $1",
+		)
+		.bind(LISTENER_APP_NAME)
+		.execute(&mut *mm_1.db().await?)
+		.await?;
+		TaskBmc::delete(&ctx, &mm_2, id).await?;
+		let event_1 = fx_next_event(&mut events_rx_1, &[id]).await?;
+		let event_2 = fx_next_event(&mut events_rx_2, &[id]).await?;
+		assert_eq!(event_1.kind, ChangeKind::Deleted);
+		assert_eq!(event_2.seq, event_1.seq);
+
+		// -- Clean
+		TaskBmc::purge(&ctx, &mm_1, id).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_listen_stopped_on_drop() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let fx_listener_pids = || {
+			sqlx::query_as::<_, (i32,)>(
+				"SELECT pid FROM pg_stat_activity WHERE application_name = $1",
+			)
+			.bind(LISTENER_APP_NAME)
+		};
+		let pids = fx_listener_pids().fetch_all(&mut *mm.db().await?).await?;
+		let dropped_mm = ModelManager::new().await?;
+		let started_pids: Vec<(i32,)> = fx_listener_pids()
+			.fetch_all(&mut *mm.db().await?)
+			.await?
+			.into_iter()
+			.filter(|pid| !pids.contains(pid))
+			.collect();
+
+		// -- Exec
+		drop(dropped_mm);
+
+		// -- Check
+		assert_eq!(started_pids.len(), 1);
+		let mut stopped = false;
+		for _ in 0..50 {
+			let pids = fx_listener_pids().fetch_all(&mut *mm.db().await?).await?;
+			stopped = !pids.contains(&started_pids[0]);
+			if stopped {
+				break;
+			}
+			tokio::time::sleep(Duration::from_millis(100)).await;
+		}
+		assert!(stopped, "listener should be stopped");
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
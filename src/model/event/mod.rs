@@ -0,0 +1,581 @@
+//! Change events of the entities (e.g., a task created, updated or deleted), emitted
+//! by the `base` functions for the `DbBmc::CHANGE_EVENTS` entities, and broadcast
+//! to the subscribers of the `ModelManager` events (e.g., the `/api/ws` clients).
+//!
+//! The changes made through a transactional `ModelManager` are persisted (with
+//! their webhook deliveries) in its transaction, so only broadcast once committed
+//! (and never when rolled back).
+//!
+//! The events are persisted in the `change_event` table, its id being the event
+//! sequence number, so that a subscriber can resume after the last event it got,
+//! from the recent events kept in memory (see `EventHub::since`), or from the db
+//! (see `ChangeEventBmc::list_after`).
+//!
+//! The events are also sent with `pg_notify`, and each `ModelManager` broadcasts the
+//! ones of its LISTEN connection (see `listener`), so that the clients of all the
+//! app instances get the changes made through any of them.
+//!
+
+// region:    --- Modules
+
+mod listener;
+
+use crate::ctx::Ctx;
+use crate::model::store::Db;
+use crate::model::webhook;
+use crate::model::{ModelManager, Result};
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::{PgHasArrayType, PgTypeInfo};
+use sqlx::FromRow;
+use sqlx::PgConnection;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, Weak};
+use time::OffsetDateTime;
+use tokio::sync::{broadcast, watch};
+use tracing::error;
+
+/// Capacity of the broadcast channel (a slower subscriber lags, and can resume).
+const BROADCAST_CAPACITY: usize = 256;
+
+/// Number of recent events kept for the resumes.
+const RECENT_EVENTS_MAX: usize = 1024;
+
+/// The entities with change events (see `DbBmc::CHANGE_EVENTS`).
+const CHANGE_EVENT_ENTITIES: &[&str] = &["task", "comment"];
+
+/// The `pg_notify` channel of the change events.
+const NOTIFY_CHANNEL: &str = "change_event";
+
+/// The advisory lock of the publishes, so that the events are notified (and
+/// committed) in `seq` order across the app instances.
+const PUBLISH_LOCK_KEY: i64 = 0x6368_616e_6765; // "change"
+
+// endregion: --- Modules
+
+// region:    --- Change Types
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "change_kind", rename_all = "snake_case")]
+pub enum ChangeKind {
+	Created,
+	Updated,
+	Deleted,
+	Restored,
+}
+
+impl PgHasArrayType for ChangeKind {
+	fn array_type_info() -> PgTypeInfo {
+		PgTypeInfo::with_name("_change_kind")
+	}
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ChangeEvent {
+	pub seq: i64,
+	/// The entity table name (e.g., `task`).
+	pub entity: String,
+	pub id: i64,
+	pub kind: ChangeKind,
+	pub actor_id: i64,
+}
+
+impl ChangeEvent {
+	/// The channels of the event, the entity one and the entity id one
+	/// (e.g., `task` and `task:1000`).
+	pub fn channels(&self) -> [String; 2] {
+		[
+			self.entity.to_string(),
+			format!("{}:{}", self.entity, self.id),
+		]
+	}
+
+	/// True for the `<entity>` and `<entity>:<id>` channels of the entities with
+	/// change events (e.g., `task` or `task:1000`).
+	pub fn is_valid_channel(channel: &str) -> bool {
+		let (entity, id) = match channel.split_once(':') {
+			Some((entity, id)) => (entity, Some(id)),
+			None => (channel, None),
+		};
+
+		CHANGE_EVENT_ENTITIES.contains(&entity)
+			&& id.map_or(true, |id| id.parse::<i64>().is_ok())
+	}
+
+	/// True when the user of `ctx` may see the event.
+	///
+	/// Note: The tasks (and their comments) are shared by all the users for now,
+	///       so their events are visible to all of them.
+	pub fn is_visible_to(&self, _ctx: &Ctx) -> bool {
+		true
+	}
+}
+
+/// A change not yet published (i.e., without its sequence number).
+#[derive(Debug, Clone)]
+pub(in crate::model) struct Change {
+	pub entity: &'static str,
+	pub id: i64,
+	pub kind: ChangeKind,
+	pub actor_id: i64,
+}
+
+// endregion: --- Change Types
+
+// region:    --- EventHub
+
+/// The change events broadcaster, shared by the clones of a `ModelManager`.
+#[derive(Clone)]
+pub struct EventHub {
+	sender: broadcast::Sender<ChangeEvent>,
+	recent: Arc<Mutex<RecentEvents>>,
+	/// Never sent, but dropped with the last clone of the hub, which stops its
+	/// LISTEN connection (only holding a `WeakEventHub`, see `listener`).
+	stop: Arc<watch::Sender<()>>,
+}
+
+/// A reference to a hub not keeping it alive (see `EventHub::downgrade`).
+struct WeakEventHub {
+	sender: broadcast::Sender<ChangeEvent>,
+	recent: Weak<Mutex<RecentEvents>>,
+	stop: Weak<watch::Sender<()>>,
+}
+
+impl WeakEventHub {
+	/// The hub, unless dropped.
+	fn upgrade(&self) -> Option<EventHub> {
+		Some(EventHub {
+			sender: self.sender.clone(),
+			recent: self.recent.upgrade()?,
+			stop: self.stop.upgrade()?,
+		})
+	}
+}
+
+#[derive(Default)]
+struct RecentEvents {
+	last_seq: i64,
+	events: VecDeque<ChangeEvent>,
+}
+
+impl EventHub {
+	/// New hub, starting after the last persisted event, and broadcasting the
+	/// events of its LISTEN connection (once listening).
+	pub(in crate::model) async fn init(db: &Db) -> Result<Self> {
+		let (last_seq,): (i64,) =
+			sqlx::query_as("SELECT COALESCE(max(id), 0) FROM change_event")
+				.fetch_one(db)
+				.await?;
+
+		let hub = Self::new(last_seq);
+		listener::start(&hub).await?;
+
+		Ok(hub)
+	}
+
+	fn new(last_seq: i64) -> Self {
+		let (sender, _) = broadcast::channel(BROADCAST_CAPACITY);
+		let recent = RecentEvents {
+			last_seq,
+			events: VecDeque::new(),
+		};
+		let (stop, _) = watch::channel(());
+
+		EventHub {
+			sender,
+			recent: Arc::new(Mutex::new(recent)),
+			stop: Arc::new(stop),
+		}
+	}
+
+	fn downgrade(&self) -> WeakEventHub {
+		WeakEventHub {
+			sender: self.sender.clone(),
+			recent: Arc::downgrade(&self.recent),
+			stop: Arc::downgrade(&self.stop),
+		}
+	}
+
+	/// Changed (as closed) once the hub is dropped.
+	fn stopped(&self) -> watch::Receiver<()> {
+		self.stop.subscribe()
+	}
+
+	/// Subscribe to the events published from now on.
+	///
+	/// Note: To resume after a given event, subscribe first, then get the missed
+	///       events with `since`, and skip the received ones already in it.
+	pub fn subscribe(&self) -> broadcast::Receiver<ChangeEvent> {
+		self.sender.subscribe()
+	}
+
+	/// The sequence number of the last published event (0 if none).
+	pub fn last_seq(&self) -> i64 {
+		self.lock_recent().last_seq
+	}
+
+	/// The events published after the `seq` one (in order), or None when some
+	/// of them are not kept in memory anymore (see `ChangeEventBmc::list_after`).
+	pub fn since(&self, seq: i64) -> Option<Vec<ChangeEvent>> {
+		let recent = self.lock_recent();
+
+		if seq >= recent.last_seq {
+			return Some(Vec::new());
+		}
+		let first_kept_seq = recent.events.front()?.seq;
+		if seq + 1 < first_kept_seq {
+			return None;
+		}
+
+		Some(
+			recent
+				.events
+				.iter()
+				.filter(|event| event.seq > seq)
+				.cloned()
+				.collect(),
+		)
+	}
+
+	/// Send the events not already sent (i.e., after `last_seq`).
+	fn send(&self, events: Vec<ChangeEvent>) {
+		let mut recent = self.lock_recent();
+
+		for event in events {
+			if event.seq <= recent.last_seq {
+				continue;
+			}
+			recent.last_seq = event.seq;
+
+			if recent.events.len() == RECENT_EVENTS_MAX {
+				recent.events.pop_front();
+			}
+			recent.events.push_back(event.clone());
+
+			// No receivers is not an error.
+			let _ = self.sender.send(event);
+		}
+	}
+
+	fn lock_recent(&self) -> std::sync::MutexGuard<'_, RecentEvents> {
+		// A panic while holding the lock cannot leave the events inconsistent.
+		self.recent
+			.lock()
+			.unwrap_or_else(|poisoned| poisoned.into_inner())
+	}
+}
+
+// endregion: --- EventHub
+
+/// Publish the changes in a transaction of their own (see `persist`).
+async fn publish(db: &Db, changes: Vec<Change>) -> Result<()> {
+	if changes.is_empty() {
+		return Ok(());
+	}
+
+	let mut txn = db.begin().await?;
+	persist(&mut txn, changes).await?;
+	txn.commit().await?;
+
+	Ok(())
+}
+
+/// Persist the changes (assigning their `seq`), and notify them (the
+/// `ModelManager` hubs broadcast them from their LISTEN connection), queuing
+/// their webhook deliveries, all in the transaction of `conn`.
+///
+/// Note: The notifications are only sent on commit (as for any `pg_notify`).
+async fn persist(conn: &mut PgConnection, changes: Vec<Change>) -> Result<()> {
+	if changes.is_empty() {
+		return Ok(());
+	}
+
+	let mut entities = Vec::with_capacity(changes.len());
+	let mut ids = Vec::with_capacity(changes.len());
+	let mut kinds = Vec::with_capacity(changes.len());
+	let mut actor_ids = Vec::with_capacity(changes.len());
+	for change in changes {
+		entities.push(change.entity);
+		ids.push(change.id);
+		kinds.push(change.kind);
+		actor_ids.push(change.actor_id);
+	}
+
+	sqlx::query("SELECT pg_advisory_xact_lock($1)")
+		.bind(PUBLISH_LOCK_KEY)
+		.execute(&mut *conn)
+		.await?;
+	let events: Vec<ChangeEvent> = sqlx::query_as(
+		"WITH event AS (
+			INSERT INTO change_event (entity, entity_id, kind, actor_id)
+			SELECT * FROM UNNEST($1::varchar[], $2::int8[], $3::change_kind[], $4::int8[])
+			RETURNING id, entity, entity_id, kind, actor_id
+		 )
+		 SELECT id AS seq, entity, entity_id AS id, kind, actor_id,
+			pg_notify($5, json_build_object(
+				'seq', id, 'entity', entity, 'id', entity_id, 'kind', kind,
+				'actor_id', actor_id
+			)::text)::text AS notified
+		 FROM event ORDER BY id",
+	)
+	.bind(entities)
+	.bind(ids)
+	.bind(kinds)
+	.bind(actor_ids)
+	.bind(NOTIFY_CHANNEL)
+	.fetch_all(&mut *conn)
+	.await?;
+	webhook::enqueue_deliveries(conn, &events).await?;
+
+	Ok(())
+}
+
+// region:    --- ChangeEventBmc
+
+pub struct ChangeEventBmc;
+
+impl ChangeEventBmc {
+	/// The persisted events after the `after_seq` one (in order, at most `limit`).
+	///
+	/// Note: All the events are returned, to be filtered with
+	///       `ChangeEvent::is_visible_to` (as the broadcast ones).
+	pub async fn list_after(
+		_ctx: &Ctx,
+		mm: &ModelManager,
+		after_seq: i64,
+		limit: i64,
+	) -> Result<Vec<ChangeEvent>> {
+		let events = sqlx::query_as(
+			"SELECT id AS seq, entity, entity_id AS id, kind, actor_id
+			 FROM change_event WHERE id > $1 ORDER BY id LIMIT $2",
+		)
+		.bind(after_seq)
+		.bind(limit)
+		.fetch_all(&mut *mm.db().await?)
+		.await?;
+
+		Ok(events)
+	}
+
+	/// Delete the events persisted before `ctime_before`, returning their count
+	/// (i.e., the oldest ones, no longer resumable from).
+	pub async fn purge_before(
+		_ctx: &Ctx,
+		mm: &ModelManager,
+		ctime_before: OffsetDateTime,
+	) -> Result<u64> {
+		let count = sqlx::query("DELETE FROM change_event WHERE ctime < $1")
+			.bind(ctime_before)
+			.execute(&mut *mm.db().await?)
+			.await?
+			.rows_affected();
+
+		Ok(count)
+	}
+}
+
+// endregion: --- ChangeEventBmc
+
+impl ModelManager {
+	/// The change events of this `ModelManager` (and its clones).
+	pub fn events(&self) -> &EventHub {
+		&self.events
+	}
+
+	/// Publish the changes, or keep them until the commit for a transactional
+	/// `ModelManager` (see `publish_txn_changes`).
+	///
+	/// Note: When not transactional, the changes are already committed, so a
+	///       failure to publish them is only logged (failing would get the client
+	///       to retry a write which succeeded, e.g., creating a task twice).
+	pub(in crate::model) async fn emit_changes(
+		&self,
+		changes: Vec<Change>,
+	) -> Result<()> {
+		match &self.txn_changes {
+			Some(txn_changes) => {
+				txn_changes
+					.lock()
+					.unwrap_or_else(|poisoned| poisoned.into_inner())
+					.extend(changes);
+			}
+			None => {
+				if let Err(ex) = publish(&self.db, changes).await {
+					error!("{:<12} - emit changes - publish fail - {ex:?}", "MODEL");
+				}
+			}
+		}
+
+		Ok(())
+	}
+
+	/// The count of the changes kept by a transactional `ModelManager`
+	/// (see `truncate_txn_changes`).
+	pub(in crate::model) fn txn_changes_len(&self) -> usize {
+		self.txn_changes.as_ref().map_or(0, |txn_changes| {
+			txn_changes
+				.lock()
+				.unwrap_or_else(|poisoned| poisoned.into_inner())
+				.len()
+		})
+	}
+
+	/// Drop the changes kept since `len` (i.e., of a rolled back savepoint).
+	pub(in crate::model) fn truncate_txn_changes(&self, len: usize) {
+		if let Some(txn_changes) = &self.txn_changes {
+			txn_changes
+				.lock()
+				.unwrap_or_else(|poisoned| poisoned.into_inner())
+				.truncate(len);
+		}
+	}
+
+	/// Publish the changes kept by a transactional `ModelManager`, in its
+	/// transaction (right before its commit, see `persist`).
+	pub(in crate::model) async fn publish_txn_changes(&self) -> Result<()> {
+		let Some(txn_changes) = &self.txn_changes else {
+			return Ok(());
+		};
+		let changes = std::mem::take(
+			&mut *txn_changes
+				.lock()
+				.unwrap_or_else(|poisoned| poisoned.into_inner()),
+		);
+
+		persist(&mut *self.db().await?, changes).await
+	}
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::_dev_utils;
+	use crate::model::task::{TaskBmc, TaskForCreate, TaskForUpdate};
+	use anyhow::{Context, Result};
+	use serial_test::serial;
+	use std::time::Duration;
+	use tokio::sync::broadcast::error::TryRecvError;
+
+	/// The next event of the tasks of `ids` (skipping the others), failing after 5s.
+	pub(super) async fn fx_next_event(
+		events_rx: &mut broadcast::Receiver<ChangeEvent>,
+		ids: &[i64],
+	) -> Result<ChangeEvent> {
+		let next = async {
+			loop {
+				let event = events_rx.recv().await?;
+				if event.entity == "task" && ids.contains(&event.id) {
+					return Ok::<_, anyhow::Error>(event);
+				}
+			}
+		};
+
+		tokio::time::timeout(Duration::from_secs(5), next)
+			.await
+			.context("No event in time")?
+	}
+
+	fn fx_event(seq: i64) -> ChangeEvent {
+		ChangeEvent {
+			seq,
+			entity: "task".to_string(),
+			id: seq,
+			kind: ChangeKind::Updated,
+			actor_id: 0,
+		}
+	}
+
+	#[test]
+	fn test_event_hub_since_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let hub = EventHub::new(0);
+		let fx_count = RECENT_EVENTS_MAX as i64 + 10;
+
+		// -- Exec
+		hub.send((1..=fx_count).map(fx_event).collect());
+
+		// -- Check
+		assert_eq!(hub.last_seq(), fx_count);
+		let events = hub.since(fx_count - 2).unwrap();
+		let seqs: Vec<i64> = events.iter().map(|e| e.seq).collect();
+		assert_eq!(seqs, &[fx_count - 1, fx_count]);
+		assert_eq!(
+			events[0].channels(),
+			["task", &format!("task:{}", fx_count - 1)]
+		);
+		assert!(hub.since(fx_count).unwrap().is_empty());
+		// The first 10 events are not kept anymore.
+		assert_eq!(hub.since(10).map(|events| events.len()), Some(1024));
+		assert!(hub.since(9).is_none());
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_txn_changes_published_on_commit() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let mut events_rx = mm.events().subscribe();
+		let fx_task_c = TaskForCreate {
+			title: "test_txn_changes_published_on_commit".to_string(),
+			..Default::default()
+		};
+
+		// -- Exec & Check
+		// Rolled back, never published.
+		let mm_txn = mm.begin().await?;
+		let rolled_back_id =
+			TaskBmc::create(&ctx, &mm_txn, fx_task_c.clone()).await?;
+		mm_txn.rollback().await?;
+
+		// Committed, published once committed.
+		let mm_txn = mm.begin().await?;
+		let id = TaskBmc::create(&ctx, &mm_txn, fx_task_c).await?;
+		let task_u = TaskForUpdate {
+			done: Some(true),
+			..Default::default()
+		};
+		TaskBmc::update(&ctx, &mm_txn, id, task_u, None).await?;
+		assert!(matches!(events_rx.try_recv(), Err(TryRecvError::Empty)));
+		mm_txn.commit().await?;
+
+		// (the events of the other tests skipped)
+		let fx_ids = [rolled_back_id, id];
+		let created = fx_next_event(&mut events_rx, &fx_ids).await?;
+		let updated = fx_next_event(&mut events_rx, &fx_ids).await?;
+		assert_eq!(
+			(created.entity.as_str(), created.id, created.kind),
+			("task", id, ChangeKind::Created)
+		);
+		assert_eq!((updated.id, updated.kind), (id, ChangeKind::Updated));
+		assert_eq!(updated.seq, created.seq + 1);
+
+		// Without transaction, published right away.
+		TaskBmc::delete(&ctx, &mm, id).await?;
+		let deleted = fx_next_event(&mut events_rx, &fx_ids).await?;
+		assert_eq!((deleted.id, deleted.kind), (id, ChangeKind::Deleted));
+
+		// Persisted.
+		let persisted =
+			ChangeEventBmc::list_after(&ctx, &mm, created.seq - 1, 10).await?;
+		let persisted: Vec<(i64, ChangeKind)> =
+			persisted.iter().map(|e| (e.seq, e.kind)).collect();
+		assert_eq!(
+			persisted,
+			&[
+				(created.seq, ChangeKind::Created),
+				(updated.seq, ChangeKind::Updated),
+				(deleted.seq, ChangeKind::Deleted)
+			]
+		);
+
+		// -- Clean
+		TaskBmc::purge(&ctx, &mm, id).await?;
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
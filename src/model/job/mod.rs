@@ -0,0 +1,251 @@
+//! Durable background jobs (e.g., sending a mail or a webhook), queued in the `job`
+//! table, and run by the workers (see `worker`).
+//!
+//! Design:
+//!
+//! - A job type implements `Job` (its `KIND`, and its `run`), and is registered in
+//!   the `Jobs` of the workers (see `app_jobs`).
+//! - `JobBmc::enqueue` queues the job with the `ModelManager` connection, so with
+//!   a transactional one, the job is only queued if the transaction commits
+//!   (i.e., a transactional outbox of the Bmc writes side effects).
+//! - The failed runs are retried with an exponential backoff, until the
+//!   `MAX_ATTEMPTS` one, after which the job is `dead` (kept for inspection).
+//!
+
+// region:    --- Modules
+
+mod worker;
+
+pub use self::worker::Jobs;
+
+use crate::ctx::Ctx;
+use crate::model::webhook::DeliverWebhook;
+use crate::model::{Error, ModelManager, Result};
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::{FromRow, PgConnection};
+use time::OffsetDateTime;
+
+// endregion: --- Modules
+
+/// The jobs of the app (i.e., run by the workers started from `main`).
+pub fn app_jobs() -> Jobs {
+	Jobs::new().register::<DeliverWebhook>()
+}
+
+// region:    --- Job Types
+
+/// A job type, (de)serialized as the `payload` of its queued jobs.
+#[async_trait]
+pub trait Job: Serialize + DeserializeOwned + Send + 'static {
+	/// The `job.kind` of this job type (e.g., `send_mail`), unique per job type.
+	const KIND: &'static str;
+
+	/// Number of runs before the job is `dead`.
+	const MAX_ATTEMPTS: i32 = 5;
+
+	async fn run(self, mm: &ModelManager) -> core::result::Result<(), JobError>;
+}
+
+/// The failure of a job run (from any error, with `?`), recorded as its
+/// `last_error`.
+#[derive(Debug)]
+pub struct JobError(pub String);
+
+impl<E: std::error::Error> From<E> for JobError {
+	fn from(val: E) -> Self {
+		Self(val.to_string())
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "job_status", rename_all = "snake_case")]
+pub enum JobStatus {
+	Pending,
+	Running,
+	Done,
+	/// Failed `max_attempts` times.
+	Dead,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct QueuedJob {
+	pub id: i64,
+	pub kind: String,
+	pub payload: Value,
+	pub status: JobStatus,
+
+	/// When the job is due (also of its next attempt after a failure).
+	pub run_at: OffsetDateTime,
+	pub attempts: i32,
+	pub max_attempts: i32,
+	pub last_error: Option<String>,
+}
+
+// endregion: --- Job Types
+
+// region:    --- JobBmc
+
+pub struct JobBmc;
+
+impl JobBmc {
+	/// Queue the job, due now (in the transaction of `mm` if any).
+	pub async fn enqueue<J: Job>(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		job: &J,
+	) -> Result<i64> {
+		Self::enqueue_at(ctx, mm, job, None).await
+	}
+
+	/// Queue the job, due at `run_at` (now if None).
+	pub async fn enqueue_at<J: Job>(
+		_ctx: &Ctx,
+		mm: &ModelManager,
+		job: &J,
+		run_at: Option<OffsetDateTime>,
+	) -> Result<i64> {
+		insert(&mut *mm.db().await?, job, run_at).await
+	}
+
+	pub async fn get(_ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<QueuedJob> {
+		sqlx::query_as(
+			"SELECT id, kind, payload, status, run_at, attempts, max_attempts, last_error
+			 FROM job WHERE id = $1",
+		)
+		.bind(id)
+		.fetch_optional(&mut *mm.db().await?)
+		.await?
+		.ok_or(Error::EntityNotFound { entity: "job", id })
+	}
+
+	/// Delete the `done` and `dead` jobs last run before `mtime_before`,
+	/// returning their count.
+	pub async fn purge_finished_before(
+		_ctx: &Ctx,
+		mm: &ModelManager,
+		mtime_before: OffsetDateTime,
+	) -> Result<u64> {
+		let count = sqlx::query(
+			"DELETE FROM job WHERE status IN ('done', 'dead') AND mtime < $1",
+		)
+		.bind(mtime_before)
+		.execute(&mut *mm.db().await?)
+		.await?
+		.rows_affected();
+
+		Ok(count)
+	}
+}
+
+// endregion: --- JobBmc
+
+/// Queue the job on the connection (e.g., in the transaction of the change events,
+/// for the webhook deliveries).
+pub(in crate::model) async fn insert<J: Job>(
+	conn: &mut PgConnection,
+	job: &J,
+	run_at: Option<OffsetDateTime>,
+) -> Result<i64> {
+	let payload = serde_json::to_value(job)?;
+
+	let (id,): (i64,) = sqlx::query_as(
+		"INSERT INTO job (kind, payload, run_at, max_attempts)
+		 VALUES ($1, $2, COALESCE($3, now()), $4)
+		 RETURNING id",
+	)
+	.bind(J::KIND)
+	.bind(payload)
+	.bind(run_at)
+	.bind(J::MAX_ATTEMPTS)
+	.fetch_one(conn)
+	.await?;
+
+	Ok(id)
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::_dev_utils;
+	use crate::model::task::{TaskBmc, TaskForCreate};
+	use anyhow::Result;
+	use serde::Deserialize;
+	use serial_test::serial;
+
+	#[derive(Serialize, Deserialize)]
+	struct FxJob {
+		task_id: i64,
+	}
+
+	#[async_trait]
+	impl Job for FxJob {
+		const KIND: &'static str = "fx_job";
+
+		async fn run(
+			self,
+			_mm: &ModelManager,
+		) -> core::result::Result<(), JobError> {
+			Ok(())
+		}
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_enqueue_in_txn_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_task_c = TaskForCreate {
+			title: "test_enqueue_in_txn_ok".to_string(),
+			..Default::default()
+		};
+
+		// -- Exec
+		// Rolled back with the task.
+		let mm_txn = mm.begin().await?;
+		let task_id = TaskBmc::create(&ctx, &mm_txn, fx_task_c.clone()).await?;
+		let rolled_back_id =
+			JobBmc::enqueue(&ctx, &mm_txn, &FxJob { task_id }).await?;
+		mm_txn.rollback().await?;
+
+		// Committed with the task.
+		let ctx_ref = &ctx;
+		let (task_id, job_id) = mm
+			.txn(|mm| {
+				let fx_task_c = fx_task_c.clone();
+				async move {
+					let task_id = TaskBmc::create(ctx_ref, &mm, fx_task_c).await?;
+					let job_id =
+						JobBmc::enqueue(ctx_ref, &mm, &FxJob { task_id }).await?;
+					Ok((task_id, job_id))
+				}
+			})
+			.await?;
+
+		// -- Check
+		let res = JobBmc::get(&ctx, &mm, rolled_back_id).await;
+		assert!(matches!(res, Err(Error::EntityNotFound { .. })));
+
+		let job = JobBmc::get(&ctx, &mm, job_id).await?;
+		assert_eq!(job.kind, "fx_job");
+		assert_eq!(job.payload["task_id"], task_id);
+		assert_eq!(job.status, JobStatus::Pending);
+		assert_eq!((job.attempts, job.max_attempts), (0, 5));
+
+		// -- Clean
+		sqlx::query("DELETE FROM job WHERE id = $1")
+			.bind(job_id)
+			.execute(&mut *mm.db().await?)
+			.await?;
+		TaskBmc::delete(&ctx, &mm, task_id).await?;
+		TaskBmc::purge(&ctx, &mm, task_id).await?;
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
@@ -0,0 +1,444 @@
+//! The job workers, running the due jobs of their registered job types.
+//!
+//! A job is claimed with `FOR UPDATE SKIP LOCKED` (so that the workers of all the
+//! app instances never run it twice), as `running` for `JOB_LEASE`, after which
+//! it is claimable again (e.g., after a crash of its worker), or `dead` when
+//! out of attempts.
+//!
+//! The outcome of a run is only recorded while the job is still claimed by its
+//! worker (i.e., same `running` attempt), so that a run past its lease does not
+//! overwrite the state of the job reclaimed by another worker.
+//!
+
+use crate::model::job::{Job, JobError};
+use crate::model::{ModelManager, Result};
+use async_trait::async_trait;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, error};
+
+/// Wait of an idle worker before looking for the due jobs again.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Run time after which a `running` job is deemed failed (and claimable again).
+const JOB_LEASE: Duration = Duration::from_secs(5 * 60);
+
+/// Delay before the second attempt, doubled for each next one (up to `BACKOFF_MAX`).
+const BACKOFF_BASE: Duration = Duration::from_secs(10);
+const BACKOFF_MAX: Duration = Duration::from_secs(60 * 60);
+
+/// The job types run by the workers (by `Job::KIND`).
+#[derive(Clone, Default)]
+pub struct Jobs {
+	runners: HashMap<&'static str, Arc<dyn JobRunner>>,
+}
+
+impl Jobs {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn register<J: Job>(mut self) -> Self {
+		self.runners
+			.insert(J::KIND, Arc::new(TypedRunner::<J>(PhantomData)));
+		self
+	}
+
+	/// Start `count` workers, running the due jobs until the process ends.
+	pub fn start_workers(self, mm: ModelManager, count: usize) {
+		let jobs = Arc::new(self);
+
+		for _ in 0..count {
+			let jobs = jobs.clone();
+			let mm = mm.clone();
+			tokio::spawn(async move { jobs.worker_loop(&mm).await });
+		}
+	}
+
+	async fn worker_loop(&self, mm: &ModelManager) {
+		loop {
+			match self.run_next(mm).await {
+				// Maybe more due jobs.
+				Ok(Some(_)) => continue,
+				Ok(None) => (),
+				Err(ex) => error!("{:<12} - run_next - {ex:?}", "JOBS"),
+			}
+
+			tokio::time::sleep(POLL_INTERVAL).await;
+		}
+	}
+
+	/// Run the next due job (of the registered job types), returning its id,
+	/// or None when no job is due.
+	pub async fn run_next(&self, mm: &ModelManager) -> Result<Option<i64>> {
+		let Some(claimed) = self.claim_next(mm).await? else {
+			return Ok(None);
+		};
+		let ClaimedJob {
+			id,
+			kind,
+			payload,
+			attempts,
+			max_attempts,
+		} = claimed;
+		debug!("{:<12} - run {kind} {id} (attempt {attempts})", "JOBS");
+
+		let res = match self.runners.get(kind.as_str()) {
+			Some(runner) => tokio::time::timeout(JOB_LEASE, runner.run(mm, payload))
+				.await
+				.unwrap_or_else(|_| Err(JobError("Run timed out".to_string()))),
+			// Not claimed without runner.
+			None => Err(JobError(format!("No runner for kind '{kind}'"))),
+		};
+
+		let updated = match res {
+			Ok(()) => sqlx::query(
+				"UPDATE job SET status = 'done', last_error = NULL, mtime = now()
+				 WHERE id = $1 AND status = 'running' AND attempts = $2",
+			)
+			.bind(id)
+			.bind(attempts)
+			.execute(&mut *mm.db().await?)
+			.await?
+			.rows_affected(),
+			Err(JobError(last_error)) => {
+				debug!("{:<12} - {kind} {id} failed - {last_error}", "JOBS");
+				let dead = attempts >= max_attempts;
+				let backoff = backoff(attempts);
+
+				sqlx::query(
+					"UPDATE job SET
+						status = CASE WHEN $2 THEN 'dead' ELSE 'pending' END::job_status,
+						run_at = CASE WHEN $2 THEN run_at
+						              ELSE now() + make_interval(secs => $3) END,
+						last_error = $4,
+						mtime = now()
+					 WHERE id = $1 AND status = 'running' AND attempts = $5",
+				)
+				.bind(id)
+				.bind(dead)
+				.bind(backoff.as_secs_f64())
+				.bind(last_error)
+				.bind(attempts)
+				.execute(&mut *mm.db().await?)
+				.await?
+				.rows_affected()
+			}
+		};
+
+		if updated == 0 {
+			debug!(
+				"{:<12} - {kind} {id} (attempt {attempts}) reclaimed, outcome dropped",
+				"JOBS"
+			);
+		}
+
+		Ok(Some(id))
+	}
+
+	/// Claim the next due job (`pending`, or `running` past its lease).
+	///
+	/// Note: The jobs past their lease without attempts left are marked `dead`.
+	async fn claim_next(&self, mm: &ModelManager) -> Result<Option<ClaimedJob>> {
+		let kinds: Vec<&str> = self.runners.keys().copied().collect();
+
+		sqlx::query(
+			"UPDATE job SET status = 'dead', last_error = 'Lease expired', mtime = now()
+			 WHERE kind = ANY($1)
+			   AND status = 'running' AND attempts >= max_attempts
+			   AND mtime < now() - make_interval(secs => $2)",
+		)
+		.bind(&kinds)
+		.bind(JOB_LEASE.as_secs_f64())
+		.execute(&mut *mm.db().await?)
+		.await?;
+
+		let claimed = sqlx::query_as(
+			"UPDATE job SET status = 'running', attempts = attempts + 1, mtime = now()
+			 WHERE id = (
+				SELECT id FROM job
+				WHERE kind = ANY($1)
+				  AND ((status = 'pending' AND run_at <= now())
+				       OR (status = 'running' AND attempts < max_attempts
+				           AND mtime < now() - make_interval(secs => $2)))
+				ORDER BY run_at, id
+				LIMIT 1
+				FOR UPDATE SKIP LOCKED
+			 )
+			 RETURNING id, kind, payload, attempts, max_attempts",
+		)
+		.bind(kinds)
+		.bind(JOB_LEASE.as_secs_f64())
+		.fetch_optional(&mut *mm.db().await?)
+		.await?;
+
+		Ok(claimed)
+	}
+}
+
+#[derive(sqlx::FromRow)]
+struct ClaimedJob {
+	id: i64,
+	kind: String,
+	payload: Value,
+	attempts: i32,
+	max_attempts: i32,
+}
+
+/// The delay before the next attempt, after the `attempts` failed ones.
+fn backoff(attempts: i32) -> Duration {
+	let exp = attempts.saturating_sub(1).clamp(0, 16) as u32;
+
+	BACKOFF_BASE.saturating_mul(2u32.pow(exp)).min(BACKOFF_MAX)
+}
+
+// region:    --- JobRunner
+
+/// The `Job::run` of a job type, from the job payload.
+#[async_trait]
+trait JobRunner: Send + Sync {
+	async fn run(
+		&self,
+		mm: &ModelManager,
+		payload: Value,
+	) -> core::result::Result<(), JobError>;
+}
+
+struct TypedRunner<J>(PhantomData<fn() -> J>);
+
+#[async_trait]
+impl<J: Job> JobRunner for TypedRunner<J> {
+	async fn run(
+		&self,
+		mm: &ModelManager,
+		payload: Value,
+	) -> core::result::Result<(), JobError> {
+		let job: J = serde_json::from_value(payload)?;
+
+		job.run(mm).await
+	}
+}
+
+// endregion: --- JobRunner
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::_dev_utils;
+	use crate::ctx::Ctx;
+	use crate::model::job::{JobBmc, JobStatus};
+	use crate::model::task::{TaskBmc, TaskForCreate};
+	use crate::utils::time::now_utc;
+	use anyhow::Result;
+	use serde::{Deserialize, Serialize};
+	use serial_test::serial;
+
+	/// Creates the task.
+	#[derive(Serialize, Deserialize)]
+	struct FxCreateTaskJob {
+		title: String,
+	}
+
+	#[async_trait]
+	impl Job for FxCreateTaskJob {
+		const KIND: &'static str = "fx_create_task";
+
+		async fn run(self, mm: &ModelManager) -> core::result::Result<(), JobError> {
+			let task_c = TaskForCreate {
+				title: self.title,
+				..Default::default()
+			};
+			TaskBmc::create(&Ctx::root_ctx(), mm, task_c).await?;
+
+			Ok(())
+		}
+	}
+
+	#[derive(Serialize, Deserialize)]
+	struct FxFailJob;
+
+	#[async_trait]
+	impl Job for FxFailJob {
+		const KIND: &'static str = "fx_fail";
+		const MAX_ATTEMPTS: i32 = 2;
+
+		async fn run(
+			self,
+			_mm: &ModelManager,
+		) -> core::result::Result<(), JobError> {
+			Err(JobError("fx failure".to_string()))
+		}
+	}
+
+	/// Bumps the attempts of its (running) job, as if reclaimed by another worker.
+	#[derive(Serialize, Deserialize)]
+	struct FxReclaimedJob;
+
+	#[async_trait]
+	impl Job for FxReclaimedJob {
+		const KIND: &'static str = "fx_reclaimed";
+
+		async fn run(self, mm: &ModelManager) -> core::result::Result<(), JobError> {
+			sqlx::query(
+				"UPDATE job SET attempts = attempts + 1, mtime = now()
+				 WHERE kind = $1 AND status = 'running'",
+			)
+			.bind(Self::KIND)
+			.execute(&mut *mm.db().await?)
+			.await
+			.map_err(|ex| JobError(ex.to_string()))?;
+
+			Ok(())
+		}
+	}
+
+	async fn fx_clean_jobs(mm: &ModelManager, ids: &[i64]) -> Result<()> {
+		sqlx::query("DELETE FROM job WHERE id = ANY($1)")
+			.bind(ids)
+			.execute(&mut *mm.db().await?)
+			.await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_run_next_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let jobs = Jobs::new().register::<FxCreateTaskJob>();
+		let fx_title = "test_run_next_ok";
+		let fx_job = FxCreateTaskJob {
+			title: fx_title.to_string(),
+		};
+		let scheduled_id = JobBmc::enqueue_at(
+			&ctx,
+			&mm,
+			&fx_job,
+			Some(now_utc() + time::Duration::hours(1)),
+		)
+		.await?;
+		let fail_id = JobBmc::enqueue(&ctx, &mm, &FxFailJob).await?;
+		let job_id = JobBmc::enqueue(&ctx, &mm, &fx_job).await?;
+
+		// -- Exec
+		let ran_id = jobs.run_next(&mm).await?;
+		// Neither the scheduled one, nor the unregistered kind one.
+		let none = jobs.run_next(&mm).await?;
+
+		// -- Check
+		assert_eq!(ran_id, Some(job_id));
+		assert_eq!(none, None);
+		let job = JobBmc::get(&ctx, &mm, job_id).await?;
+		assert_eq!((job.status, job.attempts), (JobStatus::Done, 1));
+		let tasks = TaskBmc::list(&ctx, &mm).await?;
+		let task = tasks
+			.iter()
+			.find(|t| t.title == fx_title)
+			.expect("Should have the job task");
+		let scheduled = JobBmc::get(&ctx, &mm, scheduled_id).await?;
+		assert_eq!(scheduled.status, JobStatus::Pending);
+		assert_eq!(scheduled.attempts, 0);
+
+		// -- Clean
+		fx_clean_jobs(&mm, &[scheduled_id, fail_id, job_id]).await?;
+		TaskBmc::delete(&ctx, &mm, task.id).await?;
+		TaskBmc::purge(&ctx, &mm, task.id).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_run_next_retry_then_dead() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let jobs = Jobs::new().register::<FxFailJob>();
+		let job_id = JobBmc::enqueue(&ctx, &mm, &FxFailJob).await?;
+
+		// -- Exec & Check
+		// Failed, retried after the backoff.
+		assert_eq!(jobs.run_next(&mm).await?, Some(job_id));
+		let job = JobBmc::get(&ctx, &mm, job_id).await?;
+		assert_eq!((job.status, job.attempts), (JobStatus::Pending, 1));
+		assert_eq!(job.last_error.as_deref(), Some("fx failure"));
+		assert!(job.run_at > now_utc() + time::Duration::seconds(5));
+		assert_eq!(jobs.run_next(&mm).await?, None);
+
+		// Due again, failed the last attempt.
+		sqlx::query("UPDATE job SET run_at = now() WHERE id = $1")
+			.bind(job_id)
+			.execute(&mut *mm.db().await?)
+			.await?;
+		assert_eq!(jobs.run_next(&mm).await?, Some(job_id));
+		let job = JobBmc::get(&ctx, &mm, job_id).await?;
+		assert_eq!((job.status, job.attempts), (JobStatus::Dead, 2));
+		assert_eq!(jobs.run_next(&mm).await?, None);
+
+		// -- Clean
+		fx_clean_jobs(&mm, &[job_id]).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_run_next_lease_expired() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let jobs = Jobs::new()
+			.register::<FxFailJob>()
+			.register::<FxReclaimedJob>();
+		let fx_expire = |id: i64, attempts: i32| {
+			sqlx::query(
+				"UPDATE job SET status = 'running', attempts = $2,
+				                mtime = now() - interval '1 day'
+				 WHERE id = $1",
+			)
+			.bind(id)
+			.bind(attempts)
+		};
+		let out_id = JobBmc::enqueue(&ctx, &mm, &FxFailJob).await?;
+		fx_expire(out_id, 2).execute(&mut *mm.db().await?).await?;
+
+		// -- Exec & Check
+		// Past its lease, without attempts left.
+		assert_eq!(jobs.run_next(&mm).await?, None);
+		let job = JobBmc::get(&ctx, &mm, out_id).await?;
+		assert_eq!((job.status, job.attempts), (JobStatus::Dead, 2));
+		assert_eq!(job.last_error.as_deref(), Some("Lease expired"));
+
+		// Past its lease, reclaimed while run (outcome dropped).
+		let reclaimed_id = JobBmc::enqueue(&ctx, &mm, &FxReclaimedJob).await?;
+		fx_expire(reclaimed_id, 1)
+			.execute(&mut *mm.db().await?)
+			.await?;
+		assert_eq!(jobs.run_next(&mm).await?, Some(reclaimed_id));
+		let job = JobBmc::get(&ctx, &mm, reclaimed_id).await?;
+		assert_eq!((job.status, job.attempts), (JobStatus::Running, 3));
+
+		// -- Clean
+		fx_clean_jobs(&mm, &[out_id, reclaimed_id]).await?;
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_backoff_ok() -> Result<()> {
+		// -- Exec & Check
+		assert_eq!(backoff(1), Duration::from_secs(10));
+		assert_eq!(backoff(2), Duration::from_secs(20));
+		assert_eq!(backoff(4), Duration::from_secs(80));
+		assert_eq!(backoff(20), BACKOFF_MAX);
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
@@ -15,38 +15,72 @@
 //! - In frameworks like Axum, Tauri, `ModelManager` are typically used as App State.
 //! - ModelManager are designed to be passed as an argument
 //!   to all Model Controllers functions.
+//! - A transactional ModelManager (see `txn` module) is passed the same way,
+//!   so Model Controllers functions can be composed in one transaction.
 //!
 
 // region:    --- Modules
 
 mod base;
+pub mod comment;
+pub mod cron;
+mod cursor;
 mod error;
+pub mod event;
+#[allow(dead_code)]
+// For now, the jobs being only queued with the change events (webhooks).
+pub mod job;
+pub mod notification;
+pub mod revision;
+pub mod saved_view;
+pub mod search;
 mod store;
 pub mod task;
+pub mod trash;
+mod txn;
 pub mod user;
+pub mod webhook;
 
+pub use self::base::{ListOptions, ListPage};
 pub use self::error::{Error, Result};
 
-use crate::model::store::{new_db_pool, Db};
+use crate::model::event::{Change, EventHub};
+use crate::model::store::{new_db_pool, new_db_pool_sized, Db};
+use crate::model::txn::TxnHolder;
+use std::sync::{Arc, Mutex};
 
 // endregion: --- Modules
 
 #[derive(Clone)]
 pub struct ModelManager {
 	db: Db,
+	/// Some for a transactional ModelManager (see `ModelManager::begin`).
+	txn: Option<TxnHolder>,
+	events: EventHub,
+	/// The changes of the transaction, published on commit (see `event` module).
+	txn_changes: Option<Arc<Mutex<Vec<Change>>>>,
 }
 
 impl ModelManager {
 	/// Constructor
 	pub async fn new() -> Result<Self> {
-		let db = new_db_pool().await?;
+		Self::from_db(new_db_pool().await?).await
+	}
 
-		Ok(ModelManager { db })
+	/// Constructor with a pool of `max_connections`, as the test pool has
+	/// only one (see `store::new_db_pool`), so no concurrent transactions.
+	pub async fn new_sized(max_connections: u32) -> Result<Self> {
+		Self::from_db(new_db_pool_sized(max_connections).await?).await
 	}
 
-	/// Returns the sqlx db pool reference.
-	/// (Only for the model layer)
-	pub(in crate::model) fn db(&self) -> &Db {
-		&self.db
+	async fn from_db(db: Db) -> Result<Self> {
+		let events = EventHub::init(&db).await?;
+
+		Ok(ModelManager {
+			db,
+			txn: None,
+			events,
+			txn_changes: None,
+		})
 	}
 }
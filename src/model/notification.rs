@@ -0,0 +1,566 @@
+//! The in-app notifications of the users (e.g., a task assigned to them),
+//! created along the changes of the model (see the `notify_*` functions),
+//! and by the due date reminders cron (see `DueSoonReminders`).
+//!
+//! Notes:
+//!
+//! - No notification is created for the own actions of a user
+//!   (e.g., assigning a task to oneself).
+//! - A user can disable kinds of notifications (see `NotificationBmc::set_pref`),
+//!   all the kinds being enabled by default.
+//!
+
+use crate::ctx::Ctx;
+use crate::model::base::{self, DbBmc, ListOptions, ListPage};
+use crate::model::cron::Cron;
+use crate::model::job::JobError;
+use crate::model::task::Task;
+use crate::model::{Error, ModelManager, Result};
+use crate::utils::time::{Rfc3339, Rfc3339Schema};
+use async_trait::async_trait;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use serde_with::serde_as;
+use sqlx::FromRow;
+use time::{Duration, OffsetDateTime};
+
+/// The `Notification` columns (not a `Fields` struct, since `payload` is not
+/// sqlb bindable).
+const NOTIFICATION_COLUMNS: &[&str] =
+	&["id", "recipient_id", "kind", "payload", "read", "ctime"];
+
+/// How long before its due date the assignee of a task gets reminded.
+const DUE_SOON_WINDOW: Duration = Duration::hours(24);
+
+// region:    --- Notification Types
+
+#[derive(
+	Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, sqlx::Type,
+)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "notification_kind", rename_all = "snake_case")]
+#[allow(clippy::enum_variant_names)] // Only the task kinds for now.
+pub enum NotificationKind {
+	/// Payload: `{task_id, title, actor_id}`
+	TaskAssigned,
+	/// Payload: `{task_id, title, comment_id, actor_id}`
+	TaskCommented,
+	/// Payload: `{task_id, title, due_date}`
+	TaskDueSoon,
+}
+
+impl NotificationKind {
+	pub const ALL: [NotificationKind; 3] =
+		[Self::TaskAssigned, Self::TaskCommented, Self::TaskDueSoon];
+}
+
+#[serde_as]
+#[derive(Debug, Clone, FromRow, Serialize, JsonSchema)]
+pub struct Notification {
+	pub id: i64,
+	pub recipient_id: i64,
+	pub kind: NotificationKind,
+	pub payload: Value,
+	pub read: bool,
+
+	#[serde_as(as = "Rfc3339")]
+	#[schemars(with = "Rfc3339Schema")]
+	pub ctime: OffsetDateTime,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, JsonSchema)]
+pub struct NotificationPref {
+	pub kind: NotificationKind,
+	pub enabled: bool,
+}
+
+// endregion: --- Notification Types
+
+// region:    --- NotificationBmc
+
+pub struct NotificationBmc;
+
+impl DbBmc for NotificationBmc {
+	const TABLE: &'static str = "notification";
+	const LIST_ORDER_BYS: &'static [&'static str] = &["!id"];
+}
+
+impl NotificationBmc {
+	/// Get a notification of the ctx user.
+	pub async fn get(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<Notification> {
+		let notification = sqlx::query_as(
+			"SELECT id, recipient_id, kind, payload, read, ctime
+			 FROM notification WHERE id = $1 AND recipient_id = $2",
+		)
+		.bind(id)
+		.bind(ctx.user_id())
+		.fetch_optional(&mut *mm.db().await?)
+		.await?
+		.ok_or(Error::EntityNotFound {
+			entity: "notification",
+			id,
+		})?;
+
+		Ok(notification)
+	}
+
+	/// List the notifications of the ctx user, newest first
+	/// (without cursor, see `list_page` for the cursors).
+	pub async fn list(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		unread_only: bool,
+		list_options: Option<ListOptions>,
+	) -> Result<Vec<Notification>> {
+		if let Some(list_options) = &list_options {
+			list_options.check_no_cursor()?;
+		}
+		let page = Self::list_page(ctx, mm, unread_only, list_options).await?;
+
+		Ok(page.items)
+	}
+
+	/// List a page of the notifications of the ctx user, newest first
+	/// (see `base::list_page` for the cursors).
+	pub async fn list_page(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		unread_only: bool,
+		list_options: Option<ListOptions>,
+	) -> Result<ListPage<Notification>> {
+		let mut sb = sqlb::select().and_where("recipient_id", "=", ctx.user_id());
+		if unread_only {
+			sb = sb.and_where("read", "=", false);
+		}
+
+		base::list_page_columns::<Self, _>(
+			ctx,
+			mm,
+			sb,
+			NOTIFICATION_COLUMNS,
+			list_options,
+		)
+		.await
+	}
+
+	/// The number of unread notifications of the ctx user.
+	pub async fn count_unread(ctx: &Ctx, mm: &ModelManager) -> Result<i64> {
+		let (count,): (i64,) = sqlx::query_as(
+			"SELECT count(*) FROM notification WHERE recipient_id = $1 AND NOT read",
+		)
+		.bind(ctx.user_id())
+		.fetch_one(&mut *mm.db().await?)
+		.await?;
+
+		Ok(count)
+	}
+
+	pub async fn mark_read(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
+		let count = sqlx::query(
+			"UPDATE notification SET read = true WHERE id = $1 AND recipient_id = $2",
+		)
+		.bind(id)
+		.bind(ctx.user_id())
+		.execute(&mut *mm.db().await?)
+		.await?
+		.rows_affected();
+
+		if count == 0 {
+			return Err(Error::EntityNotFound {
+				entity: "notification",
+				id,
+			});
+		}
+
+		Ok(())
+	}
+
+	/// Mark all the notifications of the ctx user as read,
+	/// returning the number of the newly read ones.
+	pub async fn mark_all_read(ctx: &Ctx, mm: &ModelManager) -> Result<u64> {
+		let count = sqlx::query(
+			"UPDATE notification SET read = true WHERE recipient_id = $1 AND NOT read",
+		)
+		.bind(ctx.user_id())
+		.execute(&mut *mm.db().await?)
+		.await?
+		.rows_affected();
+
+		Ok(count)
+	}
+
+	// -- Prefs
+
+	/// The notification preferences of the ctx user (for all the kinds).
+	pub async fn list_prefs(
+		ctx: &Ctx,
+		mm: &ModelManager,
+	) -> Result<Vec<NotificationPref>> {
+		let stored: Vec<NotificationPref> = sqlx::query_as(
+			"SELECT kind, enabled FROM notification_pref WHERE user_id = $1",
+		)
+		.bind(ctx.user_id())
+		.fetch_all(&mut *mm.db().await?)
+		.await?;
+
+		let prefs = NotificationKind::ALL
+			.into_iter()
+			.map(|kind| NotificationPref {
+				kind,
+				enabled: stored
+					.iter()
+					.find(|pref| pref.kind == kind)
+					.map_or(true, |pref| pref.enabled),
+			})
+			.collect();
+
+		Ok(prefs)
+	}
+
+	/// Enable or disable the creation of a kind of notifications for the ctx user.
+	pub async fn set_pref(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		pref: NotificationPref,
+	) -> Result<()> {
+		sqlx::query(
+			"INSERT INTO notification_pref (user_id, kind, enabled) VALUES ($1, $2, $3)
+			 ON CONFLICT (user_id, kind) DO UPDATE SET enabled = EXCLUDED.enabled",
+		)
+		.bind(ctx.user_id())
+		.bind(pref.kind)
+		.bind(pref.enabled)
+		.execute(&mut *mm.db().await?)
+		.await?;
+
+		Ok(())
+	}
+
+	// -- Notify
+
+	/// Notify the assignee of the task (just assigned by the ctx user).
+	pub(in crate::model) async fn notify_task_assigned(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		task: &Task,
+	) -> Result<()> {
+		let Some(assignee_id) = task.assignee_id else {
+			return Ok(());
+		};
+		let payload = json!({
+			"task_id": task.id,
+			"title": task.title,
+			"actor_id": ctx.user_id(),
+		});
+
+		Self::notify(
+			ctx,
+			mm,
+			assignee_id,
+			NotificationKind::TaskAssigned,
+			payload,
+		)
+		.await
+	}
+
+	/// Notify the assignee of the task of a new comment (by the ctx user).
+	pub(in crate::model) async fn notify_task_commented(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		task: &Task,
+		comment_id: i64,
+	) -> Result<()> {
+		let Some(assignee_id) = task.assignee_id else {
+			return Ok(());
+		};
+		let payload = json!({
+			"task_id": task.id,
+			"title": task.title,
+			"comment_id": comment_id,
+			"actor_id": ctx.user_id(),
+		});
+
+		Self::notify(
+			ctx,
+			mm,
+			assignee_id,
+			NotificationKind::TaskCommented,
+			payload,
+		)
+		.await
+	}
+
+	/// Create the notification, unless the recipient is the ctx user,
+	/// or disabled this kind of notifications.
+	async fn notify(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		recipient_id: i64,
+		kind: NotificationKind,
+		payload: Value,
+	) -> Result<()> {
+		if recipient_id == ctx.user_id() {
+			return Ok(());
+		}
+
+		sqlx::query(
+			"INSERT INTO notification (recipient_id, kind, payload)
+			 SELECT $1, $2, $3
+			 WHERE NOT EXISTS (
+			   SELECT 1 FROM notification_pref
+			   WHERE user_id = $1 AND kind = $2 AND NOT enabled)",
+		)
+		.bind(recipient_id)
+		.bind(kind)
+		.bind(payload)
+		.execute(&mut *mm.db().await?)
+		.await?;
+
+		Ok(())
+	}
+
+	/// Remind the assignees of the (not done) tasks due in the `window` after
+	/// `now`, once per task and due date, returning the number of reminders.
+	async fn notify_due_soon(
+		mm: &ModelManager,
+		now: OffsetDateTime,
+		window: Duration,
+	) -> Result<u64> {
+		let count = sqlx::query(
+			r#"WITH due AS (
+			   SELECT id, title, assignee_id,
+			          to_char(due_date AT TIME ZONE 'UTC',
+			                  'YYYY-MM-DD"T"HH24:MI:SS"Z"') AS due_date
+			   FROM task
+			   WHERE assignee_id IS NOT NULL AND NOT done AND deleted_at IS NULL
+			     AND due_date > $1 AND due_date <= $2)
+			 INSERT INTO notification (recipient_id, kind, payload)
+			 SELECT d.assignee_id, 'task_due_soon',
+			        jsonb_build_object('task_id', d.id, 'title', d.title,
+			                           'due_date', d.due_date)
+			 FROM due d
+			 WHERE NOT EXISTS (
+			     SELECT 1 FROM notification n
+			     WHERE n.recipient_id = d.assignee_id AND n.kind = 'task_due_soon'
+			       AND n.payload @> jsonb_build_object('task_id', d.id,
+			                                           'due_date', d.due_date))
+			   AND NOT EXISTS (
+			     SELECT 1 FROM notification_pref p
+			     WHERE p.user_id = d.assignee_id AND p.kind = 'task_due_soon'
+			       AND NOT p.enabled)"#,
+		)
+		.bind(now)
+		.bind(now + window)
+		.execute(&mut *mm.db().await?)
+		.await?
+		.rows_affected();
+
+		Ok(count)
+	}
+}
+
+// endregion: --- NotificationBmc
+
+// region:    --- DueSoonReminders
+
+/// Reminds the assignees of the tasks due within a day, every 15 minutes.
+pub struct DueSoonReminders;
+
+#[async_trait]
+impl Cron for DueSoonReminders {
+	const NAME: &'static str = "due_soon_reminders";
+	const SCHEDULE: &'static str = "*/15 * * * *";
+
+	async fn run(
+		&self,
+		mm: &ModelManager,
+		now: OffsetDateTime,
+	) -> core::result::Result<String, JobError> {
+		let count =
+			NotificationBmc::notify_due_soon(mm, now, DUE_SOON_WINDOW).await?;
+
+		Ok(format!("{count} reminder(s) created"))
+	}
+}
+
+// endregion: --- DueSoonReminders
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::_dev_utils;
+	use crate::model::comment::{CommentBmc, CommentForCreate};
+	use crate::model::task::{TaskBmc, TaskForCreate, TaskForUpdate};
+	use crate::utils::time::parse_utc;
+	use anyhow::Result;
+	use serial_test::serial;
+
+	fn fx_kinds(notifications: &[Notification]) -> Vec<NotificationKind> {
+		notifications.iter().map(|n| n.kind).collect()
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_assigned_and_commented_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let root_ctx = Ctx::root_ctx();
+		let user_ids = _dev_utils::seed_users(
+			&root_ctx,
+			&mm,
+			&[
+				"test_assigned_and_commented_ok-user-01",
+				"test_assigned_and_commented_ok-user-02",
+			],
+		)
+		.await?;
+		let (ctx_01, ctx_02) = (Ctx::new(user_ids[0])?, Ctx::new(user_ids[1])?);
+		let fx_task_c = TaskForCreate {
+			title: "test_assigned_and_commented_ok".to_string(),
+			assignee_id: Some(user_ids[1]),
+			..Default::default()
+		};
+
+		// -- Exec
+		let task_id = TaskBmc::create(&ctx_01, &mm, fx_task_c).await?;
+		// Same assignee, so no new assignment.
+		let task_u = TaskForUpdate {
+			assignee_id: Some(user_ids[1]),
+			..Default::default()
+		};
+		TaskBmc::update(&ctx_01, &mm, task_id, task_u, None).await?;
+		// The own comment of the assignee is not notified.
+		for ctx in [&ctx_02, &ctx_01] {
+			let comment_c = CommentForCreate {
+				task_id,
+				body: "test_assigned_and_commented_ok".to_string(),
+			};
+			CommentBmc::create(ctx, &mm, comment_c).await?;
+		}
+
+		// -- Check
+		let notifications = NotificationBmc::list(&ctx_02, &mm, true, None).await?;
+		assert_eq!(
+			fx_kinds(&notifications),
+			&[
+				NotificationKind::TaskCommented,
+				NotificationKind::TaskAssigned
+			]
+		);
+		assert_eq!(notifications[1].payload["task_id"], task_id);
+		assert_eq!(notifications[1].payload["actor_id"], user_ids[0]);
+		assert!(NotificationBmc::list(&ctx_01, &mm, false, None)
+			.await?
+			.is_empty());
+
+		// Paged by keyset, newest first.
+		let page_options = |cursor| ListOptions {
+			limit: Some(1),
+			cursor,
+			..Default::default()
+		};
+		let page =
+			NotificationBmc::list_page(&ctx_02, &mm, true, Some(page_options(None)))
+				.await?;
+		assert!(page.has_more);
+		assert_eq!(fx_kinds(&page.items), &[NotificationKind::TaskCommented]);
+		let cursor = page.next_cursor;
+		let page = NotificationBmc::list_page(
+			&ctx_02,
+			&mm,
+			true,
+			Some(page_options(cursor)),
+		)
+		.await?;
+		assert!(!page.has_more);
+		assert_eq!(fx_kinds(&page.items), &[NotificationKind::TaskAssigned]);
+
+		// Only the recipient can mark it read.
+		let res =
+			NotificationBmc::mark_read(&ctx_01, &mm, notifications[1].id).await;
+		assert!(
+			matches!(res, Err(Error::EntityNotFound { .. })),
+			"Should have been EntityNotFound, was {res:?}"
+		);
+		NotificationBmc::mark_read(&ctx_02, &mm, notifications[1].id).await?;
+		assert_eq!(NotificationBmc::count_unread(&ctx_02, &mm).await?, 1);
+		assert_eq!(NotificationBmc::mark_all_read(&ctx_02, &mm).await?, 1);
+		assert_eq!(NotificationBmc::count_unread(&ctx_02, &mm).await?, 0);
+
+		// -- Clean
+		sqlx::query("DELETE FROM task WHERE id = $1")
+			.bind(task_id)
+			.execute(&mut *mm.db().await?)
+			.await?;
+		_dev_utils::clean_users(&root_ctx, &mm, &user_ids).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_due_soon_reminders_and_prefs_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let root_ctx = Ctx::root_ctx();
+		let user_ids = _dev_utils::seed_users(
+			&root_ctx,
+			&mm,
+			&["test_due_soon_reminders_and_prefs_ok-user-01"],
+		)
+		.await?;
+		let ctx = Ctx::new(user_ids[0])?;
+		let fx_now = parse_utc("2040-01-01T00:00:00Z")?;
+		NotificationBmc::set_pref(
+			&ctx,
+			&mm,
+			NotificationPref {
+				kind: NotificationKind::TaskAssigned,
+				enabled: false,
+			},
+		)
+		.await?;
+		let mut task_ids = Vec::new();
+		for (title, due_in) in [
+			("test_due_soon_reminders-due-soon", Duration::hours(2)),
+			("test_due_soon_reminders-due-later", Duration::days(3)),
+		] {
+			let task_c = TaskForCreate {
+				title: title.to_string(),
+				due_date: Some(fx_now + due_in),
+				assignee_id: Some(user_ids[0]),
+				..Default::default()
+			};
+			task_ids.push(TaskBmc::create(&root_ctx, &mm, task_c).await?);
+		}
+
+		// -- Exec
+		let first_count =
+			NotificationBmc::notify_due_soon(&mm, fx_now, DUE_SOON_WINDOW).await?;
+		let second_count =
+			NotificationBmc::notify_due_soon(&mm, fx_now, DUE_SOON_WINDOW).await?;
+
+		// -- Check
+		assert_eq!(first_count, 1);
+		assert_eq!(second_count, 0, "Should have been reminded once");
+		let notifications = NotificationBmc::list(&ctx, &mm, false, None).await?;
+		assert_eq!(fx_kinds(&notifications), &[NotificationKind::TaskDueSoon]);
+		assert_eq!(notifications[0].payload["task_id"], task_ids[0]);
+		assert_eq!(notifications[0].payload["due_date"], "2040-01-01T02:00:00Z");
+		let prefs = NotificationBmc::list_prefs(&ctx, &mm).await?;
+		let enabled: Vec<bool> = prefs.iter().map(|pref| pref.enabled).collect();
+		assert_eq!(enabled, &[false, true, true]);
+
+		// -- Clean
+		sqlx::query("DELETE FROM task WHERE id = ANY($1)")
+			.bind(&task_ids)
+			.execute(&mut *mm.db().await?)
+			.await?;
+		_dev_utils::clean_users(&root_ctx, &mm, &user_ids).await?;
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
@@ -0,0 +1,207 @@
+//! Revisions are the history of the versioned entities (i.e., `DbBmc::VERSIONED`).
+//!
+//! Each `base::update` of a versioned entity records, beforehand, the full row
+//! snapshot (as JSON) with the actor (from `Ctx`) and timestamp. So, a revision is
+//! the state of the entity before a given update, and two revisions (or a revision
+//! and the current state) can be diffed field by field.
+//!
+
+use crate::ctx::Ctx;
+use crate::model::base::{and_not_deleted_sql, DbBmc};
+use crate::model::ModelManager;
+use crate::model::{Error, Result};
+use crate::utils::time::{Rfc3339, Rfc3339Schema};
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_json::Value;
+use serde_with::serde_as;
+use sqlx::FromRow;
+use std::collections::BTreeSet;
+use time::OffsetDateTime;
+
+/// Note: Not a `Fields` struct, since `data` is not sqlb bindable.
+const REVISION_COLUMNS: &[&str] =
+	&["id", "entity", "entity_id", "actor_id", "ctime", "data"];
+
+/// The row snapshot of an entity `e`
+/// (without the full-text search column, derived from the other ones).
+const SNAPSHOT_SQL: &str = "to_jsonb(e) - 'search_vec'";
+
+// region:    --- Revision Types
+#[serde_as]
+#[derive(Debug, Clone, FromRow, Serialize, JsonSchema)]
+pub struct Revision {
+	pub id: i64,
+	pub entity: String,
+	pub entity_id: i64,
+
+	pub actor_id: i64,
+	#[serde_as(as = "Rfc3339")]
+	#[schemars(with = "Rfc3339Schema")]
+	pub ctime: OffsetDateTime,
+
+	/// The entity row before the update.
+	pub data: Value,
+}
+
+#[derive(Debug, PartialEq, Serialize, JsonSchema)]
+pub struct FieldDiff {
+	pub field: String,
+	pub from: Value,
+	pub to: Value,
+}
+// endregion: --- Revision Types
+
+// region:    --- RevisionBmc
+pub struct RevisionBmc;
+
+impl DbBmc for RevisionBmc {
+	const TABLE: &'static str = "revision";
+}
+
+impl RevisionBmc {
+	/// Record the current rows of the `MC` entities as new revisions.
+	/// (called by `base::update` and `base::update_many` before the update)
+	pub(in crate::model) async fn record<MC>(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		entity_ids: &[i64],
+	) -> Result<()>
+	where
+		MC: DbBmc,
+	{
+		let sql = format!(
+			r#"INSERT INTO revision (entity, entity_id, actor_id, data)
+			SELECT $1, id, $2, {SNAPSHOT_SQL} FROM "{}" e
+			WHERE id = ANY($3){}"#,
+			MC::TABLE,
+			and_not_deleted_sql::<MC>()
+		);
+
+		sqlx::query(&sql)
+			.bind(MC::TABLE)
+			.bind(ctx.user_id())
+			.bind(entity_ids)
+			.execute(&mut *mm.db().await?)
+			.await?;
+
+		Ok(())
+	}
+
+	/// List the revisions of a `MC` entity, oldest first.
+	pub async fn list_for<MC>(
+		_ctx: &Ctx,
+		mm: &ModelManager,
+		entity_id: i64,
+	) -> Result<Vec<Revision>>
+	where
+		MC: DbBmc,
+	{
+		let revisions: Vec<Revision> = sqlb::select()
+			.table(Self::TABLE)
+			.columns(REVISION_COLUMNS)
+			.and_where("entity", "=", MC::TABLE)
+			.and_where("entity_id", "=", entity_id)
+			.order_by("id")
+			.fetch_all(&mut *mm.db().await?)
+			.await?;
+
+		Ok(revisions)
+	}
+
+	/// Get a revision, which must belong to the given `MC` entity.
+	pub async fn get_for<MC>(
+		_ctx: &Ctx,
+		mm: &ModelManager,
+		entity_id: i64,
+		id: i64,
+	) -> Result<Revision>
+	where
+		MC: DbBmc,
+	{
+		let revision: Revision = sqlb::select()
+			.table(Self::TABLE)
+			.columns(REVISION_COLUMNS)
+			.and_where("id", "=", id)
+			.and_where("entity", "=", MC::TABLE)
+			.and_where("entity_id", "=", entity_id)
+			.fetch_optional(&mut *mm.db().await?)
+			.await?
+			.ok_or(Error::EntityNotFound {
+				entity: Self::TABLE,
+				id,
+			})?;
+
+		Ok(revision)
+	}
+
+	/// Field level diff from a revision to another one
+	/// (or to the current entity state when `to_id` is None).
+	pub async fn diff_for<MC>(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		entity_id: i64,
+		from_id: i64,
+		to_id: Option<i64>,
+	) -> Result<Vec<FieldDiff>>
+	where
+		MC: DbBmc,
+	{
+		let from = Self::get_for::<MC>(ctx, mm, entity_id, from_id).await?.data;
+		let to = match to_id {
+			Some(to_id) => {
+				Self::get_for::<MC>(ctx, mm, entity_id, to_id).await?.data
+			}
+			None => Self::current_snapshot::<MC>(mm, entity_id).await?,
+		};
+
+		Ok(diff_snapshots(&from, &to))
+	}
+
+	async fn current_snapshot<MC>(mm: &ModelManager, entity_id: i64) -> Result<Value>
+	where
+		MC: DbBmc,
+	{
+		let sql = format!(
+			r#"SELECT {SNAPSHOT_SQL} FROM "{}" e WHERE id = $1"#,
+			MC::TABLE
+		);
+
+		let (snapshot,): (Value,) = sqlx::query_as(&sql)
+			.bind(entity_id)
+			.fetch_optional(&mut *mm.db().await?)
+			.await?
+			.ok_or(Error::EntityNotFound {
+				entity: MC::TABLE,
+				id: entity_id,
+			})?;
+
+		Ok(snapshot)
+	}
+}
+// endregion: --- RevisionBmc
+
+/// Returns the fields (sorted by name) which differ between two row snapshots.
+/// (the `id` and `version` bookkeeping fields are skipped)
+pub fn diff_snapshots(from: &Value, to: &Value) -> Vec<FieldDiff> {
+	let fields: BTreeSet<&String> = [from, to]
+		.iter()
+		.filter_map(|v| v.as_object())
+		.flat_map(|obj| obj.keys())
+		.collect();
+
+	fields
+		.into_iter()
+		.filter(|field| !matches!(field.as_str(), "id" | "version"))
+		.filter_map(|field| {
+			let from = from.get(field).cloned().unwrap_or(Value::Null);
+			let to = to.get(field).cloned().unwrap_or(Value::Null);
+
+			(from != to).then(|| FieldDiff {
+				field: field.to_string(),
+				from,
+				to,
+			})
+		})
+		.collect()
+}
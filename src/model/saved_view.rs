@@ -0,0 +1,352 @@
+//! Saved views are the named tasks list definitions (i.e., the `list_tasks`
+//! filter and list options) of a user, to be listed again by id
+//! (see `task_rpc::list_tasks_by_view`).
+//!
+//! Notes:
+//!
+//! - The view names are unique per user.
+//! - A shared view is visible to all the users (the tasks being shared by all
+//!   of them for now), but only its owner can update or delete it.
+//!
+
+use crate::ctx::Ctx;
+use crate::model::base::ListOptions;
+use crate::model::task::{TaskBmc, TaskFilter};
+use crate::model::{Error, ModelManager, Result};
+use crate::utils::time::{now_utc, Rfc3339, Rfc3339Schema};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use sqlx::types::Json;
+use sqlx::FromRow;
+use time::OffsetDateTime;
+
+const TABLE: &str = "saved_view";
+
+const NAME_MAX_LEN: usize = 128;
+
+// region:    --- SavedView Types
+
+/// The tasks list of a view (the same shape as the `list_tasks` params).
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct SavedViewQuery {
+	pub filter: Option<TaskFilter>,
+	pub list_options: Option<ListOptions>,
+}
+
+#[serde_as]
+#[derive(Debug, Clone, FromRow, Serialize, JsonSchema)]
+pub struct SavedView {
+	pub id: i64,
+	pub owner_id: i64,
+	pub name: String,
+	#[sqlx(json)]
+	pub query: SavedViewQuery,
+	pub shared: bool,
+
+	#[serde_as(as = "Rfc3339")]
+	#[schemars(with = "Rfc3339Schema")]
+	pub ctime: OffsetDateTime,
+	#[serde_as(as = "Option<Rfc3339>")]
+	#[schemars(with = "Option<Rfc3339Schema>")]
+	pub mtime: Option<OffsetDateTime>,
+}
+
+#[derive(Clone, Deserialize, JsonSchema)]
+pub struct SavedViewForCreate {
+	pub name: String,
+	pub query: SavedViewQuery,
+	#[serde(default)]
+	pub shared: bool,
+}
+
+#[derive(Clone, Default, Deserialize, JsonSchema)]
+pub struct SavedViewForUpdate {
+	pub name: Option<String>,
+	pub query: Option<SavedViewQuery>,
+	pub shared: Option<bool>,
+}
+
+// endregion: --- SavedView Types
+
+// region:    --- SavedViewBmc
+
+pub struct SavedViewBmc;
+
+impl SavedViewBmc {
+	/// Create a view of the ctx user, returning its id.
+	pub async fn create(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		saved_view_c: SavedViewForCreate,
+	) -> Result<i64> {
+		let SavedViewForCreate {
+			name,
+			query,
+			shared,
+		} = saved_view_c;
+		let name = validate(Some(name), Some(&query))?.unwrap_or_default();
+
+		let (id,): (i64,) = sqlx::query_as(
+			"INSERT INTO saved_view (owner_id, name, query, shared)
+			 VALUES ($1, $2, $3, $4) RETURNING id",
+		)
+		.bind(ctx.user_id())
+		.bind(&name)
+		.bind(Json(query))
+		.bind(shared)
+		.fetch_one(&mut *mm.db().await?)
+		.await
+		.map_err(|ex| name_taken_or(ex, &name))?;
+
+		Ok(id)
+	}
+
+	/// Get a view of the ctx user, or a shared one.
+	pub async fn get(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<SavedView> {
+		let saved_view: SavedView = sqlx::query_as(
+			"SELECT id, owner_id, name, query, shared, ctime, mtime
+			 FROM saved_view WHERE id = $1",
+		)
+		.bind(id)
+		.fetch_optional(&mut *mm.db().await?)
+		.await?
+		.ok_or(Error::EntityNotFound { entity: TABLE, id })?;
+
+		if !saved_view.shared {
+			check_owner(ctx, &saved_view)?;
+		}
+
+		Ok(saved_view)
+	}
+
+	/// The views of the ctx user, and the shared ones, by name.
+	pub async fn list(ctx: &Ctx, mm: &ModelManager) -> Result<Vec<SavedView>> {
+		let saved_views = sqlx::query_as(
+			"SELECT id, owner_id, name, query, shared, ctime, mtime
+			 FROM saved_view WHERE owner_id = $1 OR shared
+			 ORDER BY name, id",
+		)
+		.bind(ctx.user_id())
+		.fetch_all(&mut *mm.db().await?)
+		.await?;
+
+		Ok(saved_views)
+	}
+
+	/// Update a view (of the ctx user only).
+	pub async fn update(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		id: i64,
+		saved_view_u: SavedViewForUpdate,
+	) -> Result<()> {
+		let SavedViewForUpdate {
+			name,
+			query,
+			shared,
+		} = saved_view_u;
+		let name = validate(name, query.as_ref())?;
+		check_owner(ctx, &Self::get(ctx, mm, id).await?)?;
+
+		sqlx::query(
+			"UPDATE saved_view SET
+				name = COALESCE($2, name),
+				query = COALESCE($3, query),
+				shared = COALESCE($4, shared),
+				mtime = $5
+			 WHERE id = $1",
+		)
+		.bind(id)
+		.bind(&name)
+		.bind(query.map(Json))
+		.bind(shared)
+		.bind(now_utc())
+		.execute(&mut *mm.db().await?)
+		.await
+		.map_err(|ex| name_taken_or(ex, name.as_deref().unwrap_or_default()))?;
+
+		Ok(())
+	}
+
+	/// Delete a view (of the ctx user only).
+	pub async fn delete(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
+		check_owner(ctx, &Self::get(ctx, mm, id).await?)?;
+
+		sqlx::query("DELETE FROM saved_view WHERE id = $1")
+			.bind(id)
+			.execute(&mut *mm.db().await?)
+			.await?;
+
+		Ok(())
+	}
+}
+
+/// Validate the name (returned trimmed) and the query order bys (when given).
+fn validate(
+	name: Option<String>,
+	query: Option<&SavedViewQuery>,
+) -> Result<Option<String>> {
+	let name = name.map(|name| name.trim().to_string());
+	if let Some(name) = &name {
+		if name.is_empty() || name.chars().count() > NAME_MAX_LEN {
+			return Err(Error::SavedViewInvalidName(name.to_string()));
+		}
+	}
+
+	if let Some(list_options) = query.and_then(|query| query.list_options.as_ref()) {
+		list_options.order_bys::<TaskBmc>()?;
+		// Note: A cursor is for the page after another, not a view option.
+		list_options.check_no_cursor()?;
+	}
+
+	Ok(name)
+}
+
+fn check_owner(ctx: &Ctx, saved_view: &SavedView) -> Result<()> {
+	let user_id = ctx.user_id();
+
+	if user_id == saved_view.owner_id || user_id == Ctx::root_ctx().user_id() {
+		Ok(())
+	} else {
+		Err(Error::AccessDenied {
+			entity: TABLE,
+			id: saved_view.id,
+		})
+	}
+}
+
+/// `Error::SavedViewNameTaken` for the `(owner_id, name)` unique violation.
+fn name_taken_or(ex: sqlx::Error, name: &str) -> Error {
+	match ex {
+		sqlx::Error::Database(db_ex) if db_ex.is_unique_violation() => {
+			Error::SavedViewNameTaken(name.to_string())
+		}
+		ex => ex.into(),
+	}
+}
+
+// endregion: --- SavedViewBmc
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::_dev_utils;
+	use anyhow::Result;
+	use serial_test::serial;
+
+	fn fx_saved_view_c(name: &str, shared: bool) -> SavedViewForCreate {
+		SavedViewForCreate {
+			name: name.to_string(),
+			query: SavedViewQuery {
+				filter: Some(TaskFilter {
+					done: Some(false),
+					..Default::default()
+				}),
+				list_options: Some(ListOptions {
+					order_bys: Some(vec!["!due_date".to_string()]),
+					..Default::default()
+				}),
+			},
+			shared,
+		}
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_create_list_ok_shared() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let root_ctx = Ctx::root_ctx();
+		let user_ids = _dev_utils::seed_users(
+			&root_ctx,
+			&mm,
+			&[
+				"test_create_list_ok_shared-user-01",
+				"test_create_list_ok_shared-user-02",
+			],
+		)
+		.await?;
+		let (ctx_01, ctx_02) = (Ctx::new(user_ids[0])?, Ctx::new(user_ids[1])?);
+
+		// -- Exec
+		let private_id =
+			SavedViewBmc::create(&ctx_01, &mm, fx_saved_view_c(" Mine ", false))
+				.await?;
+		let shared_id =
+			SavedViewBmc::create(&ctx_01, &mm, fx_saved_view_c("Team", true))
+				.await?;
+		let taken_res =
+			SavedViewBmc::create(&ctx_01, &mm, fx_saved_view_c("Mine", true)).await;
+
+		// -- Check
+		assert!(
+			matches!(&taken_res, Err(Error::SavedViewNameTaken(name)) if name == "Mine"),
+			"Should have been SavedViewNameTaken, was {taken_res:?}"
+		);
+		let own_views = SavedViewBmc::list(&ctx_01, &mm).await?;
+		let names: Vec<&str> = own_views.iter().map(|v| v.name.as_str()).collect();
+		assert_eq!(names, &["Mine", "Team"]);
+		assert_eq!(
+			own_views[0]
+				.query
+				.list_options
+				.as_ref()
+				.and_then(|o| o.order_bys.clone()),
+			Some(vec!["!due_date".to_string()])
+		);
+
+		// The other user only sees (and cannot change) the shared view.
+		let other_views = SavedViewBmc::list(&ctx_02, &mm).await?;
+		let ids: Vec<i64> = other_views.iter().map(|v| v.id).collect();
+		assert_eq!(ids, &[shared_id]);
+		let get_res = SavedViewBmc::get(&ctx_02, &mm, private_id).await;
+		assert!(matches!(get_res, Err(Error::AccessDenied { .. })));
+		let delete_res = SavedViewBmc::delete(&ctx_02, &mm, shared_id).await;
+		assert!(matches!(delete_res, Err(Error::AccessDenied { .. })));
+
+		// -- Clean
+		_dev_utils::clean_users(&root_ctx, &mm, &user_ids).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_create_err_invalid() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let mut fx_bad_order = fx_saved_view_c("test_create_err_invalid", false);
+		fx_bad_order.query.list_options = Some(ListOptions {
+			order_bys: Some(vec!["title; DROP TABLE task".to_string()]),
+			..Default::default()
+		});
+		let mut fx_cursor = fx_saved_view_c("test_create_err_invalid", false);
+		fx_cursor.query.list_options = Some(ListOptions {
+			cursor: Some("some-cursor".to_string()),
+			..Default::default()
+		});
+
+		// -- Exec
+		let name_res =
+			SavedViewBmc::create(&ctx, &mm, fx_saved_view_c("  ", false)).await;
+		let order_res = SavedViewBmc::create(&ctx, &mm, fx_bad_order).await;
+		let cursor_res = SavedViewBmc::create(&ctx, &mm, fx_cursor).await;
+
+		// -- Check
+		assert!(matches!(name_res, Err(Error::SavedViewInvalidName(_))));
+		assert!(
+			matches!(
+				order_res,
+				Err(Error::ListOrderByInvalid { entity: "task", .. })
+			),
+			"Should have been ListOrderByInvalid, was {order_res:?}"
+		);
+		assert!(matches!(cursor_res, Err(Error::ListCursorUnsupported)));
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
@@ -0,0 +1,299 @@
+//! Full-text search across the tasks (title) and comments (body).
+//!
+//! Design:
+//!
+//! - The `search_vec` tsvector columns are maintained by triggers (see the schema),
+//!   with the text search configuration of `SERVICE_SEARCH_CONFIG`.
+//! - The task titles weigh more than the comment bodies in the ranking.
+//! - The query words all match, the last one as a prefix (for type-ahead).
+//! - The tasks are shared by all the users for now (see `ChangeEvent::is_visible_to`),
+//!   so only the tasks in the trash (and their comments) are filtered out.
+//!
+
+use crate::ctx::Ctx;
+use crate::model::base::ListOptions;
+use crate::model::{ModelManager, Result};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// The `ts_headline` highlight delimiters (private use characters, so not in
+/// the text, and replaced by `<mark>`s once the snippet is escaped).
+const MARK_START: char = '\u{E000}';
+const MARK_END: char = '\u{E001}';
+
+// region:    --- Search Types
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchEntity {
+	Task,
+	Comment,
+}
+
+impl SearchEntity {
+	fn table(self) -> &'static str {
+		match self {
+			Self::Task => "task",
+			Self::Comment => "comment",
+		}
+	}
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct SearchHit {
+	pub entity: SearchEntity,
+	pub id: i64,
+	/// The task of the hit (i.e., the `id` for a task).
+	pub task_id: i64,
+	pub task_title: String,
+	pub rank: f32,
+	/// HTML escaped excerpt, with the matches in `<mark>` elements.
+	pub snippet: String,
+}
+
+#[derive(FromRow)]
+struct SearchRow {
+	entity: String,
+	id: i64,
+	task_id: i64,
+	task_title: String,
+	rank: f32,
+	snippet: String,
+}
+
+impl From<SearchRow> for SearchHit {
+	fn from(row: SearchRow) -> Self {
+		Self {
+			entity: if row.entity == "task" {
+				SearchEntity::Task
+			} else {
+				SearchEntity::Comment
+			},
+			id: row.id,
+			task_id: row.task_id,
+			task_title: row.task_title,
+			rank: row.rank,
+			snippet: highlight_html(&row.snippet),
+		}
+	}
+}
+
+// endregion: --- Search Types
+
+// region:    --- SearchBmc
+
+pub struct SearchBmc;
+
+impl SearchBmc {
+	/// Search the `entities` (all when None), best ranked first.
+	///
+	/// Returns no hits when the query has no words.
+	pub async fn search(
+		_ctx: &Ctx,
+		mm: &ModelManager,
+		query: &str,
+		entities: Option<Vec<SearchEntity>>,
+		list_options: Option<ListOptions>,
+	) -> Result<Vec<SearchHit>> {
+		let Some(tsquery) = prefix_tsquery(query) else {
+			return Ok(Vec::new());
+		};
+		let tables: Vec<&str> = entities
+			.unwrap_or_else(|| vec![SearchEntity::Task, SearchEntity::Comment])
+			.into_iter()
+			.map(SearchEntity::table)
+			.collect();
+		let list_options = list_options.unwrap_or_default();
+		list_options.check_no_cursor()?;
+		let (limit, offset) = list_options.limit_offset();
+		let headline_options =
+			format!("StartSel={MARK_START}, StopSel={MARK_END}, MaxWords=30");
+
+		// Notes: The snippets are only computed for the returned page.
+		//        The titles are short, so highlighted whole (the excerpts of the
+		//        comments dropping the tag like parts of the text, e.g., `<b>`).
+		let rows: Vec<SearchRow> = sqlx::query_as(
+			"WITH q AS (SELECT to_tsquery(search_config(), $1) AS query),
+			 hits AS (
+			   SELECT 'task' AS entity, t.id, t.id AS task_id, t.title AS task_title,
+			          t.title AS text, ts_rank(t.search_vec, q.query) AS rank
+			   FROM task t, q
+			   WHERE 'task' = ANY($2) AND t.search_vec @@ q.query
+			     AND t.deleted_at IS NULL
+			   UNION ALL
+			   SELECT 'comment', c.id, c.task_id, t.title,
+			          c.body, ts_rank(c.search_vec, q.query)
+			   FROM comment c JOIN task t ON t.id = c.task_id, q
+			   WHERE 'comment' = ANY($2) AND c.search_vec @@ q.query
+			     AND t.deleted_at IS NULL
+			   ORDER BY rank DESC, entity DESC, id
+			   LIMIT $3 OFFSET $4)
+			 SELECT entity, id, task_id, task_title, rank,
+			        ts_headline(search_config(), text, q.query,
+			          $5 || CASE entity WHEN 'task' THEN ', HighlightAll=true' ELSE '' END)
+			          AS snippet
+			 FROM hits, q
+			 ORDER BY rank DESC, entity DESC, id",
+		)
+		.bind(tsquery)
+		.bind(tables)
+		.bind(limit)
+		.bind(offset)
+		.bind(headline_options)
+		.fetch_all(&mut *mm.db().await?)
+		.await?;
+
+		Ok(rows.into_iter().map(SearchHit::from).collect())
+	}
+}
+
+// endregion: --- SearchBmc
+
+// region:    --- Query Helpers
+
+/// The `to_tsquery` input of the user query words (letters and digits only),
+/// all required, the last one as a prefix (e.g., `buy mil` -> `buy & mil:*`).
+///
+/// Returns None when the query has no words.
+fn prefix_tsquery(query: &str) -> Option<String> {
+	let words: Vec<&str> = query
+		.split(|c: char| !c.is_alphanumeric())
+		.filter(|word| !word.is_empty())
+		.collect();
+	let (last, words) = words.split_last()?;
+
+	let mut tsquery: Vec<String> =
+		words.iter().map(|word| word.to_string()).collect();
+	tsquery.push(format!("{last}:*"));
+
+	Some(tsquery.join(" & "))
+}
+
+/// HTML escape the `ts_headline` snippet, then turn its marks into `<mark>`s.
+fn highlight_html(snippet: &str) -> String {
+	let mut html = String::with_capacity(snippet.len());
+	for c in snippet.chars() {
+		match c {
+			'&' => html.push_str("&amp;"),
+			'<' => html.push_str("&lt;"),
+			'>' => html.push_str("&gt;"),
+			'"' => html.push_str("&quot;"),
+			'\'' => html.push_str("&#39;"),
+			MARK_START => html.push_str("<mark>"),
+			MARK_END => html.push_str("</mark>"),
+			c => html.push(c),
+		}
+	}
+
+	html
+}
+
+// endregion: --- Query Helpers
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::_dev_utils;
+	use crate::model::comment::{CommentBmc, CommentForCreate};
+	use crate::model::task::{TaskBmc, TaskForCreate};
+	use anyhow::Result;
+	use serial_test::serial;
+
+	#[test]
+	fn test_prefix_tsquery_ok() -> Result<()> {
+		// -- Fixtures
+		let fx_cases = &[
+			("buy mil", Some("buy & mil:*")),
+			("  it's (done)!", Some("it & s & done:*")),
+			("café", Some("café:*")),
+			(" &|!:* ", None),
+		];
+
+		// -- Exec & Check
+		for (fx_query, fx_tsquery) in fx_cases {
+			assert_eq!(prefix_tsquery(fx_query).as_deref(), *fx_tsquery);
+		}
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_search_ok_ranked_and_highlighted() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_titles = [
+			"test_search_ok Buy <fresh> milkshakes",
+			"test_search_ok Call the plumber",
+			"test_search_ok Buy milk (trashed)",
+		];
+		let mut task_ids = Vec::new();
+		for title in fx_titles {
+			let task_c = TaskForCreate {
+				title: title.to_string(),
+				..Default::default()
+			};
+			task_ids.push(TaskBmc::create(&ctx, &mm, task_c).await?);
+		}
+		let comment_id = CommentBmc::create(
+			&ctx,
+			&mm,
+			CommentForCreate {
+				task_id: task_ids[1],
+				body: "He will buy the milk on his way.".to_string(),
+			},
+		)
+		.await?;
+		TaskBmc::delete(&ctx, &mm, task_ids[2]).await?;
+
+		// -- Exec
+		let hits = SearchBmc::search(&ctx, &mm, "buying mil", None, None).await?;
+		let comment_hits = SearchBmc::search(
+			&ctx,
+			&mm,
+			"buying mil",
+			Some(vec![SearchEntity::Comment]),
+			None,
+		)
+		.await?;
+
+		// -- Check
+		// The task title ranks before the comment.
+		let hits: Vec<&SearchHit> = hits
+			.iter()
+			.filter(|hit| hit.task_id >= task_ids[0])
+			.collect();
+		let found: Vec<(SearchEntity, i64)> =
+			hits.iter().map(|hit| (hit.entity, hit.id)).collect();
+		assert_eq!(
+			found,
+			&[
+				(SearchEntity::Task, task_ids[0]),
+				(SearchEntity::Comment, comment_id)
+			]
+		);
+		assert_eq!(
+			hits[0].snippet,
+			"test_search_ok <mark>Buy</mark> &lt;fresh&gt; <mark>milkshakes</mark>"
+		);
+		assert_eq!(hits[1].task_title, fx_titles[1]);
+		assert!(comment_hits
+			.iter()
+			.all(|h| h.entity == SearchEntity::Comment));
+		assert!(SearchBmc::search(&ctx, &mm, " ! ", None, None)
+			.await?
+			.is_empty());
+
+		// -- Clean
+		sqlx::query("DELETE FROM task WHERE id = ANY($1)")
+			.bind(&task_ids)
+			.execute(&mut *mm.db().await?)
+			.await?;
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
@@ -16,8 +16,27 @@ pub async fn new_db_pool() -> Result<Db> {
 	// * See NOTE 1) below
 	let max_connections = if cfg!(test) { 1 } else { 5 };
 
+	new_db_pool_sized(max_connections).await
+}
+
+/// A pool of `max_connections` (e.g., for the tests of concurrent transactions).
+pub async fn new_db_pool_sized(max_connections: u32) -> Result<Db> {
 	PgPoolOptions::new()
 		.max_connections(max_connections)
+		// The text search configuration of the full-text search triggers and
+		// queries (see `search_config()` in the schema). The cast fails the
+		// connection when it is not a valid configuration.
+		.after_connect(|conn, _meta| {
+			Box::pin(async move {
+				sqlx::query(
+					"SELECT set_config('app.search_config', $1, false)::regconfig",
+				)
+				.bind(&config().SEARCH_CONFIG)
+				.execute(conn)
+				.await?;
+				Ok(())
+			})
+		})
 		.connect(&config().DB_URL)
 		.await
 		.map_err(|ex| Error::FailToCreatePool(ex.to_string()))
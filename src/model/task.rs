@@ -1,26 +1,155 @@
 use crate::ctx::Ctx;
-use crate::model::base::{self, DbBmc};
+use crate::model::base::{self, DbBmc, ListOptions, ListPage};
+use crate::model::event::ChangeKind;
+use crate::model::notification::NotificationBmc;
+use crate::model::revision::{FieldDiff, Revision, RevisionBmc};
+use crate::model::user::{User, UserBmc};
 use crate::model::ModelManager;
-use crate::model::Result;
+use crate::model::{Error, Result};
+use crate::recurrence::RRule;
+use crate::utils::rank::{rank_between, rank_evenly, ranks_between};
+use crate::utils::time::{Clock, Rfc3339, Rfc3339Schema, SystemClock};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use sqlb::Fields;
+use serde_with::serde_as;
+use sqlb::{Fields, HasFields, Raw, SelectSqlBuilder};
 use sqlx::FromRow;
+use time::OffsetDateTime;
+
+/// Rank keys longer than this trigger a rebalance of all the task ranks.
+const RANK_REBALANCE_LEN: usize = 16;
+
+/// The table locked by the task appends (see `ModelManager::lock_table`).
+const APPEND_LOCK_TABLE: &str = "task_append_lock";
 
 // region:    --- Task Types
-#[derive(Debug, Clone, Fields, FromRow, Serialize)]
+#[serde_as]
+#[derive(Debug, Clone, Fields, FromRow, Serialize, JsonSchema)]
 pub struct Task {
 	pub id: i64,
 	pub title: String,
+	pub done: bool,
+	pub rank: String,
+	#[serde_as(as = "Option<Rfc3339>")]
+	#[schemars(with = "Option<Rfc3339Schema>")]
+	pub due_date: Option<OffsetDateTime>,
+	pub rrule: Option<String>,
+	pub assignee_id: Option<i64>,
+	pub version: i64,
 }
 
-#[derive(Fields, Deserialize)]
+#[serde_as]
+#[derive(Clone, Default, Fields, Deserialize, JsonSchema)]
 pub struct TaskForCreate {
 	pub title: String,
+	#[serde_as(as = "Option<Rfc3339>")]
+	#[schemars(with = "Option<Rfc3339Schema>")]
+	#[serde(default)]
+	pub due_date: Option<OffsetDateTime>,
+	pub rrule: Option<String>,
+	/// The assigned user (notified, see `model::notification`).
+	pub assignee_id: Option<i64>,
 }
 
-#[derive(Fields, Deserialize)]
+#[derive(Fields)]
+struct TaskForInsert {
+	title: String,
+	rank: String,
+	due_date: Option<OffsetDateTime>,
+	rrule: Option<String>,
+	assignee_id: Option<i64>,
+}
+
+#[serde_as]
+#[derive(Clone, Default, Fields, Deserialize, JsonSchema)]
 pub struct TaskForUpdate {
 	pub title: Option<String>,
+	pub done: Option<bool>,
+	#[serde_as(as = "Option<Rfc3339>")]
+	#[schemars(with = "Option<Rfc3339Schema>")]
+	#[serde(default)]
+	pub due_date: Option<OffsetDateTime>,
+	pub rrule: Option<String>,
+	pub assignee_id: Option<i64>,
+}
+
+/// The fields of a task restored by `TaskBmc::revert`, from a revision snapshot
+/// (the nullable ones set back to null when null in the snapshot).
+#[serde_as]
+#[derive(Fields, Deserialize)]
+struct TaskForRevert {
+	title: String,
+	done: bool,
+	#[serde_as(as = "Option<Rfc3339>")]
+	#[serde(default)]
+	due_date: Option<OffsetDateTime>,
+	rrule: Option<String>,
+	assignee_id: Option<i64>,
+}
+
+/// Where to move a task, relative to another task of the list.
+/// (e.g., `{"before": 1001}`)
+#[derive(Debug, Clone, Copy, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskMove {
+	Before(i64),
+	After(i64),
+}
+
+/// The filter of `TaskBmc::list_by_filter` (all the given conditions apply).
+#[serde_as]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct TaskFilter {
+	pub done: Option<bool>,
+	/// Case insensitive substring of the title.
+	pub title_contains: Option<String>,
+	/// Tasks due strictly before this time.
+	#[serde_as(as = "Option<Rfc3339>")]
+	#[schemars(with = "Option<Rfc3339Schema>")]
+	#[serde(default)]
+	pub due_before: Option<OffsetDateTime>,
+	/// Tasks due at or after this time.
+	#[serde_as(as = "Option<Rfc3339>")]
+	#[schemars(with = "Option<Rfc3339Schema>")]
+	#[serde(default)]
+	pub due_after: Option<OffsetDateTime>,
+}
+
+impl TaskFilter {
+	fn and_wheres(self, sb: SelectSqlBuilder) -> SelectSqlBuilder {
+		let TaskFilter {
+			done,
+			title_contains,
+			due_before,
+			due_after,
+		} = self;
+
+		let mut sb = sb;
+		if let Some(done) = done {
+			sb = sb.and_where("done", "=", done);
+		}
+		if let Some(title_contains) = title_contains {
+			sb = sb.and_where("title", "ILIKE", like_contains(&title_contains));
+		}
+		if let Some(due_before) = due_before {
+			sb = sb.and_where("due_date", "<", due_before);
+		}
+		if let Some(due_after) = due_after {
+			sb = sb.and_where("due_date", ">=", due_after);
+		}
+
+		sb
+	}
+}
+
+/// The `LIKE` pattern matching `value` anywhere (its wildcards escaped).
+fn like_contains(value: &str) -> String {
+	let value = value
+		.replace('\\', "\\\\")
+		.replace('%', "\\%")
+		.replace('_', "\\_");
+
+	format!("%{value}%")
 }
 // endregion: --- Task Types
 
@@ -29,15 +158,60 @@ pub struct TaskBmc;
 
 impl DbBmc for TaskBmc {
 	const TABLE: &'static str = "task";
+	const LIST_ORDER_BYS: &'static [&'static str] = &["rank", "id"];
+	const LIST_ORDERABLE: &'static [&'static str] =
+		&["id", "title", "done", "rank", "due_date"];
+	const SOFT_DELETE: bool = true;
+	const VERSIONED: bool = true;
+	const CHANGE_EVENTS: bool = true;
 }
 
 impl TaskBmc {
+	/// Create the task, at the end of the list.
+	///
+	/// Note: In a transaction, the creates run one at a time, and are best first
+	///       in it (see `ModelManager::lock_table`).
 	pub async fn create(
 		ctx: &Ctx,
 		mm: &ModelManager,
 		task_c: TaskForCreate,
 	) -> Result<i64> {
-		base::create::<Self, _>(ctx, mm, task_c).await
+		let TaskForCreate {
+			title,
+			due_date,
+			rrule,
+			assignee_id,
+		} = task_c;
+
+		// -- New tasks go at the end of the list (one append at a time).
+		mm.lock_table(APPEND_LOCK_TABLE).await?;
+		Self::validate(ctx, mm, rrule.as_deref(), assignee_id).await?;
+
+		let last_rank = Self::rank_neighbor(mm, None, "!rank", None).await?;
+		let rank = rank_between(last_rank.as_deref(), None)?;
+
+		let task_fi = TaskForInsert {
+			title,
+			rank,
+			due_date,
+			rrule,
+			assignee_id,
+		};
+
+		let rank_len = task_fi.rank.len();
+		let id = base::create::<Self, _>(ctx, mm, task_fi).await?;
+
+		// Appending grows the last rank key, until rebalanced.
+		if rank_len > RANK_REBALANCE_LEN {
+			Self::rebalance_ranks(ctx, mm).await?;
+		}
+
+		if assignee_id.is_some() {
+			let task = Self::get(ctx, mm, id).await?;
+			NotificationBmc::notify_task_assigned(ctx, mm, &task).await?;
+		}
+
+		Ok(id)
 	}
 
 	pub async fn get(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<Task> {
@@ -48,18 +222,573 @@ impl TaskBmc {
 		base::list::<Self, _>(ctx, mm).await
 	}
 
+	/// List the tasks matching the filter, in the list order
+	/// (without cursor, see `list_page` for the cursors).
+	pub async fn list_by_filter(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		filter: TaskFilter,
+		list_options: Option<ListOptions>,
+	) -> Result<Vec<Task>> {
+		if let Some(list_options) = &list_options {
+			list_options.check_no_cursor()?;
+		}
+		let page = Self::list_page(ctx, mm, filter, list_options).await?;
+
+		Ok(page.items)
+	}
+
+	/// List a page of the tasks matching the filter, in the list order
+	/// (see `base::list_page` for the cursors).
+	pub async fn list_page(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		filter: TaskFilter,
+		list_options: Option<ListOptions>,
+	) -> Result<ListPage<Task>> {
+		let sb = filter.and_wheres(sqlb::select());
+
+		base::list_page::<Self, _>(ctx, mm, sb, list_options).await
+	}
+
+	/// Update the task (see `update_with_clock`).
 	pub async fn update(
 		ctx: &Ctx,
 		mm: &ModelManager,
 		id: i64,
 		task_u: TaskForUpdate,
+		expected_version: Option<i64>,
+	) -> Result<()> {
+		Self::update_with_clock(ctx, mm, id, task_u, expected_version, &SystemClock)
+			.await?;
+
+		Ok(())
+	}
+
+	/// Update the task, and when it gets completed (`done` set to true) and has
+	/// a recurrence rule, create the task of the next occurrence.
+	///
+	/// When it gets assigned to another user, the new assignee is notified.
+	///
+	/// When `expected_version` is given, fails with `Error::VersionConflict`
+	/// if the task was updated since.
+	///
+	/// Returns the id of the next occurrence task, if one was created.
+	pub async fn update_with_clock(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		id: i64,
+		task_u: TaskForUpdate,
+		expected_version: Option<i64>,
+		clock: &dyn Clock,
+	) -> Result<Option<i64>> {
+		Self::validate(ctx, mm, task_u.rrule.as_deref(), task_u.assignee_id).await?;
+
+		let completing = task_u.done == Some(true);
+		let assigning = task_u.assignee_id.is_some();
+		let before = if completing || assigning {
+			Some(Self::get(ctx, mm, id).await?)
+		} else {
+			None
+		};
+		let was_done = before.as_ref().is_some_and(|task| task.done);
+		let reassigned = before
+			.is_some_and(|task| assigning && task.assignee_id != task_u.assignee_id);
+
+		base::update::<Self, _>(ctx, mm, id, task_u, expected_version).await?;
+
+		if reassigned {
+			let task = Self::get(ctx, mm, id).await?;
+			NotificationBmc::notify_task_assigned(ctx, mm, &task).await?;
+		}
+
+		if !completing || was_done {
+			return Ok(None);
+		}
+
+		let task = Self::get(ctx, mm, id).await?;
+		Self::create_next_occurrence(ctx, mm, task, clock).await
+	}
+
+	pub async fn list_revisions(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		id: i64,
+	) -> Result<Vec<Revision>> {
+		RevisionBmc::list_for::<Self>(ctx, mm, id).await
+	}
+
+	/// Field level diff between two revisions of the task
+	/// (or with the current task when `to_revision_id` is None).
+	pub async fn diff_revisions(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		id: i64,
+		from_revision_id: i64,
+		to_revision_id: Option<i64>,
+	) -> Result<Vec<FieldDiff>> {
+		RevisionBmc::diff_for::<Self>(ctx, mm, id, from_revision_id, to_revision_id)
+			.await
+	}
+
+	/// Apply the task state of a revision as a new update (so, a new revision).
+	///
+	/// Notes:
+	/// - Only the `TaskForUpdate` fields are reverted (e.g., not the rank), and
+	///   the fields that were null in the revision are set back to null.
+	/// - Without the update side effects (i.e., reverting to a done task does
+	///   not create its next occurrence, nor notifies the assignee).
+	pub async fn revert(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		id: i64,
+		revision_id: i64,
 	) -> Result<()> {
-		base::update::<Self, _>(ctx, mm, id, task_u).await
+		let revision =
+			RevisionBmc::get_for::<Self>(ctx, mm, id, revision_id).await?;
+		let task_r: TaskForRevert = serde_json::from_value(revision.data)?;
+		Self::validate(ctx, mm, task_r.rrule.as_deref(), task_r.assignee_id).await?;
+
+		base::update_fields::<Self>(ctx, mm, id, task_r.all_fields(), None).await
 	}
 
+	/// Move the task to the trash (see `restore` and `purge`).
 	pub async fn delete(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
 		base::delete::<Self>(ctx, mm, id).await
 	}
+
+	// -- Bulk
+	//    (in one transaction, with per task results,
+	//     and at most `base::BULK_MAX_ITEMS` tasks)
+
+	/// Create the tasks, appended in order at the end of the list.
+	/// The invalid ones (e.g., bad rrule) are reported and not created.
+	pub async fn create_many(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		tasks_c: Vec<TaskForCreate>,
+	) -> Result<Vec<Result<i64>>> {
+		base::check_bulk_count(tasks_c.len())?;
+
+		// -- Validate (the invalid ones get their error rather than an insert,
+		//    the other errors failing the call).
+		let mut validated = Vec::with_capacity(tasks_c.len());
+		for task_c in tasks_c {
+			let res =
+				Self::validate(ctx, mm, task_c.rrule.as_deref(), task_c.assignee_id)
+					.await;
+			match res {
+				Ok(()) => validated.push(Ok(task_c)),
+				Err(ex @ (Error::Recurrence(_) | Error::EntityNotFound { .. })) => {
+					validated.push(Err(ex))
+				}
+				Err(ex) => return Err(ex),
+			}
+		}
+		let tasks_c: Vec<Result<TaskForCreate>> = validated;
+		let valid_count = tasks_c.iter().filter(|t| t.is_ok()).count();
+
+		let ids = mm
+			.txn(|mm| {
+				let tasks_c: Vec<TaskForCreate> = tasks_c
+					.iter()
+					.filter_map(|t| t.as_ref().ok().cloned())
+					.collect();
+				async move {
+					mm.lock_table(APPEND_LOCK_TABLE).await?;
+					let last_rank =
+						Self::rank_neighbor(&mm, None, "!rank", None).await?;
+					let ranks =
+						ranks_between(last_rank.as_deref(), None, valid_count)?;
+
+					let tasks_fi = tasks_c
+						.into_iter()
+						.zip(ranks)
+						.map(|(task_c, rank)| TaskForInsert {
+							title: task_c.title,
+							rank,
+							due_date: task_c.due_date,
+							rrule: task_c.rrule,
+							assignee_id: task_c.assignee_id,
+						})
+						.collect::<Vec<_>>();
+					let assigned: Vec<bool> =
+						tasks_fi.iter().map(|t| t.assignee_id.is_some()).collect();
+					let rank_len = tasks_fi.iter().map(|t| t.rank.len()).max();
+
+					let ids =
+						base::create_many::<Self, _>(ctx, &mm, tasks_fi).await?;
+					if ids.len() != valid_count {
+						return Err(Error::BulkCountMismatch {
+							expected: valid_count,
+							count: ids.len(),
+						});
+					}
+
+					if rank_len.is_some_and(|len| len > RANK_REBALANCE_LEN) {
+						Self::rebalance_ranks(ctx, &mm).await?;
+					}
+
+					for (id, _) in ids.iter().zip(assigned).filter(|(_, a)| *a) {
+						let task = Self::get(ctx, &mm, *id).await?;
+						NotificationBmc::notify_task_assigned(ctx, &mm, &task)
+							.await?;
+					}
+
+					Ok(ids)
+				}
+			})
+			.await?;
+
+		// -- Results in the tasks_c order (one id per valid task, see above).
+		let count = ids.len();
+		let mut ids = ids.into_iter();
+		let mut results = Vec::with_capacity(tasks_c.len());
+		for task_c in tasks_c {
+			let res = match task_c {
+				Ok(_) => Ok(ids.next().ok_or(Error::BulkCountMismatch {
+					expected: valid_count,
+					count,
+				})?),
+				Err(ex) => Err(ex),
+			};
+			results.push(res);
+		}
+
+		Ok(results)
+	}
+
+	/// Apply the same update to the tasks of `ids`.
+	pub async fn update_many(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		ids: &[i64],
+		task_u: TaskForUpdate,
+	) -> Result<Vec<Result<i64>>> {
+		base::check_bulk_count(ids.len())?;
+		Self::validate(ctx, mm, task_u.rrule.as_deref(), task_u.assignee_id).await?;
+
+		mm.txn(|mm| {
+			let task_u = task_u.clone();
+			async move {
+				// -- The tasks getting assigned to another user (to notify).
+				let reassigned_ids: Vec<(i64,)> = match task_u.assignee_id {
+					Some(assignee_id) => {
+						sqlx::query_as(
+							"SELECT id FROM task WHERE id = ANY($1) \
+							 AND assignee_id IS DISTINCT FROM $2 AND deleted_at IS NULL",
+						)
+						.bind(ids)
+						.bind(assignee_id)
+						.fetch_all(&mut *mm.db().await?)
+						.await?
+					}
+					None => Vec::new(),
+				};
+
+				// -- The tasks getting completed (for their next occurrence).
+				let completing_ids: Vec<(i64,)> = if task_u.done == Some(true) {
+					sqlx::query_as(
+						"SELECT id FROM task \
+						 WHERE id = ANY($1) AND NOT done AND deleted_at IS NULL",
+					)
+					.bind(ids)
+					.fetch_all(&mut *mm.db().await?)
+					.await?
+				} else {
+					Vec::new()
+				};
+
+				let updated_ids =
+					base::update_many::<Self, _>(ctx, &mm, ids, task_u).await?;
+
+				for (id,) in reassigned_ids {
+					let task = Self::get(ctx, &mm, id).await?;
+					NotificationBmc::notify_task_assigned(ctx, &mm, &task).await?;
+				}
+
+				for (id,) in completing_ids {
+					let task = Self::get(ctx, &mm, id).await?;
+					Self::create_next_occurrence(ctx, &mm, task, &SystemClock)
+						.await?;
+				}
+
+				Ok(base::results_by_ids::<Self>(ids, &updated_ids))
+			}
+		})
+		.await
+	}
+
+	/// Apply per task updates, as `(id, task_u, expected_version)`
+	/// (see `update_with_clock`).
+	pub async fn update_each(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		updates: Vec<(i64, TaskForUpdate, Option<i64>)>,
+	) -> Result<Vec<Result<i64>>> {
+		base::check_bulk_count(updates.len())?;
+
+		mm.txn(|mm| {
+			let updates = updates.clone();
+			async move {
+				let mut results = Vec::with_capacity(updates.len());
+				for (id, task_u, expected_version) in updates {
+					// Each in a savepoint, so a failed one leaves no partial
+					// writes (e.g., its revision).
+					let res = mm
+						.savepoint(Self::update_with_clock(
+							ctx,
+							&mm,
+							id,
+							task_u,
+							expected_version,
+							&SystemClock,
+						))
+						.await;
+
+					match res {
+						Ok(_) => results.push(Ok(id)),
+						// Fails the whole transaction (to be retried).
+						Err(ex) if ex.is_retryable() => return Err(ex),
+						Err(ex) => results.push(Err(ex)),
+					}
+				}
+
+				Ok(results)
+			}
+		})
+		.await
+	}
+
+	/// Move the tasks of `ids` to the trash.
+	pub async fn delete_many(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		ids: &[i64],
+	) -> Result<Vec<Result<i64>>> {
+		let deleted_ids = base::delete_many::<Self>(ctx, mm, ids).await?;
+
+		Ok(base::results_by_ids::<Self>(ids, &deleted_ids))
+	}
+
+	pub async fn list_trash(ctx: &Ctx, mm: &ModelManager) -> Result<Vec<Task>> {
+		base::list_deleted::<Self, _>(ctx, mm).await
+	}
+
+	/// Restore a task from the trash, at the end of the list.
+	///
+	/// Note: Re-ranked as an appended task, as its rank (kept while in the trash)
+	///       might now tie with, or fall between other tasks than its
+	///       neighbours (e.g., after a rebalance).
+	pub async fn restore(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
+		mm.lock_table(APPEND_LOCK_TABLE).await?;
+		base::restore::<Self>(ctx, mm, id).await?;
+
+		let last_rank = Self::rank_neighbor(mm, None, "!rank", Some(id)).await?;
+		let rank = rank_between(last_rank.as_deref(), None)?;
+		sqlb::update()
+			.table(Self::TABLE)
+			.and_where("id", "=", id)
+			.data(vec![("rank", rank.clone()).into()])
+			.exec(&mut *mm.db().await?)
+			.await?;
+
+		// Appending grows the last rank key, until rebalanced.
+		if rank.len() > RANK_REBALANCE_LEN {
+			Self::rebalance_ranks(ctx, mm).await?;
+		}
+
+		Ok(())
+	}
+
+	/// Permanently delete a task from the trash (with its comments).
+	pub async fn purge(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
+		base::purge::<Self>(ctx, mm, id).await
+	}
+
+	/// Permanently delete the tasks in the trash since before `deleted_before`.
+	pub async fn purge_trash(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		deleted_before: OffsetDateTime,
+	) -> Result<u64> {
+		base::purge_deleted_before::<Self>(ctx, mm, deleted_before).await
+	}
+
+	/// Move the task before/after another task, by only updating
+	/// the moved task rank (unless the ranks need a rebalance).
+	///
+	/// As for `update`, the version is incremented, and when `expected_version`
+	/// is given, fails with `Error::VersionConflict` if the task was updated since.
+	pub async fn move_to(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		id: i64,
+		task_move: TaskMove,
+		expected_version: Option<i64>,
+	) -> Result<()> {
+		let (target_id, before_target) = match task_move {
+			TaskMove::Before(target_id) => (target_id, true),
+			TaskMove::After(target_id) => (target_id, false),
+		};
+
+		let task = Self::get(ctx, mm, id).await?;
+		base::check_version::<Self>(id, expected_version, task.version)?;
+		if target_id == id {
+			return Ok(());
+		}
+		let target = Self::get(ctx, mm, target_id).await?;
+
+		// -- Get the rank on the other side of the target (excluding the moved task).
+		let rank = if before_target {
+			let prev = Self::rank_neighbor(
+				mm,
+				Some(("<", &target.rank)),
+				"!rank",
+				Some(id),
+			)
+			.await?;
+			rank_between(prev.as_deref(), Some(&target.rank))?
+		} else {
+			let next =
+				Self::rank_neighbor(mm, Some((">", &target.rank)), "rank", Some(id))
+					.await?;
+			rank_between(Some(&target.rank), next.as_deref())?
+		};
+
+		let rank_len = rank.len();
+		if rank != task.rank {
+			// Note: Matching the read version (see `base::update`).
+			let sb = sqlb::update()
+				.table(Self::TABLE)
+				.and_where("id", "=", id)
+				.and_where("version", "=", task.version)
+				.data(vec![
+					("rank", rank).into(),
+					("version", Raw("version + 1")).into(),
+				]);
+			let count = base::and_where_not_deleted::<Self, _>(sb)
+				.exec(&mut *mm.db().await?)
+				.await?;
+			if count == 0 {
+				let task = Self::get(ctx, mm, id).await?;
+				return Err(Error::VersionConflict {
+					entity: Self::TABLE,
+					id,
+					expected: expected_version,
+					current: task.version,
+				});
+			}
+			base::emit_changes::<Self>(ctx, mm, &[id], ChangeKind::Updated).await?;
+		}
+
+		if rank_len > RANK_REBALANCE_LEN {
+			Self::rebalance_ranks(ctx, mm).await?;
+		}
+
+		Ok(())
+	}
+
+	/// Reassign evenly spread (and short) rank keys to all tasks,
+	/// keeping their current order (the trashed tasks keep their rank).
+	pub async fn rebalance_ranks(_ctx: &Ctx, mm: &ModelManager) -> Result<()> {
+		let sb = sqlb::select()
+			.table(Self::TABLE)
+			.columns(&["id"])
+			.order_bys(Self::LIST_ORDER_BYS);
+		let ids: Vec<(i64,)> = base::and_where_not_deleted::<Self, _>(sb)
+			.fetch_all(&mut *mm.db().await?)
+			.await?;
+		let ids: Vec<i64> = ids.into_iter().map(|(id,)| id).collect();
+		let ranks = rank_evenly(ids.len());
+
+		// Single statement, so the list is never seen half rebalanced.
+		sqlx::query(
+			"UPDATE task SET rank = r.rank \
+			 FROM unnest($1::bigint[], $2::text[]) AS r(id, rank) \
+			 WHERE task.id = r.id",
+		)
+		.bind(&ids)
+		.bind(&ranks)
+		.execute(&mut *mm.db().await?)
+		.await?;
+
+		Ok(())
+	}
+
+	/// Returns the first rank per `order_by` (of the tasks not in the trash),
+	/// optionally after/before (`cond`) a given rank, and excluding a task id.
+	async fn rank_neighbor(
+		mm: &ModelManager,
+		cond: Option<(&'static str, &str)>,
+		order_by: &str,
+		exclude_id: Option<i64>,
+	) -> Result<Option<String>> {
+		let mut sb = sqlb::select()
+			.table(Self::TABLE)
+			.columns(&["rank"])
+			.order_bys(&[order_by, "id"])
+			.limit(1);
+		if let Some((op, rank)) = cond {
+			sb = sb.and_where("rank", op, rank.to_string());
+		}
+		if let Some(exclude_id) = exclude_id {
+			sb = sb.and_where("id", "!=", exclude_id);
+		}
+
+		let rank: Option<(String,)> = base::and_where_not_deleted::<Self, _>(sb)
+			.fetch_optional(&mut *mm.db().await?)
+			.await?;
+
+		Ok(rank.map(|(rank,)| rank))
+	}
+
+	/// Validate the rrule and the assignee (when given) of a task create/update.
+	async fn validate(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		rrule: Option<&str>,
+		assignee_id: Option<i64>,
+	) -> Result<()> {
+		if let Some(rrule) = rrule {
+			rrule.parse::<RRule>()?;
+		}
+		if let Some(assignee_id) = assignee_id {
+			UserBmc::get::<User>(ctx, mm, assignee_id).await?;
+		}
+
+		Ok(())
+	}
+
+	async fn create_next_occurrence(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		task: Task,
+		clock: &dyn Clock,
+	) -> Result<Option<i64>> {
+		let Some(rrule) = task.rrule else {
+			return Ok(None);
+		};
+		let rrule: RRule = rrule.parse()?;
+
+		// A recurring task without due date starts its series when completed.
+		let dtstart = task.due_date.unwrap_or_else(|| clock.now_utc());
+
+		let Some((due_date, rrule_rest)) = rrule.next_occurrence(dtstart, clock)
+		else {
+			return Ok(None);
+		};
+
+		let task_c = TaskForCreate {
+			title: task.title,
+			due_date: Some(due_date),
+			rrule: Some(rrule_rest.to_string()),
+			assignee_id: task.assignee_id,
+		};
+		let id = Self::create(ctx, mm, task_c).await?;
+
+		Ok(Some(id))
+	}
 }
 // endregion: --- TaskBmc
 
@@ -68,8 +797,8 @@ impl TaskBmc {
 mod tests {
 	use super::*;
 	use crate::_dev_utils;
-	use crate::model::Error;
-	use anyhow::Result;
+	use crate::utils::time::{format_time, parse_utc, FixedClock};
+	use anyhow::{Context, Result};
 	use serial_test::serial;
 
 	#[serial]
@@ -83,6 +812,7 @@ mod tests {
 		// -- Exec
 		let task_c = TaskForCreate {
 			title: fx_title.to_string(),
+			..Default::default()
 		};
 		let id = TaskBmc::create(&ctx, &mm, task_c).await?;
 
@@ -151,30 +881,1093 @@ mod tests {
 
 	#[serial]
 	#[tokio::test]
-	async fn test_update_ok() -> Result<()> {
+	async fn test_list_by_filter_ok() -> Result<()> {
 		// -- Setup & Fixtures
 		let mm = _dev_utils::init_test().await;
 		let ctx = Ctx::root_ctx();
-		let fx_title = "test_update_ok - task 01";
-		let fx_title_new = "test_update_ok - task 01 - new";
-		let fx_task = _dev_utils::seed_tasks(&ctx, &mm, &[fx_title])
-			.await?
-			.remove(0);
-
-		// -- Exec
+		let fx_titles = &[
+			"test_list_by_filter_ok-task 01",
+			"test_list_by_filter_ok-task 02",
+			"test_list_by_filter_ok-task 100%",
+		];
+		let fx_tasks = _dev_utils::seed_tasks(&ctx, &mm, fx_titles).await?;
+		let fx_due_date = parse_utc("2023-06-01T00:00:00Z")?;
 		TaskBmc::update(
 			&ctx,
 			&mm,
-			fx_task.id,
+			fx_tasks[0].id,
 			TaskForUpdate {
-				title: Some(fx_title_new.to_string()),
+				done: Some(true),
+				due_date: Some(fx_due_date),
+				..Default::default()
+			},
+			None,
+		)
+		.await?;
+		let fx_filter = |filter: TaskFilter| TaskFilter {
+			title_contains: Some("test_list_by_filter_ok".to_string()),
+			..filter
+		};
+
+		// -- Exec
+		let all =
+			TaskBmc::list_by_filter(&ctx, &mm, fx_filter(Default::default()), None)
+				.await?;
+		let not_done = TaskBmc::list_by_filter(
+			&ctx,
+			&mm,
+			fx_filter(TaskFilter {
+				done: Some(false),
+				..Default::default()
+			}),
+			None,
+		)
+		.await?;
+		let percent = TaskBmc::list_by_filter(
+			&ctx,
+			&mm,
+			TaskFilter {
+				title_contains: Some("TASK 100%".to_string()),
+				..Default::default()
+			},
+			None,
+		)
+		.await?;
+		let wildcard = TaskBmc::list_by_filter(
+			&ctx,
+			&mm,
+			TaskFilter {
+				title_contains: Some("filter_ok-task 0_".to_string()),
+				..Default::default()
 			},
+			None,
+		)
+		.await?;
+		let due_before = TaskBmc::list_by_filter(
+			&ctx,
+			&mm,
+			fx_filter(TaskFilter {
+				due_before: Some(parse_utc("2023-06-02T00:00:00Z")?),
+				..Default::default()
+			}),
+			None,
+		)
+		.await?;
+		let due_after = TaskBmc::list_by_filter(
+			&ctx,
+			&mm,
+			fx_filter(TaskFilter {
+				due_after: Some(parse_utc("2023-06-02T00:00:00Z")?),
+				..Default::default()
+			}),
+			None,
+		)
+		.await?;
+		let limited = TaskBmc::list_by_filter(
+			&ctx,
+			&mm,
+			fx_filter(Default::default()),
+			Some(ListOptions {
+				limit: Some(1),
+				offset: Some(1),
+				..Default::default()
+			}),
 		)
 		.await?;
 
 		// -- Check
-		let task = TaskBmc::get(&ctx, &mm, fx_task.id).await?;
-		assert_eq!(task.title, fx_title_new);
+		let ids = |tasks: &[Task]| tasks.iter().map(|t| t.id).collect::<Vec<_>>();
+		let fx_ids = ids(&fx_tasks);
+		assert_eq!(ids(&all), fx_ids);
+		assert_eq!(ids(&not_done), &fx_ids[1..]);
+		assert_eq!(ids(&percent), &fx_ids[2..]);
+		assert!(wildcard.is_empty(), "`_` should not be a wildcard");
+		assert_eq!(ids(&due_before), &fx_ids[..1]);
+		assert!(due_after.is_empty());
+		assert_eq!(ids(&limited), &fx_ids[1..2]);
+
+		// -- Clean
+		for id in fx_ids {
+			TaskBmc::delete(&ctx, &mm, id).await?;
+		}
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_list_page_ok_keyset() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_titles = &[
+			"test_list_page_ok-task 01",
+			"test_list_page_ok-task 02",
+			"test_list_page_ok-task 03",
+			"test_list_page_ok-task 04",
+			"test_list_page_ok-task 05",
+		];
+		let fx_tasks = _dev_utils::seed_tasks(&ctx, &mm, fx_titles).await?;
+		let fx_due_dates = ["2023-06-01T00:00:00Z", "2023-06-02T00:00:00Z"];
+		for (idx, due_date) in [(0, 0), (1, 1), (2, 0)] {
+			let task_u = TaskForUpdate {
+				due_date: Some(parse_utc(fx_due_dates[due_date])?),
+				..Default::default()
+			};
+			TaskBmc::update(&ctx, &mm, fx_tasks[idx].id, task_u, None).await?;
+		}
+		let fx_filter = || TaskFilter {
+			title_contains: Some("test_list_page_ok".to_string()),
+			..Default::default()
+		};
+		let fx_list_options = |cursor: Option<String>| ListOptions {
+			limit: Some(2),
+			order_bys: Some(vec!["!due_date".to_string(), "title".to_string()]),
+			cursor,
+			..Default::default()
+		};
+
+		// -- Exec
+		// The NULL due dates first (descending), then by title.
+		let page_1 =
+			TaskBmc::list_page(&ctx, &mm, fx_filter(), Some(fx_list_options(None)))
+				.await?;
+		// Inserted before the cursor, so neither skipping nor repeating a task.
+		let inserted_ids =
+			_dev_utils::seed_tasks(&ctx, &mm, &["test_list_page_ok-task 00"])
+				.await?
+				.into_iter()
+				.map(|t| t.id)
+				.collect::<Vec<_>>();
+		let page_2 = TaskBmc::list_page(
+			&ctx,
+			&mm,
+			fx_filter(),
+			Some(fx_list_options(page_1.next_cursor.clone())),
+		)
+		.await?;
+		let page_3 = TaskBmc::list_page(
+			&ctx,
+			&mm,
+			fx_filter(),
+			Some(fx_list_options(page_2.next_cursor.clone())),
+		)
+		.await?;
+		let other_order_res = TaskBmc::list_page(
+			&ctx,
+			&mm,
+			fx_filter(),
+			Some(ListOptions {
+				cursor: page_1.next_cursor.clone(),
+				..Default::default()
+			}),
+		)
+		.await;
+
+		// -- Check
+		let ids = |tasks: &[Task]| tasks.iter().map(|t| t.id).collect::<Vec<_>>();
+		let fx_ids = ids(&fx_tasks);
+		assert_eq!(ids(&page_1.items), &[fx_ids[3], fx_ids[4]]);
+		assert!(page_1.has_more);
+		assert_eq!(ids(&page_2.items), &[fx_ids[1], fx_ids[0]]);
+		assert_eq!(ids(&page_3.items), &[fx_ids[2]]);
+		assert!(!page_3.has_more);
+		assert!(page_3.next_cursor.is_none());
+		assert!(
+			matches!(other_order_res, Err(Error::ListCursorInvalid { .. })),
+			"Should have been ListCursorInvalid, was {other_order_res:?}"
+		);
+
+		// -- Clean
+		for id in fx_ids.into_iter().chain(inserted_ids) {
+			TaskBmc::delete(&ctx, &mm, id).await?;
+		}
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_update_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_title = "test_update_ok - task 01";
+		let fx_title_new = "test_update_ok - task 01 - new";
+		let fx_task = _dev_utils::seed_tasks(&ctx, &mm, &[fx_title])
+			.await?
+			.remove(0);
+
+		// -- Exec
+		TaskBmc::update(
+			&ctx,
+			&mm,
+			fx_task.id,
+			TaskForUpdate {
+				title: Some(fx_title_new.to_string()),
+				..Default::default()
+			},
+			None,
+		)
+		.await?;
+
+		// -- Check
+		let task = TaskBmc::get(&ctx, &mm, fx_task.id).await?;
+		assert_eq!(task.title, fx_title_new);
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_update_err_version_conflict() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_task =
+			_dev_utils::seed_tasks(&ctx, &mm, &["test_update_err_version_conflict"])
+				.await?
+				.remove(0);
+		let fx_task_u = || TaskForUpdate {
+			title: Some("test_update_err_version_conflict - new".to_string()),
+			..Default::default()
+		};
+
+		// -- Exec
+		// First editor, with the up to date version.
+		TaskBmc::update(&ctx, &mm, fx_task.id, fx_task_u(), Some(fx_task.version))
+			.await?;
+		// Second editor, with the now stale version.
+		let res = TaskBmc::update(
+			&ctx,
+			&mm,
+			fx_task.id,
+			fx_task_u(),
+			Some(fx_task.version),
+		)
+		.await;
+
+		// -- Check
+		let task = TaskBmc::get(&ctx, &mm, fx_task.id).await?;
+		assert_eq!(task.version, fx_task.version + 1);
+		assert!(
+			matches!(
+				res,
+				Err(Error::VersionConflict { expected: Some(expected), current, .. })
+					if expected == fx_task.version && current == task.version
+			),
+			"VersionConflict not matching"
+		);
+		let revisions = TaskBmc::list_revisions(&ctx, &mm, fx_task.id).await?;
+		assert_eq!(revisions.len(), 1, "conflict should not record a revision");
+
+		// -- Clean
+		TaskBmc::delete(&ctx, &mm, fx_task.id).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_create_err_invalid_rrule() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+
+		// -- Exec
+		let res = TaskBmc::create(
+			&ctx,
+			&mm,
+			TaskForCreate {
+				title: "test_create_err_invalid_rrule".to_string(),
+				rrule: Some("FREQ=HOURLY".to_string()),
+				..Default::default()
+			},
+		)
+		.await;
+
+		// -- Check
+		assert!(
+			matches!(res, Err(Error::Recurrence(_))),
+			"Error::Recurrence not matching"
+		);
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_update_done_creates_next_occurrence() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_title = "test_update_done_creates_next_occurrence";
+		let fx_clock = FixedClock(parse_utc("2024-01-02T12:00:00Z")?);
+		let fx_id = TaskBmc::create(
+			&ctx,
+			&mm,
+			TaskForCreate {
+				title: fx_title.to_string(),
+				due_date: Some(parse_utc("2024-01-01T09:00:00Z")?),
+				rrule: Some("FREQ=DAILY;COUNT=3".to_string()),
+				assignee_id: None,
+			},
+		)
+		.await?;
+
+		// -- Exec
+		let next_id = TaskBmc::update_with_clock(
+			&ctx,
+			&mm,
+			fx_id,
+			TaskForUpdate {
+				done: Some(true),
+				..Default::default()
+			},
+			None,
+			&fx_clock,
+		)
+		.await?
+		.context("Should have created the next occurrence")?;
+		// Already done, so should not create another one.
+		let res = TaskBmc::update_with_clock(
+			&ctx,
+			&mm,
+			fx_id,
+			TaskForUpdate {
+				done: Some(true),
+				..Default::default()
+			},
+			None,
+			&fx_clock,
+		)
+		.await?;
+
+		// -- Check
+		assert!(res.is_none(), "Should not create a next occurrence twice");
+		let next = TaskBmc::get(&ctx, &mm, next_id).await?;
+		assert_eq!(next.title, fx_title);
+		assert!(!next.done);
+		let due_date = next.due_date.context("Should have a due_date")?;
+		assert_eq!(format_time(due_date), "2024-01-03T09:00:00Z");
+		assert_eq!(next.rrule.as_deref(), Some("FREQ=DAILY;COUNT=1"));
+
+		// -- Clean
+		TaskBmc::delete(&ctx, &mm, fx_id).await?;
+		TaskBmc::delete(&ctx, &mm, next_id).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_move_to_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_titles = &[
+			"test_move_to_ok-task A",
+			"test_move_to_ok-task B",
+			"test_move_to_ok-task C",
+		];
+		let fx_tasks = _dev_utils::seed_tasks(&ctx, &mm, fx_titles).await?;
+		let (id_a, id_b, id_c) = (fx_tasks[0].id, fx_tasks[1].id, fx_tasks[2].id);
+		let list_titles = || async {
+			let titles: Vec<String> = TaskBmc::list(&ctx, &mm)
+				.await?
+				.into_iter()
+				.filter(|t| t.title.starts_with("test_move_to_ok-task"))
+				.map(|t| t.title.replace("test_move_to_ok-task ", ""))
+				.collect();
+			Ok::<_, Error>(titles)
+		};
+
+		// -- Exec & Check
+		TaskBmc::move_to(&ctx, &mm, id_c, TaskMove::Before(id_a), None).await?;
+		assert_eq!(list_titles().await?, &["C", "A", "B"]);
+		let task_c = TaskBmc::get(&ctx, &mm, id_c).await?;
+		assert_eq!(task_c.version, fx_tasks[2].version + 1);
+		let stale_res = TaskBmc::move_to(
+			&ctx,
+			&mm,
+			id_c,
+			TaskMove::After(id_b),
+			Some(fx_tasks[2].version),
+		)
+		.await;
+		assert!(
+			matches!(stale_res, Err(Error::VersionConflict { .. })),
+			"Should have been VersionConflict, was {stale_res:?}"
+		);
+
+		TaskBmc::move_to(&ctx, &mm, id_a, TaskMove::After(id_b), None).await?;
+		assert_eq!(list_titles().await?, &["C", "B", "A"]);
+
+		// Keep moving in the same gap, until rebalanced.
+		for _ in 0..120 {
+			TaskBmc::move_to(&ctx, &mm, id_c, TaskMove::Before(id_b), None).await?;
+			TaskBmc::move_to(&ctx, &mm, id_c, TaskMove::After(id_b), None).await?;
+			TaskBmc::move_to(&ctx, &mm, id_b, TaskMove::After(id_c), None).await?;
+		}
+		assert_eq!(list_titles().await?, &["C", "B", "A"]);
+		let task_c = TaskBmc::get(&ctx, &mm, id_c).await?;
+		assert!(
+			task_c.rank.len() <= RANK_REBALANCE_LEN,
+			"should be rebalanced"
+		);
+
+		// -- Clean
+		for task in fx_tasks.iter() {
+			TaskBmc::delete(&ctx, &mm, task.id).await?;
+		}
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+	async fn test_create_ok_concurrent() -> Result<()> {
+		// -- Setup & Fixtures
+		_dev_utils::init_test().await;
+		let mm = ModelManager::new_sized(8).await?;
+		let ctx = Ctx::root_ctx();
+		let fx_count = 16;
+
+		// -- Exec
+		// As `create_task`, each in its own transaction.
+		let creates = (0..fx_count).map(|i| {
+			let (ctx, mm) = (ctx.clone(), mm.clone());
+			tokio::spawn(async move {
+				let ctx = &ctx;
+				mm.txn(|mm| async move {
+					let task_c = TaskForCreate {
+						title: format!("test_create_ok_concurrent-task {i:02}"),
+						..Default::default()
+					};
+					let id = TaskBmc::create(ctx, &mm, task_c).await?;
+					TaskBmc::get(ctx, &mm, id).await
+				})
+				.await
+			})
+		});
+		let results = futures::future::join_all(creates).await;
+
+		// -- Check
+		let mut ids = Vec::new();
+		for res in results {
+			ids.push(res??.id);
+		}
+		let tasks: Vec<Task> = TaskBmc::list(&ctx, &mm)
+			.await?
+			.into_iter()
+			.filter(|t| ids.contains(&t.id))
+			.collect();
+		assert_eq!(tasks.len(), fx_count);
+		let mut ranks: Vec<&str> = tasks.iter().map(|t| t.rank.as_str()).collect();
+		ranks.dedup();
+		assert_eq!(ranks.len(), fx_count, "ranks should be distinct");
+
+		// -- Clean
+		TaskBmc::delete_many(&ctx, &mm, &ids).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_create_ok_rank_rebalanced() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		// A trashed task ranked last, which new tasks must not append after.
+		let fx_trashed = _dev_utils::seed_tasks(&ctx, &mm, &["trashed task"])
+			.await?
+			.remove(0);
+		sqlx::query(
+			"UPDATE task SET rank = 'zzzzzzzzzzzzzzzzzzzzzzzzzzzzzz' WHERE id = $1",
+		)
+		.bind(fx_trashed.id)
+		.execute(&mut *mm.db().await?)
+		.await?;
+		TaskBmc::delete(&ctx, &mm, fx_trashed.id).await?;
+		// Past the `varchar(128)` rank key length without rebalance.
+		let fx_count = 800;
+
+		// -- Exec
+		let mut ids = Vec::with_capacity(fx_count);
+		for i in 0..fx_count {
+			let task_c = TaskForCreate {
+				title: format!("test_create_ok_rank_rebalanced-task {i:03}"),
+				..Default::default()
+			};
+			ids.push(TaskBmc::create(&ctx, &mm, task_c).await?);
+		}
+
+		// -- Check
+		let tasks: Vec<Task> = TaskBmc::list(&ctx, &mm)
+			.await?
+			.into_iter()
+			.filter(|t| ids.contains(&t.id))
+			.collect();
+		let listed_ids: Vec<i64> = tasks.iter().map(|t| t.id).collect();
+		assert_eq!(listed_ids, ids);
+		assert!(
+			tasks.iter().all(|t| t.rank.len() <= RANK_REBALANCE_LEN + 1),
+			"should be rebalanced"
+		);
+
+		// -- Clean
+		TaskBmc::purge(&ctx, &mm, fx_trashed.id).await?;
+		TaskBmc::delete_many(&ctx, &mm, &ids).await?;
+		for id in ids {
+			TaskBmc::purge(&ctx, &mm, id).await?;
+		}
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_restore_ok_after_rebalance() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_tasks = _dev_utils::seed_tasks(
+			&ctx,
+			&mm,
+			&[
+				"test_restore_ok-01",
+				"test_restore_ok-02",
+				"test_restore_ok-03",
+			],
+		)
+		.await?;
+		let fx_ids: Vec<i64> = fx_tasks.iter().map(|t| t.id).collect();
+		TaskBmc::delete(&ctx, &mm, fx_ids[0]).await?;
+		TaskBmc::rebalance_ranks(&ctx, &mm).await?;
+		// The trashed rank, now tied with a live task.
+		let tie_rank = TaskBmc::get(&ctx, &mm, fx_ids[1]).await?.rank;
+		sqlx::query("UPDATE task SET rank = $2 WHERE id = $1")
+			.bind(fx_ids[0])
+			.bind(&tie_rank)
+			.execute(&mut *mm.db().await?)
+			.await?;
+
+		// -- Exec
+		TaskBmc::restore(&ctx, &mm, fx_ids[0]).await?;
+
+		// -- Check
+		let tasks = TaskBmc::list(&ctx, &mm).await?;
+		let listed_ids: Vec<i64> = tasks
+			.iter()
+			.map(|t| t.id)
+			.filter(|id| fx_ids.contains(id))
+			.collect();
+		assert_eq!(listed_ids, &[fx_ids[1], fx_ids[2], fx_ids[0]]);
+		let restored = TaskBmc::get(&ctx, &mm, fx_ids[0]).await?;
+		assert!(
+			tasks
+				.iter()
+				.all(|t| t.id == restored.id || t.rank < restored.rank),
+			"should be ranked last"
+		);
+
+		// -- Clean
+		for id in fx_ids {
+			TaskBmc::delete(&ctx, &mm, id).await?;
+			TaskBmc::purge(&ctx, &mm, id).await?;
+		}
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_delete_restore_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_task = _dev_utils::seed_tasks(&ctx, &mm, &["test_delete_restore_ok"])
+			.await?
+			.remove(0);
+
+		// -- Exec & Check
+		TaskBmc::delete(&ctx, &mm, fx_task.id).await?;
+		let res = TaskBmc::get(&ctx, &mm, fx_task.id).await;
+		assert!(
+			matches!(res, Err(Error::EntityNotFound { .. })),
+			"deleted task should not be found"
+		);
+		let res = TaskBmc::update(
+			&ctx,
+			&mm,
+			fx_task.id,
+			TaskForUpdate {
+				title: Some("new title".to_string()),
+				..Default::default()
+			},
+			None,
+		)
+		.await;
+		assert!(
+			matches!(res, Err(Error::EntityNotFound { .. })),
+			"deleted task should not be updatable"
+		);
+		let trash = TaskBmc::list_trash(&ctx, &mm).await?;
+		assert!(
+			trash.iter().any(|t| t.id == fx_task.id),
+			"should be in trash"
+		);
+
+		TaskBmc::restore(&ctx, &mm, fx_task.id).await?;
+		let task = TaskBmc::get(&ctx, &mm, fx_task.id).await?;
+		assert_eq!(task.title, fx_task.title);
+		let trash = TaskBmc::list_trash(&ctx, &mm).await?;
+		assert!(
+			trash.iter().all(|t| t.id != fx_task.id),
+			"should not be in trash"
+		);
+
+		// -- Clean
+		TaskBmc::delete(&ctx, &mm, fx_task.id).await?;
+		TaskBmc::purge(&ctx, &mm, fx_task.id).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_purge_err_not_in_trash() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_task =
+			_dev_utils::seed_tasks(&ctx, &mm, &["test_purge_err_not_in_trash"])
+				.await?
+				.remove(0);
+
+		// -- Exec
+		let res = TaskBmc::purge(&ctx, &mm, fx_task.id).await;
+
+		// -- Check
+		assert!(
+			matches!(res, Err(Error::EntityNotFound { entity: "task", .. })),
+			"EntityNotFound not matching"
+		);
+		TaskBmc::get(&ctx, &mm, fx_task.id).await?;
+
+		// -- Clean
+		TaskBmc::delete(&ctx, &mm, fx_task.id).await?;
+		TaskBmc::purge(&ctx, &mm, fx_task.id).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_purge_trash_ok_retention() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_task =
+			_dev_utils::seed_tasks(&ctx, &mm, &["test_purge_trash_ok_retention"])
+				.await?
+				.remove(0);
+		TaskBmc::delete(&ctx, &mm, fx_task.id).await?;
+
+		// -- Exec & Check
+		// Deleted after the cutoff, so kept.
+		let cutoff = parse_utc("2024-01-01T00:00:00Z")?;
+		TaskBmc::purge_trash(&ctx, &mm, cutoff).await?;
+		let trash = TaskBmc::list_trash(&ctx, &mm).await?;
+		assert!(trash.iter().any(|t| t.id == fx_task.id), "should be kept");
+
+		// Deleted before the cutoff, so purged.
+		let cutoff = crate::utils::time::now_utc() + time::Duration::seconds(1);
+		let count = TaskBmc::purge_trash(&ctx, &mm, cutoff).await?;
+		assert!(count >= 1);
+		let trash = TaskBmc::list_trash(&ctx, &mm).await?;
+		assert!(trash.iter().all(|t| t.id != fx_task.id), "should be purged");
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_update_revisions_diff_revert_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_title_01 = "test_update_revisions_diff_revert_ok - 01";
+		let fx_title_02 = "test_update_revisions_diff_revert_ok - 02";
+		let fx_title_03 = "test_update_revisions_diff_revert_ok - 03";
+		let fx_task = _dev_utils::seed_tasks(&ctx, &mm, &[fx_title_01])
+			.await?
+			.remove(0);
+		for title in [fx_title_02, fx_title_03] {
+			let task_u = TaskForUpdate {
+				title: Some(title.to_string()),
+				..Default::default()
+			};
+			TaskBmc::update(&ctx, &mm, fx_task.id, task_u, None).await?;
+		}
+
+		// -- Exec & Check - revisions
+		let revisions = TaskBmc::list_revisions(&ctx, &mm, fx_task.id).await?;
+		assert_eq!(revisions.len(), 2);
+		assert_eq!(revisions[0].data["title"], fx_title_01);
+		assert_eq!(revisions[1].data["title"], fx_title_02);
+		assert_eq!(revisions[0].actor_id, ctx.user_id());
+
+		// -- Exec & Check - diff
+		let diff =
+			TaskBmc::diff_revisions(&ctx, &mm, fx_task.id, revisions[0].id, None)
+				.await?;
+		assert_eq!(
+			diff,
+			vec![FieldDiff {
+				field: "title".to_string(),
+				from: fx_title_01.into(),
+				to: fx_title_03.into(),
+			}]
+		);
+
+		// -- Exec & Check - revert
+		TaskBmc::revert(&ctx, &mm, fx_task.id, revisions[0].id).await?;
+		let task = TaskBmc::get(&ctx, &mm, fx_task.id).await?;
+		assert_eq!(task.title, fx_title_01);
+		let revisions = TaskBmc::list_revisions(&ctx, &mm, fx_task.id).await?;
+		assert_eq!(revisions.len(), 3, "revert should be a new revision");
+
+		// -- Clean
+		TaskBmc::delete(&ctx, &mm, fx_task.id).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_revert_ok_nulls_no_next_occurrence() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_title = "test_revert_ok_nulls_no_next_occurrence";
+		let fx_task = _dev_utils::seed_tasks(&ctx, &mm, &[fx_title])
+			.await?
+			.remove(0);
+		let fx_due_date = parse_utc("2030-01-01T09:00:00Z")?;
+		let task_u = TaskForUpdate {
+			done: Some(true),
+			due_date: Some(fx_due_date),
+			rrule: Some("FREQ=DAILY".to_string()),
+			..Default::default()
+		};
+		let clock = FixedClock(fx_due_date);
+		let fx_next_id =
+			TaskBmc::update_with_clock(&ctx, &mm, fx_task.id, task_u, None, &clock)
+				.await?
+				.context("should have a next occurrence")?;
+		let count_titled = || async {
+			let count = TaskBmc::list(&ctx, &mm)
+				.await?
+				.into_iter()
+				.filter(|t| t.title == fx_title)
+				.count();
+			Ok::<_, Error>(count)
+		};
+
+		// -- Exec & Check - revert to the null fields
+		let revision_undone = TaskBmc::list_revisions(&ctx, &mm, fx_task.id)
+			.await?
+			.remove(0);
+		TaskBmc::revert(&ctx, &mm, fx_task.id, revision_undone.id).await?;
+		let task = TaskBmc::get(&ctx, &mm, fx_task.id).await?;
+		assert!(!task.done);
+		assert_eq!(task.due_date, None);
+		assert_eq!(task.rrule, None);
+
+		// -- Exec & Check - revert to done, without a new next occurrence
+		let revision_done = TaskBmc::list_revisions(&ctx, &mm, fx_task.id)
+			.await?
+			.remove(1);
+		TaskBmc::revert(&ctx, &mm, fx_task.id, revision_done.id).await?;
+		let task = TaskBmc::get(&ctx, &mm, fx_task.id).await?;
+		assert!(task.done);
+		assert_eq!(task.due_date, Some(fx_due_date));
+		assert_eq!(count_titled().await?, 2);
+
+		// -- Clean
+		TaskBmc::delete(&ctx, &mm, fx_task.id).await?;
+		TaskBmc::delete(&ctx, &mm, fx_next_id).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_revert_err_other_task_revision() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_tasks =
+			_dev_utils::seed_tasks(&ctx, &mm, &["task 01", "task 02"]).await?;
+		let task_u = TaskForUpdate {
+			title: Some("task 01 - new".to_string()),
+			..Default::default()
+		};
+		TaskBmc::update(&ctx, &mm, fx_tasks[0].id, task_u, None).await?;
+		let revision = TaskBmc::list_revisions(&ctx, &mm, fx_tasks[0].id)
+			.await?
+			.remove(0);
+
+		// -- Exec
+		let res = TaskBmc::revert(&ctx, &mm, fx_tasks[1].id, revision.id).await;
+
+		// -- Check
+		assert!(
+			matches!(
+				res,
+				Err(Error::EntityNotFound {
+					entity: "revision",
+					..
+				})
+			),
+			"EntityNotFound not matching"
+		);
+
+		// -- Clean
+		for task in fx_tasks.iter() {
+			TaskBmc::delete(&ctx, &mm, task.id).await?;
+		}
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_create_many_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_last = _dev_utils::seed_tasks(&ctx, &mm, &["existing task"])
+			.await?
+			.remove(0);
+		let fx_task_c = |title: &str, rrule: Option<&str>| TaskForCreate {
+			title: title.to_string(),
+			rrule: rrule.map(|r| r.to_string()),
+			..Default::default()
+		};
+		let fx_tasks_c = vec![
+			fx_task_c("bulk 01", None),
+			fx_task_c("bulk 02", Some("FREQ=NEVER")),
+			fx_task_c("bulk 03", Some("FREQ=DAILY")),
+			// Unknown assignee.
+			TaskForCreate {
+				assignee_id: Some(100),
+				..fx_task_c("bulk 04", None)
+			},
+		];
+
+		// -- Exec
+		let results = TaskBmc::create_many(&ctx, &mm, fx_tasks_c).await?;
+
+		// -- Check
+		assert_eq!(results.len(), 4);
+		assert!(matches!(results[1], Err(Error::Recurrence(_))));
+		assert!(matches!(
+			results[3],
+			Err(Error::EntityNotFound {
+				entity: "user",
+				id: 100
+			})
+		));
+		let mut fx_ids = vec![fx_last.id];
+		fx_ids.extend(results.iter().flatten());
+		let titles: Vec<String> = TaskBmc::list(&ctx, &mm)
+			.await?
+			.into_iter()
+			.filter(|t| fx_ids.contains(&t.id))
+			.map(|t| t.title)
+			.collect();
+		assert_eq!(titles, &["existing task", "bulk 01", "bulk 03"]);
+		for res in [&results[0], &results[2]] {
+			let Ok(id) = res else {
+				panic!("should be created, but was {res:?}")
+			};
+			TaskBmc::get(&ctx, &mm, *id).await?;
+		}
+
+		// -- Clean
+		for id in fx_ids {
+			TaskBmc::delete(&ctx, &mm, id).await?;
+			TaskBmc::purge(&ctx, &mm, id).await?;
+		}
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_create_many_err_too_many() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_tasks_c = vec![
+			TaskForCreate {
+				title: "test_create_many_err_too_many".to_string(),
+				..Default::default()
+			};
+			base::BULK_MAX_ITEMS + 1
+		];
+
+		// -- Exec
+		let res = TaskBmc::create_many(&ctx, &mm, fx_tasks_c).await;
+
+		// -- Check
+		assert!(
+			matches!(
+				res,
+				Err(Error::BulkTooManyItems { max: base::BULK_MAX_ITEMS, count })
+					if count == base::BULK_MAX_ITEMS + 1
+			),
+			"Should have been BulkTooManyItems, was {res:?}"
+		);
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_update_many_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_tasks =
+			_dev_utils::seed_tasks(&ctx, &mm, &["task 01", "task 02"]).await?;
+		let fx_ids = vec![fx_tasks[0].id, fx_tasks[1].id, 100];
+
+		// -- Exec
+		let results = TaskBmc::update_many(
+			&ctx,
+			&mm,
+			&fx_ids,
+			TaskForUpdate {
+				done: Some(true),
+				..Default::default()
+			},
+		)
+		.await?;
+
+		// -- Check
+		assert!(matches!(results[0], Ok(id) if id == fx_tasks[0].id));
+		assert!(matches!(results[1], Ok(id) if id == fx_tasks[1].id));
+		assert!(matches!(
+			results[2],
+			Err(Error::EntityNotFound { id: 100, .. })
+		));
+		for task in fx_tasks.iter() {
+			let task_updated = TaskBmc::get(&ctx, &mm, task.id).await?;
+			assert!(task_updated.done);
+			assert_eq!(task_updated.version, task.version + 1);
+			let revisions = TaskBmc::list_revisions(&ctx, &mm, task.id).await?;
+			assert_eq!(revisions.len(), 1);
+		}
+
+		// -- Clean
+		for task in fx_tasks.iter() {
+			TaskBmc::delete(&ctx, &mm, task.id).await?;
+		}
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_update_each_ok_partial() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_tasks =
+			_dev_utils::seed_tasks(&ctx, &mm, &["task 01", "task 02", "task 03"])
+				.await?;
+		let fx_task_u = |title: &str| TaskForUpdate {
+			title: Some(title.to_string()),
+			..Default::default()
+		};
+		let fx_updates = vec![
+			(
+				fx_tasks[0].id,
+				fx_task_u("task 01 - new"),
+				Some(fx_tasks[0].version),
+			),
+			// Stale version.
+			(
+				fx_tasks[1].id,
+				fx_task_u("task 02 - new"),
+				Some(fx_tasks[1].version + 1),
+			),
+			// Title too long for the column (a db error, after its revision).
+			(fx_tasks[2].id, fx_task_u(&"x".repeat(300)), None),
+		];
+
+		// -- Exec
+		let results = TaskBmc::update_each(&ctx, &mm, fx_updates).await?;
+
+		// -- Check
+		assert!(matches!(results[0], Ok(id) if id == fx_tasks[0].id));
+		assert!(matches!(results[1], Err(Error::VersionConflict { .. })));
+		assert!(matches!(results[2], Err(Error::Sqlx(_))));
+		assert_eq!(
+			TaskBmc::get(&ctx, &mm, fx_tasks[0].id).await?.title,
+			"task 01 - new"
+		);
+		assert_eq!(
+			TaskBmc::get(&ctx, &mm, fx_tasks[1].id).await?.title,
+			"task 02"
+		);
+		let revisions = TaskBmc::list_revisions(&ctx, &mm, fx_tasks[2].id).await?;
+		assert!(revisions.is_empty(), "should be rolled back");
+
+		// -- Clean
+		for task in fx_tasks.iter() {
+			TaskBmc::delete(&ctx, &mm, task.id).await?;
+		}
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_delete_many_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_tasks =
+			_dev_utils::seed_tasks(&ctx, &mm, &["task 01", "task 02"]).await?;
+		let fx_ids = vec![fx_tasks[0].id, fx_tasks[1].id, 100];
+
+		// -- Exec
+		let results = TaskBmc::delete_many(&ctx, &mm, &fx_ids).await?;
+
+		// -- Check
+		assert!(results[..2].iter().all(|r| r.is_ok()));
+		assert!(matches!(
+			results[2],
+			Err(Error::EntityNotFound { id: 100, .. })
+		));
+		let trash_ids: Vec<i64> = TaskBmc::list_trash(&ctx, &mm)
+			.await?
+			.into_iter()
+			.map(|t| t.id)
+			.collect();
+		assert!(fx_ids[..2].iter().all(|id| trash_ids.contains(id)));
+		let tasks = TaskBmc::list(&ctx, &mm).await?;
+		assert!(tasks.iter().all(|t| !fx_ids[..2].contains(&t.id)));
+
+		// -- Clean
+		for id in &fx_ids[..2] {
+			TaskBmc::purge(&ctx, &mm, *id).await?;
+		}
 
 		Ok(())
 	}
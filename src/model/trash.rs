@@ -0,0 +1,34 @@
+//! Purge of the trash (i.e., the soft deleted entities),
+//! once past the configured retention (`SERVICE_TRASH_RETENTION_SEC`).
+
+use crate::config;
+use crate::ctx::Ctx;
+use crate::model::cron::Cron;
+use crate::model::job::JobError;
+use crate::model::task::TaskBmc;
+use crate::model::ModelManager;
+use async_trait::async_trait;
+use time::OffsetDateTime;
+
+/// Purges the trash, hourly.
+pub struct PurgeTrash;
+
+#[async_trait]
+impl Cron for PurgeTrash {
+	const NAME: &'static str = "purge_trash";
+	const SCHEDULE: &'static str = "@hourly";
+
+	async fn run(
+		&self,
+		mm: &ModelManager,
+		now: OffsetDateTime,
+	) -> core::result::Result<String, JobError> {
+		let retention = time::Duration::seconds_f64(config().TRASH_RETENTION_SEC);
+		let deleted_before = now - retention;
+
+		let count =
+			TaskBmc::purge_trash(&Ctx::root_ctx(), mm, deleted_before).await?;
+
+		Ok(format!("{count} task(s) purged"))
+	}
+}
@@ -0,0 +1,353 @@
+//! Transactions spanning multiple Bmc calls.
+//!
+//! A transactional `ModelManager` (from `mm.begin()`) is passed to the Bmcs like any
+//! other `ModelManager`, and all its queries run in the same db transaction until
+//! `commit` or `rollback` (dropping it without commit rolls back).
+//!
+//! Transactions are `SERIALIZABLE`, so the compound operations should go through
+//! `mm.txn(...)`, which retries them on serialization failures (and deadlocks).
+//!
+
+use crate::model::{Error, ModelManager, Result};
+use sqlx::pool::PoolConnection;
+use sqlx::{PgConnection, Postgres, Transaction};
+use std::future::Future;
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{MappedMutexGuard, Mutex, MutexGuard};
+use tracing::debug;
+
+/// Attempts for `ModelManager::txn` (the first one included).
+const TXN_MAX_ATTEMPTS: u32 = 3;
+const TXN_RETRY_DELAY: Duration = Duration::from_millis(20);
+
+/// Postgres `serialization_failure` and `deadlock_detected` error codes.
+const RETRYABLE_CODES: &[&str] = &["40001", "40P01"];
+
+/// Shared by the clones of a transactional `ModelManager`.
+/// (None once committed or rolled back)
+pub(in crate::model) type TxnHolder =
+	Arc<Mutex<Option<Transaction<'static, Postgres>>>>;
+
+// region:    --- DbConn
+
+/// The connection a model query runs on, from `ModelManager::db`.
+///
+/// Note: Must not be held across Bmc calls (e.g., use as
+///       `.fetch_one(&mut *mm.db().await?)`), as a transaction has only one.
+pub(in crate::model) enum DbConn<'a> {
+	Pool(Box<PoolConnection<Postgres>>),
+	Txn(MappedMutexGuard<'a, Transaction<'static, Postgres>>),
+}
+
+impl Deref for DbConn<'_> {
+	type Target = PgConnection;
+
+	fn deref(&self) -> &Self::Target {
+		match self {
+			DbConn::Pool(conn) => conn,
+			DbConn::Txn(txn) => txn,
+		}
+	}
+}
+
+impl DerefMut for DbConn<'_> {
+	fn deref_mut(&mut self) -> &mut Self::Target {
+		match self {
+			DbConn::Pool(conn) => conn,
+			DbConn::Txn(txn) => txn,
+		}
+	}
+}
+
+// endregion: --- DbConn
+
+impl ModelManager {
+	/// Returns the connection for the next query, which is the transaction one
+	/// for a transactional `ModelManager`.
+	/// (Only for the model layer)
+	pub(in crate::model) async fn db(&self) -> Result<DbConn<'_>> {
+		match &self.txn {
+			None => Ok(DbConn::Pool(Box::new(self.db.acquire().await?))),
+			Some(txn) => MutexGuard::try_map(txn.lock().await, |txn| txn.as_mut())
+				.map(DbConn::Txn)
+				.map_err(|_| Error::TxnNotStarted),
+		}
+	}
+
+	/// Returns a `ModelManager` running all its queries in a new transaction.
+	pub async fn begin(&self) -> Result<ModelManager> {
+		if self.txn.is_some() {
+			return Err(Error::TxnAlreadyStarted);
+		}
+
+		let mut txn = self.db.begin().await?;
+		sqlx::query("SET TRANSACTION ISOLATION LEVEL SERIALIZABLE")
+			.execute(&mut *txn)
+			.await?;
+
+		Ok(ModelManager {
+			db: self.db.clone(),
+			txn: Some(Arc::new(Mutex::new(Some(txn)))),
+			events: self.events.clone(),
+			txn_changes: Some(Default::default()),
+		})
+	}
+
+	/// Publish the change events of a `ModelManager` from `begin` in its
+	/// transaction, then commit it (so, the events with the writes, or none).
+	pub async fn commit(self) -> Result<()> {
+		self.publish_txn_changes().await?;
+		self.take_txn().await?.commit().await?;
+
+		Ok(())
+	}
+
+	/// Rollback the transaction of a `ModelManager` from `begin`.
+	pub async fn rollback(self) -> Result<()> {
+		self.take_txn().await?.rollback().await?;
+
+		Ok(())
+	}
+
+	/// Run `f` in a transaction, committed when `f` succeeds and rolled back
+	/// otherwise, and retried on serialization failures.
+	///
+	/// When `self` is already transactional, `f` just runs in that transaction
+	/// (the outer one decides of the commit and retries).
+	pub async fn txn<F, Fut, T>(&self, f: F) -> Result<T>
+	where
+		F: Fn(ModelManager) -> Fut,
+		Fut: Future<Output = Result<T>>,
+	{
+		if self.txn.is_some() {
+			return f(self.clone()).await;
+		}
+
+		let mut attempt = 1;
+		loop {
+			let mm = self.begin().await?;
+
+			let res = match f(mm.clone()).await {
+				Ok(val) => mm.commit().await.map(|_| val),
+				Err(ex) => {
+					// The original error matters more than a failed rollback.
+					let _ = mm.rollback().await;
+					Err(ex)
+				}
+			};
+
+			match res {
+				Err(ex) if ex.is_retryable() && attempt < TXN_MAX_ATTEMPTS => {
+					debug!("{:<12} - txn retry {attempt} - {ex:?}", "MODEL");
+					tokio::time::sleep(TXN_RETRY_DELAY * attempt).await;
+					attempt += 1;
+				}
+				res => return res,
+			}
+		}
+	}
+
+	/// Run `fut` (on this transactional `ModelManager`) in a savepoint, rolled back
+	/// to when it fails, so that the transaction can go on (e.g., for the per item
+	/// results of the bulk updates).
+	///
+	/// Note: The retryable errors (see `txn`) are returned as is, as they fail
+	///       the whole transaction anyway.
+	pub(in crate::model) async fn savepoint<Fut, T>(&self, fut: Fut) -> Result<T>
+	where
+		Fut: Future<Output = Result<T>>,
+	{
+		let txn_changes_len = self.txn_changes_len();
+		sqlx::query("SAVEPOINT model_savepoint")
+			.execute(&mut *self.db().await?)
+			.await?;
+
+		match fut.await {
+			Ok(val) => {
+				sqlx::query("RELEASE SAVEPOINT model_savepoint")
+					.execute(&mut *self.db().await?)
+					.await?;
+				Ok(val)
+			}
+			Err(ex) if ex.is_retryable() => Err(ex),
+			Err(ex) => {
+				sqlx::query("ROLLBACK TO SAVEPOINT model_savepoint")
+					.execute(&mut *self.db().await?)
+					.await?;
+				self.truncate_txn_changes(txn_changes_len);
+				Err(ex)
+			}
+		}
+	}
+
+	/// Lock `table` until the end of the transaction (no-op when not transactional),
+	/// so that the transactions taking it run one at a time.
+	///
+	/// Note: Unlike an advisory lock (a `SELECT`), `LOCK TABLE` takes no snapshot,
+	///       so when first in the transaction, it sees the data committed by
+	///       the previous lock holder (rather than failing to serialize).
+	pub(in crate::model) async fn lock_table(&self, table: &str) -> Result<()> {
+		if self.txn.is_none() {
+			return Ok(());
+		}
+
+		sqlx::query(&format!(r#"LOCK TABLE "{table}" IN EXCLUSIVE MODE"#))
+			.execute(&mut *self.db().await?)
+			.await?;
+
+		Ok(())
+	}
+
+	async fn take_txn(&self) -> Result<Transaction<'static, Postgres>> {
+		let txn = self.txn.as_ref().ok_or(Error::TxnNotStarted)?;
+		let txn = txn.lock().await.take().ok_or(Error::TxnNotStarted)?;
+
+		Ok(txn)
+	}
+}
+
+impl Error {
+	/// True for the db errors worth retrying the transaction for.
+	pub(in crate::model) fn is_retryable(&self) -> bool {
+		match self {
+			Error::Sqlx(sqlx::Error::Database(ex)) => ex
+				.code()
+				.is_some_and(|code| RETRYABLE_CODES.contains(&code.as_ref())),
+			_ => false,
+		}
+	}
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::_dev_utils;
+	use crate::ctx::Ctx;
+	use crate::model::task::{TaskBmc, TaskForCreate};
+	use anyhow::Result;
+	use serial_test::serial;
+	use std::sync::atomic::{AtomicU32, Ordering};
+
+	#[serial]
+	#[tokio::test]
+	async fn test_commit_rollback_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_task_c = |title: &str| TaskForCreate {
+			title: title.to_string(),
+			..Default::default()
+		};
+
+		// -- Exec
+		let mm_txn = mm.begin().await?;
+		let committed_id =
+			TaskBmc::create(&ctx, &mm_txn, fx_task_c("committed")).await?;
+		mm_txn.commit().await?;
+
+		let mm_txn = mm.begin().await?;
+		let rolled_back_id =
+			TaskBmc::create(&ctx, &mm_txn, fx_task_c("rolled back")).await?;
+		// Visible in the transaction only.
+		TaskBmc::get(&ctx, &mm_txn, rolled_back_id).await?;
+		mm_txn.rollback().await?;
+
+		// -- Check
+		TaskBmc::get(&ctx, &mm, committed_id).await?;
+		let res = TaskBmc::get(&ctx, &mm, rolled_back_id).await;
+		assert!(
+			matches!(res, Err(Error::EntityNotFound { .. })),
+			"rolled back task should not be found"
+		);
+
+		// -- Clean
+		TaskBmc::delete(&ctx, &mm, committed_id).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_txn_err_rolled_back() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_id = _dev_utils::seed_tasks(&ctx, &mm, &["test_txn_err"])
+			.await?
+			.remove(0)
+			.id;
+
+		// -- Exec
+		// Deletes the task, then fails.
+		let ctx_ref = &ctx;
+		let res = mm
+			.txn(|mm| async move {
+				TaskBmc::delete(ctx_ref, &mm, fx_id).await?;
+				TaskBmc::get(ctx_ref, &mm, fx_id).await
+			})
+			.await;
+
+		// -- Check
+		assert!(matches!(res, Err(Error::EntityNotFound { .. })));
+		TaskBmc::get(&ctx, &mm, fx_id).await?;
+
+		// -- Clean
+		TaskBmc::delete(&ctx, &mm, fx_id).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_txn_ok_retry_serialization_failure() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let attempts = AtomicU32::new(0);
+
+		// -- Exec
+		let res = mm
+			.txn(|mm| {
+				let attempt = attempts.fetch_add(1, Ordering::SeqCst) + 1;
+				async move {
+					if attempt == 1 {
+						// Simulate a serialization failure on the first attempt.
+						sqlx::query(
+							"DO $$ BEGIN RAISE EXCEPTION 'fx' \
+							 USING ERRCODE = 'serialization_failure'; END $$",
+						)
+						.execute(&mut *mm.db().await?)
+						.await?;
+					}
+					Ok(attempt)
+				}
+			})
+			.await?;
+
+		// -- Check
+		assert_eq!(res, 2);
+		assert_eq!(attempts.load(Ordering::SeqCst), 2);
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_begin_err_already_started() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+
+		// -- Exec
+		let mm_txn = mm.begin().await?;
+		let res = mm_txn.begin().await;
+
+		// -- Check
+		assert!(matches!(res, Err(Error::TxnAlreadyStarted)));
+		mm_txn.rollback().await?;
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
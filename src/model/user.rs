@@ -1,7 +1,7 @@
 use crate::ctx::Ctx;
 use crate::model::base::{self, DbBmc};
 use crate::model::ModelManager;
-use crate::model::Result;
+use crate::model::{Error, Result};
 use crate::pwd::{self, ContentToHash};
 use serde::{Deserialize, Serialize};
 use sqlb::{Fields, HasFields};
@@ -63,6 +63,24 @@ impl DbBmc for UserBmc {
 }
 
 impl UserBmc {
+	pub async fn create(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		user_c: UserForCreate,
+	) -> Result<i64> {
+		let UserForCreate {
+			username,
+			pwd_clear,
+		} = user_c;
+
+		let user_fi = UserForInsert { username };
+		let user_id = base::create::<Self, _>(ctx, mm, user_fi).await?;
+
+		Self::update_pwd(ctx, mm, user_id, &pwd_clear).await?;
+
+		Ok(user_id)
+	}
+
 	pub async fn get<E>(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<E>
 	where
 		E: UserBy,
@@ -78,12 +96,10 @@ impl UserBmc {
 	where
 		E: UserBy,
 	{
-		let db = mm.db();
-
 		let user = sqlb::select()
 			.table(Self::TABLE)
 			.and_where("username", "=", username)
-			.fetch_optional::<_, E>(db)
+			.fetch_optional::<_, E>(&mut *mm.db().await?)
 			.await?;
 
 		Ok(user)
@@ -95,8 +111,6 @@ impl UserBmc {
 		id: i64,
 		pwd_clear: &str,
 	) -> Result<()> {
-		let db = mm.db();
-
 		let user: UserForLogin = Self::get(ctx, mm, id).await?;
 		let pwd = pwd::hash_pwd(&ContentToHash {
 			content: pwd_clear.to_string(),
@@ -107,11 +121,41 @@ impl UserBmc {
 			.table(Self::TABLE)
 			.and_where("id", "=", id)
 			.data(vec![("pwd", pwd.to_string()).into()])
-			.exec(db)
+			.exec(&mut *mm.db().await?)
 			.await?;
 
 		Ok(())
 	}
+
+	/// Delete the users, their owned rows cascading (e.g., webhooks, saved views),
+	/// returning the deleted ids.
+	pub async fn delete_many(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		ids: &[i64],
+	) -> Result<Vec<i64>> {
+		base::delete_many::<Self>(ctx, mm, ids).await
+	}
+
+	/// Fails with `Error::AdminRequired` unless the ctx user is an admin
+	/// (the root ctx being one).
+	pub async fn require_admin(ctx: &Ctx, mm: &ModelManager) -> Result<()> {
+		let user_id = ctx.user_id();
+		if user_id == Ctx::root_ctx().user_id() {
+			return Ok(());
+		}
+
+		let is_admin: Option<(bool,)> =
+			sqlx::query_as(r#"SELECT is_admin FROM "user" WHERE id = $1"#)
+				.bind(user_id)
+				.fetch_optional(&mut *mm.db().await?)
+				.await?;
+
+		match is_admin {
+			Some((true,)) => Ok(()),
+			_ => Err(Error::AdminRequired { user_id }),
+		}
+	}
 }
 
 // region:    --- Tests
@@ -140,5 +184,40 @@ mod tests {
 
 		Ok(())
 	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_create_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_username = "test_create_ok-user-01";
+		let fx_pwd_clear = "test_create_ok pwd 01";
+
+		// -- Exec
+		let user_id = UserBmc::create(
+			&ctx,
+			&mm,
+			UserForCreate {
+				username: fx_username.to_string(),
+				pwd_clear: fx_pwd_clear.to_string(),
+			},
+		)
+		.await?;
+
+		// -- Check
+		let user: UserForLogin = UserBmc::get(&ctx, &mm, user_id).await?;
+		assert_eq!(user.username, fx_username);
+		let pwd = user.pwd.context("Should have a pwd")?;
+		pwd::validate_pwd(
+			&ContentToHash {
+				content: fx_pwd_clear.to_string(),
+				salt: user.pwd_salt,
+			},
+			&pwd,
+		)?;
+
+		Ok(())
+	}
 }
 // endregion: --- Tests
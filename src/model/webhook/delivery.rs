@@ -0,0 +1,401 @@
+//! The webhook deliveries (POST of the `WebhookBody`), signed with HMAC-SHA256.
+//!
+//! Request headers:
+//!
+//! - `X-Webhook-Id` - The delivery id (same for its retries, to dedupe them).
+//! - `X-Webhook-Event` - The event type (e.g., `task.created`).
+//! - `X-Webhook-Timestamp` - The send time (unix seconds).
+//! - `X-Webhook-Signature` - `sha256=<hex>`, the HMAC-SHA256 (with the webhook
+//!   secret) of `<timestamp>.<body>`.
+//!
+
+use crate::model::job::{Job, JobError};
+use crate::model::webhook::target;
+use crate::model::webhook::{WebhookBody, WebhookDelivery};
+use crate::model::{Error, ModelManager, Result};
+use crate::utils::time::now_utc;
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+/// Max time for the endpoint to respond.
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+const HEADER_ID: &str = "X-Webhook-Id";
+const HEADER_EVENT: &str = "X-Webhook-Event";
+const HEADER_TIMESTAMP: &str = "X-Webhook-Timestamp";
+const HEADER_SIGNATURE: &str = "X-Webhook-Signature";
+
+/// The delivery of an event to a webhook (retried with the job backoff,
+/// until a 2xx response).
+#[derive(Serialize, Deserialize)]
+pub struct DeliverWebhook {
+	pub webhook_id: i64,
+	pub body: WebhookBody,
+}
+
+#[async_trait]
+impl Job for DeliverWebhook {
+	const KIND: &'static str = "deliver_webhook";
+	const MAX_ATTEMPTS: i32 = 8;
+
+	async fn run(self, mm: &ModelManager) -> core::result::Result<(), JobError> {
+		let delivery = match deliver(mm, self.webhook_id, &self.body).await {
+			Ok(delivery) => delivery,
+			// Deleted since queued.
+			Err(Error::EntityNotFound { .. }) => return Ok(()),
+			Err(ex) => return Err(ex.into()),
+		};
+
+		match (delivery.status_code, delivery.error) {
+			(Some(status_code), _) if (200..300).contains(&status_code) => Ok(()),
+			(Some(status_code), _) => Err(JobError(format!("HTTP {status_code}"))),
+			(None, error) => Err(JobError(error.unwrap_or_default())),
+		}
+	}
+}
+
+/// POST the body to the webhook, returning the logged attempt.
+pub(super) async fn deliver(
+	mm: &ModelManager,
+	webhook_id: i64,
+	body: &WebhookBody,
+) -> Result<WebhookDelivery> {
+	let (url, secret): (String, String) =
+		sqlx::query_as("SELECT url, secret FROM webhook WHERE id = $1")
+			.bind(webhook_id)
+			.fetch_optional(&mut *mm.db().await?)
+			.await?
+			.ok_or(Error::EntityNotFound {
+				entity: "webhook",
+				id: webhook_id,
+			})?;
+
+	let body_json = serde_json::to_string(body)?;
+	let timestamp = now_utc().unix_timestamp().to_string();
+	let signature = sign(&secret, &timestamp, &body_json)?;
+
+	let start = Instant::now();
+	// Note: Checked again, as the url host might resolve differently since
+	//       registered.
+	let res = async {
+		let addrs = target::check_url(&url).await.map_err(|ex| ex.to_string())?;
+		http_client(&url, &addrs)
+			.map_err(|ex| ex.to_string())?
+			.post(&url)
+			.header(reqwest::header::CONTENT_TYPE, "application/json")
+			.header(HEADER_ID, body.delivery_id.to_string())
+			.header(HEADER_EVENT, &body.event)
+			.header(HEADER_TIMESTAMP, &timestamp)
+			.header(HEADER_SIGNATURE, signature)
+			.body(body_json)
+			.send()
+			.await
+			.map_err(|ex| ex.to_string())
+	}
+	.await;
+	let duration_ms = start.elapsed().as_millis().min(i32::MAX as u128) as i32;
+
+	let (status_code, error) = match res {
+		Ok(res) => (Some(res.status().as_u16() as i32), None),
+		Err(error) => (None, Some(error)),
+	};
+	debug!(
+		"{:<12} - {} to webhook {webhook_id} - {status_code:?} {error:?}",
+		"WEBHOOKS", body.event
+	);
+
+	let delivery = sqlx::query_as(
+		"INSERT INTO webhook_delivery
+			(webhook_id, delivery_id, event, attempt, status_code, error, duration_ms)
+		 SELECT $1, $2, $3, count(*) + 1, $4, $5, $6
+		 FROM webhook_delivery WHERE delivery_id = $2
+		 RETURNING id, webhook_id, delivery_id, event, attempt, status_code, error,
+		           duration_ms, ctime",
+	)
+	.bind(webhook_id)
+	.bind(body.delivery_id)
+	.bind(&body.event)
+	.bind(status_code)
+	.bind(error)
+	.bind(duration_ms)
+	.fetch_one(&mut *mm.db().await?)
+	.await?;
+
+	Ok(delivery)
+}
+
+/// The `X-Webhook-Signature` of the body.
+pub(super) fn sign(secret: &str, timestamp: &str, body: &str) -> Result<String> {
+	let mut hmac_sha256 = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+		.map_err(|_| Error::WebhookSignFail)?;
+
+	hmac_sha256.update(timestamp.as_bytes());
+	hmac_sha256.update(b".");
+	hmac_sha256.update(body.as_bytes());
+
+	let hex: String = hmac_sha256
+		.finalize()
+		.into_bytes()
+		.iter()
+		.map(|b| format!("{b:02x}"))
+		.collect();
+
+	Ok(format!("sha256={hex}"))
+}
+
+/// The http client of a delivery, connecting to the checked addresses of the
+/// url host (see `target::check_url`), and not following the redirects
+/// (a `3xx` being a failure).
+///
+/// Note: One client per delivery (i.e., no connection reuse), as the
+///       deliveries are not frequent enough for their reuse.
+fn http_client(url: &str, addrs: &[SocketAddr]) -> reqwest::Result<reqwest::Client> {
+	let mut builder = reqwest::Client::builder()
+		.timeout(DELIVERY_TIMEOUT)
+		.redirect(reqwest::redirect::Policy::none());
+
+	let host = reqwest::Url::parse(url)
+		.ok()
+		.and_then(|url| url.host_str().map(String::from));
+	if let (Some(host), false) = (host, addrs.is_empty()) {
+		builder = builder.resolve_to_addrs(&host, addrs);
+	}
+
+	builder.build()
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::_dev_utils;
+	use crate::ctx::Ctx;
+	use crate::model::base::ListOptions;
+	use crate::model::job::{JobBmc, JobStatus, Jobs};
+	use crate::model::task::{TaskBmc, TaskForCreate};
+	use crate::model::webhook::{WebhookBmc, WebhookForCreate};
+	use anyhow::{Context, Result};
+	use axum::body::Bytes;
+	use axum::extract::State;
+	use axum::http::{HeaderMap, StatusCode};
+	use axum::routing::post;
+	use axum::Router;
+	use serial_test::serial;
+	use std::net::TcpListener;
+	use std::sync::atomic::{AtomicU16, Ordering};
+	use std::sync::{Arc, Mutex};
+	use std::thread;
+
+	/// A local stand-in of a webhook endpoint, recording the requests it gets,
+	/// and responding with its `status`.
+	#[derive(Clone)]
+	struct FxEndpoint {
+		url: String,
+		status: Arc<AtomicU16>,
+		requests: Arc<Mutex<Vec<(HeaderMap, Bytes)>>>,
+	}
+
+	impl FxEndpoint {
+		/// Serve the endpoint on a free local port (on its own runtime).
+		fn serve() -> Result<Self> {
+			let listener = TcpListener::bind("127.0.0.1:0")?;
+			listener.set_nonblocking(true)?;
+			let endpoint = FxEndpoint {
+				url: format!("http://{}/hook", listener.local_addr()?),
+				status: Arc::new(AtomicU16::new(200)),
+				requests: Arc::default(),
+			};
+
+			let state = endpoint.clone();
+			thread::spawn(move || {
+				let rt = tokio::runtime::Builder::new_current_thread()
+					.enable_all()
+					.build()
+					.expect("endpoint runtime");
+				rt.block_on(async move {
+					let app = Router::new()
+						.route("/hook", post(fx_endpoint_handler))
+						.with_state(state);
+					axum::Server::from_tcp(listener)
+						.expect("endpoint listener")
+						.serve(app.into_make_service())
+						.await
+						.expect("endpoint run");
+				});
+			});
+
+			Ok(endpoint)
+		}
+
+		fn take_requests(&self) -> Vec<(HeaderMap, Bytes)> {
+			std::mem::take(&mut *self.requests.lock().unwrap())
+		}
+	}
+
+	async fn fx_endpoint_handler(
+		State(endpoint): State<FxEndpoint>,
+		headers: HeaderMap,
+		body: Bytes,
+	) -> StatusCode {
+		endpoint.requests.lock().unwrap().push((headers, body));
+
+		StatusCode::from_u16(endpoint.status.load(Ordering::SeqCst))
+			.unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_deliver_signed_and_retried() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let root_ctx = Ctx::root_ctx();
+		let user_id =
+			_dev_utils::seed_users(&root_ctx, &mm, &["test_deliver_signed-user-01"])
+				.await?[0];
+		let ctx = Ctx::new(user_id)?;
+		let endpoint = FxEndpoint::serve()?;
+		let webhook_id = WebhookBmc::create(
+			&ctx,
+			&mm,
+			WebhookForCreate {
+				url: endpoint.url.clone(),
+				events: vec!["task.created".to_string()],
+			}
+			.validate()
+			.await?,
+		)
+		.await?;
+		let secret = WebhookBmc::secret(&ctx, &mm, webhook_id).await?;
+		let jobs = Jobs::new().register::<DeliverWebhook>();
+		let fx_task_c = TaskForCreate {
+			title: "test_deliver_signed_and_retried".to_string(),
+			..Default::default()
+		};
+
+		// -- Exec
+		// Created (delivered), then deleted (not in the webhook events).
+		let task_id = TaskBmc::create(&ctx, &mm, fx_task_c).await?;
+		TaskBmc::delete(&ctx, &mm, task_id).await?;
+		endpoint.status.store(500, Ordering::SeqCst);
+		let failed_job_id = jobs.run_next(&mm).await?.context("Should run a job")?;
+		endpoint.status.store(200, Ordering::SeqCst);
+		sqlx::query("UPDATE job SET run_at = now() WHERE id = $1")
+			.bind(failed_job_id)
+			.execute(&mut *mm.db().await?)
+			.await?;
+		let job_id = jobs.run_next(&mm).await?.context("Should retry the job")?;
+
+		// -- Check
+		assert_eq!(job_id, failed_job_id);
+		assert_eq!(jobs.run_next(&mm).await?, None);
+		let job = JobBmc::get(&root_ctx, &mm, job_id).await?;
+		assert_eq!((job.status, job.attempts), (JobStatus::Done, 2));
+
+		let requests = endpoint.take_requests();
+		assert_eq!(requests.len(), 2);
+		let (headers, body) = &requests[1];
+		let header = |name| headers[name].to_str().unwrap_or_default();
+		assert_eq!(header(HEADER_EVENT), "task.created");
+		assert_eq!(header(HEADER_ID), requests[0].0[HEADER_ID]);
+		let expected_signature = sign(
+			&secret,
+			header(HEADER_TIMESTAMP),
+			std::str::from_utf8(body)?,
+		)?;
+		assert_eq!(header(HEADER_SIGNATURE), expected_signature);
+		let body: WebhookBody = serde_json::from_slice(body)?;
+		assert_eq!(body.data["id"], task_id);
+
+		let deliveries =
+			WebhookBmc::list_deliveries(&ctx, &mm, webhook_id, None).await?;
+		let attempts: Vec<_> = deliveries
+			.iter()
+			.map(|d| (d.attempt, d.status_code))
+			.collect();
+		assert_eq!(attempts, &[(2, Some(200)), (1, Some(500))]);
+		let page_options = |cursor| ListOptions {
+			limit: Some(1),
+			cursor,
+			..Default::default()
+		};
+		let page = WebhookBmc::list_deliveries_page(
+			&ctx,
+			&mm,
+			webhook_id,
+			Some(page_options(None)),
+		)
+		.await?;
+		assert!(page.has_more);
+		assert_eq!(page.items[0].attempt, 2);
+		let page = WebhookBmc::list_deliveries_page(
+			&ctx,
+			&mm,
+			webhook_id,
+			Some(page_options(page.next_cursor)),
+		)
+		.await?;
+		assert!(!page.has_more);
+		assert_eq!(page.items[0].attempt, 1);
+
+		// -- Clean
+		sqlx::query("DELETE FROM job WHERE id = $1")
+			.bind(job_id)
+			.execute(&mut *mm.db().await?)
+			.await?;
+		TaskBmc::purge(&root_ctx, &mm, task_id).await?;
+		// With its webhook (and its deliveries).
+		_dev_utils::clean_users(&root_ctx, &mm, &[user_id]).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_send_test_ok_and_unreachable() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let root_ctx = Ctx::root_ctx();
+		let user_id =
+			_dev_utils::seed_users(&root_ctx, &mm, &["test_send_test_ok-user-01"])
+				.await?[0];
+		let ctx = Ctx::new(user_id)?;
+		let endpoint = FxEndpoint::serve()?;
+		let fx_webhook_c = |url: &str| WebhookForCreate {
+			url: url.to_string(),
+			events: vec!["*".to_string()],
+		};
+		let ok_webhook_c = fx_webhook_c(&endpoint.url).validate().await?;
+		let ok_id = WebhookBmc::create(&ctx, &mm, ok_webhook_c).await?;
+		// Nothing listening on the port 9 (discard).
+		let unreachable_webhook_c =
+			fx_webhook_c("http://127.0.0.1:9/").validate().await?;
+		let unreachable_id =
+			WebhookBmc::create(&ctx, &mm, unreachable_webhook_c).await?;
+
+		// -- Exec
+		let ok_delivery = WebhookBmc::send_test(&ctx, &mm, ok_id).await?;
+		let unreachable_delivery =
+			WebhookBmc::send_test(&ctx, &mm, unreachable_id).await?;
+
+		// -- Check
+		assert_eq!(ok_delivery.event, "webhook.test");
+		assert_eq!(ok_delivery.status_code, Some(200));
+		let requests = endpoint.take_requests();
+		let body: WebhookBody = serde_json::from_slice(&requests[0].1)?;
+		assert_eq!(body.data["webhook_id"], ok_id);
+		assert_eq!(unreachable_delivery.status_code, None);
+		assert!(unreachable_delivery.error.is_some());
+
+		// -- Clean
+		// With its webhooks (and their deliveries).
+		_dev_utils::clean_users(&root_ctx, &mm, &[user_id]).await?;
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
@@ -0,0 +1,522 @@
+//! Outgoing webhooks, the change events being delivered (POST) to the endpoints
+//! registered by the users, filtered by event type (e.g., `task.created`).
+//!
+//! Design:
+//!
+//! - The deliveries of the change events are queued as `DeliverWebhook` jobs
+//!   in the transaction persisting the events (see `event::publish`), so they
+//!   are retried (with the job backoff) until delivered, or `dead`.
+//! - Each delivery is signed with HMAC-SHA256 with the webhook secret
+//!   (see `delivery`), and each attempt is logged in `webhook_delivery`
+//!   (see `WebhookBmc::list_deliveries`).
+//! - A webhook is only managed by its owner, and only gets the events visible
+//!   to its owner (see `ChangeEvent::is_visible_to`).
+//!
+
+// region:    --- Modules
+
+mod delivery;
+mod target;
+
+pub use self::delivery::DeliverWebhook;
+
+use crate::ctx::Ctx;
+use crate::model::base::{self, DbBmc, ListOptions, ListPage};
+use crate::model::event::{ChangeEvent, ChangeKind};
+use crate::model::job;
+use crate::model::{Error, ModelManager, Result};
+use crate::utils::time::{now_utc, Rfc3339, Rfc3339Schema};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use serde_with::serde_as;
+use sqlx::{FromRow, PgConnection};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+// endregion: --- Modules
+
+const TABLE: &str = "webhook";
+
+/// The event type of the test deliveries (see `WebhookBmc::send_test`).
+const TEST_EVENT: &str = "webhook.test";
+
+/// The `WebhookDelivery` columns (for `base::list_page_columns`).
+const DELIVERY_COLUMNS: &[&str] = &[
+	"id",
+	"webhook_id",
+	"delivery_id",
+	"event",
+	"attempt",
+	"status_code",
+	"error",
+	"duration_ms",
+	"ctime",
+];
+
+// region:    --- Webhook Types
+
+#[serde_as]
+#[derive(Debug, Clone, FromRow, Serialize, JsonSchema)]
+pub struct Webhook {
+	pub id: i64,
+	pub owner_id: i64,
+	pub url: String,
+	/// The delivered event types (e.g., `task.created`, `task.*`, or `*` for all).
+	pub events: Vec<String>,
+	pub active: bool,
+
+	#[serde_as(as = "Rfc3339")]
+	#[schemars(with = "Rfc3339Schema")]
+	pub ctime: OffsetDateTime,
+	#[serde_as(as = "Option<Rfc3339>")]
+	#[schemars(with = "Option<Rfc3339Schema>")]
+	pub mtime: Option<OffsetDateTime>,
+}
+
+#[derive(Clone, Deserialize, JsonSchema)]
+pub struct WebhookForCreate {
+	/// The `http(s)` url the events are posted to.
+	pub url: String,
+	pub events: Vec<String>,
+}
+
+#[derive(Clone, Default, Deserialize, JsonSchema)]
+pub struct WebhookForUpdate {
+	pub url: Option<String>,
+	pub events: Option<Vec<String>>,
+	pub active: Option<bool>,
+}
+
+/// A webhook create/update checked by its `validate`, so that the writes
+/// (e.g., in a transaction) do not wait on the url DNS resolution.
+#[derive(Clone)]
+pub struct Validated<T>(T);
+
+impl WebhookForCreate {
+	/// Checks the url (resolved, see `target`) and the event filters.
+	pub async fn validate(self) -> Result<Validated<Self>> {
+		validate(Some(&self.url), Some(&self.events)).await?;
+
+		Ok(Validated(self))
+	}
+}
+
+impl WebhookForUpdate {
+	/// Checks the url (resolved, see `target`) and the event filters, when given.
+	pub async fn validate(self) -> Result<Validated<Self>> {
+		validate(self.url.as_deref(), self.events.as_deref()).await?;
+
+		Ok(Validated(self))
+	}
+}
+
+/// A delivery attempt (of the log).
+#[serde_as]
+#[derive(Debug, Clone, FromRow, Serialize, JsonSchema)]
+pub struct WebhookDelivery {
+	pub id: i64,
+	pub webhook_id: i64,
+	/// Same for all the attempts of a delivery (sent as `X-Webhook-Id`).
+	#[schemars(with = "String")]
+	pub delivery_id: Uuid,
+	pub event: String,
+	pub attempt: i32,
+
+	/// None when the endpoint did not respond (see `error`).
+	pub status_code: Option<i32>,
+	pub error: Option<String>,
+	pub duration_ms: i32,
+
+	#[serde_as(as = "Rfc3339")]
+	#[schemars(with = "Rfc3339Schema")]
+	pub ctime: OffsetDateTime,
+}
+
+/// The body of the delivery requests.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookBody {
+	pub delivery_id: Uuid,
+	/// The event type (e.g., `task.created`).
+	pub event: String,
+	/// The change event (or the test event data).
+	pub data: Value,
+}
+
+impl WebhookBody {
+	fn from_change_event(event: &ChangeEvent) -> Result<Self> {
+		Ok(Self {
+			delivery_id: Uuid::new_v4(),
+			event: event_type(event),
+			data: serde_json::to_value(event)?,
+		})
+	}
+}
+
+/// The webhook event type of a change event (e.g., `task.created`).
+fn event_type(event: &ChangeEvent) -> String {
+	let kind = serde_json::to_value(event.kind)
+		.ok()
+		.and_then(|kind| kind.as_str().map(String::from))
+		.unwrap_or_default();
+
+	format!("{}.{kind}", event.entity)
+}
+
+/// True for `*`, `<entity>.*`, and `<entity>.<kind>` (e.g., `task.created`),
+/// of the entities with change events.
+fn is_valid_event_filter(filter: &str) -> bool {
+	if filter == "*" {
+		return true;
+	}
+	let Some((entity, kind)) = filter.split_once('.') else {
+		return false;
+	};
+
+	!entity.contains(':')
+		&& ChangeEvent::is_valid_channel(entity)
+		&& (kind == "*"
+			|| serde_json::from_value::<ChangeKind>(Value::from(kind)).is_ok())
+}
+
+fn matches_event_filters(filters: &[String], event_type: &str) -> bool {
+	let entity_all = event_type
+		.split_once('.')
+		.map(|(entity, _)| format!("{entity}.*"));
+
+	filters.iter().any(|filter| {
+		filter == "*" || filter == event_type || Some(filter) == entity_all.as_ref()
+	})
+}
+
+async fn validate(url: Option<&str>, events: Option<&[String]>) -> Result<()> {
+	if let Some(url) = url {
+		target::check_url(url).await?;
+	}
+
+	if let Some(events) = events {
+		if events.is_empty() {
+			return Err(Error::WebhookInvalidEvent(String::new()));
+		}
+		if let Some(invalid) = events.iter().find(|e| !is_valid_event_filter(e)) {
+			return Err(Error::WebhookInvalidEvent(invalid.to_string()));
+		}
+	}
+
+	Ok(())
+}
+
+// endregion: --- Webhook Types
+
+// region:    --- WebhookBmc
+
+/// The delivery attempts log, for `base::list_page_columns`
+/// (written by the `delivery` module only).
+struct WebhookDeliveryBmc;
+
+impl DbBmc for WebhookDeliveryBmc {
+	const TABLE: &'static str = "webhook_delivery";
+	const LIST_ORDER_BYS: &'static [&'static str] = &["!id"];
+}
+
+pub struct WebhookBmc;
+
+impl WebhookBmc {
+	/// Register a webhook of the ctx user, returning its id
+	/// (its signing secret being returned by `WebhookBmc::secret`).
+	pub async fn create(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		webhook_c: Validated<WebhookForCreate>,
+	) -> Result<i64> {
+		let Validated(WebhookForCreate { url, events }) = webhook_c;
+
+		let secret = format!(
+			"whsec_{}{}",
+			Uuid::new_v4().simple(),
+			Uuid::new_v4().simple()
+		);
+		let (id,): (i64,) = sqlx::query_as(
+			"INSERT INTO webhook (owner_id, url, events, secret)
+			 VALUES ($1, $2, $3, $4) RETURNING id",
+		)
+		.bind(ctx.user_id())
+		.bind(url)
+		.bind(events)
+		.bind(secret)
+		.fetch_one(&mut *mm.db().await?)
+		.await?;
+
+		Ok(id)
+	}
+
+	pub async fn get(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<Webhook> {
+		let webhook: Webhook = sqlx::query_as(
+			"SELECT id, owner_id, url, events, active, ctime, mtime
+			 FROM webhook WHERE id = $1",
+		)
+		.bind(id)
+		.fetch_optional(&mut *mm.db().await?)
+		.await?
+		.ok_or(Error::EntityNotFound { entity: TABLE, id })?;
+
+		check_owner(ctx, &webhook)?;
+
+		Ok(webhook)
+	}
+
+	/// The signing secret of the webhook.
+	pub async fn secret(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<String> {
+		Self::get(ctx, mm, id).await?;
+
+		let (secret,): (String,) =
+			sqlx::query_as("SELECT secret FROM webhook WHERE id = $1")
+				.bind(id)
+				.fetch_one(&mut *mm.db().await?)
+				.await?;
+
+		Ok(secret)
+	}
+
+	/// The webhooks of the ctx user.
+	pub async fn list(ctx: &Ctx, mm: &ModelManager) -> Result<Vec<Webhook>> {
+		let webhooks = sqlx::query_as(
+			"SELECT id, owner_id, url, events, active, ctime, mtime
+			 FROM webhook WHERE owner_id = $1 ORDER BY id",
+		)
+		.bind(ctx.user_id())
+		.fetch_all(&mut *mm.db().await?)
+		.await?;
+
+		Ok(webhooks)
+	}
+
+	pub async fn update(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		id: i64,
+		webhook_u: Validated<WebhookForUpdate>,
+	) -> Result<()> {
+		let Validated(WebhookForUpdate {
+			url,
+			events,
+			active,
+		}) = webhook_u;
+		Self::get(ctx, mm, id).await?;
+
+		sqlx::query(
+			"UPDATE webhook SET
+				url = COALESCE($2, url),
+				events = COALESCE($3, events),
+				active = COALESCE($4, active),
+				mtime = $5
+			 WHERE id = $1",
+		)
+		.bind(id)
+		.bind(url)
+		.bind(events)
+		.bind(active)
+		.bind(now_utc())
+		.execute(&mut *mm.db().await?)
+		.await?;
+
+		Ok(())
+	}
+
+	/// Delete the webhook (and its delivery log).
+	pub async fn delete(ctx: &Ctx, mm: &ModelManager, id: i64) -> Result<()> {
+		Self::get(ctx, mm, id).await?;
+
+		sqlx::query("DELETE FROM webhook WHERE id = $1")
+			.bind(id)
+			.execute(&mut *mm.db().await?)
+			.await?;
+
+		Ok(())
+	}
+
+	/// The delivery attempts of the webhook, latest first
+	/// (without cursor, see `list_deliveries_page` for the cursors).
+	pub async fn list_deliveries(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		id: i64,
+		list_options: Option<ListOptions>,
+	) -> Result<Vec<WebhookDelivery>> {
+		if let Some(list_options) = &list_options {
+			list_options.check_no_cursor()?;
+		}
+		let page = Self::list_deliveries_page(ctx, mm, id, list_options).await?;
+
+		Ok(page.items)
+	}
+
+	/// A page of the delivery attempts of the webhook, latest first
+	/// (see `base::list_page` for the cursors).
+	pub async fn list_deliveries_page(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		id: i64,
+		list_options: Option<ListOptions>,
+	) -> Result<ListPage<WebhookDelivery>> {
+		Self::get(ctx, mm, id).await?;
+		let sb = sqlb::select().and_where("webhook_id", "=", id);
+
+		base::list_page_columns::<WebhookDeliveryBmc, _>(
+			ctx,
+			mm,
+			sb,
+			DELIVERY_COLUMNS,
+			list_options,
+		)
+		.await
+	}
+
+	/// Deliver a `webhook.test` event now (even if the webhook is not active,
+	/// and without retries), returning the logged attempt.
+	pub async fn send_test(
+		ctx: &Ctx,
+		mm: &ModelManager,
+		id: i64,
+	) -> Result<WebhookDelivery> {
+		let webhook = Self::get(ctx, mm, id).await?;
+		let body = WebhookBody {
+			delivery_id: Uuid::new_v4(),
+			event: TEST_EVENT.to_string(),
+			data: serde_json::json!({ "webhook_id": webhook.id }),
+		};
+
+		delivery::deliver(mm, webhook.id, &body).await
+	}
+}
+
+fn check_owner(ctx: &Ctx, webhook: &Webhook) -> Result<()> {
+	let user_id = ctx.user_id();
+
+	if user_id == webhook.owner_id || user_id == Ctx::root_ctx().user_id() {
+		Ok(())
+	} else {
+		Err(Error::AccessDenied {
+			entity: TABLE,
+			id: webhook.id,
+		})
+	}
+}
+
+// endregion: --- WebhookBmc
+
+/// Queue the deliveries of the events to the matching active webhooks,
+/// on the connection persisting the events.
+pub(in crate::model) async fn enqueue_deliveries(
+	conn: &mut PgConnection,
+	events: &[ChangeEvent],
+) -> Result<()> {
+	let webhooks: Vec<(i64, i64, Vec<String>)> =
+		sqlx::query_as("SELECT id, owner_id, events FROM webhook WHERE active")
+			.fetch_all(&mut *conn)
+			.await?;
+	if webhooks.is_empty() {
+		return Ok(());
+	}
+
+	for event in events {
+		let event_type = event_type(event);
+
+		for (webhook_id, owner_id, filters) in webhooks.iter() {
+			let visible = Ctx::new(*owner_id)
+				.map(|owner_ctx| event.is_visible_to(&owner_ctx))
+				.unwrap_or(false);
+			if !visible || !matches_event_filters(filters, &event_type) {
+				continue;
+			}
+
+			let deliver = DeliverWebhook {
+				webhook_id: *webhook_id,
+				body: WebhookBody::from_change_event(event)?,
+			};
+			job::insert(&mut *conn, &deliver, None).await?;
+		}
+	}
+
+	Ok(())
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::_dev_utils;
+	use anyhow::Result;
+	use serial_test::serial;
+
+	#[test]
+	fn test_event_filters_ok() -> Result<()> {
+		// -- Exec & Check
+		for valid in ["*", "task.*", "task.created", "comment.deleted"] {
+			assert!(is_valid_event_filter(valid), "Should be valid '{valid}'");
+		}
+		for invalid in ["task", "task.moved", "user.*", "task:1000.*", ""] {
+			assert!(
+				!is_valid_event_filter(invalid),
+				"Should be invalid '{invalid}'"
+			);
+		}
+
+		let fx_filters = vec!["task.*".to_string(), "comment.created".to_string()];
+		assert!(matches_event_filters(&fx_filters, "task.deleted"));
+		assert!(matches_event_filters(&fx_filters, "comment.created"));
+		assert!(!matches_event_filters(&fx_filters, "comment.updated"));
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_create_err_invalid_and_access_denied() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let root_ctx = Ctx::root_ctx();
+		let user_ids = _dev_utils::seed_users(
+			&root_ctx,
+			&mm,
+			&[
+				"test_create_err_invalid-user-01",
+				"test_create_err_invalid-user-02",
+			],
+		)
+		.await?;
+		let ctx = Ctx::new(user_ids[0])?;
+		let other_ctx = Ctx::new(user_ids[1])?;
+		let fx_webhook_c = |url: &str, events: &[&str]| WebhookForCreate {
+			url: url.to_string(),
+			events: events.iter().map(|e| e.to_string()).collect(),
+		};
+
+		// -- Exec & Check
+		let res = fx_webhook_c("ftp://x.test", &["*"]).validate().await;
+		assert!(matches!(res, Err(Error::WebhookInvalidUrl(_))));
+		let res = fx_webhook_c("http://169.254.169.254/", &["*"])
+			.validate()
+			.await;
+		assert!(matches!(res, Err(Error::WebhookInvalidUrl(_))));
+		let res = fx_webhook_c("http://127.0.0.1:9/", &["task.moved"])
+			.validate()
+			.await;
+		assert!(matches!(res, Err(Error::WebhookInvalidEvent(_))));
+
+		let webhook_c = fx_webhook_c("http://127.0.0.1:9/", &["*"])
+			.validate()
+			.await?;
+		let id = WebhookBmc::create(&ctx, &mm, webhook_c).await?;
+		let res = WebhookBmc::get(&other_ctx, &mm, id).await;
+		assert!(matches!(res, Err(Error::AccessDenied { .. })));
+		assert!(WebhookBmc::list(&other_ctx, &mm).await?.is_empty());
+
+		// -- Clean
+		WebhookBmc::delete(&ctx, &mm, id).await?;
+		_dev_utils::clean_users(&root_ctx, &mm, &user_ids).await?;
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
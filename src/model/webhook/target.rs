@@ -0,0 +1,163 @@
+//! The webhook targets, restricted to the public addresses (i.e., no loopback,
+//! private, or link-local ones), so that a webhook cannot reach the internal
+//! network of the service (SSRF).
+//!
+//! The url host is checked after its DNS resolution, when the webhook is
+//! registered, and again on each delivery, the delivery connecting to the
+//! checked addresses (so that a host re-resolving to an internal address
+//! after the check is not reached).
+//!
+//! The hosts of `WEBHOOK_ALLOWED_HOSTS` are not checked (e.g., the local
+//! endpoints of dev and tests).
+//!
+
+use crate::config::config;
+use crate::model::{Error, Result};
+use reqwest::Url;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+/// Checks that the url is http(s), and that its host resolves to public
+/// addresses only, returning these addresses (empty for an allowed host).
+pub(super) async fn check_url(url: &str) -> Result<Vec<SocketAddr>> {
+	let invalid = || Error::WebhookInvalidUrl(url.to_string());
+
+	let parsed = Url::parse(url).map_err(|_| invalid())?;
+	if !matches!(parsed.scheme(), "http" | "https") {
+		return Err(invalid());
+	}
+	let host = parsed.host_str().ok_or_else(invalid)?;
+	if is_allowed_host(host) {
+		return Ok(Vec::new());
+	}
+
+	// Note: `Url` keeps the brackets of the ipv6 hosts.
+	let host = host.trim_start_matches('[').trim_end_matches(']');
+	let port = parsed.port_or_known_default().unwrap_or(80);
+	let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, port))
+		.await
+		.map_err(|_| invalid())?
+		.collect();
+
+	if addrs.is_empty() || !addrs.iter().all(|addr| is_public_ip(addr.ip())) {
+		return Err(invalid());
+	}
+
+	Ok(addrs)
+}
+
+fn is_allowed_host(host: &str) -> bool {
+	config()
+		.WEBHOOK_ALLOWED_HOSTS
+		.iter()
+		.any(|allowed| allowed.eq_ignore_ascii_case(host))
+}
+
+fn is_public_ip(ip: IpAddr) -> bool {
+	match ip {
+		IpAddr::V4(ip) => is_public_ipv4(ip),
+		IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+			Some(ip) => is_public_ipv4(ip),
+			None => is_public_ipv6(ip),
+		},
+	}
+}
+
+fn is_public_ipv4(ip: Ipv4Addr) -> bool {
+	let [a, b, ..] = ip.octets();
+
+	!(ip.is_unspecified()
+		|| ip.is_loopback()
+		|| ip.is_private()
+		|| ip.is_link_local()
+		|| ip.is_broadcast()
+		|| ip.is_documentation()
+		|| ip.is_multicast()
+		// 0.0.0.0/8 (this network)
+		|| a == 0
+		// 100.64.0.0/10 (carrier-grade NAT)
+		|| (a == 100 && (b & 0xc0) == 64)
+		// 192.0.0.0/24 (protocol assignments)
+		|| (a == 192 && b == 0 && ip.octets()[2] == 0)
+		// 198.18.0.0/15 (benchmarking)
+		|| (a == 198 && (b & 0xfe) == 18)
+		// 240.0.0.0/4 (reserved)
+		|| a >= 240)
+}
+
+fn is_public_ipv6(ip: Ipv6Addr) -> bool {
+	let first = ip.segments()[0];
+
+	!(ip.is_unspecified()
+		|| ip.is_loopback()
+		|| ip.is_multicast()
+		// fc00::/7 (unique local)
+		|| (first & 0xfe00) == 0xfc00
+		// fe80::/10 (link-local), fec0::/10 (deprecated site-local)
+		|| (first & 0xffc0) == 0xfe80
+		|| (first & 0xffc0) == 0xfec0
+		// 2001:db8::/32 (documentation)
+		|| (first == 0x2001 && ip.segments()[1] == 0x0db8))
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use anyhow::Result;
+
+	#[test]
+	fn test_is_public_ip_ok() -> Result<()> {
+		// -- Fixtures
+		let fx_cases = &[
+			("93.184.216.34", true),
+			("2606:2800:220:1::", true),
+			("127.0.0.1", false),
+			("10.1.2.3", false),
+			("172.16.0.1", false),
+			("192.168.1.1", false),
+			("169.254.169.254", false),
+			("100.64.0.1", false),
+			("0.0.0.0", false),
+			("::1", false),
+			("::", false),
+			("fd00::1", false),
+			("fe80::1", false),
+			("::ffff:127.0.0.1", false),
+			("::ffff:10.0.0.1", false),
+		];
+
+		// -- Exec & Check
+		for (ip, expected) in fx_cases {
+			assert_eq!(is_public_ip(ip.parse()?), *expected, "for {ip}");
+		}
+
+		Ok(())
+	}
+
+	#[tokio::test]
+	async fn test_check_url_err_non_public() -> Result<()> {
+		// -- Fixtures
+		let fx_urls = &[
+			"ftp://93.184.216.34/",
+			"http://localhost:8080/hook",
+			"http://10.0.0.1/hook",
+			"http://169.254.169.254/latest/meta-data",
+			"http://[::1]/hook",
+			"http://[::ffff:7f00:1]/hook",
+			"http://0/hook",
+		];
+
+		// -- Exec & Check
+		for url in fx_urls {
+			assert!(
+				matches!(check_url(url).await, Err(Error::WebhookInvalidUrl(_))),
+				"for {url}"
+			);
+		}
+		// Allowed host (see `SERVICE_WEBHOOK_ALLOWED_HOSTS`).
+		check_url("http://127.0.0.1:9/hook").await?;
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
@@ -0,0 +1,27 @@
+use serde::Serialize;
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[derive(Debug, Serialize)]
+pub enum Error {
+	// -- Parsing
+	InvalidPart(String),
+	UnsupportedPart(String),
+	InvalidValue { part: &'static str, value: String },
+	MissingFreq,
+	CountAndUntil,
+	ByDayNotSupportedForMonthly,
+}
+
+// region:    --- Error Boilerplate
+impl core::fmt::Display for Error {
+	fn fmt(
+		&self,
+		fmt: &mut core::fmt::Formatter,
+	) -> core::result::Result<(), core::fmt::Error> {
+		write!(fmt, "{self:?}")
+	}
+}
+
+impl std::error::Error for Error {}
+// endregion: --- Error Boilerplate
@@ -0,0 +1,636 @@
+//! Recurrence rules, following a subset of the iCalendar RRULE (RFC 5545).
+//!
+//! Supported rule parts:
+//!
+//! - `FREQ` - `DAILY`, `WEEKLY` or `MONTHLY` (required).
+//! - `INTERVAL` - Every n periods (default 1, at most `INTERVAL_MAX`).
+//! - `BYDAY` - Weekday set (e.g., `MO,WE,FR`). Expands the week for `WEEKLY`,
+//!   filters the days for `DAILY`. Not supported for `MONTHLY`.
+//! - `COUNT` or `UNTIL` - End of the series (mutually exclusive).
+//!
+//! Notes:
+//!
+//! - As in RFC 5545, `dtstart` always counts as the first occurrence.
+//! - Weeks start on Monday (i.e., `WKST=MO`).
+//! - `MONTHLY` occurrences on a day the month does not have are skipped.
+//! - The series end past the supported dates (i.e., the year 9999).
+//!
+
+// region:    --- Modules
+
+mod error;
+
+pub use self::error::{Error, Result};
+
+use crate::utils::time::Clock;
+use std::collections::VecDeque;
+use std::fmt::Display;
+use std::str::FromStr;
+use time::{
+	Date, Duration, Month, OffsetDateTime, PrimitiveDateTime, Time, Weekday,
+};
+
+// endregion: --- Modules
+
+/// Max number of periods scanned for occurrences
+/// (guard for rules with far away or no more matches).
+const MAX_PERIODS: u32 = 100_000;
+
+/// Max `INTERVAL` (e.g., every 1000 days, weeks, or months).
+const INTERVAL_MAX: u32 = 1_000;
+
+// region:    --- RRule Types
+
+/// String format: `FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,WE;COUNT=10`
+#[derive(Debug, Clone, PartialEq)]
+pub struct RRule {
+	pub freq: Freq,
+	pub interval: u32,
+	pub by_day: Vec<Weekday>, // Sorted from Monday, no duplicates.
+	pub end: Option<End>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Freq {
+	Daily,
+	Weekly,
+	Monthly,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum End {
+	Count(u32),
+	Until(OffsetDateTime),
+}
+
+impl FromStr for RRule {
+	type Err = Error;
+
+	fn from_str(rrule_str: &str) -> std::result::Result<Self, Self::Err> {
+		let rrule_str = rrule_str.strip_prefix("RRULE:").unwrap_or(rrule_str);
+
+		let mut freq = None;
+		let mut interval = 1;
+		let mut by_day = Vec::new();
+		let mut count = None;
+		let mut until = None;
+
+		for part in rrule_str.split(';').filter(|p| !p.is_empty()) {
+			let (name, value) = part
+				.split_once('=')
+				.ok_or_else(|| Error::InvalidPart(part.to_string()))?;
+
+			match name.to_ascii_uppercase().as_str() {
+				"FREQ" => freq = Some(parse_freq(value)?),
+				"INTERVAL" => interval = parse_interval(value)?,
+				"BYDAY" => {
+					by_day = value
+						.split(',')
+						.map(parse_weekday)
+						.collect::<Result<Vec<_>>>()?
+				}
+				"COUNT" => count = Some(parse_positive("COUNT", value)?),
+				"UNTIL" => until = Some(parse_until(value)?),
+				_ => return Err(Error::UnsupportedPart(name.to_string())),
+			}
+		}
+
+		let freq = freq.ok_or(Error::MissingFreq)?;
+		if freq == Freq::Monthly && !by_day.is_empty() {
+			return Err(Error::ByDayNotSupportedForMonthly);
+		}
+
+		let end = match (count, until) {
+			(Some(_), Some(_)) => return Err(Error::CountAndUntil),
+			(Some(count), None) => Some(End::Count(count)),
+			(None, Some(until)) => Some(End::Until(until)),
+			(None, None) => None,
+		};
+
+		by_day.sort_by_key(|d| d.number_days_from_monday());
+		by_day.dedup();
+
+		Ok(Self {
+			freq,
+			interval,
+			by_day,
+			end,
+		})
+	}
+}
+
+impl Display for RRule {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let freq = match self.freq {
+			Freq::Daily => "DAILY",
+			Freq::Weekly => "WEEKLY",
+			Freq::Monthly => "MONTHLY",
+		};
+		write!(f, "FREQ={freq}")?;
+
+		if self.interval != 1 {
+			write!(f, ";INTERVAL={}", self.interval)?;
+		}
+
+		if !self.by_day.is_empty() {
+			let days: Vec<&str> = self.by_day.iter().map(weekday_code).collect();
+			write!(f, ";BYDAY={}", days.join(","))?;
+		}
+
+		match self.end {
+			Some(End::Count(count)) => write!(f, ";COUNT={count}"),
+			Some(End::Until(until)) => write!(
+				f,
+				";UNTIL={:04}{:02}{:02}T{:02}{:02}{:02}Z",
+				until.year(),
+				until.month() as u8,
+				until.day(),
+				until.hour(),
+				until.minute(),
+				until.second()
+			),
+			None => Ok(()),
+		}
+	}
+}
+
+// endregion: --- RRule Types
+
+// region:    --- Occurrences
+
+impl RRule {
+	/// Returns the iterator of the occurrences of the series
+	/// starting at `dtstart` (included).
+	pub fn occurrences(&self, dtstart: OffsetDateTime) -> Occurrences<'_> {
+		Occurrences {
+			rrule: self,
+			dtstart,
+			period: 0,
+			pending: VecDeque::new(),
+			emitted: 0,
+		}
+	}
+
+	/// Returns the occurrence following the `dtstart` one, skipping the
+	/// occurrences already past per the `clock`, along with the rule for
+	/// the rest of the series (i.e., `COUNT` reduced by the consumed occurrences).
+	///
+	/// Returns `None` when the series is over.
+	pub fn next_occurrence(
+		&self,
+		dtstart: OffsetDateTime,
+		clock: &dyn Clock,
+	) -> Option<(OffsetDateTime, RRule)> {
+		let now = clock.now_utc();
+
+		let (idx, next) = self
+			.occurrences(dtstart)
+			.enumerate()
+			.skip(1)
+			.find(|(_, occ)| *occ > now)?;
+
+		let mut rest = self.clone();
+		if let Some(End::Count(count)) = self.end {
+			rest.end = Some(End::Count(count - idx as u32));
+		}
+
+		Some((next, rest))
+	}
+
+	/// The candidate occurrences of the nth period (not filtered by the series end).
+	///
+	/// Returns `None` when the period is past the supported dates
+	/// (i.e., the end of the series).
+	fn period_candidates(
+		&self,
+		dtstart: OffsetDateTime,
+		period: u32,
+	) -> Option<Vec<OffsetDateTime>> {
+		let step = period as i64 * self.interval as i64;
+
+		let candidates = match self.freq {
+			Freq::Daily => {
+				let day = dtstart.checked_add(Duration::days(step))?;
+				if self.by_day.is_empty() || self.by_day.contains(&day.weekday()) {
+					vec![day]
+				} else {
+					vec![]
+				}
+			}
+
+			Freq::Weekly => {
+				let week_start = dtstart
+					.checked_sub(Duration::days(
+						dtstart.weekday().number_days_from_monday() as i64,
+					))?
+					.checked_add(Duration::weeks(step))?;
+				let days = if self.by_day.is_empty() {
+					vec![dtstart.weekday()]
+				} else {
+					self.by_day.clone()
+				};
+
+				// Note: The days past the supported dates of the last week are
+				//       left out (the next week ending the series).
+				days.into_iter()
+					.filter_map(|d| {
+						week_start.checked_add(Duration::days(
+							d.number_days_from_monday() as i64,
+						))
+					})
+					.collect()
+			}
+
+			Freq::Monthly => {
+				let months = dtstart.month() as i64 - 1 + step;
+				let year = dtstart.year() as i64 + months / 12;
+				if year > Date::MAX.year() as i64 {
+					return None;
+				}
+				let month = Month::try_from((months % 12 + 1) as u8).ok();
+
+				month
+					.and_then(|month| {
+						Date::from_calendar_date(year as i32, month, dtstart.day())
+							.ok()
+					})
+					.map(|date| dtstart.replace_date(date))
+					.into_iter()
+					.collect()
+			}
+		};
+
+		Some(candidates)
+	}
+}
+
+/// Iterator of the occurrences of a `RRule` series (see `RRule::occurrences`).
+pub struct Occurrences<'a> {
+	rrule: &'a RRule,
+	dtstart: OffsetDateTime,
+	period: u32,
+	pending: VecDeque<OffsetDateTime>,
+	emitted: u32,
+}
+
+impl Iterator for Occurrences<'_> {
+	type Item = OffsetDateTime;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		loop {
+			if let Some(End::Count(count)) = self.rrule.end {
+				if self.emitted >= count {
+					return None;
+				}
+			}
+
+			// dtstart always counts as the first occurrence.
+			if self.emitted == 0 {
+				self.emitted = 1;
+				return Some(self.dtstart);
+			}
+
+			if let Some(occ) = self.pending.pop_front() {
+				if occ <= self.dtstart {
+					continue;
+				}
+				if let Some(End::Until(until)) = self.rrule.end {
+					if occ > until {
+						return None;
+					}
+				}
+
+				self.emitted += 1;
+				return Some(occ);
+			}
+
+			if self.period >= MAX_PERIODS {
+				return None;
+			}
+			let candidates =
+				self.rrule.period_candidates(self.dtstart, self.period)?;
+			self.pending.extend(candidates);
+			self.period += 1;
+		}
+	}
+}
+
+// endregion: --- Occurrences
+
+// region:    --- Parsing Helpers
+
+fn parse_freq(value: &str) -> Result<Freq> {
+	match value.to_ascii_uppercase().as_str() {
+		"DAILY" => Ok(Freq::Daily),
+		"WEEKLY" => Ok(Freq::Weekly),
+		"MONTHLY" => Ok(Freq::Monthly),
+		_ => Err(Error::InvalidValue {
+			part: "FREQ",
+			value: value.to_string(),
+		}),
+	}
+}
+
+fn parse_positive(part: &'static str, value: &str) -> Result<u32> {
+	value
+		.parse::<u32>()
+		.ok()
+		.filter(|v| *v > 0)
+		.ok_or_else(|| Error::InvalidValue {
+			part,
+			value: value.to_string(),
+		})
+}
+
+fn parse_interval(value: &str) -> Result<u32> {
+	let interval = parse_positive("INTERVAL", value)?;
+	if interval > INTERVAL_MAX {
+		return Err(Error::InvalidValue {
+			part: "INTERVAL",
+			value: value.to_string(),
+		});
+	}
+
+	Ok(interval)
+}
+
+fn parse_weekday(value: &str) -> Result<Weekday> {
+	match value.to_ascii_uppercase().as_str() {
+		"MO" => Ok(Weekday::Monday),
+		"TU" => Ok(Weekday::Tuesday),
+		"WE" => Ok(Weekday::Wednesday),
+		"TH" => Ok(Weekday::Thursday),
+		"FR" => Ok(Weekday::Friday),
+		"SA" => Ok(Weekday::Saturday),
+		"SU" => Ok(Weekday::Sunday),
+		_ => Err(Error::InvalidValue {
+			part: "BYDAY",
+			value: value.to_string(),
+		}),
+	}
+}
+
+fn weekday_code(weekday: &Weekday) -> &'static str {
+	match weekday {
+		Weekday::Monday => "MO",
+		Weekday::Tuesday => "TU",
+		Weekday::Wednesday => "WE",
+		Weekday::Thursday => "TH",
+		Weekday::Friday => "FR",
+		Weekday::Saturday => "SA",
+		Weekday::Sunday => "SU",
+	}
+}
+
+/// Parse `YYYYMMDD` (end of that day, UTC) or `YYYYMMDDTHHMMSSZ`.
+fn parse_until(value: &str) -> Result<OffsetDateTime> {
+	let err = || Error::InvalidValue {
+		part: "UNTIL",
+		value: value.to_string(),
+	};
+	let num = |range: std::ops::Range<usize>| -> Result<u32> {
+		value
+			.get(range)
+			.filter(|s| s.bytes().all(|b| b.is_ascii_digit()))
+			.and_then(|s| s.parse().ok())
+			.ok_or_else(err)
+	};
+
+	let month = Month::try_from(num(4..6)? as u8).map_err(|_| err())?;
+	let date = Date::from_calendar_date(num(0..4)? as i32, month, num(6..8)? as u8)
+		.map_err(|_| err())?;
+
+	let time = match value.len() {
+		8 => Time::from_hms(23, 59, 59),
+		16 if &value[8..9] == "T" && &value[15..16] == "Z" => {
+			Time::from_hms(num(9..11)? as u8, num(11..13)? as u8, num(13..15)? as u8)
+		}
+		_ => return Err(err()),
+	}
+	.map_err(|_| err())?;
+
+	Ok(PrimitiveDateTime::new(date, time).assume_utc())
+}
+
+// endregion: --- Parsing Helpers
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::utils::time::{format_time, parse_utc, FixedClock};
+	use anyhow::Result;
+
+	fn fmt_all(occs: impl Iterator<Item = OffsetDateTime>) -> Vec<String> {
+		occs.map(format_time).collect()
+	}
+
+	#[test]
+	fn test_rrule_from_str_and_display_ok() -> Result<()> {
+		// -- Fixtures
+		let fx_rrule_str =
+			"RRULE:freq=weekly;BYDAY=FR,MO,MO;INTERVAL=2;UNTIL=20240131";
+
+		// -- Exec
+		let rrule: RRule = fx_rrule_str.parse()?;
+
+		// -- Check
+		assert_eq!(rrule.freq, Freq::Weekly);
+		assert_eq!(rrule.interval, 2);
+		assert_eq!(rrule.by_day, vec![Weekday::Monday, Weekday::Friday]);
+		assert_eq!(
+			rrule.to_string(),
+			"FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,FR;UNTIL=20240131T235959Z"
+		);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_rrule_from_str_err() -> Result<()> {
+		// -- Fixtures
+		let fx_cases = &[
+			"INTERVAL=2",
+			"FREQ=YEARLY",
+			"FREQ=DAILY;INTERVAL=0",
+			"FREQ=DAILY;INTERVAL=3000000",
+			"FREQ=DAILY;COUNT=2;UNTIL=20240101",
+			"FREQ=MONTHLY;BYDAY=MO",
+			"FREQ=DAILY;BYMONTH=1",
+			"FREQ=DAILY;UNTIL=2024-01-01",
+		];
+
+		// -- Exec & Check
+		for fx_case in fx_cases {
+			let res = fx_case.parse::<RRule>();
+			assert!(res.is_err(), "Should have failed for '{fx_case}'");
+		}
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_occurrences_daily_interval_count() -> Result<()> {
+		// -- Fixtures
+		let rrule: RRule = "FREQ=DAILY;INTERVAL=3;COUNT=3".parse()?;
+		let fx_dtstart = parse_utc("2024-01-30T09:00:00Z")?;
+
+		// -- Exec
+		let occs = fmt_all(rrule.occurrences(fx_dtstart));
+
+		// -- Check
+		assert_eq!(
+			occs,
+			&[
+				"2024-01-30T09:00:00Z",
+				"2024-02-02T09:00:00Z",
+				"2024-02-05T09:00:00Z"
+			]
+		);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_occurrences_weekly_by_day_until() -> Result<()> {
+		// -- Fixtures
+		// 2024-01-03 is a Wednesday.
+		let rrule: RRule =
+			"FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,WE,FR;UNTIL=20240122".parse()?;
+		let fx_dtstart = parse_utc("2024-01-03T09:00:00Z")?;
+
+		// -- Exec
+		let occs = fmt_all(rrule.occurrences(fx_dtstart));
+
+		// -- Check
+		assert_eq!(
+			occs,
+			&[
+				"2024-01-03T09:00:00Z",
+				"2024-01-05T09:00:00Z",
+				"2024-01-15T09:00:00Z",
+				"2024-01-17T09:00:00Z",
+				"2024-01-19T09:00:00Z",
+			]
+		);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_occurrences_monthly_skip_missing_days() -> Result<()> {
+		// -- Fixtures
+		let rrule: RRule = "FREQ=MONTHLY;COUNT=4".parse()?;
+		let fx_dtstart = parse_utc("2024-01-31T09:00:00Z")?;
+
+		// -- Exec
+		let occs = fmt_all(rrule.occurrences(fx_dtstart));
+
+		// -- Check
+		assert_eq!(
+			occs,
+			&[
+				"2024-01-31T09:00:00Z",
+				"2024-03-31T09:00:00Z",
+				"2024-05-31T09:00:00Z",
+				"2024-07-31T09:00:00Z",
+			]
+		);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_next_occurrence_ok_skip_past() -> Result<()> {
+		// -- Fixtures
+		let rrule: RRule = "FREQ=DAILY;BYDAY=MO,TU,WE,TH,FR;COUNT=10".parse()?;
+		// Fri 2024-01-05, completed on Tue 2024-01-09 at noon.
+		let fx_dtstart = parse_utc("2024-01-05T09:00:00Z")?;
+		let fx_clock = FixedClock(parse_utc("2024-01-09T12:00:00Z")?);
+
+		// -- Exec
+		let (next, rest) = rrule
+			.next_occurrence(fx_dtstart, &fx_clock)
+			.expect("Should have a next occurrence");
+
+		// -- Check
+		// Mon 8th and Tue 9th are past, so consumed.
+		assert_eq!(format_time(next), "2024-01-10T09:00:00Z");
+		assert_eq!(rest.to_string(), "FREQ=DAILY;BYDAY=MO,TU,WE,TH,FR;COUNT=7");
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_rrule_from_str_ok_interval_max() -> Result<()> {
+		// -- Fixtures
+		let fx_rrule_str = format!("FREQ=DAILY;INTERVAL={INTERVAL_MAX}");
+		let fx_over_str = format!("FREQ=DAILY;INTERVAL={}", INTERVAL_MAX + 1);
+
+		// -- Exec
+		let rrule: RRule = fx_rrule_str.parse()?;
+		let over_res = fx_over_str.parse::<RRule>();
+
+		// -- Check
+		assert_eq!(rrule.interval, INTERVAL_MAX);
+		assert!(
+			matches!(
+				over_res,
+				Err(Error::InvalidValue {
+					part: "INTERVAL",
+					..
+				})
+			),
+			"Should have been InvalidValue, was {over_res:?}"
+		);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_occurrences_end_past_max_date() -> Result<()> {
+		// -- Fixtures
+		let fx_dtstart = parse_utc("9999-12-30T09:00:00Z")?;
+		let fx_clock = FixedClock(parse_utc("9999-12-31T12:00:00Z")?);
+		let fx_cases = &[
+			(
+				"FREQ=DAILY",
+				&["9999-12-30T09:00:00Z", "9999-12-31T09:00:00Z"][..],
+			),
+			("FREQ=DAILY;INTERVAL=1000", &["9999-12-30T09:00:00Z"][..]),
+			// 9999-12-30 is a Thursday.
+			(
+				"FREQ=WEEKLY;BYDAY=TH,FR,SA",
+				&["9999-12-30T09:00:00Z", "9999-12-31T09:00:00Z"][..],
+			),
+			("FREQ=MONTHLY", &["9999-12-30T09:00:00Z"][..]),
+		];
+
+		// -- Exec & Check
+		for (fx_rrule_str, fx_occs) in fx_cases {
+			let rrule: RRule = fx_rrule_str.parse()?;
+			let occs = fmt_all(rrule.occurrences(fx_dtstart));
+			assert_eq!(&occs, fx_occs, "for '{fx_rrule_str}'");
+			assert!(rrule.next_occurrence(fx_dtstart, &fx_clock).is_none());
+		}
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_next_occurrence_none_series_over() -> Result<()> {
+		// -- Fixtures
+		let rrule: RRule = "FREQ=WEEKLY;COUNT=2".parse()?;
+		let fx_dtstart = parse_utc("2024-01-01T09:00:00Z")?;
+		let fx_clock = FixedClock(parse_utc("2024-03-01T00:00:00Z")?);
+
+		// -- Exec
+		let res = rrule.next_occurrence(fx_dtstart, &fx_clock);
+
+		// -- Check
+		assert!(res.is_none(), "Should be None but was {res:?}");
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
@@ -5,4 +5,5 @@
 //!
 
 pub mod b64;
+pub mod rank;
 pub mod time;
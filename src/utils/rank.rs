@@ -0,0 +1,258 @@
+//! Rank keys for manual ordering (a.k.a., fractional indexing).
+//!
+//! A rank key is a base-62 fraction (`0.<key>`) written with the ASCII ordered
+//! digits `0-9A-Za-z`, so that keys sort lexicographically (with a "C" collation).
+//! There is always a key between two keys, so moving an item only updates that item.
+//!
+//! Keys never end with `0` (i.e., the smallest digit), so there is always room before a key.
+//!
+
+use serde::Serialize;
+
+const DIGITS: &[u8] =
+	b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+const BASE: usize = DIGITS.len();
+
+/// Returns a key sorting strictly between `before` and `after`.
+/// `None` means the start (for `before`) or the end (for `after`) of the list.
+pub fn rank_between(before: Option<&str>, after: Option<&str>) -> Result<String> {
+	let before = before.map(validate).transpose()?.unwrap_or(&[]);
+	let after = after.map(validate).transpose()?;
+
+	if let Some(after) = after {
+		if before >= after {
+			return Err(Error::RanksNotOrdered);
+		}
+	}
+
+	let key = midpoint(before, after);
+
+	// Note: Only made of DIGITS, so always valid utf8.
+	Ok(String::from_utf8(key).unwrap_or_default())
+}
+
+/// Returns `count` ordered keys between `before` and `after` (see `rank_between`),
+/// spread by bisection so that the keys stay short (e.g., for bulk inserts).
+pub fn ranks_between(
+	before: Option<&str>,
+	after: Option<&str>,
+	count: usize,
+) -> Result<Vec<String>> {
+	if count == 0 {
+		return Ok(Vec::new());
+	}
+
+	let mid = rank_between(before, after)?;
+	let before_count = count / 2;
+
+	let mut keys = ranks_between(before, Some(&mid), before_count)?;
+	let after_keys = ranks_between(Some(&mid), after, count - before_count - 1)?;
+	keys.push(mid);
+	keys.extend(after_keys);
+
+	Ok(keys)
+}
+
+/// Returns `count` ordered keys, evenly spread and as short as possible
+/// (to rebalance a list when keys grow too long).
+pub fn rank_evenly(count: usize) -> Vec<String> {
+	// -- Smallest width with enough room for count + 1 gaps.
+	let mut width = 1;
+	let mut space = BASE as u128;
+	while space <= count as u128 + 1 {
+		width += 1;
+		space *= BASE as u128;
+	}
+
+	(1..=count as u128)
+		.map(|i| {
+			let mut val = i * space / (count as u128 + 1);
+			let mut key = vec![b'0'; width];
+			for idx in (0..width).rev() {
+				key[idx] = DIGITS[(val % BASE as u128) as usize];
+				val /= BASE as u128;
+			}
+
+			// Trailing zeros do not change the fraction value (nor the order).
+			let key = String::from_utf8(key).unwrap_or_default();
+			key.trim_end_matches('0').to_string()
+		})
+		.collect()
+}
+
+/// Midpoint of the `0.a` and `0.b` fractions (`b` None being `1`).
+/// (Precondition: `a < b` and no trailing zero)
+fn midpoint(a: &[u8], b: Option<&[u8]>) -> Vec<u8> {
+	// -- Common prefix (`a` padded with zeros) is kept as is.
+	if let Some(b) = b {
+		let mut n = 0;
+		while n < b.len() && a.get(n).copied().unwrap_or(DIGITS[0]) == b[n] {
+			n += 1;
+		}
+		if n > 0 {
+			let mut key = b[..n].to_vec();
+			key.extend(midpoint(a.get(n..).unwrap_or(&[]), Some(&b[n..])));
+			return key;
+		}
+	}
+
+	// -- First digits differ.
+	let digit_a = a.first().map(|c| digit_value(*c)).unwrap_or(0);
+	let digit_b = b.map(|b| digit_value(b[0])).unwrap_or(BASE);
+
+	if digit_b - digit_a > 1 {
+		// Room for a digit in between.
+		vec![DIGITS[(digit_a + digit_b) / 2]]
+	} else if let Some(b) = b.filter(|b| b.len() > 1) {
+		// Consecutive digits, `b` first digit alone is in between.
+		vec![b[0]]
+	} else {
+		// Consecutive digits, go after the rest of `a`.
+		let mut key = vec![DIGITS[digit_a]];
+		key.extend(midpoint(a.get(1..).unwrap_or(&[]), None));
+		key
+	}
+}
+
+fn digit_value(c: u8) -> usize {
+	DIGITS.iter().position(|d| *d == c).unwrap_or(0)
+}
+
+fn validate(key: &str) -> Result<&[u8]> {
+	let bytes = key.as_bytes();
+	let valid = !bytes.is_empty()
+		&& bytes.iter().all(|c| DIGITS.contains(c))
+		&& bytes.last() != Some(&DIGITS[0]);
+
+	if valid {
+		Ok(bytes)
+	} else {
+		Err(Error::InvalidRank(key.to_string()))
+	}
+}
+
+// region:    --- Error
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[derive(Debug, Serialize)]
+pub enum Error {
+	InvalidRank(String),
+	RanksNotOrdered,
+}
+
+// region:    --- Error Boilerplate
+impl core::fmt::Display for Error {
+	fn fmt(
+		&self,
+		fmt: &mut core::fmt::Formatter,
+	) -> core::result::Result<(), core::fmt::Error> {
+		write!(fmt, "{self:?}")
+	}
+}
+
+impl std::error::Error for Error {}
+// endregion: --- Error Boilerplate
+
+// endregion: --- Error
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use anyhow::Result;
+
+	#[test]
+	fn test_rank_between_ok() -> Result<()> {
+		// -- Fixtures
+		let fx_cases: &[(Option<&str>, Option<&str>, &str)] = &[
+			(None, None, "V"),
+			(Some("V"), None, "k"),
+			(None, Some("V"), "F"),
+			(Some("a"), Some("b"), "aV"),
+			(Some("a1"), Some("a2"), "a1V"),
+			(Some("az"), Some("b"), "azV"),
+			(Some("a"), Some("a01"), "a00V"),
+			(Some("y"), Some("z2"), "z"),
+		];
+
+		// -- Exec & Check
+		for (before, after, expected) in fx_cases {
+			let key = rank_between(*before, *after)?;
+			assert_eq!(&key, expected, "for {before:?} - {after:?}");
+			if let Some(before) = before {
+				assert!(key.as_str() > *before);
+			}
+			if let Some(after) = after {
+				assert!(key.as_str() < *after);
+			}
+		}
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_rank_between_repeated_stays_ordered() -> Result<()> {
+		// -- Setup & Fixtures
+		let mut low = rank_between(None, None)?;
+		let high = rank_between(Some(&low), None)?;
+
+		// -- Exec & Check
+		// Always inserting right after the same key (worst case for growth).
+		for _ in 0..100 {
+			let key = rank_between(Some(&low), Some(&high))?;
+			assert!(key > low && key < high);
+			low = key;
+		}
+		assert!(low.len() < 30, "key too long: {}", low.len());
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_rank_between_err() -> Result<()> {
+		// -- Exec & Check
+		assert!(matches!(
+			rank_between(Some("b"), Some("a")),
+			Err(Error::RanksNotOrdered)
+		));
+		assert!(matches!(
+			rank_between(Some("a0"), None),
+			Err(Error::InvalidRank(_))
+		));
+		assert!(matches!(
+			rank_between(Some("a-b"), None),
+			Err(Error::InvalidRank(_))
+		));
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_ranks_between_ok() -> Result<()> {
+		// -- Exec
+		let keys = ranks_between(Some("a"), None, 500)?;
+
+		// -- Check
+		assert_eq!(keys.len(), 500);
+		assert!(keys[0].as_str() > "a");
+		assert!(keys.windows(2).all(|w| w[0] < w[1]), "keys not ordered");
+		assert!(keys.iter().all(|k| k.len() <= 4), "keys too long");
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_rank_evenly_ok() -> Result<()> {
+		// -- Exec
+		let keys = rank_evenly(100);
+
+		// -- Check
+		assert_eq!(keys.len(), 100);
+		assert!(keys.iter().all(|k| k.len() <= 2 && !k.ends_with('0')));
+		assert!(keys.windows(2).all(|w| w[0] < w[1]), "keys not ordered");
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
@@ -1,11 +1,87 @@
+use schemars::{json_schema, JsonSchema, Schema, SchemaGenerator};
+use std::borrow::Cow;
 use time::{Duration, OffsetDateTime};
 
 pub use time::format_description::well_known::Rfc3339;
 
+/// JSON Schema of the `Rfc3339` (de)serialized times
+/// (e.g., `#[schemars(with = "Option<Rfc3339Schema>")]`).
+pub struct Rfc3339Schema;
+
+impl JsonSchema for Rfc3339Schema {
+	fn inline_schema() -> bool {
+		true
+	}
+
+	fn schema_name() -> Cow<'static, str> {
+		"Rfc3339".into()
+	}
+
+	fn json_schema(_gen: &mut SchemaGenerator) -> Schema {
+		json_schema!({
+			"type": "string",
+			"format": "date-time"
+		})
+	}
+}
+
 pub fn now_utc() -> OffsetDateTime {
 	OffsetDateTime::now_utc()
 }
 
+// region:    --- Clock
+
+/// Source of the current time, so that time dependent logic
+/// (e.g., recurrence) can be tested against a fixed clock.
+pub trait Clock: Send + Sync {
+	fn now_utc(&self) -> OffsetDateTime;
+}
+
+/// The real clock (i.e., `now_utc()`).
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+	fn now_utc(&self) -> OffsetDateTime {
+		now_utc()
+	}
+}
+
+/// A clock always returning the same time (for unit tests).
+#[cfg(test)]
+pub struct FixedClock(pub OffsetDateTime);
+
+#[cfg(test)]
+impl Clock for FixedClock {
+	fn now_utc(&self) -> OffsetDateTime {
+		self.0
+	}
+}
+
+/// A clock only moving when advanced (for the tests of the time driven
+/// background tasks, e.g., the crons).
+#[cfg(test)]
+pub struct FakeClock(std::sync::Mutex<OffsetDateTime>);
+
+#[cfg(test)]
+impl FakeClock {
+	pub fn new(now: OffsetDateTime) -> Self {
+		Self(std::sync::Mutex::new(now))
+	}
+
+	pub fn advance(&self, duration: Duration) {
+		*self.0.lock().unwrap() += duration;
+	}
+}
+
+#[cfg(test)]
+impl Clock for FakeClock {
+	fn now_utc(&self) -> OffsetDateTime {
+		*self.0.lock().unwrap()
+	}
+}
+
+// endregion: --- Clock
+
 pub fn format_time(time: OffsetDateTime) -> String {
 	time.format(&Rfc3339).unwrap() // TODO: need to check if safe.
 }
@@ -25,6 +101,7 @@ pub fn parse_utc(moment: &str) -> Result<OffsetDateTime> {
 pub type Result<T> = core::result::Result<T, Error>;
 
 #[derive(Debug)]
+#[allow(dead_code)] // Variant data only read through Debug.
 pub enum Error {
 	FailToDateParse(String),
 }
@@ -0,0 +1,466 @@
+//! Integration tests of the `lib-rpc-client` crate, against the app served
+//! in-process (all the routes and middlewares, on a free local port).
+
+use crate::_dev_utils;
+use crate::model::ModelManager;
+use crate::web::routes_all;
+use anyhow::Result;
+use lib_rpc_client::{
+	BulkItemResult, ClientError, CommentForCreate, Error, ListOptions,
+	NotificationKind, NotificationPref, RpcClient, SavedViewForCreate,
+	SavedViewForUpdate, SavedViewQuery, TaskFilter, TaskForCreate, TaskForUpdate,
+	WebhookForCreate, WebhookForUpdate,
+};
+use serial_test::serial;
+use std::net::TcpListener;
+use std::sync::OnceLock;
+use std::thread;
+
+const DEMO_USERNAME: &str = "demo1";
+const DEMO_PWD: &str = "welcome";
+
+/// Serve the app once for all the tests, on a free local port, returning its
+/// base url. (the db must be initialized, e.g., by `_dev_utils::init_test`)
+///
+/// Note: The server runs on its own runtime (thread) with its own `ModelManager`,
+///       as the test ones end with each test, possibly with a db connection
+///       of the (single connection) test pool still in flight.
+pub(in crate::web) fn fx_serve_app() -> Result<String> {
+	static BASE_URL: OnceLock<String> = OnceLock::new();
+
+	if let Some(base_url) = BASE_URL.get() {
+		return Ok(base_url.clone());
+	}
+
+	let listener = TcpListener::bind("127.0.0.1:0")?;
+	listener.set_nonblocking(true)?;
+	let addr = listener.local_addr()?;
+
+	thread::spawn(move || {
+		let rt = tokio::runtime::Builder::new_current_thread()
+			.enable_all()
+			.build()
+			.expect("server runtime");
+		rt.block_on(async move {
+			let mm = ModelManager::new().await.expect("server model manager");
+			axum::Server::from_tcp(listener)
+				.expect("server listener")
+				.serve(routes_all(mm).into_make_service())
+				.await
+				.expect("server run");
+		});
+	});
+
+	Ok(BASE_URL.get_or_init(|| format!("http://{addr}")).clone())
+}
+
+/// A client logged in as the demo user.
+pub(in crate::web) async fn fx_logged_client(base_url: &str) -> Result<RpcClient> {
+	let client = RpcClient::new(base_url)?;
+	client.login(DEMO_USERNAME, DEMO_PWD).await?;
+
+	Ok(client)
+}
+
+#[serial]
+#[tokio::test]
+async fn test_client_auth_err() -> Result<()> {
+	// -- Setup & Fixtures
+	_dev_utils::init_test().await;
+	let base_url = fx_serve_app()?;
+	let client = RpcClient::new(&base_url)?;
+
+	// -- Exec
+	let login_res = client.login(DEMO_USERNAME, "wrong_pwd").await;
+	let list_res = client.list_tasks().await;
+
+	// -- Check
+	assert_eq!(
+		login_res.unwrap_err().client_error(),
+		Some(&ClientError::LOGIN_FAIL)
+	);
+	assert_eq!(
+		list_res.unwrap_err().client_error(),
+		Some(&ClientError::NO_AUTH)
+	);
+
+	Ok(())
+}
+
+#[serial]
+#[tokio::test]
+async fn test_client_task_ok() -> Result<()> {
+	// -- Setup & Fixtures
+	_dev_utils::init_test().await;
+	let base_url = fx_serve_app()?;
+	let client = fx_logged_client(&base_url).await?;
+	let fx_title = "test_client_task_ok";
+
+	// -- Exec
+	let task = client.create_task(TaskForCreate::new(fx_title)).await?;
+	let tasks = client.list_tasks().await?;
+	let filter = TaskFilter {
+		title_contains: Some(fx_title.to_string()),
+		..Default::default()
+	};
+	let filtered_tasks = client.list_tasks_by_filter(filter.clone(), None).await?;
+	let page_options = |cursor| ListOptions {
+		limit: Some(1),
+		cursor,
+		..Default::default()
+	};
+	let page = client
+		.list_tasks_page(TaskFilter::default(), Some(page_options(None)))
+		.await?;
+	let next_page = client
+		.list_tasks_page(TaskFilter::default(), Some(page_options(page.next_cursor)))
+		.await?;
+	let last_page = client.list_tasks_page(filter, None).await?;
+	let task_u = TaskForUpdate {
+		done: Some(true),
+		..Default::default()
+	};
+	let updated_task = client
+		.update_task(task.id, task_u.clone(), Some(task.version))
+		.await?;
+	// Same expected version, now stale.
+	let conflict_res = client
+		.update_task(task.id, task_u, Some(task.version))
+		.await;
+
+	// -- Check
+	assert!(tasks.iter().any(|item| item.task.id == task.id));
+	assert_eq!(filtered_tasks.len(), 1);
+	assert_eq!(filtered_tasks[0].task.id, task.id);
+	assert!(page.has_more);
+	assert_eq!(next_page.items.len(), 1);
+	assert_ne!(next_page.items[0].task.id, page.items[0].task.id);
+	assert!(!last_page.has_more && last_page.next_cursor.is_none());
+	assert!(updated_task.done);
+	match conflict_res {
+		Err(Error::Rpc {
+			code,
+			client_error:
+				ClientError::VERSION_CONFLICT {
+					current_version, ..
+				},
+			req_uuid,
+		}) => {
+			assert_eq!(code, -32000);
+			assert_eq!(current_version, updated_task.version);
+			assert!(req_uuid.is_some());
+		}
+		other => panic!("Expected VERSION_CONFLICT, got: {other:?}"),
+	}
+
+	// -- Clean
+	client.delete_task(task.id).await?;
+	client.purge_task(task.id).await?;
+	let res = client.delete_task(task.id).await;
+	assert!(matches!(
+		res.unwrap_err().client_error(),
+		Some(ClientError::ENTITY_NOT_FOUND { .. })
+	));
+
+	Ok(())
+}
+
+#[serial]
+#[tokio::test]
+async fn test_client_bulk_and_comments_ok() -> Result<()> {
+	// -- Setup & Fixtures
+	_dev_utils::init_test().await;
+	let base_url = fx_serve_app()?;
+	let client = fx_logged_client(&base_url).await?;
+	let fx_titles = ["test_client_bulk_01", "test_client_bulk_02"];
+
+	// -- Exec
+	let results = client
+		.create_tasks(fx_titles.iter().map(|t| TaskForCreate::new(*t)).collect())
+		.await?;
+	let ids: Vec<i64> = results
+		.iter()
+		.filter_map(|res| match res {
+			BulkItemResult::Ok(id) => Some(*id),
+			BulkItemResult::Error(_) => None,
+		})
+		.collect();
+	let comment = client
+		.create_comment(CommentForCreate {
+			task_id: ids[0],
+			body: "test_client_comment".to_string(),
+		})
+		.await?;
+	let comment_02 = client
+		.create_comment(CommentForCreate {
+			task_id: ids[0],
+			body: "test_client_comment_02".to_string(),
+		})
+		.await?;
+	let comments = client.list_comments(ids[0], None).await?;
+	let page_options = |cursor| ListOptions {
+		limit: Some(1),
+		cursor,
+		..Default::default()
+	};
+	let page = client
+		.list_comments_page(ids[0], Some(page_options(None)))
+		.await?;
+	let last_page = client
+		.list_comments_page(ids[0], Some(page_options(page.next_cursor.clone())))
+		.await?;
+	// Not paginated by keyset (see `list_comments_page`).
+	let cursor_options = ListOptions {
+		cursor: Some("some-cursor".to_string()),
+		..Default::default()
+	};
+	let cursor_res = client.list_comments(ids[0], Some(cursor_options)).await;
+
+	// Above the bulk max items (1000).
+	let too_many_res = client.delete_tasks(&vec![ids[0]; 1_001]).await;
+
+	// -- Check
+	assert_eq!(ids.len(), fx_titles.len());
+	assert!(matches!(
+		too_many_res.unwrap_err().client_error(),
+		Some(ClientError::INVALID_INPUT(_))
+	));
+	assert!(matches!(
+		cursor_res.unwrap_err().client_error(),
+		Some(ClientError::INVALID_INPUT(_))
+	));
+	assert_eq!(comments.len(), 2);
+	assert_eq!(comments[0].id, comment.id);
+	assert_eq!(comments[0].body, "test_client_comment");
+	assert!(page.has_more);
+	assert_eq!(page.items[0].id, comment.id);
+	assert!(!last_page.has_more);
+	assert!(last_page.next_cursor.is_none());
+	assert_eq!(last_page.items[0].id, comment_02.id);
+
+	// -- Clean
+	let results = client.delete_tasks(&ids).await?;
+	assert!(results
+		.iter()
+		.all(|res| matches!(res, BulkItemResult::Ok(_))));
+	for id in ids {
+		client.purge_task(id).await?;
+	}
+
+	Ok(())
+}
+
+#[serial]
+#[tokio::test]
+async fn test_client_bearer_token_ok() -> Result<()> {
+	// -- Setup & Fixtures
+	_dev_utils::init_test().await;
+	let base_url = fx_serve_app()?;
+	let cookie_client = fx_logged_client(&base_url).await?;
+
+	// -- Exec
+	let token = cookie_client.auth_token()?;
+	let bearer_client = RpcClient::with_bearer_token(&base_url, &token)?;
+	let bearer_res = bearer_client.list_tasks().await;
+
+	cookie_client.logoff().await?;
+	let logged_off_res = cookie_client.list_tasks().await;
+
+	// -- Check
+	assert!(bearer_res.is_ok(), "bearer call failed: {bearer_res:?}");
+	assert!(matches!(
+		cookie_client.auth_token(),
+		Err(Error::AuthTokenNotFound)
+	));
+	assert_eq!(
+		logged_off_res.unwrap_err().client_error(),
+		Some(&ClientError::NO_AUTH)
+	);
+
+	Ok(())
+}
+
+#[serial]
+#[tokio::test]
+async fn test_client_list_cron_runs_ok_admin() -> Result<()> {
+	// -- Setup & Fixtures
+	_dev_utils::init_test().await;
+	let base_url = fx_serve_app()?;
+	// The demo user is an admin (see the dev seed).
+	let client = fx_logged_client(&base_url).await?;
+
+	// -- Exec
+	let res = client.list_cron_runs().await;
+
+	// -- Check
+	assert!(res.is_ok(), "list_cron_runs failed: {res:?}");
+
+	Ok(())
+}
+
+#[serial]
+#[tokio::test]
+async fn test_client_webhook_ok() -> Result<()> {
+	// -- Setup & Fixtures
+	_dev_utils::init_test().await;
+	let base_url = fx_serve_app()?;
+	let client = fx_logged_client(&base_url).await?;
+	let fx_url = "http://127.0.0.1:9/test_client_webhook_ok";
+
+	// -- Exec
+	let created = client
+		.create_webhook(WebhookForCreate {
+			url: fx_url.to_string(),
+			events: vec!["task.*".to_string()],
+		})
+		.await?;
+	let invalid_res = client
+		.create_webhook(WebhookForCreate {
+			url: "ftp://example.com".to_string(),
+			events: vec!["task.*".to_string()],
+		})
+		.await;
+	let updated = client
+		.update_webhook(
+			created.webhook.id,
+			WebhookForUpdate {
+				active: Some(false),
+				..Default::default()
+			},
+		)
+		.await?;
+	let webhooks = client.list_webhooks().await?;
+
+	// -- Check
+	assert!(created.secret.starts_with("whsec_"));
+	assert!(matches!(
+		invalid_res.unwrap_err().client_error(),
+		Some(ClientError::INVALID_INPUT(_))
+	));
+	assert!(!updated.active);
+	assert_eq!(updated.url, fx_url);
+	assert!(webhooks.iter().any(|w| w.id == created.webhook.id));
+
+	// -- Clean
+	client.delete_webhook(created.webhook.id).await?;
+	let webhooks = client.list_webhooks().await?;
+	assert!(!webhooks.iter().any(|w| w.id == created.webhook.id));
+
+	Ok(())
+}
+
+#[serial]
+#[tokio::test]
+async fn test_client_notifications_ok() -> Result<()> {
+	// -- Setup & Fixtures
+	_dev_utils::init_test().await;
+	let base_url = fx_serve_app()?;
+	let client = fx_logged_client(&base_url).await?;
+	let fx_pref = |enabled| NotificationPref {
+		kind: NotificationKind::TaskDueSoon,
+		enabled,
+	};
+
+	// -- Exec
+	let prefs = client.set_notification_pref(fx_pref(false)).await?;
+	client.mark_all_notifications_read().await?;
+	let unread_count = client.count_unread_notifications().await?;
+	let unread = client.list_notifications(true, None).await?;
+
+	// -- Check
+	let due_soon = prefs
+		.iter()
+		.find(|pref| pref.kind == NotificationKind::TaskDueSoon);
+	assert!(matches!(due_soon, Some(pref) if !pref.enabled));
+	assert_eq!(unread_count, 0);
+	assert!(unread.is_empty());
+
+	// -- Clean
+	let prefs = client.set_notification_pref(fx_pref(true)).await?;
+	assert!(prefs.iter().all(|pref| pref.enabled));
+
+	Ok(())
+}
+
+#[serial]
+#[tokio::test]
+async fn test_client_saved_view_ok() -> Result<()> {
+	// -- Setup & Fixtures
+	_dev_utils::init_test().await;
+	let base_url = fx_serve_app()?;
+	let client = fx_logged_client(&base_url).await?;
+	let fx_prefix = "test_client_saved_view_ok";
+	let mut task_ids = Vec::new();
+	for suffix in ["A", "B", "C"] {
+		let task_c = TaskForCreate::new(format!("{fx_prefix} {suffix}"));
+		task_ids.push(client.create_task(task_c).await?.id);
+	}
+	let fx_view_c = SavedViewForCreate {
+		name: fx_prefix.to_string(),
+		query: SavedViewQuery {
+			filter: Some(TaskFilter {
+				title_contains: Some(fx_prefix.to_string()),
+				..Default::default()
+			}),
+			list_options: Some(ListOptions {
+				order_bys: Some(vec!["!title".to_string()]),
+				..Default::default()
+			}),
+		},
+		shared: false,
+	};
+
+	// -- Exec
+	let view = client.create_saved_view(fx_view_c.clone()).await?;
+	let items = client.list_tasks_by_view(view.id, None).await?;
+	let page_options = ListOptions {
+		limit: Some(1),
+		offset: Some(1),
+		..Default::default()
+	};
+	let page = client
+		.list_tasks_by_view(view.id, Some(page_options))
+		.await?;
+	let cursor_options = ListOptions {
+		cursor: Some("some-cursor".to_string()),
+		..Default::default()
+	};
+	let cursor_res = client
+		.list_tasks_by_view(view.id, Some(cursor_options))
+		.await;
+	let view_u = SavedViewForUpdate {
+		shared: Some(true),
+		..Default::default()
+	};
+	let updated_view = client.update_saved_view(view.id, view_u).await?;
+	let taken_res = client.create_saved_view(fx_view_c).await;
+
+	// -- Check
+	let ids: Vec<i64> = items.iter().map(|item| item.task.id).collect();
+	assert_eq!(ids, &[task_ids[2], task_ids[1], task_ids[0]]);
+	let page_ids: Vec<i64> = page.iter().map(|item| item.task.id).collect();
+	assert_eq!(page_ids, &[task_ids[1]]);
+	assert!(matches!(
+		cursor_res.unwrap_err().client_error(),
+		Some(ClientError::INVALID_INPUT(_))
+	));
+	assert!(updated_view.shared);
+	assert!(client
+		.list_saved_views()
+		.await?
+		.iter()
+		.any(|v| v.id == view.id));
+	assert!(matches!(
+		taken_res.unwrap_err().client_error(),
+		Some(ClientError::NAME_TAKEN { name }) if name == fx_prefix
+	));
+
+	// -- Clean
+	client.delete_saved_view(view.id).await?;
+	for id in task_ids {
+		client.delete_task(id).await?;
+		client.purge_task(id).await?;
+	}
+
+	Ok(())
+}
@@ -2,6 +2,7 @@ use crate::{model, pwd, token, web};
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
 use derive_more::From;
+use schemars::JsonSchema;
 use serde::Serialize;
 use serde_with::{serde_as, DisplayFromStr};
 use tracing::debug;
@@ -13,6 +14,8 @@ pub type Result<T> = core::result::Result<T, Error>;
 #[serde(tag = "type", content = "data")]
 pub enum Error {
 	// -- RPC
+	RpcParseFail,
+	RpcInvalidRequest,
 	RpcMethodUnknown(String),
 	RpcMissingParams {
 		rpc_method: String,
@@ -20,6 +23,10 @@ pub enum Error {
 	RpcFailJsonParams {
 		rpc_method: String,
 	},
+	RpcResourceNotFound(&'static str),
+
+	// -- REST
+	RestInvalidInput(String),
 
 	// -- Login
 	LoginFailUsernameNotFound,
@@ -95,11 +102,107 @@ impl Error {
 			// -- Auth
 			CtxExt(_) => (StatusCode::FORBIDDEN, ClientError::NO_AUTH),
 
+			// -- RPC
+			RpcParseFail => (StatusCode::BAD_REQUEST, ClientError::RPC_PARSE_ERROR),
+			RpcInvalidRequest => {
+				(StatusCode::BAD_REQUEST, ClientError::RPC_INVALID_REQUEST)
+			}
+			RpcMethodUnknown(rpc_method) => (
+				StatusCode::BAD_REQUEST,
+				ClientError::RPC_METHOD_NOT_FOUND {
+					rpc_method: rpc_method.to_string(),
+				},
+			),
+			RpcMissingParams { rpc_method } | RpcFailJsonParams { rpc_method } => (
+				StatusCode::BAD_REQUEST,
+				ClientError::RPC_INVALID_PARAMS {
+					rpc_method: rpc_method.to_string(),
+				},
+			),
+
+			// -- REST
+			RestInvalidInput(detail) => (
+				StatusCode::BAD_REQUEST,
+				ClientError::INVALID_INPUT(detail.to_string()),
+			),
+
 			// -- Model
 			Model(model::Error::EntityNotFound { entity, id }) => (
 				StatusCode::BAD_REQUEST,
 				ClientError::ENTITY_NOT_FOUND { entity, id: *id },
 			),
+			Model(model::Error::AccessDenied { entity, id }) => (
+				StatusCode::FORBIDDEN,
+				ClientError::ACCESS_DENIED { entity, id: *id },
+			),
+			Model(model::Error::AdminRequired { .. }) => {
+				(StatusCode::FORBIDDEN, ClientError::ADMIN_REQUIRED)
+			}
+			Model(model::Error::VersionConflict {
+				entity,
+				id,
+				current,
+				..
+			}) => (
+				StatusCode::CONFLICT,
+				ClientError::VERSION_CONFLICT {
+					entity,
+					id: *id,
+					current_version: *current,
+				},
+			),
+			Model(model::Error::Recurrence(ex)) => (
+				StatusCode::BAD_REQUEST,
+				ClientError::INVALID_RRULE(ex.to_string()),
+			),
+			Model(model::Error::WebhookInvalidUrl(url)) => (
+				StatusCode::BAD_REQUEST,
+				ClientError::INVALID_INPUT(format!("Invalid webhook url '{url}'")),
+			),
+			Model(model::Error::WebhookInvalidEvent(event)) => (
+				StatusCode::BAD_REQUEST,
+				ClientError::INVALID_INPUT(format!(
+					"Invalid webhook event '{event}'"
+				)),
+			),
+			Model(model::Error::ListOrderByInvalid { entity, order_by }) => (
+				StatusCode::BAD_REQUEST,
+				ClientError::INVALID_INPUT(format!(
+					"Invalid order by '{order_by}' for '{entity}'"
+				)),
+			),
+			Model(model::Error::ListCursorInvalid { entity }) => (
+				StatusCode::BAD_REQUEST,
+				ClientError::INVALID_INPUT(format!(
+					"Invalid list cursor for '{entity}' (or its order changed)"
+				)),
+			),
+			Model(model::Error::ListCursorUnsupported) => (
+				StatusCode::BAD_REQUEST,
+				ClientError::INVALID_INPUT(
+					"List cursor only supported by the page lists (e.g., list_tasks_page)"
+						.to_string(),
+				),
+			),
+			Model(model::Error::BulkTooManyItems { max, count }) => (
+				StatusCode::BAD_REQUEST,
+				ClientError::INVALID_INPUT(format!(
+					"Too many bulk items ({count}, max {max})"
+				)),
+			),
+			Model(model::Error::SavedViewInvalidName(name)) => (
+				StatusCode::BAD_REQUEST,
+				ClientError::INVALID_INPUT(format!(
+					"Invalid saved view name '{name}'"
+				)),
+			),
+			Model(model::Error::SavedViewNameTaken(name)) => (
+				StatusCode::CONFLICT,
+				ClientError::NAME_TAKEN { name: name.clone() },
+			),
+			Model(model::Error::Rank(_)) => {
+				(StatusCode::BAD_REQUEST, ClientError::INVALID_RANK)
+			}
 
 			// -- Fallback.
 			_ => (
@@ -110,14 +213,59 @@ impl Error {
 	}
 }
 
-#[derive(Debug, Serialize, strum_macros::AsRefStr)]
+#[derive(Debug, Serialize, JsonSchema, strum_macros::AsRefStr)]
 #[serde(tag = "message", content = "detail")]
 #[allow(non_camel_case_types)]
 pub enum ClientError {
+	RPC_PARSE_ERROR,
+	RPC_INVALID_REQUEST,
+	RPC_METHOD_NOT_FOUND {
+		rpc_method: String,
+	},
+	RPC_INVALID_PARAMS {
+		rpc_method: String,
+	},
+
+	INVALID_INPUT(String),
+
 	LOGIN_FAIL,
 	NO_AUTH,
-	ENTITY_NOT_FOUND { entity: &'static str, id: i64 },
+	ENTITY_NOT_FOUND {
+		entity: &'static str,
+		id: i64,
+	},
+	ACCESS_DENIED {
+		entity: &'static str,
+		id: i64,
+	},
+	ADMIN_REQUIRED,
+	VERSION_CONFLICT {
+		entity: &'static str,
+		id: i64,
+		current_version: i64,
+	},
+	/// A name already used (e.g., by another saved view of the user).
+	NAME_TAKEN {
+		name: String,
+	},
+	INVALID_RRULE(String),
+	INVALID_RANK,
 
 	SERVICE_ERROR,
 }
+
+impl ClientError {
+	/// The JSON-RPC 2.0 error code (the app errors share the -32000 server error code,
+	/// the `ClientError` variant name telling them apart).
+	pub fn rpc_code(&self) -> i64 {
+		match self {
+			ClientError::RPC_PARSE_ERROR => -32700,
+			ClientError::RPC_INVALID_REQUEST => -32600,
+			ClientError::RPC_METHOD_NOT_FOUND { .. } => -32601,
+			ClientError::RPC_INVALID_PARAMS { .. } => -32602,
+			ClientError::SERVICE_ERROR => -32603,
+			_ => -32000,
+		}
+	}
+}
 // endregion: --- Client Error
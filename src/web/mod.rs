@@ -1,22 +1,57 @@
 // region:    --- Modules
 
+#[cfg(test)]
+mod client_tests;
 mod error;
 pub mod mw_auth;
 pub mod mw_res_map;
+pub mod openapi;
+pub mod rest;
 pub mod routes_login;
+pub mod routes_sse;
 pub mod routes_static;
+pub mod routes_ws;
 pub mod rpc;
 
 pub use self::error::ClientError;
 pub use self::error::{Error, Result};
+use crate::model::ModelManager;
 use crate::token::generate_web_token;
-use tower_cookies::{Cookie, Cookies};
+use crate::web::mw_auth::{mw_ctx_require, mw_ctx_resolve};
+use crate::web::mw_res_map::mw_reponse_map;
+use axum::{middleware, Router};
+use tower_cookies::{Cookie, CookieManagerLayer, Cookies};
 use uuid::Uuid;
 
 // endregion: --- Modules
 
 pub const AUTH_TOKEN: &str = "auth-token";
 
+/// All the routes of the app, with their middlewares.
+pub fn routes_all(mm: ModelManager) -> Router {
+	let routes_rpc =
+		rpc::routes(mm.clone()).route_layer(middleware::from_fn(mw_ctx_require));
+	let routes_rest = rest::routes(mm.clone())
+		.route_layer(middleware::from_fn(mw_ctx_require))
+		.layer(middleware::map_response(rest::mw_rest_info));
+	let routes_sse = routes_sse::routes(mm.clone())
+		.route_layer(middleware::from_fn(mw_ctx_require));
+	let routes_ws = routes_ws::routes(mm.clone())
+		.route_layer(middleware::from_fn(mw_ctx_require));
+
+	Router::new()
+		.merge(routes_login::routes(mm.clone()))
+		.merge(openapi::routes())
+		.nest("/api", routes_rpc)
+		.nest("/api/v1", routes_rest)
+		.merge(routes_sse)
+		.merge(routes_ws)
+		.layer(middleware::map_response(mw_reponse_map))
+		.layer(middleware::from_fn_with_state(mm, mw_ctx_resolve))
+		.layer(CookieManagerLayer::new())
+		.fallback_service(routes_static::serve_dir())
+}
+
 fn set_token_cookie(cookies: &Cookies, user: &str, salt: Uuid) -> Result<()> {
 	let token = generate_web_token(user, salt)?;
 
@@ -6,8 +6,9 @@ use crate::web::{set_token_cookie, AUTH_TOKEN};
 use crate::web::{Error, Result};
 use async_trait::async_trait;
 use axum::extract::{FromRequestParts, State};
+use axum::http::header::AUTHORIZATION;
 use axum::http::request::Parts;
-use axum::http::Request;
+use axum::http::{HeaderMap, Request};
 use axum::middleware::Next;
 use axum::response::Response;
 use serde::Serialize;
@@ -35,10 +36,11 @@ pub async fn mw_ctx_resolve<B>(
 ) -> Result<Response> {
 	debug!("{:<12} - mw_ctx_resolve", "MIDDLEWARE");
 
-	let ctx_ext_result = _ctx_resolve(mm, &cookies).await;
+	let bearer_token = bearer_token(req.headers());
+	let ctx_ext_result = _ctx_resolve(mm, &cookies, bearer_token).await;
 
 	if ctx_ext_result.is_err()
-		&& !matches!(ctx_ext_result, Err(CtxExtError::TokenNotInCookie))
+		&& !matches!(ctx_ext_result, Err(CtxExtError::TokenNotInRequest))
 	{
 		cookies.remove(Cookie::named(AUTH_TOKEN))
 	}
@@ -50,12 +52,17 @@ pub async fn mw_ctx_resolve<B>(
 	Ok(next.run(req).await)
 }
 
-async fn _ctx_resolve(mm: State<ModelManager>, cookies: &Cookies) -> CtxExtResult {
-	// -- Get Token String
+async fn _ctx_resolve(
+	mm: State<ModelManager>,
+	cookies: &Cookies,
+	bearer_token: Option<String>,
+) -> CtxExtResult {
+	// -- Get Token String (from the cookie, or else the bearer token)
 	let token = cookies
 		.get(AUTH_TOKEN)
 		.map(|c| c.value().to_string())
-		.ok_or(CtxExtError::TokenNotInCookie)?;
+		.or(bearer_token)
+		.ok_or(CtxExtError::TokenNotInRequest)?;
 
 	// -- Parse Token
 	let token: Token = token.parse().map_err(|_| CtxExtError::TokenWrongFormat)?;
@@ -79,6 +86,16 @@ async fn _ctx_resolve(mm: State<ModelManager>, cookies: &Cookies) -> CtxExtResul
 	Ctx::new(user.id).map_err(|ex| CtxExtError::CtxCreateFail(ex.to_string()))
 }
 
+/// The token of the `Authorization: Bearer <token>` header
+/// (for the non browser clients, e.g., `lib-rpc-client`).
+fn bearer_token(headers: &HeaderMap) -> Option<String> {
+	let auth_value = headers.get(AUTHORIZATION)?.to_str().ok()?;
+
+	auth_value
+		.strip_prefix("Bearer ")
+		.map(|token| token.trim().to_string())
+}
+
 // region:    --- Ctx Extractor
 #[async_trait]
 impl<S: Send + Sync> FromRequestParts<S> for Ctx {
@@ -102,7 +119,7 @@ type CtxExtResult = core::result::Result<Ctx, CtxExtError>;
 
 #[derive(Clone, Serialize, Debug)]
 pub enum CtxExtError {
-	TokenNotInCookie,
+	TokenNotInRequest,
 	TokenWrongFormat,
 
 	UserNotFound,
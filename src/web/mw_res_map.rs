@@ -1,11 +1,12 @@
 use crate::ctx::Ctx;
 use crate::log::log_request;
 use crate::web;
-use crate::web::rpc::RpcInfo;
+use crate::web::rest::{rest_error_body, rest_status, RestInfo};
+use crate::web::rpc::{rpc_error_body, RpcInfo};
 use axum::http::{Method, Uri};
 use axum::response::{IntoResponse, Response};
 use axum::Json;
-use serde_json::{json, to_value};
+use serde_json::Value;
 use tracing::debug;
 use uuid::Uuid;
 
@@ -19,6 +20,7 @@ pub async fn mw_reponse_map(
 	let uuid = Uuid::new_v4();
 
 	let rpc_info = res.extensions().get::<RpcInfo>();
+	let rest_info = res.extensions().get::<RestInfo>();
 
 	// -- Get the eventual response error.
 	let web_error = res.extensions().get::<web::Error>();
@@ -29,25 +31,26 @@ pub async fn mw_reponse_map(
 		client_status_error
 			.as_ref()
 			.map(|(status_code, client_error)| {
-				let client_error = to_value(client_error).ok();
-				let message = client_error.as_ref().and_then(|v| v.get("message"));
-				let detail = client_error.as_ref().and_then(|v| v.get("detail"));
-
-				let client_error_body = json!({
-					"id": rpc_info.as_ref().map(|rpc| rpc.id.clone()),
-					"error": {
-						"message": message, // Variant name
-						"data": {
-							"req_uuid": uuid.to_string(),
-							"detail": detail
-						},
+				let (status_code, client_error_body) = match rest_info {
+					Some(_) => (
+						rest_status(*status_code, client_error),
+						rest_error_body(client_error, uuid),
+					),
+					None => {
+						let rpc_id = rpc_info.and_then(|rpc| rpc.id.clone());
+						let body = rpc_error_body(
+							rpc_id.unwrap_or(Value::Null),
+							client_error,
+							Some(uuid),
+						);
+						(*status_code, body)
 					}
-				});
+				};
 
 				debug!("CLIENT ERROR BODY:\n{client_error_body}");
 
 				// Build the new response from the client_error_body
-				(*status_code, Json(client_error_body)).into_response()
+				(status_code, Json(client_error_body)).into_response()
 			});
 
 	// -- Build and log the server log line.
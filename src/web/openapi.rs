@@ -0,0 +1,308 @@
+//! The OpenAPI 3.1 document (https://spec.openapis.org/oas/v3.1.0) of the HTTP routes,
+//! served at `OPENAPI_PATH`.
+//!
+//! Each routes module describes its own routes (see their `openapi_paths`), from
+//! their payload types (e.g., `LoginPayload`, `TaskForCreate`), and the error bodies
+//! are the ones of `mw_reponse_map` (i.e., the REST or the JSON-RPC error body).
+//!
+//! Note: The rpc methods are described by the OpenRPC document of `rpc.discover`.
+//!
+//! Note: The document is also committed as `OPENAPI_DOC_PATH`, kept in sync by the
+//!       `test_openapi_doc_in_sync` test (`UPDATE_OPENAPI=1 cargo test` to update it).
+//!
+
+use crate::web::{
+	rest, routes_login, routes_sse, routes_static, routes_ws, rpc, ClientError,
+	AUTH_TOKEN,
+};
+use axum::extract::State;
+use axum::routing::get;
+use axum::{Json, Router};
+use schemars::generate::SchemaSettings;
+use schemars::{json_schema, Schema, SchemaGenerator};
+use serde_json::{json, Map, Value};
+use std::sync::Arc;
+
+pub const OPENAPI_PATH: &str = "/api/openapi.json";
+
+const OPENAPI_VERSION: &str = "3.1.0";
+
+/// Where the OpenAPI document is committed (relative to the crate root).
+#[cfg(test)]
+const OPENAPI_DOC_PATH: &str = "docs/openapi.json";
+
+/// The OpenAPI path items of a routes module (by path).
+pub(in crate::web) type PathItems = Map<String, Value>;
+
+pub fn routes() -> Router {
+	Router::new()
+		.route(OPENAPI_PATH, get(openapi_handler))
+		.with_state(Arc::new(openapi_doc()))
+}
+
+async fn openapi_handler(State(doc): State<Arc<Value>>) -> Json<Value> {
+	Json(doc.as_ref().clone())
+}
+
+/// Returns the OpenAPI document of all the HTTP routes.
+pub fn openapi_doc() -> Value {
+	let mut gen = SchemaSettings::draft2020_12()
+		.with(|settings| settings.definitions_path = "/components/schemas".into())
+		.into_generator();
+
+	let mut paths = PathItems::new();
+	paths.extend(routes_login::openapi_paths(&mut gen));
+	paths.extend(rpc::openapi_paths(&mut gen));
+	paths.extend(rest::openapi_paths(&mut gen));
+	paths.extend(routes_sse::openapi_paths(&mut gen));
+	paths.extend(routes_ws::openapi_paths());
+	paths.extend(openapi_paths());
+	paths.extend(routes_static::openapi_paths());
+
+	let rest_error_body = rest_error_body_schema(&mut gen);
+	let rpc_error_body = rpc_error_body_schema();
+
+	json!({
+		"openapi": OPENAPI_VERSION,
+		"info": {
+			"title": env!("CARGO_PKG_NAME"),
+			"version": env!("CARGO_PKG_VERSION"),
+		},
+		"paths": paths,
+		"components": {
+			"schemas": gen.take_definitions(true),
+			"responses": {
+				"RestError": {
+					"description": "REST error (e.g., `ENTITY_NOT_FOUND`).",
+					"content": json_content(rest_error_body),
+				},
+				"RpcError": {
+					"description": "JSON-RPC error (also for the login routes).",
+					"content": json_content(rpc_error_body),
+				},
+			},
+			"securitySchemes": {
+				"cookieAuth": {
+					"type": "apiKey",
+					"in": "cookie",
+					"name": AUTH_TOKEN,
+				},
+				"bearerAuth": {
+					"type": "http",
+					"scheme": "bearer",
+				},
+			},
+		},
+	})
+}
+
+fn openapi_paths() -> PathItems {
+	let mut paths = PathItems::new();
+	paths.insert(
+		OPENAPI_PATH.to_string(),
+		json!({
+			"get": {
+				"summary": "This OpenAPI document.",
+				"responses": {
+					"200": {
+						"description": "The OpenAPI document.",
+						"content": json_content(json_schema!({"type": "object"})),
+					},
+				},
+			},
+		}),
+	);
+
+	paths
+}
+
+// region:    --- Error Bodies
+
+/// The REST error body (see `rest::rest_error_body`).
+fn rest_error_body_schema(gen: &mut SchemaGenerator) -> Schema {
+	let client_error = gen.subschema_for::<ClientError>();
+
+	json_schema!({
+		"type": "object",
+		"properties": {
+			"error": {
+				"allOf": [
+					client_error,
+					{
+						"type": "object",
+						"properties": {
+							"req_uuid": {"type": "string", "format": "uuid"},
+						},
+						"required": ["req_uuid"],
+					},
+				],
+			},
+		},
+		"required": ["error"],
+	})
+}
+
+/// The JSON-RPC error body (see `rpc::rpc_error_body`), the `ClientError` variant
+/// name being the `message`, and its detail the `data.detail`.
+fn rpc_error_body_schema() -> Schema {
+	json_schema!({
+		"type": "object",
+		"properties": {
+			"jsonrpc": {"const": "2.0"},
+			"id": {},
+			"error": {
+				"type": "object",
+				"properties": {
+					"code": {"type": "integer"},
+					"message": {
+						"type": "string",
+						"description": "The `ClientError` variant name.",
+					},
+					"data": {
+						"type": "object",
+						"properties": {
+							"req_uuid": {"type": ["string", "null"], "format": "uuid"},
+							"detail": {"description": "The `ClientError` detail."},
+						},
+					},
+				},
+				"required": ["code", "message"],
+			},
+		},
+		"required": ["jsonrpc", "id", "error"],
+	})
+}
+
+// endregion: --- Error Bodies
+
+// region:    --- Path Item Helpers
+
+/// The json `content` of a request body or response.
+pub(in crate::web) fn json_content(schema: Schema) -> Value {
+	json!({
+		"application/json": {
+			"schema": schema,
+		}
+	})
+}
+
+/// The query parameters, from the properties of the query type schema.
+pub(in crate::web) fn query_parameters(query_schema: Schema) -> Vec<Value> {
+	let Some(properties) = query_schema.get("properties").and_then(Value::as_object)
+	else {
+		return Vec::new();
+	};
+
+	properties
+		.iter()
+		.map(|(name, schema)| {
+			let mut parameter = json!({
+				"name": name,
+				"in": "query",
+				"required": false,
+				"schema": schema,
+			});
+			if let Some(description) = schema.get("description") {
+				parameter["description"] = description.clone();
+			}
+			parameter
+		})
+		.collect()
+}
+
+/// The `{id}` path parameter.
+pub(in crate::web) fn id_path_parameter() -> Value {
+	json!({
+		"name": "id",
+		"in": "path",
+		"required": true,
+		"schema": {"type": "integer", "format": "int64"},
+	})
+}
+
+/// The security of the routes requiring the auth (cookie or bearer token).
+pub(in crate::web) fn auth_security() -> Value {
+	json!([{"cookieAuth": []}, {"bearerAuth": []}])
+}
+
+pub(in crate::web) fn rest_error_response() -> Value {
+	json!({"$ref": "#/components/responses/RestError"})
+}
+
+pub(in crate::web) fn rpc_error_response() -> Value {
+	json!({"$ref": "#/components/responses/RpcError"})
+}
+
+// endregion: --- Path Item Helpers
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::_dev_utils;
+	use crate::web::routes_all;
+	use anyhow::Result;
+	use axum::body::Body;
+	use axum::http::{Request, StatusCode};
+	use serial_test::serial;
+	use std::path::Path;
+	use tower::ServiceExt;
+
+	#[test]
+	fn test_openapi_doc_in_sync() -> Result<()> {
+		// -- Setup & Fixtures
+		let doc_path = Path::new(env!("CARGO_MANIFEST_DIR")).join(OPENAPI_DOC_PATH);
+
+		// -- Exec
+		let doc = serde_json::to_string_pretty(&openapi_doc())? + "\n";
+		if std::env::var("UPDATE_OPENAPI").is_ok() {
+			std::fs::write(&doc_path, &doc)?;
+		}
+
+		// -- Check
+		let committed_doc = std::fs::read_to_string(&doc_path)?;
+		assert!(
+			committed_doc == doc,
+			"{OPENAPI_DOC_PATH} is out of sync with the routes \
+			 (run `UPDATE_OPENAPI=1 cargo test` to update it)"
+		);
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_openapi_route_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let req = Request::get(OPENAPI_PATH).body(Body::empty())?;
+
+		// -- Exec
+		// No auth required.
+		let res = routes_all(mm).oneshot(req).await?;
+
+		// -- Check
+		assert_eq!(res.status(), StatusCode::OK);
+		let body = hyper::body::to_bytes(res.into_body()).await?;
+		let doc: Value = serde_json::from_slice(&body)?;
+		assert_eq!(doc["openapi"], OPENAPI_VERSION);
+		let paths = doc["paths"].as_object().unwrap();
+		for path in [
+			"/api/login",
+			"/api/logoff",
+			"/api/rpc",
+			"/api/v1/tasks/{id}",
+		] {
+			assert!(paths.contains_key(path), "missing path {path}");
+		}
+		assert_eq!(
+			doc["paths"]["/api/v1/tasks"]["post"]["requestBody"]["content"]
+				["application/json"]["schema"]["$ref"],
+			"#/components/schemas/TaskForCreate"
+		);
+		assert!(doc["components"]["schemas"]["LoginPayload"].is_object());
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
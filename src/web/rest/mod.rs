@@ -0,0 +1,71 @@
+//! The RESTful routes (under `/api/v1`), alongside the JSON-RPC ones, for the tools
+//! working better with resource urls (e.g., curl scripts, caching proxies).
+//!
+//! The handlers reuse the rpc functions (e.g., `task_rpc::create_task`), and their
+//! errors get the same `client_status_and_error` mapping, but with a REST error body
+//! (see `rest_error_body`) and the `404` status for the entities not found.
+//!
+
+// region:    --- Modules
+
+mod task_rest;
+
+use crate::model::ModelManager;
+use crate::web::openapi::PathItems;
+use crate::web::{ClientError, Error};
+use axum::http::StatusCode;
+use axum::response::Response;
+use axum::Router;
+use schemars::SchemaGenerator;
+use serde_json::{json, to_value, Value};
+use uuid::Uuid;
+
+// endregion: --- Modules
+
+/// Marks the REST responses, so that `mw_reponse_map` builds REST error bodies.
+#[derive(Debug, Clone, Copy)]
+pub struct RestInfo;
+
+pub fn routes(mm: ModelManager) -> Router {
+	Router::new().merge(task_rest::routes(mm))
+}
+
+pub(in crate::web) fn openapi_paths(gen: &mut SchemaGenerator) -> PathItems {
+	task_rest::openapi_paths(gen)
+}
+
+/// Mark the responses as REST ones (see `RestInfo`).
+pub async fn mw_rest_info(mut res: Response) -> Response {
+	res.extensions_mut().insert(RestInfo);
+
+	res
+}
+
+/// The REST status of a client error, from its `client_status_and_error` status.
+pub fn rest_status(status: StatusCode, client_error: &ClientError) -> StatusCode {
+	match client_error {
+		ClientError::ENTITY_NOT_FOUND { .. } => StatusCode::NOT_FOUND,
+		_ => status,
+	}
+}
+
+/// The REST error response body for a `ClientError`
+/// (e.g., `{"error": {"message": "ENTITY_NOT_FOUND", "detail": {..}, "req_uuid": ..}}`).
+pub fn rest_error_body(client_error: &ClientError, req_uuid: Uuid) -> Value {
+	let client_error_value = to_value(client_error).ok();
+	let message = client_error_value.as_ref().and_then(|v| v.get("message"));
+	let detail = client_error_value.as_ref().and_then(|v| v.get("detail"));
+
+	json!({
+		"error": {
+			"message": message, // Variant name
+			"detail": detail,
+			"req_uuid": req_uuid.to_string(),
+		}
+	})
+}
+
+/// An extractor rejection (e.g., invalid json body or query) as a web error.
+fn invalid_input(rejection: impl std::fmt::Display) -> Error {
+	Error::RestInvalidInput(rejection.to_string())
+}
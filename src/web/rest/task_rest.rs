@@ -0,0 +1,530 @@
+use crate::ctx::Ctx;
+use crate::model::task::{Task, TaskBmc, TaskFilter, TaskForCreate, TaskForUpdate};
+use crate::model::{ListOptions, ModelManager};
+use crate::utils::time::{Rfc3339, Rfc3339Schema};
+use crate::web::openapi::{
+	auth_security, id_path_parameter, json_content, query_parameters,
+	rest_error_response, PathItems,
+};
+use crate::web::rest::invalid_input;
+use crate::web::rpc::task_rpc::{self, ParamsForListTasks, TaskListItem};
+use crate::web::rpc::{ParamsForCreate, ParamsForUpdate, ParamsIded};
+use crate::web::{Error, Result};
+use axum::extract::rejection::{JsonRejection, PathRejection, QueryRejection};
+use axum::extract::{Path, Query, State};
+use axum::http::header::{ETAG, IF_MATCH, LOCATION};
+use axum::http::{HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use schemars::{JsonSchema, SchemaGenerator};
+use serde::Deserialize;
+use serde_json::json;
+use serde_with::serde_as;
+use time::OffsetDateTime;
+use tracing::debug;
+
+const TASKS_PATH: &str = "/api/v1/tasks";
+
+/// The `GET /tasks` response header of the next page cursor (absent on the last page).
+const X_NEXT_CURSOR: &str = "x-next-cursor";
+
+pub fn routes(mm: ModelManager) -> Router {
+	Router::new()
+		.route("/tasks", get(list_tasks_handler).post(create_task_handler))
+		.route(
+			"/tasks/:id",
+			get(get_task_handler)
+				.patch(update_task_handler)
+				.delete(delete_task_handler),
+		)
+		.with_state(mm)
+}
+
+/// The `GET /tasks` query string, the `TaskFilter` and `ListOptions` members
+/// (e.g., `?done=false&title_contains=milk&limit=20&order_by=!due_date`).
+/// The next page cursor is returned as the `X-Next-Cursor` header.
+#[serde_as]
+#[derive(Deserialize, JsonSchema)]
+struct TaskListQuery {
+	done: Option<bool>,
+	/// Case insensitive substring of the title.
+	title_contains: Option<String>,
+	/// Tasks due strictly before this time.
+	#[serde_as(as = "Option<Rfc3339>")]
+	#[schemars(with = "Option<Rfc3339Schema>")]
+	due_before: Option<OffsetDateTime>,
+	/// Tasks due at or after this time.
+	#[serde_as(as = "Option<Rfc3339>")]
+	#[schemars(with = "Option<Rfc3339Schema>")]
+	due_after: Option<OffsetDateTime>,
+	limit: Option<i64>,
+	offset: Option<i64>,
+	/// Comma separated order bys (e.g., `!due_date,title`, `!` for descending).
+	order_by: Option<String>,
+	/// The `X-Next-Cursor` of the previous page (`offset` then being ignored).
+	cursor: Option<String>,
+}
+
+impl From<TaskListQuery> for ParamsForListTasks {
+	fn from(query: TaskListQuery) -> Self {
+		let TaskListQuery {
+			done,
+			title_contains,
+			due_before,
+			due_after,
+			limit,
+			offset,
+			order_by,
+			cursor,
+		} = query;
+
+		ParamsForListTasks {
+			filter: Some(TaskFilter {
+				done,
+				title_contains,
+				due_before,
+				due_after,
+			}),
+			list_options: Some(ListOptions {
+				limit,
+				offset,
+				order_bys: order_by.map(|order_by| {
+					order_by.split(',').map(str::to_string).collect()
+				}),
+				cursor,
+			}),
+		}
+	}
+}
+
+async fn list_tasks_handler(
+	State(mm): State<ModelManager>,
+	ctx: Ctx,
+	query: core::result::Result<Query<TaskListQuery>, QueryRejection>,
+) -> Result<Response> {
+	debug!("{:<12} - list_tasks_handler", "HANDLER");
+
+	let Query(query) = query.map_err(invalid_input)?;
+	let page = task_rpc::list_tasks_page(ctx, mm, query.into()).await?;
+
+	let mut res = Json(page.items).into_response();
+	if let Some(next_cursor) = page
+		.next_cursor
+		.and_then(|cursor| HeaderValue::from_str(&cursor).ok())
+	{
+		res.headers_mut().insert(X_NEXT_CURSOR, next_cursor);
+	}
+
+	Ok(res)
+}
+
+async fn get_task_handler(
+	State(mm): State<ModelManager>,
+	ctx: Ctx,
+	id: core::result::Result<Path<i64>, PathRejection>,
+) -> Result<Response> {
+	debug!("{:<12} - get_task_handler", "HANDLER");
+
+	let Path(id) = id.map_err(invalid_input)?;
+	let task = TaskBmc::get(&ctx, &mm, id).await?;
+
+	Ok(task_response(StatusCode::OK, task))
+}
+
+async fn create_task_handler(
+	State(mm): State<ModelManager>,
+	ctx: Ctx,
+	data: core::result::Result<Json<TaskForCreate>, JsonRejection>,
+) -> Result<Response> {
+	debug!("{:<12} - create_task_handler", "HANDLER");
+
+	let Json(data) = data.map_err(invalid_input)?;
+	let task = task_rpc::create_task(ctx, mm, ParamsForCreate { data }).await?;
+
+	let location = format!("{TASKS_PATH}/{}", task.id);
+	let mut res = task_response(StatusCode::CREATED, task);
+	if let Ok(location) = HeaderValue::from_str(&location) {
+		res.headers_mut().insert(LOCATION, location);
+	}
+
+	Ok(res)
+}
+
+/// Partial update of the task, with the optional `If-Match: "<version>"`
+/// header as the expected version (see `ParamsForUpdate`).
+async fn update_task_handler(
+	State(mm): State<ModelManager>,
+	ctx: Ctx,
+	id: core::result::Result<Path<i64>, PathRejection>,
+	headers: HeaderMap,
+	data: core::result::Result<Json<TaskForUpdate>, JsonRejection>,
+) -> Result<Response> {
+	debug!("{:<12} - update_task_handler", "HANDLER");
+
+	let Path(id) = id.map_err(invalid_input)?;
+	let Json(data) = data.map_err(invalid_input)?;
+	let expected_version = if_match_version(&headers)?;
+
+	let params = ParamsForUpdate {
+		id,
+		data,
+		expected_version,
+	};
+	let task = task_rpc::update_task(ctx, mm, params).await?;
+
+	Ok(task_response(StatusCode::OK, task))
+}
+
+async fn delete_task_handler(
+	State(mm): State<ModelManager>,
+	ctx: Ctx,
+	id: core::result::Result<Path<i64>, PathRejection>,
+) -> Result<StatusCode> {
+	debug!("{:<12} - delete_task_handler", "HANDLER");
+
+	let Path(id) = id.map_err(invalid_input)?;
+	task_rpc::delete_task(ctx, mm, ParamsIded { id }).await?;
+
+	Ok(StatusCode::NO_CONTENT)
+}
+
+/// The task json response, with its version as `ETag`.
+fn task_response(status: StatusCode, task: Task) -> Response {
+	let etag = format!("\"{}\"", task.version);
+
+	(status, [(ETAG, etag)], Json(task)).into_response()
+}
+
+/// The version of the `If-Match: "<version>"` header, if any.
+fn if_match_version(headers: &HeaderMap) -> Result<Option<i64>> {
+	let Some(if_match) = headers.get(IF_MATCH) else {
+		return Ok(None);
+	};
+
+	let version = if_match
+		.to_str()
+		.ok()
+		.map(|v| v.trim().trim_start_matches("W/").trim_matches('"'))
+		.and_then(|v| v.parse::<i64>().ok())
+		.ok_or_else(|| {
+			Error::RestInvalidInput(format!("Invalid If-Match header {if_match:?}"))
+		})?;
+
+	Ok(Some(version))
+}
+
+// region:    --- OpenAPI
+pub(super) fn openapi_paths(gen: &mut SchemaGenerator) -> PathItems {
+	let task = json_content(gen.subschema_for::<Task>());
+	let etag = json!({
+		"ETag": {
+			"description": "The task version (e.g., `\"3\"`).",
+			"schema": {"type": "string"},
+		},
+	});
+	let errors = rest_error_response();
+
+	let mut paths = PathItems::new();
+	paths.insert(
+		TASKS_PATH.to_string(),
+		json!({
+			"get": {
+				"summary": "List the tasks (with the optional filters).",
+				"security": auth_security(),
+				"parameters": query_parameters(TaskListQuery::json_schema(gen)),
+				"responses": {
+					"200": {
+						"description": "The tasks.",
+						"headers": {
+							"X-Next-Cursor": {
+								"description": "The `cursor` of the next page (absent on the last page).",
+								"schema": {"type": "string"},
+							},
+						},
+						"content": json_content(gen.subschema_for::<Vec<TaskListItem>>()),
+					},
+					"default": errors,
+				},
+			},
+			"post": {
+				"summary": "Create a task.",
+				"security": auth_security(),
+				"requestBody": {
+					"required": true,
+					"content": json_content(gen.subschema_for::<TaskForCreate>()),
+				},
+				"responses": {
+					"201": {
+						"description": "The created task.",
+						"headers": {
+							"Location": {
+								"description": "The task url.",
+								"schema": {"type": "string"},
+							},
+						},
+						"content": task,
+					},
+					"default": errors,
+				},
+			},
+		}),
+	);
+
+	paths.insert(
+		format!("{TASKS_PATH}/{{id}}"),
+		json!({
+			"parameters": [id_path_parameter()],
+			"get": {
+				"summary": "Get a task.",
+				"security": auth_security(),
+				"responses": {
+					"200": {"description": "The task.", "headers": etag, "content": task},
+					"404": errors,
+					"default": errors,
+				},
+			},
+			"patch": {
+				"summary": "Update the given fields of a task.",
+				"security": auth_security(),
+				"parameters": [{
+					"name": "If-Match",
+					"in": "header",
+					"required": false,
+					"description": "The expected task version (its `ETag`).",
+					"schema": {"type": "string"},
+				}],
+				"requestBody": {
+					"required": true,
+					"content": json_content(gen.subschema_for::<TaskForUpdate>()),
+				},
+				"responses": {
+					"200": {
+						"description": "The updated task.",
+						"headers": etag,
+						"content": task,
+					},
+					"404": errors,
+					"409": errors,
+					"default": errors,
+				},
+			},
+			"delete": {
+				"summary": "Delete a task (to the trash).",
+				"security": auth_security(),
+				"responses": {
+					"204": {"description": "Deleted."},
+					"404": errors,
+					"default": errors,
+				},
+			},
+		}),
+	);
+
+	paths
+}
+// endregion: --- OpenAPI
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::_dev_utils;
+	use crate::model::user::{UserBmc, UserForAuth};
+	use crate::token::generate_web_token;
+	use crate::web::routes_all;
+	use anyhow::{Context, Result};
+	use axum::body::Body;
+	use axum::http::header::{AUTHORIZATION, CONTENT_TYPE};
+	use axum::http::{Method, Request};
+	use serde_json::{json, Value};
+	use serial_test::serial;
+	use tower::ServiceExt;
+
+	/// Send the request to the app as the demo user (when `auth`),
+	/// returning the response status, headers and json body (null when empty).
+	async fn fx_send(
+		mm: &ModelManager,
+		auth: bool,
+		req: axum::http::request::Builder,
+		body: Option<Value>,
+	) -> Result<(StatusCode, HeaderMap, Value)> {
+		let mut req = req;
+		if auth {
+			let user: UserForAuth =
+				UserBmc::first_by_username(&Ctx::root_ctx(), mm, "demo1")
+					.await?
+					.context("Should have demo1")?;
+			let token = generate_web_token(&user.username, user.token_salt)?;
+			req = req.header(AUTHORIZATION, format!("Bearer {token}"));
+		}
+		let req = match body {
+			Some(body) => req
+				.header(CONTENT_TYPE, "application/json")
+				.body(Body::from(body.to_string()))?,
+			None => req.body(Body::empty())?,
+		};
+
+		let res = routes_all(mm.clone()).oneshot(req).await?;
+		let status = res.status();
+		let headers = res.headers().clone();
+		let body = hyper::body::to_bytes(res.into_body()).await?;
+		let body = if body.is_empty() {
+			Value::Null
+		} else {
+			serde_json::from_slice(&body)?
+		};
+
+		Ok((status, headers, body))
+	}
+
+	fn fx_req(method: Method, uri: &str) -> axum::http::request::Builder {
+		Request::builder().method(method).uri(uri)
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_rest_task_crud_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let fx_title = "test_rest_task_crud_ok";
+
+		// -- Exec & Check
+		// Create
+		let (status, headers, task) = fx_send(
+			&mm,
+			true,
+			fx_req(Method::POST, TASKS_PATH),
+			Some(json!({ "title": fx_title })),
+		)
+		.await?;
+		assert_eq!(status, StatusCode::CREATED);
+		let id = task["id"].as_i64().context("Should have an id")?;
+		let task_path = format!("{TASKS_PATH}/{id}");
+		assert_eq!(headers[LOCATION], task_path.as_str());
+		assert_eq!(headers[ETAG], format!("\"{}\"", task["version"]).as_str());
+
+		// Get
+		let (status, _, task) =
+			fx_send(&mm, true, fx_req(Method::GET, &task_path), None).await?;
+		assert_eq!(status, StatusCode::OK);
+		assert_eq!(task["title"], fx_title);
+
+		// List (with filters)
+		let list_uri = format!("{TASKS_PATH}?done=false&title_contains={fx_title}");
+		let (status, _, tasks) =
+			fx_send(&mm, true, fx_req(Method::GET, &list_uri), None).await?;
+		assert_eq!(status, StatusCode::OK);
+		assert_eq!(tasks.as_array().map(Vec::len), Some(1));
+		assert_eq!(tasks[0]["id"], id);
+		assert_eq!(tasks[0]["comment_count"], 0);
+
+		// List (by pages)
+		let page_uri = format!("{TASKS_PATH}?title_contains=test_rest&limit=1");
+		let (status, headers, _) =
+			fx_send(&mm, true, fx_req(Method::GET, &page_uri), None).await?;
+		assert_eq!(status, StatusCode::OK);
+		assert!(headers.get(X_NEXT_CURSOR).is_none());
+		let (_, headers, _) = fx_send(
+			&mm,
+			true,
+			fx_req(Method::GET, &format!("{TASKS_PATH}?limit=1")),
+			None,
+		)
+		.await?;
+		let next_cursor = headers[X_NEXT_CURSOR].to_str()?;
+		let next_uri = format!("{TASKS_PATH}?limit=1&cursor={next_cursor}");
+		let (status, _, tasks) =
+			fx_send(&mm, true, fx_req(Method::GET, &next_uri), None).await?;
+		assert_eq!(status, StatusCode::OK);
+		assert_eq!(tasks.as_array().map(Vec::len), Some(1));
+
+		// Update (with the version as If-Match)
+		let fx_if_match = format!("\"{}\"", task["version"]);
+		let fx_update =
+			|| fx_req(Method::PATCH, &task_path).header(IF_MATCH, &fx_if_match);
+		let (status, _, task) =
+			fx_send(&mm, true, fx_update(), Some(json!({ "done": true }))).await?;
+		assert_eq!(status, StatusCode::OK);
+		assert_eq!(task["done"], true);
+		let (status, _, body) =
+			fx_send(&mm, true, fx_update(), Some(json!({ "done": false }))).await?;
+		assert_eq!(status, StatusCode::CONFLICT);
+		assert_eq!(body["error"]["message"], "VERSION_CONFLICT");
+
+		// Delete
+		let (status, _, body) =
+			fx_send(&mm, true, fx_req(Method::DELETE, &task_path), None).await?;
+		assert_eq!(status, StatusCode::NO_CONTENT);
+		assert_eq!(body, Value::Null);
+		let (status, _, body) =
+			fx_send(&mm, true, fx_req(Method::GET, &task_path), None).await?;
+		assert_eq!(status, StatusCode::NOT_FOUND);
+		assert_eq!(body["error"]["message"], "ENTITY_NOT_FOUND");
+		assert_eq!(body["error"]["detail"]["id"], id);
+		assert!(body["error"]["req_uuid"].is_string());
+
+		// -- Clean
+		TaskBmc::purge(&Ctx::root_ctx(), &mm, id).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_rest_task_err_input_and_auth() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let fx_cases = [
+			(
+				true,
+				fx_req(Method::POST, TASKS_PATH),
+				Some(json!({ "no_title": 1 })),
+				StatusCode::BAD_REQUEST,
+				"INVALID_INPUT",
+			),
+			(
+				true,
+				fx_req(Method::GET, &format!("{TASKS_PATH}/not_an_id")),
+				None,
+				StatusCode::BAD_REQUEST,
+				"INVALID_INPUT",
+			),
+			(
+				true,
+				fx_req(Method::GET, &format!("{TASKS_PATH}?done=maybe")),
+				None,
+				StatusCode::BAD_REQUEST,
+				"INVALID_INPUT",
+			),
+			(
+				true,
+				fx_req(Method::PATCH, &format!("{TASKS_PATH}/1000"))
+					.header(IF_MATCH, "*"),
+				Some(json!({ "done": true })),
+				StatusCode::BAD_REQUEST,
+				"INVALID_INPUT",
+			),
+			(
+				false,
+				fx_req(Method::GET, TASKS_PATH),
+				None,
+				StatusCode::FORBIDDEN,
+				"NO_AUTH",
+			),
+		];
+
+		for (auth, req, body, expected_status, expected_message) in fx_cases {
+			// -- Exec
+			let (status, _, body) = fx_send(&mm, auth, req, body).await?;
+
+			// -- Check
+			assert_eq!(status, expected_status, "for {body}");
+			assert_eq!(body["error"]["message"], expected_message);
+			assert!(body.get("jsonrpc").is_none(), "should be a REST error body");
+		}
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
@@ -2,10 +2,12 @@ use crate::ctx::Ctx;
 use crate::model::user::{UserBmc, UserForLogin};
 use crate::model::ModelManager;
 use crate::pwd::{self, ContentToHash};
+use crate::web::openapi::{json_content, rpc_error_response, PathItems};
 use crate::web::{self, remove_token_cookie, Error, Result};
 use axum::extract::State;
 use axum::routing::post;
 use axum::{Json, Router};
+use schemars::{json_schema, JsonSchema, SchemaGenerator};
 use serde::Deserialize;
 use serde_json::{json, Value};
 use tower_cookies::Cookies;
@@ -65,7 +67,7 @@ async fn api_login_handler(
 	Ok(body)
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 struct LoginPayload {
 	username: String,
 	pwd: String,
@@ -94,8 +96,73 @@ async fn api_logoff_handler(
 	Ok(body)
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 struct LogoffPayload {
 	logoff: bool,
 }
 // endregion: --- Logoff
+
+// region:    --- OpenAPI
+pub(in crate::web) fn openapi_paths(gen: &mut SchemaGenerator) -> PathItems {
+	let mut paths = PathItems::new();
+
+	paths.insert(
+		"/api/login".to_string(),
+		json!({
+			"post": {
+				"summary": "Login, setting the auth-token cookie.",
+				"requestBody": {
+					"required": true,
+					"content": json_content(gen.subschema_for::<LoginPayload>()),
+				},
+				"responses": {
+					"200": {
+						"description": "Logged in.",
+						"content": json_content(json_schema!({
+							"type": "object",
+							"properties": {
+								"result": {
+									"type": "object",
+									"properties": {"success": {"type": "boolean"}},
+								},
+							},
+						})),
+					},
+					"403": rpc_error_response(),
+					"default": rpc_error_response(),
+				},
+			},
+		}),
+	);
+
+	paths.insert(
+		"/api/logoff".to_string(),
+		json!({
+			"post": {
+				"summary": "Logoff, removing the auth-token cookie.",
+				"requestBody": {
+					"required": true,
+					"content": json_content(gen.subschema_for::<LogoffPayload>()),
+				},
+				"responses": {
+					"200": {
+						"description": "Logged off (when `logoff` is true).",
+						"content": json_content(json_schema!({
+							"type": "object",
+							"properties": {
+								"result": {
+									"type": "object",
+									"properties": {"logged_off": {"type": "boolean"}},
+								},
+							},
+						})),
+					},
+					"default": rpc_error_response(),
+				},
+			},
+		}),
+	);
+
+	paths
+}
+// endregion: --- OpenAPI
@@ -0,0 +1,458 @@
+//! The `/api/events` Server-Sent Events stream of the change events (see
+//! `model::event`), for the clients without WebSocket (see `routes_ws`).
+//!
+//! Each SSE event has the event `seq` as `id`, and the `ChangeEvent` json as `data`
+//! (e.g., `{"seq": 43, "entity": "task", "id": 1000, "kind": "updated", ..}`).
+//!
+//! The `Last-Event-ID` header (sent by the `EventSource` clients on reconnect)
+//! resumes the stream after that event, from the persisted events.
+//!
+//! The optional `channels` query parameter (comma separated, e.g.,
+//! `?channels=task,comment:1000`) only streams the events of these channels
+//! (see `routes_ws` for the channels), and only the events visible to the ctx user
+//! are streamed.
+//!
+//! Note: The errors have the REST error body (see `rest::mw_rest_info`).
+//!
+
+use crate::ctx::Ctx;
+use crate::model::event::{ChangeEvent, ChangeEventBmc};
+use crate::model::{self, ModelManager};
+use crate::web::openapi::{
+	auth_security, query_parameters, rest_error_response, PathItems,
+};
+use crate::web::{rest, Error, Result};
+use axum::extract::rejection::QueryRejection;
+use axum::extract::{Query, State};
+use axum::http::HeaderMap;
+use axum::middleware;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::routing::get;
+use axum::Router;
+use futures::Stream;
+use schemars::{json_schema, JsonSchema, SchemaGenerator};
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::BTreeSet;
+use std::convert::Infallible;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, mpsc};
+use tracing::debug;
+
+const EVENTS_PATH: &str = "/api/events";
+
+const LAST_EVENT_ID: &str = "Last-Event-ID";
+
+/// Page size of the persisted events replayed (on resume or lag).
+const REPLAY_PAGE_SIZE: i64 = 200;
+
+/// Buffer of the SSE events not yet streamed to the client.
+const STREAM_BUFFER: usize = 64;
+
+pub fn routes(mm: ModelManager) -> Router {
+	Router::new()
+		.route(EVENTS_PATH, get(events_handler))
+		.layer(middleware::map_response(rest::mw_rest_info))
+		.with_state(mm)
+}
+
+/// The query of `/api/events`.
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+struct EventsQuery {
+	/// Comma separated channels (e.g., `task,comment:1000`), all when absent.
+	channels: Option<String>,
+}
+
+async fn events_handler(
+	State(mm): State<ModelManager>,
+	ctx: Ctx,
+	headers: HeaderMap,
+	query: core::result::Result<Query<EventsQuery>, QueryRejection>,
+) -> Result<Sse<impl Stream<Item = core::result::Result<Event, Infallible>>>> {
+	debug!("{:<12} - events_handler", "HANDLER");
+
+	let Query(query) =
+		query.map_err(|rejection| Error::RestInvalidInput(rejection.to_string()))?;
+	let channels = query.channels.map(|channels| parse_channels(&channels));
+	let channels = channels.transpose()?;
+
+	let last_event_id = headers
+		.get(LAST_EVENT_ID)
+		.map(|value| {
+			value
+				.to_str()
+				.ok()
+				.and_then(|value| value.trim().parse::<i64>().ok())
+				.ok_or_else(|| {
+					Error::RestInvalidInput(format!(
+						"Invalid {LAST_EVENT_ID} header"
+					))
+				})
+		})
+		.transpose()?;
+
+	// Subscribed before the replay, so no event is missed in between.
+	let events_rx = mm.events().subscribe();
+	let (tx, mut rx) = mpsc::channel(STREAM_BUFFER);
+	let session = SseSession {
+		last_seq: last_event_id.unwrap_or_else(|| mm.events().last_seq()),
+		ctx,
+		mm,
+		channels,
+		tx,
+	};
+	tokio::spawn(session.run(events_rx, last_event_id.is_some()));
+
+	let stream = futures::stream::poll_fn(move |cx| rx.poll_recv(cx));
+
+	Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+/// The comma separated channels (InvalidInput for an invalid one).
+fn parse_channels(channels: &str) -> Result<BTreeSet<String>> {
+	channels
+		.split(',')
+		.map(str::trim)
+		.filter(|channel| !channel.is_empty())
+		.map(|channel| {
+			if ChangeEvent::is_valid_channel(channel) {
+				Ok(channel.to_string())
+			} else {
+				Err(Error::RestInvalidInput(format!(
+					"Invalid channel '{channel}'"
+				)))
+			}
+		})
+		.collect()
+}
+
+pub(in crate::web) fn openapi_paths(gen: &mut SchemaGenerator) -> PathItems {
+	let mut parameters = query_parameters(EventsQuery::json_schema(gen));
+	parameters.push(json!({
+		"name": LAST_EVENT_ID,
+		"in": "header",
+		"required": false,
+		"description": "Resume after this event seq.",
+		"schema": {"type": "integer", "format": "int64"},
+	}));
+
+	let mut paths = PathItems::new();
+	paths.insert(
+		EVENTS_PATH.to_string(),
+		json!({
+			"get": {
+				"summary": "Server-Sent Events stream of the change events \
+							(`id` being the event seq, and `data` the event json).",
+				"security": auth_security(),
+				"parameters": parameters,
+				"responses": {
+					"200": {
+						"description": "The change events stream.",
+						"content": {
+							"text/event-stream": {
+								"schema": json_schema!({"type": "string"}),
+							},
+						},
+					},
+					"default": rest_error_response(),
+				},
+			},
+		}),
+	);
+
+	paths
+}
+
+// region:    --- SseSession
+
+/// Why a session ends.
+enum SseEnd {
+	ClientGone,
+	Model(model::Error),
+}
+
+impl From<model::Error> for SseEnd {
+	fn from(val: model::Error) -> Self {
+		Self::Model(val)
+	}
+}
+
+struct SseSession {
+	ctx: Ctx,
+	mm: ModelManager,
+	/// None for all the channels.
+	channels: Option<BTreeSet<String>>,
+	/// The seq of the last event streamed (or skipped).
+	last_seq: i64,
+	tx: mpsc::Sender<core::result::Result<Event, Infallible>>,
+}
+
+impl SseSession {
+	async fn run(
+		mut self,
+		mut events_rx: broadcast::Receiver<ChangeEvent>,
+		resume: bool,
+	) {
+		let res = async {
+			if resume {
+				self.replay().await?;
+			}
+
+			loop {
+				let event = tokio::select! {
+					_ = self.tx.closed() => return Err(SseEnd::ClientGone),
+					event = events_rx.recv() => event,
+				};
+
+				match event {
+					Ok(event) => self.send_event(&event).await?,
+					// Missed events, replayed from the persisted ones.
+					Err(RecvError::Lagged(_)) => self.replay().await?,
+					Err(RecvError::Closed) => return Ok(()),
+				}
+			}
+		}
+		.await;
+
+		match res {
+			Ok(()) | Err(SseEnd::ClientGone) => {
+				debug!("{:<12} - sse session closed", "SSE")
+			}
+			Err(SseEnd::Model(ex)) => {
+				debug!("{:<12} - sse session fail - {ex:?}", "SSE")
+			}
+		}
+	}
+
+	/// Stream the persisted events after `last_seq`.
+	async fn replay(&mut self) -> core::result::Result<(), SseEnd> {
+		loop {
+			let events = ChangeEventBmc::list_after(
+				&self.ctx,
+				&self.mm,
+				self.last_seq,
+				REPLAY_PAGE_SIZE,
+			)
+			.await?;
+			let caught_up = (events.len() as i64) < REPLAY_PAGE_SIZE;
+
+			for event in events.iter() {
+				self.send_event(event).await?;
+			}
+			if caught_up {
+				return Ok(());
+			}
+		}
+	}
+
+	/// Stream the event if in the channels, visible to the ctx user,
+	/// and not already streamed.
+	async fn send_event(
+		&mut self,
+		event: &ChangeEvent,
+	) -> core::result::Result<(), SseEnd> {
+		if event.seq <= self.last_seq {
+			return Ok(());
+		}
+		self.last_seq = event.seq;
+
+		let in_channels = self.channels.as_ref().map_or(true, |channels| {
+			event
+				.channels()
+				.iter()
+				.any(|channel| channels.contains(channel))
+		});
+		if !in_channels || !event.is_visible_to(&self.ctx) {
+			return Ok(());
+		}
+
+		let data = serde_json::to_string(event).map_err(model::Error::from)?;
+		let sse_event = Event::default().id(event.seq.to_string()).data(data);
+
+		self.tx
+			.send(Ok(sse_event))
+			.await
+			.map_err(|_| SseEnd::ClientGone)
+	}
+}
+
+// endregion: --- SseSession
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::_dev_utils;
+	use crate::model::task::{TaskBmc, TaskForCreate};
+	use crate::model::user::{UserBmc, UserForAuth};
+	use crate::token::generate_web_token;
+	use crate::web::routes_all;
+	use anyhow::{Context, Result};
+	use axum::body::{Body, HttpBody};
+	use axum::http::header::{AUTHORIZATION, CONTENT_TYPE};
+	use axum::http::{Request, StatusCode};
+	use axum::response::Response;
+	use serde_json::Value;
+	use serial_test::serial;
+	use std::time::Duration;
+	use tower::ServiceExt;
+
+	/// GET the `uri` as the demo user, with the `Last-Event-ID` if any.
+	async fn fx_get_events(
+		mm: &ModelManager,
+		uri: &str,
+		last_event_id: Option<&str>,
+	) -> Result<Response> {
+		let user: UserForAuth =
+			UserBmc::first_by_username(&Ctx::root_ctx(), mm, "demo1")
+				.await?
+				.context("Should have demo1")?;
+		let token = generate_web_token(&user.username, user.token_salt)?;
+
+		let mut req =
+			Request::get(uri).header(AUTHORIZATION, format!("Bearer {token}"));
+		if let Some(last_event_id) = last_event_id {
+			req = req.header(LAST_EVENT_ID, last_event_id);
+		}
+		let res = routes_all(mm.clone())
+			.oneshot(req.body(Body::empty())?)
+			.await?;
+
+		Ok(res)
+	}
+
+	/// The next SSE event of the stream (id and json data), failing after 5s.
+	async fn fx_next_event(
+		body: &mut axum::body::BoxBody,
+		buf: &mut String,
+	) -> Result<(i64, Value)> {
+		loop {
+			if let Some(end) = buf.find("\n\n") {
+				let block: String = buf.drain(..end + 2).collect();
+				let mut id = None;
+				let mut data = None;
+				for line in block.lines() {
+					if let Some(val) = line.strip_prefix("id:") {
+						id = Some(val.trim().parse()?);
+					} else if let Some(val) = line.strip_prefix("data:") {
+						data = Some(serde_json::from_str(val)?);
+					}
+				}
+				// Skip the keep-alive comments.
+				if let (Some(id), Some(data)) = (id, data) {
+					return Ok((id, data));
+				}
+				continue;
+			}
+
+			let chunk = tokio::time::timeout(Duration::from_secs(5), body.data())
+				.await
+				.context("No sse event in time")?
+				.context("Sse stream ended")??;
+			buf.push_str(std::str::from_utf8(&chunk)?);
+		}
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_sse_events_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_task_c = |title: &str| TaskForCreate {
+			title: title.to_string(),
+			..Default::default()
+		};
+
+		// -- Exec
+		let res = fx_get_events(&mm, "/api/events?channels=task", None).await?;
+		assert_eq!(res.status(), StatusCode::OK);
+		assert_eq!(res.headers()[CONTENT_TYPE], "text/event-stream");
+		let mut body = res.into_body();
+		let mut buf = String::new();
+
+		let id = TaskBmc::create(&ctx, &mm, fx_task_c("test_sse_events_ok")).await?;
+		TaskBmc::delete(&ctx, &mm, id).await?;
+
+		// -- Check
+		let (seq, created) = fx_next_event(&mut body, &mut buf).await?;
+		assert_eq!(created["seq"], seq);
+		assert_eq!(
+			(&created["id"], &created["kind"]),
+			(&id.into(), &"created".into())
+		);
+		let (seq_2, deleted) = fx_next_event(&mut body, &mut buf).await?;
+		assert_eq!(seq_2, seq + 1);
+		assert_eq!(deleted["kind"], "deleted");
+
+		// -- Clean
+		TaskBmc::purge(&ctx, &mm, id).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_sse_resume_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_task_c = TaskForCreate {
+			title: "test_sse_resume_ok".to_string(),
+			..Default::default()
+		};
+		// Changed while disconnected.
+		let seq_before = mm.events().last_seq();
+		let id = TaskBmc::create(&ctx, &mm, fx_task_c).await?;
+		TaskBmc::delete(&ctx, &mm, id).await?;
+		let created_seq = ChangeEventBmc::list_after(&ctx, &mm, seq_before, 100)
+			.await?
+			.iter()
+			.find(|event| event.id == id)
+			.map(|event| event.seq)
+			.context("Should have the created event")?;
+
+		// -- Exec
+		let last_event_id = (created_seq - 1).to_string();
+		let res =
+			fx_get_events(&mm, "/api/events?channels=task", Some(&last_event_id))
+				.await?;
+		let mut body = res.into_body();
+		let mut buf = String::new();
+
+		// -- Check
+		let (seq, created) = fx_next_event(&mut body, &mut buf).await?;
+		assert_eq!((seq, &created["kind"]), (created_seq, &"created".into()));
+		let (seq, deleted) = fx_next_event(&mut body, &mut buf).await?;
+		assert_eq!(
+			(seq, &deleted["kind"]),
+			(created_seq + 1, &"deleted".into())
+		);
+
+		// -- Clean
+		drop(body);
+		TaskBmc::purge(&ctx, &mm, id).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_sse_err_invalid_input() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+
+		// -- Exec & Check
+		let res = fx_get_events(&mm, "/api/events?channels=user", None).await?;
+		assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+		let body = hyper::body::to_bytes(res.into_body()).await?;
+		let body: Value = serde_json::from_slice(&body)?;
+		assert_eq!(body["error"]["message"], "INVALID_INPUT");
+
+		let res = fx_get_events(&mm, "/api/events", Some("abc")).await?;
+		assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
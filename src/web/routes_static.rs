@@ -1,7 +1,9 @@
 use crate::config;
+use crate::web::openapi::PathItems;
 use axum::handler::HandlerWithoutStateExt;
 use axum::http::StatusCode;
 use axum::routing::{any_service, MethodRouter};
+use serde_json::json;
 use tower_http::services::ServeDir;
 
 // Note: Here we can just return a MethodRouter rather than a full Router
@@ -16,3 +18,31 @@ pub fn serve_dir() -> MethodRouter {
 			.not_found_service(handle_404.into_service()),
 	)
 }
+
+/// The web folder files, served for all the paths not matching a route.
+pub(in crate::web) fn openapi_paths() -> PathItems {
+	let mut paths = PathItems::new();
+	paths.insert(
+		"/{file}".to_string(),
+		json!({
+			"get": {
+				"summary": "A file of the web folder (e.g., `/index.html`).",
+				"parameters": [{
+					"name": "file",
+					"in": "path",
+					"required": true,
+					"schema": {"type": "string"},
+				}],
+				"responses": {
+					"200": {"description": "The file."},
+					"404": {
+						"description": "No such file.",
+						"content": {"text/plain": {"schema": {"type": "string"}}},
+					},
+				},
+			},
+		}),
+	);
+
+	paths
+}
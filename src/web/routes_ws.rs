@@ -0,0 +1,483 @@
+//! The `/api/ws` WebSocket of the change events (see `model::event`), so that the
+//! clients see the changes of the other users without polling.
+//!
+//! The messages are json texts tagged by their `type`:
+//!
+//! - Client: `{"type": "subscribe", "channels": ["task", "comment:1000"], "after_seq": 42}`
+//!   (`after_seq`, optional, to resume after the last event received before a reconnect),
+//!   `{"type": "unsubscribe", "channels": [..]}` and `{"type": "ping"}`.
+//! - Server: `{"type": "event", "seq": 43, "entity": "task", "id": 1000, "kind": "updated", ..}`,
+//!   `{"type": "subscribed", "channels": [..], "seq": 43}` (the last event seq),
+//!   `{"type": "resync", "seq": ..}` when events were missed (the client must reload its data),
+//!   `{"type": "pong"}` and `{"type": "error", "message": ..}`.
+//!
+//! The channels are `<entity>` or `<entity>:<id>` (e.g., `task` or `task:1000`),
+//! and only the events visible to the ctx user are sent.
+//!
+//! Heartbeat: The server pings every `HEARTBEAT_INTERVAL`, and closes the
+//! connections silent (no message nor pong) for `CLIENT_TIMEOUT`.
+//!
+
+use crate::ctx::Ctx;
+use crate::model::event::{ChangeEvent, EventHub};
+use crate::model::ModelManager;
+use crate::web::openapi::{auth_security, rpc_error_response, PathItems};
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::State;
+use axum::response::Response;
+use axum::routing::get;
+use axum::Router;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeSet;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::time::Instant;
+use tracing::debug;
+
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(75);
+
+pub fn routes(mm: ModelManager) -> Router {
+	Router::new()
+		.route("/api/ws", get(ws_handler))
+		.with_state(mm)
+}
+
+async fn ws_handler(
+	State(mm): State<ModelManager>,
+	ctx: Ctx,
+	ws: WebSocketUpgrade,
+) -> Response {
+	debug!("{:<12} - ws_handler", "HANDLER");
+
+	let events = mm.events().clone();
+	ws.on_upgrade(move |socket| ws_session(socket, ctx, events))
+}
+
+pub(in crate::web) fn openapi_paths() -> PathItems {
+	let mut paths = PathItems::new();
+	paths.insert(
+		"/api/ws".to_string(),
+		json!({
+			"get": {
+				"summary": "WebSocket of the change events (subscribe to the `task`, \
+							`task:<id>`, `comment`, or `comment:<id>` channels).",
+				"security": auth_security(),
+				"responses": {
+					"101": {"description": "Switching to the WebSocket protocol."},
+					"403": rpc_error_response(),
+					"default": rpc_error_response(),
+				},
+			},
+		}),
+	);
+
+	paths
+}
+
+// region:    --- Messages
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum WsClientMsg {
+	Subscribe {
+		channels: Vec<String>,
+		after_seq: Option<i64>,
+	},
+	Unsubscribe {
+		channels: Vec<String>,
+	},
+	Ping,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum WsServerMsg<'a> {
+	Event(&'a ChangeEvent),
+	Subscribed {
+		channels: &'a BTreeSet<String>,
+		seq: i64,
+	},
+	Unsubscribed {
+		channels: &'a BTreeSet<String>,
+	},
+	Resync {
+		seq: i64,
+	},
+	Pong,
+	Error {
+		message: String,
+	},
+}
+
+// endregion: --- Messages
+
+// region:    --- WsSession
+
+async fn ws_session(mut socket: WebSocket, ctx: Ctx, events: EventHub) {
+	debug!("{:<12} - ws_session - {ctx:?}", "WS");
+
+	// Subscribed first, so no event is missed from `last_seq`.
+	let mut events_rx = events.subscribe();
+	let mut session = WsSession {
+		ctx,
+		channels: BTreeSet::new(),
+		last_seq: events.last_seq(),
+		events,
+	};
+	let mut heartbeat = tokio::time::interval(HEARTBEAT_INTERVAL);
+	let mut last_seen = Instant::now();
+
+	loop {
+		let res = tokio::select! {
+			msg = socket.recv() => match msg {
+				Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+				Some(Ok(msg)) => {
+					last_seen = Instant::now();
+					session.on_client_msg(&mut socket, msg).await
+				}
+			},
+			event = events_rx.recv() => match event {
+				Ok(event) => session.send_event(&mut socket, &event).await,
+				// Missed events, resent from the recent ones (if still there).
+				Err(RecvError::Lagged(_)) => {
+					let after_seq = session.last_seq;
+					session.resume(&mut socket, after_seq).await
+				}
+				Err(RecvError::Closed) => break,
+			},
+			_ = heartbeat.tick() => {
+				if last_seen.elapsed() > CLIENT_TIMEOUT {
+					debug!("{:<12} - ws_session - client timeout", "WS");
+					break;
+				}
+				socket.send(Message::Ping(Vec::new())).await
+			}
+		};
+
+		if res.is_err() {
+			break;
+		}
+	}
+
+	debug!("{:<12} - ws_session closed", "WS");
+}
+
+struct WsSession {
+	ctx: Ctx,
+	events: EventHub,
+	channels: BTreeSet<String>,
+	/// The seq of the last event sent (or skipped), the live events up to it
+	/// being skipped (e.g., when already sent by a resume).
+	last_seq: i64,
+}
+
+impl WsSession {
+	async fn on_client_msg(
+		&mut self,
+		socket: &mut WebSocket,
+		msg: Message,
+	) -> Result<(), axum::Error> {
+		let msg = match msg {
+			Message::Text(text) => serde_json::from_str::<WsClientMsg>(&text),
+			// The pongs (and the pings, answered by axum) only keep the session alive.
+			Message::Ping(_) | Message::Pong(_) => return Ok(()),
+			Message::Binary(_) | Message::Close(_) => {
+				return send(socket, &error_msg("Only json text messages")).await;
+			}
+		};
+
+		match msg {
+			Err(ex) => {
+				send(socket, &error_msg(format!("Invalid message - {ex}"))).await
+			}
+
+			Ok(WsClientMsg::Subscribe {
+				channels,
+				after_seq,
+			}) => {
+				if let Some(channel) =
+					channels.iter().find(|c| !ChangeEvent::is_valid_channel(c))
+				{
+					let message = format!("Invalid channel '{channel}'");
+					return send(socket, &error_msg(message)).await;
+				}
+				self.channels.extend(channels);
+
+				if let Some(after_seq) = after_seq {
+					self.resume(socket, after_seq).await?;
+				}
+
+				let msg = WsServerMsg::Subscribed {
+					channels: &self.channels,
+					seq: self.last_seq,
+				};
+				send(socket, &msg).await
+			}
+
+			Ok(WsClientMsg::Unsubscribe { channels }) => {
+				for channel in channels {
+					self.channels.remove(&channel);
+				}
+				let msg = WsServerMsg::Unsubscribed {
+					channels: &self.channels,
+				};
+				send(socket, &msg).await
+			}
+
+			Ok(WsClientMsg::Ping) => send(socket, &WsServerMsg::Pong).await,
+		}
+	}
+
+	/// Send the event if subscribed to, visible to the ctx user,
+	/// and not already sent.
+	async fn send_event(
+		&mut self,
+		socket: &mut WebSocket,
+		event: &ChangeEvent,
+	) -> Result<(), axum::Error> {
+		if event.seq <= self.last_seq {
+			return Ok(());
+		}
+		self.last_seq = event.seq;
+
+		let subscribed = event
+			.channels()
+			.iter()
+			.any(|channel| self.channels.contains(channel));
+		if !subscribed || !event.is_visible_to(&self.ctx) {
+			return Ok(());
+		}
+
+		send(socket, &WsServerMsg::Event(event)).await
+	}
+
+	/// Send the events after `after_seq`, or a `resync` when some of them are
+	/// not available anymore.
+	async fn resume(
+		&mut self,
+		socket: &mut WebSocket,
+		after_seq: i64,
+	) -> Result<(), axum::Error> {
+		match self.events.since(after_seq) {
+			Some(events) => {
+				self.last_seq = self.last_seq.min(after_seq);
+				for event in events.iter() {
+					self.send_event(socket, event).await?;
+				}
+				Ok(())
+			}
+			None => {
+				self.last_seq = self.events.last_seq();
+				send(socket, &WsServerMsg::Resync { seq: self.last_seq }).await
+			}
+		}
+	}
+}
+
+// endregion: --- WsSession
+
+fn error_msg(message: impl Into<String>) -> WsServerMsg<'static> {
+	WsServerMsg::Error {
+		message: message.into(),
+	}
+}
+
+async fn send(
+	socket: &mut WebSocket,
+	msg: &WsServerMsg<'_>,
+) -> Result<(), axum::Error> {
+	let text = serde_json::to_string(msg).map_err(axum::Error::new)?;
+
+	socket.send(Message::Text(text)).await
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::_dev_utils;
+	use crate::web::client_tests::{fx_logged_client, fx_serve_app};
+	use anyhow::{Context, Result};
+	use futures::{SinkExt, StreamExt};
+	use lib_rpc_client::TaskForCreate;
+	use serde_json::{json, Value};
+	use serial_test::serial;
+	use tokio::net::TcpStream;
+	use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+	use tokio_tungstenite::tungstenite::{self, http};
+	use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+	type FxWs = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+	/// Connect to `/api/ws` (with the bearer token if any).
+	async fn fx_connect(
+		base_url: &str,
+		token: Option<&str>,
+	) -> tungstenite::Result<FxWs> {
+		let ws_url = base_url.replacen("http", "ws", 1) + "/api/ws";
+		let mut req = ws_url.into_client_request()?;
+		if let Some(token) = token {
+			let value = format!("Bearer {token}").parse().expect("header value");
+			req.headers_mut().insert(http::header::AUTHORIZATION, value);
+		}
+
+		let (ws, _) = tokio_tungstenite::connect_async(req).await?;
+
+		Ok(ws)
+	}
+
+	async fn fx_send(ws: &mut FxWs, msg: Value) -> Result<()> {
+		ws.send(tungstenite::Message::Text(msg.to_string())).await?;
+
+		Ok(())
+	}
+
+	/// The next json message (skipping the pings), failing after 5s.
+	async fn fx_recv(ws: &mut FxWs) -> Result<Value> {
+		loop {
+			let msg = tokio::time::timeout(Duration::from_secs(5), ws.next())
+				.await
+				.context("No ws message in time")?
+				.context("Ws closed")??;
+			if let tungstenite::Message::Text(text) = msg {
+				return Ok(serde_json::from_str(&text)?);
+			}
+		}
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_ws_events_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		_dev_utils::init_test().await;
+		let base_url = fx_serve_app()?;
+		let client = fx_logged_client(&base_url).await?;
+		let mut ws = fx_connect(&base_url, Some(&client.auth_token()?)).await?;
+
+		// -- Exec & Check
+		fx_send(&mut ws, json!({"type": "subscribe", "channels": ["task"]})).await?;
+		let msg = fx_recv(&mut ws).await?;
+		assert_eq!(msg["type"], "subscribed");
+		assert_eq!(msg["channels"], json!(["task"]));
+
+		let task = client
+			.create_task(TaskForCreate::new("test_ws_events_ok"))
+			.await?;
+		let msg = fx_recv(&mut ws).await?;
+		assert_eq!(msg["type"], "event");
+		assert_eq!(msg["entity"], "task");
+		assert_eq!(msg["id"], task.id);
+		assert_eq!(msg["kind"], "created");
+
+		// Only the `task:<id>` channel, so no `task` channel event for the others.
+		let fx_channel = format!("task:{}", task.id);
+		fx_send(
+			&mut ws,
+			json!({"type": "unsubscribe", "channels": ["task"]}),
+		)
+		.await?;
+		assert_eq!(fx_recv(&mut ws).await?["type"], "unsubscribed");
+		fx_send(
+			&mut ws,
+			json!({"type": "subscribe", "channels": [fx_channel]}),
+		)
+		.await?;
+		assert_eq!(fx_recv(&mut ws).await?["type"], "subscribed");
+
+		let other_task = client
+			.create_task(TaskForCreate::new("test_ws_events_ok other"))
+			.await?;
+		client.delete_task(task.id).await?;
+		let msg = fx_recv(&mut ws).await?;
+		assert_eq!(
+			(&msg["id"], &msg["kind"]),
+			(&json!(task.id), &json!("deleted"))
+		);
+
+		// Errors, and app level ping.
+		fx_send(&mut ws, json!({"type": "subscribe", "channels": ["user"]})).await?;
+		assert_eq!(fx_recv(&mut ws).await?["type"], "error");
+		fx_send(&mut ws, json!({"type": "ping"})).await?;
+		assert_eq!(fx_recv(&mut ws).await?["type"], "pong");
+
+		// -- Clean
+		client.delete_task(other_task.id).await?;
+		client.purge_task(other_task.id).await?;
+		client.purge_task(task.id).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_ws_resume_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		_dev_utils::init_test().await;
+		let base_url = fx_serve_app()?;
+		let client = fx_logged_client(&base_url).await?;
+		let token = client.auth_token()?;
+		let mut ws = fx_connect(&base_url, Some(&token)).await?;
+		fx_send(&mut ws, json!({"type": "subscribe", "channels": ["task"]})).await?;
+		let last_seq = fx_recv(&mut ws).await?["seq"]
+			.as_i64()
+			.context("Should have a seq")?;
+		ws.close(None).await?;
+
+		// -- Exec
+		// Changed while disconnected.
+		let task = client
+			.create_task(TaskForCreate::new("test_ws_resume_ok"))
+			.await?;
+		let mut ws = fx_connect(&base_url, Some(&token)).await?;
+		let fx_subscribe = json!({"type": "subscribe", "channels": ["task"], "after_seq": last_seq});
+		fx_send(&mut ws, fx_subscribe).await?;
+
+		// -- Check
+		let msg = fx_recv(&mut ws).await?;
+		assert_eq!(msg["type"], "event");
+		assert_eq!(msg["seq"], last_seq + 1);
+		assert_eq!(
+			(&msg["id"], &msg["kind"]),
+			(&json!(task.id), &json!("created"))
+		);
+		let msg = fx_recv(&mut ws).await?;
+		assert_eq!(msg["type"], "subscribed");
+		assert_eq!(msg["seq"], last_seq + 1);
+
+		// A resume too far back (e.g., after a server restart) needs a resync.
+		let fx_subscribe =
+			json!({"type": "subscribe", "channels": ["task"], "after_seq": -10});
+		fx_send(&mut ws, fx_subscribe).await?;
+		assert_eq!(fx_recv(&mut ws).await?["type"], "resync");
+
+		// -- Clean
+		client.delete_task(task.id).await?;
+		client.purge_task(task.id).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_ws_err_no_auth() -> Result<()> {
+		// -- Setup & Fixtures
+		_dev_utils::init_test().await;
+		let base_url = fx_serve_app()?;
+
+		// -- Exec
+		let res = fx_connect(&base_url, None).await;
+
+		// -- Check
+		match res {
+			Err(tungstenite::Error::Http(res)) => {
+				assert_eq!(res.status(), http::StatusCode::FORBIDDEN)
+			}
+			other => panic!("Expected a 403, got: {other:?}"),
+		}
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
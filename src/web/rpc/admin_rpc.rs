@@ -0,0 +1,19 @@
+//! The admin rpc methods (the `ctx` user being an admin, see
+//! `UserBmc::require_admin`).
+
+use crate::ctx::Ctx;
+use crate::model::cron::{CronRun, CronRunBmc};
+use crate::model::ModelManager;
+use crate::web::rpc::router::rpc_router;
+use crate::web::rpc::RpcRouter;
+use crate::web::Result;
+
+pub fn rpc_router() -> RpcRouter {
+	rpc_router!(list_cron_runs)
+}
+
+pub async fn list_cron_runs(ctx: Ctx, mm: ModelManager) -> Result<Vec<CronRun>> {
+	let cron_runs = CronRunBmc::list(&ctx, &mm).await?;
+
+	Ok(cron_runs)
+}
@@ -0,0 +1,125 @@
+use crate::ctx::Ctx;
+use crate::model::comment::{
+	Comment, CommentBmc, CommentForCreate, CommentForUpdate,
+};
+use crate::model::{ListOptions, ListPage, ModelManager};
+use crate::web::rpc::router::rpc_router;
+use crate::web::rpc::{
+	IntoParams, ParamsForCreate, ParamsForUpdate, ParamsIded, RpcRouter,
+};
+use crate::web::Result;
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+pub fn rpc_router() -> RpcRouter {
+	rpc_router!(
+		create_comment,
+		list_comments,
+		list_comments_page,
+		update_comment,
+		delete_comment
+	)
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ParamsForListComments {
+	pub task_id: i64,
+	pub list_options: Option<ListOptions>,
+}
+
+impl IntoParams for ParamsForListComments {}
+
+pub async fn create_comment(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsForCreate<CommentForCreate>,
+) -> Result<Comment> {
+	let ParamsForCreate { data } = params;
+
+	let ctx = &ctx;
+	let comment = mm
+		.txn(|mm| {
+			let data = data.clone();
+			async move {
+				let id = CommentBmc::create(ctx, &mm, data).await?;
+				CommentBmc::get(ctx, &mm, id).await
+			}
+		})
+		.await?;
+
+	Ok(comment)
+}
+
+pub async fn list_comments(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsForListComments,
+) -> Result<Vec<Comment>> {
+	let ParamsForListComments {
+		task_id,
+		list_options,
+	} = params;
+
+	let comments =
+		CommentBmc::list_by_task(&ctx, &mm, task_id, list_options).await?;
+
+	Ok(comments)
+}
+
+/// A page of the comments of a task (see `ListOptions::cursor`), with its
+/// next cursor.
+pub async fn list_comments_page(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsForListComments,
+) -> Result<ListPage<Comment>> {
+	let ParamsForListComments {
+		task_id,
+		list_options,
+	} = params;
+
+	let page =
+		CommentBmc::list_page_by_task(&ctx, &mm, task_id, list_options).await?;
+
+	Ok(page)
+}
+
+pub async fn update_comment(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsForUpdate<CommentForUpdate>,
+) -> Result<Comment> {
+	let ParamsForUpdate { id, data, .. } = params;
+
+	let ctx = &ctx;
+	let comment = mm
+		.txn(|mm| {
+			let data = data.clone();
+			async move {
+				CommentBmc::update(ctx, &mm, id, data).await?;
+				CommentBmc::get(ctx, &mm, id).await
+			}
+		})
+		.await?;
+
+	Ok(comment)
+}
+
+pub async fn delete_comment(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsIded,
+) -> Result<Comment> {
+	let ParamsIded { id } = params;
+
+	let ctx = &ctx;
+	let comment = mm
+		.txn(|mm| async move {
+			let comment = CommentBmc::get(ctx, &mm, id).await?;
+			CommentBmc::delete(ctx, &mm, id).await?;
+			Ok(comment)
+		})
+		.await?;
+
+	Ok(comment)
+}
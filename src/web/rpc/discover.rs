@@ -0,0 +1,215 @@
+//! The `rpc.discover` method, returning the OpenRPC document (https://spec.open-rpc.org)
+//! of all the rpc methods, generated from the `RpcRouter` registered functions.
+//!
+//! The params are by-name, so each member of the params object (e.g., `id` and `data`
+//! of `ParamsForUpdate<TaskForUpdate>`) is a content descriptor of the method.
+//! The params which are not a plain object (e.g., the untagged `ParamsForUpdateMany`)
+//! are described as one `params` descriptor.
+//!
+//! The named types (e.g., `Task`, `TaskForCreate`) are in the `components.schemas`.
+//!
+//! Note: The document is also committed as `OPENRPC_DOC_PATH`, kept in sync by the
+//!       `test_openrpc_doc_in_sync` test (`UPDATE_OPENRPC=1 cargo test` to update it).
+//!
+
+use crate::web::rpc::router::{Resource, RpcMethodSchema};
+use crate::web::rpc::RpcRouter;
+use crate::web::Result;
+use schemars::generate::SchemaSettings;
+use schemars::{Schema, SchemaGenerator};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+pub const RPC_DISCOVER: &str = "rpc.discover";
+
+const OPENRPC_VERSION: &str = "1.3.2";
+
+/// Where the OpenRPC document is committed (relative to the crate root).
+#[cfg(test)]
+const OPENRPC_DOC_PATH: &str = "docs/openrpc.json";
+
+/// The OpenRPC document, generated once (as the methods do not change).
+#[derive(Clone)]
+struct OpenRpcDoc(Arc<Value>);
+
+/// Add the `rpc.discover` method, describing all the `rpc_router` methods
+/// (including itself).
+pub fn with_rpc_discover(rpc_router: RpcRouter) -> RpcRouter {
+	let rpc_router = rpc_router.add(RPC_DISCOVER, rpc_discover);
+	let doc = openrpc_doc(&rpc_router);
+
+	rpc_router.with_resource(OpenRpcDoc(Arc::new(doc)))
+}
+
+async fn rpc_discover(doc: Resource<OpenRpcDoc>) -> Result<Value> {
+	let Resource(OpenRpcDoc(doc)) = doc;
+
+	Ok(doc.as_ref().clone())
+}
+
+/// Returns the OpenRPC document of the `rpc_router` methods.
+pub fn openrpc_doc(rpc_router: &RpcRouter) -> Value {
+	let mut gen = SchemaSettings::draft07()
+		.with(|settings| settings.definitions_path = "/components/schemas".into())
+		.into_generator();
+
+	let methods: Vec<Value> = rpc_router
+		.method_schemas(&mut gen)
+		.into_iter()
+		.map(|method| method_doc(&mut gen, method))
+		.collect();
+
+	json!({
+		"openrpc": OPENRPC_VERSION,
+		"info": {
+			"title": env!("CARGO_PKG_NAME"),
+			"version": env!("CARGO_PKG_VERSION"),
+		},
+		"methods": methods,
+		"components": {
+			"schemas": gen.take_definitions(true),
+		},
+	})
+}
+
+fn method_doc(gen: &mut SchemaGenerator, method: RpcMethodSchema) -> Value {
+	let RpcMethodSchema {
+		name,
+		params,
+		result,
+	} = method;
+
+	let params = params
+		.map(|params| params_descriptors(transformed(gen, params)))
+		.unwrap_or_default();
+
+	json!({
+		"name": name,
+		"paramStructure": "by-name",
+		"params": params,
+		"result": {
+			"name": "result",
+			"schema": transformed(gen, result),
+		},
+	})
+}
+
+/// The content descriptors of the params object members.
+fn params_descriptors(params: Schema) -> Vec<Value> {
+	let Some(properties) = params.get("properties").and_then(Value::as_object)
+	else {
+		return vec![json!({
+			"name": "params",
+			"required": true,
+			"schema": params,
+		})];
+	};
+
+	let required: Vec<&str> = params
+		.get("required")
+		.and_then(Value::as_array)
+		.map(|names| names.iter().filter_map(Value::as_str).collect())
+		.unwrap_or_default();
+
+	properties
+		.iter()
+		.map(|(name, schema)| {
+			let mut descriptor = json!({
+				"name": name,
+				"required": required.contains(&name.as_str()),
+				"schema": schema,
+			});
+			if let Some(description) = schema.get("description") {
+				descriptor["description"] = description.clone();
+			}
+			descriptor
+		})
+		.collect()
+}
+
+/// Apply the generator transforms (e.g., the draft 07 ones), as done for its
+/// definitions, to a schema obtained outside of `gen.root_schema_for`.
+fn transformed(gen: &mut SchemaGenerator, mut schema: Schema) -> Schema {
+	for transform in gen.transforms_mut() {
+		transform.transform(&mut schema);
+	}
+
+	schema
+}
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::_dev_utils;
+	use crate::ctx::Ctx;
+	use crate::web::rpc::rpc_router;
+	use anyhow::Result;
+	use serial_test::serial;
+	use std::path::Path;
+
+	#[test]
+	fn test_openrpc_doc_in_sync() -> Result<()> {
+		// -- Setup & Fixtures
+		let doc_path = Path::new(env!("CARGO_MANIFEST_DIR")).join(OPENRPC_DOC_PATH);
+
+		// -- Exec
+		let doc = serde_json::to_string_pretty(&openrpc_doc(&rpc_router()))? + "\n";
+		if std::env::var("UPDATE_OPENRPC").is_ok() {
+			std::fs::write(&doc_path, &doc)?;
+		}
+
+		// -- Check
+		let committed_doc = std::fs::read_to_string(&doc_path)?;
+		assert!(
+			committed_doc == doc,
+			"{OPENRPC_DOC_PATH} is out of sync with the rpc methods \
+			 (run `UPDATE_OPENRPC=1 cargo test` to update it)"
+		);
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_rpc_discover_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let rpc_router = rpc_router();
+
+		// -- Exec
+		let doc = rpc_router.call(RPC_DISCOVER, ctx, mm, None).await?;
+
+		// -- Check
+		let method_names: Vec<&str> = doc["methods"]
+			.as_array()
+			.unwrap()
+			.iter()
+			.filter_map(|method| method["name"].as_str())
+			.collect();
+		assert_eq!(method_names, rpc_router.method_names());
+
+		let update_task = doc["methods"]
+			.as_array()
+			.unwrap()
+			.iter()
+			.find(|method| method["name"] == "update_task")
+			.unwrap();
+		let param_names: Vec<&str> = update_task["params"]
+			.as_array()
+			.unwrap()
+			.iter()
+			.filter_map(|param| param["name"].as_str())
+			.collect();
+		assert_eq!(param_names, &["data", "expected_version", "id"]);
+		assert_eq!(
+			update_task["result"]["schema"]["$ref"],
+			"#/components/schemas/Task"
+		);
+		assert!(doc["components"]["schemas"]["TaskForUpdate"].is_object());
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
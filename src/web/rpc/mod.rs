@@ -1,34 +1,92 @@
 // region:    --- Modules
 
+mod admin_rpc;
+mod comment_rpc;
+mod discover;
+mod notification_rpc;
 mod params;
-mod task_rpc;
+mod router;
+mod saved_view_rpc;
+mod search_rpc;
+pub(in crate::web) mod task_rpc;
+mod ts_client;
+mod webhook_rpc;
 
-use params::*;
+pub use params::*;
+pub use router::{IntoParams, RpcRouter};
+pub use ts_client::{export_ts_client, TS_CLIENT_PATH};
 
 use crate::ctx::Ctx;
+use crate::model;
 use crate::model::ModelManager;
-use crate::web::rpc::task_rpc::{create_task, delete_task, list_tasks, update_task};
-use crate::web::{Error, Result};
+use crate::web::openapi::{
+	auth_security, json_content, rpc_error_response, PathItems,
+};
+use crate::web::{ClientError, Error, Result};
+use axum::body::Bytes;
 use axum::extract::State;
+use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
 use axum::routing::post;
 use axum::{Json, Router};
-use serde::Deserialize;
-use serde_json::{from_value, json, to_value, Value};
+use futures::stream::{self, StreamExt};
+use schemars::{json_schema, JsonSchema, SchemaGenerator};
+use serde::{Deserialize, Serialize};
+use serde_json::{from_slice, from_value, json, to_value, Value};
+use std::sync::Arc;
 use tracing::debug;
+use uuid::Uuid;
 
 // endregion: --- Modules
 
 // region:    --- RPC Types
 
-/// The raw JSON-RPC request object, serving as the foundation for RPC routing.
-#[derive(Deserialize)]
+const JSONRPC_VERSION: &str = "2.0";
+
+/// Max entries of a batch request (rejected as an invalid request above).
+const RPC_BATCH_MAX_ENTRIES: usize = 100;
+/// Max batch entries executed at a time (the db pool being small).
+const RPC_BATCH_CONCURRENCY: usize = 4;
+
+/// The JSON-RPC request object, serving as the foundation for RPC routing.
 struct RpcRequest {
+	/// None for a notification (i.e., no `id` member), which gets no response.
 	id: Option<Value>,
 	method: String,
 	params: Option<Value>,
 }
 
+/// The raw JSON-RPC request object (the `id` being taken from the json value,
+/// to tell a notification from a `null` id).
+///
+/// Note: `jsonrpc` is optional for the clients predating the JSON-RPC 2.0 support,
+///       but must be "2.0" when present.
+#[derive(Deserialize)]
+struct RpcRequestRaw {
+	jsonrpc: Option<String>,
+	method: String,
+	params: Option<Value>,
+}
+
+impl TryFrom<Value> for RpcRequest {
+	type Error = Error;
+
+	fn try_from(value: Value) -> Result<Self> {
+		let id = value.get("id").cloned();
+		let RpcRequestRaw {
+			jsonrpc,
+			method,
+			params,
+		} = from_value(value).map_err(|_| Error::RpcInvalidRequest)?;
+
+		if jsonrpc.is_some_and(|v| v != JSONRPC_VERSION) {
+			return Err(Error::RpcInvalidRequest);
+		}
+
+		Ok(RpcRequest { id, method, params })
+	}
+}
+
 /// RPC basic information containing the rpc request
 /// id and method for additional logging purposes.
 #[derive(Debug)]
@@ -37,19 +95,89 @@ pub struct RpcInfo {
 	pub method: String,
 }
 
+/// The per item result of the bulk RPC methods (e.g., `create_tasks`),
+/// as `{"ok": ..}` or `{"error": {"message": .., "detail": ..}}`.
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkItemResult<T> {
+	Ok(T),
+	Error(ClientError),
+}
+
+impl<T> From<model::Result<T>> for BulkItemResult<T> {
+	fn from(res: model::Result<T>) -> Self {
+		match res {
+			Ok(val) => BulkItemResult::Ok(val),
+			Err(ex) => {
+				let (_, client_error) = Error::Model(ex).client_status_and_error();
+				BulkItemResult::Error(client_error)
+			}
+		}
+	}
+}
+
 // endregion: --- RPC Types
 
+/// The router of all the RPC methods (each rpc module owning its methods),
+/// plus `rpc.discover` describing them.
+pub fn rpc_router() -> RpcRouter {
+	let rpc_router = RpcRouter::new()
+		.extend(task_rpc::rpc_router())
+		.extend(comment_rpc::rpc_router())
+		.extend(webhook_rpc::rpc_router())
+		.extend(notification_rpc::rpc_router())
+		.extend(search_rpc::rpc_router())
+		.extend(saved_view_rpc::rpc_router())
+		.extend(admin_rpc::rpc_router());
+
+	discover::with_rpc_discover(rpc_router)
+}
+
+#[derive(Clone)]
+struct RpcState {
+	mm: ModelManager,
+	rpc_router: Arc<RpcRouter>,
+}
+
 pub fn routes(mm: ModelManager) -> Router {
+	let rpc_state = RpcState {
+		mm,
+		rpc_router: Arc::new(rpc_router()),
+	};
+
 	Router::new()
 		.route("/rpc", post(rpc_handler))
-		.with_state(mm)
+		.with_state(rpc_state)
 }
 
+/// Handles a single JSON-RPC request object or a batch (array) of them.
 async fn rpc_handler(
-	State(mm): State<ModelManager>,
+	State(rpc_state): State<RpcState>,
+	ctx: Ctx,
+	body: Bytes,
+) -> Response {
+	let Ok(rpc_body) = from_slice::<Value>(&body) else {
+		return Error::RpcParseFail.into_response();
+	};
+
+	match rpc_body {
+		Value::Array(rpc_entries) => {
+			rpc_batch_handler(ctx, rpc_state, rpc_entries).await
+		}
+		rpc_entry => rpc_single_handler(ctx, rpc_state, rpc_entry).await,
+	}
+}
+
+async fn rpc_single_handler(
 	ctx: Ctx,
-	Json(rpc_req): Json<RpcRequest>,
+	rpc_state: RpcState,
+	rpc_entry: Value,
 ) -> Response {
+	let rpc_req = match RpcRequest::try_from(rpc_entry) {
+		Ok(rpc_req) => rpc_req,
+		Err(ex) => return ex.into_response(),
+	};
+
 	// -- Create the RPC Info to be set to the response.extensions.
 	let rpc_info = RpcInfo {
 		id: rpc_req.id.clone(),
@@ -57,59 +185,324 @@ async fn rpc_handler(
 	};
 
 	// -- Exec & Store RpcInfo in response.
-	let mut res = _rpc_handler(ctx, mm, rpc_req).await.into_response();
+	let mut res = match (
+		rpc_req.id.clone(),
+		_rpc_handler(ctx, rpc_state, rpc_req).await,
+	) {
+		// -- Notification, no response (even for an error).
+		(None, Ok(_)) => StatusCode::NO_CONTENT.into_response(),
+		(None, Err(ex)) => {
+			debug!("{:<12} - rpc notification error - {ex:?}", "HANDLER");
+			StatusCode::NO_CONTENT.into_response()
+		}
+
+		(Some(rpc_id), Ok(result)) => Json(json!({
+			"jsonrpc": JSONRPC_VERSION,
+			"id": rpc_id,
+			"result": result
+		}))
+		.into_response(),
+		(Some(_), Err(ex)) => ex.into_response(),
+	};
 	res.extensions_mut().insert(rpc_info);
 
 	res
 }
 
-macro_rules! exec_rpc_fn {
-	// With Params
-	($rpc_fn:expr, $ctx:expr, $mm:expr, $rpc_params:expr) => {{
-		let rpc_fn_name = stringify!($rpc_fn);
-		let params = $rpc_params.ok_or(Error::RpcMissingParams {
-			rpc_method: rpc_fn_name.to_string(),
-		})?;
-		let params = from_value(params).map_err(|_| Error::RpcFailJsonParams {
-			rpc_method: rpc_fn_name.to_string(),
-		})?;
-		$rpc_fn($ctx, $mm, params).await.map(to_value)??
-	}};
-
-	// Without Params
-	($rpc_fn:expr, $ctx:expr, $mm:expr) => {
-		$rpc_fn($ctx, $mm).await.map(to_value)??
+/// Executes the batch entries concurrently (`RPC_BATCH_CONCURRENCY` at a time),
+/// and responds with the entries responses in the request order (none for the
+/// notifications).
+async fn rpc_batch_handler(
+	ctx: Ctx,
+	rpc_state: RpcState,
+	rpc_entries: Vec<Value>,
+) -> Response {
+	if rpc_entries.is_empty() || rpc_entries.len() > RPC_BATCH_MAX_ENTRIES {
+		return Error::RpcInvalidRequest.into_response();
+	}
+
+	let rpc_responses: Vec<Option<Value>> =
+		stream::iter(rpc_entries.into_iter().map(|rpc_entry| {
+			rpc_batch_entry(ctx.clone(), rpc_state.clone(), rpc_entry)
+		}))
+		.buffered(RPC_BATCH_CONCURRENCY)
+		.collect()
+		.await;
+	let rpc_responses: Vec<Value> = rpc_responses.into_iter().flatten().collect();
+
+	if rpc_responses.is_empty() {
+		StatusCode::NO_CONTENT.into_response()
+	} else {
+		Json(rpc_responses).into_response()
+	}
+}
+
+/// Returns the response of a batch entry (None for a notification).
+async fn rpc_batch_entry(
+	ctx: Ctx,
+	rpc_state: RpcState,
+	rpc_entry: Value,
+) -> Option<Value> {
+	let (rpc_id, res) = match RpcRequest::try_from(rpc_entry) {
+		// The id of an invalid request is `null` (even if it had one).
+		Err(ex) => (Some(Value::Null), Err(ex)),
+		Ok(rpc_req) => (
+			rpc_req.id.clone(),
+			_rpc_handler(ctx, rpc_state, rpc_req).await,
+		),
 	};
+
+	match (rpc_id, res) {
+		(None, Ok(_)) => None,
+		(None, Err(ex)) => {
+			debug!("{:<12} - rpc notification error - {ex:?}", "HANDLER");
+			None
+		}
+		(Some(rpc_id), Ok(result)) => Some(json!({
+			"jsonrpc": JSONRPC_VERSION,
+			"id": rpc_id,
+			"result": result
+		})),
+		(Some(rpc_id), Err(ex)) => {
+			let (_, client_error) = ex.client_status_and_error();
+			Some(rpc_error_body(rpc_id, &client_error, None))
+		}
+	}
+}
+
+/// The JSON-RPC 2.0 error response body for a `ClientError`
+/// (its variant name as `message`, and its detail in `data`).
+pub fn rpc_error_body(
+	rpc_id: Value,
+	client_error: &ClientError,
+	req_uuid: Option<Uuid>,
+) -> Value {
+	let client_error_value = to_value(client_error).ok();
+	let message = client_error_value.as_ref().and_then(|v| v.get("message"));
+	let detail = client_error_value.as_ref().and_then(|v| v.get("detail"));
+
+	json!({
+		"jsonrpc": JSONRPC_VERSION,
+		"id": rpc_id,
+		"error": {
+			"code": client_error.rpc_code(),
+			"message": message, // Variant name
+			"data": {
+				"req_uuid": req_uuid.map(|uuid| uuid.to_string()),
+				"detail": detail
+			},
+		}
+	})
 }
 
+/// Routes and executes the request, returning its `result` value.
 async fn _rpc_handler(
 	ctx: Ctx,
-	mm: ModelManager,
+	rpc_state: RpcState,
 	rpc_req: RpcRequest,
-) -> Result<Json<Value>> {
+) -> Result<Value> {
 	let RpcRequest {
-		id: rpc_id,
 		method: rpc_method,
 		params: rpc_params,
+		..
 	} = rpc_req;
+	let RpcState { mm, rpc_router } = rpc_state;
 
 	debug!("{:<12} - _rpc_handler - method: {rpc_method}", "HANDLER");
 
-	let result_json: Value = match rpc_method.as_str() {
-		// -- Task RPC methods.
-		"create_task" => exec_rpc_fn!(create_task, ctx, mm, rpc_params),
-		"list_tasks" => exec_rpc_fn!(list_tasks, ctx, mm),
-		"update_task" => exec_rpc_fn!(update_task, ctx, mm, rpc_params),
-		"delete_task" => exec_rpc_fn!(delete_task, ctx, mm, rpc_params),
-
-		// -- Fallback as Err.
-		_ => return Err(Error::RpcMethodUnknown(rpc_method)),
-	};
+	rpc_router.call(&rpc_method, ctx, mm, rpc_params).await
+}
 
-	let body_response = json!({
-		"id": rpc_id,
-		"result": result_json
+// region:    --- OpenAPI
+/// The `/api/rpc` route (its methods being described by `rpc.discover`).
+pub(in crate::web) fn openapi_paths(_gen: &mut SchemaGenerator) -> PathItems {
+	let rpc_request = json!({
+		"type": "object",
+		"properties": {
+			"jsonrpc": {"const": JSONRPC_VERSION},
+			"id": {"description": "Absent for a notification (no response)."},
+			"method": {"type": "string"},
+			"params": {"type": "object"},
+		},
+		"required": ["method"],
 	});
 
-	Ok(Json(body_response))
+	let mut paths = PathItems::new();
+	paths.insert(
+		"/api/rpc".to_string(),
+		json!({
+			"post": {
+				"summary": "JSON-RPC 2.0 request or batch.",
+				"description": "The rpc methods are described by the OpenRPC document \
+					returned by the `rpc.discover` method.",
+				"security": auth_security(),
+				"requestBody": {
+					"required": true,
+					"content": json_content(json_schema!({
+						"oneOf": [
+							rpc_request,
+							{"type": "array", "items": rpc_request, "minItems": 1},
+						],
+					})),
+				},
+				"responses": {
+					"200": {
+						"description": "The JSON-RPC response (or responses of a batch).",
+						"content": json_content(json_schema!({
+							"oneOf": [{"type": "object"}, {"type": "array"}],
+						})),
+					},
+					"204": {"description": "Only notifications (no responses)."},
+					"default": rpc_error_response(),
+				},
+			},
+		}),
+	);
+
+	paths
+}
+// endregion: --- OpenAPI
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::_dev_utils;
+	use anyhow::{Context, Result};
+	use serial_test::serial;
+
+	fn fx_rpc_state(mm: &ModelManager) -> RpcState {
+		RpcState {
+			mm: mm.clone(),
+			rpc_router: Arc::new(rpc_router()),
+		}
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_rpc_batch_entry_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_entry = json!({
+			"jsonrpc": "2.0",
+			"id": 1,
+			"method": "create_task",
+			"params": {"data": {"title": "test_rpc_batch_entry_ok"}}
+		});
+
+		// -- Exec
+		let res = rpc_batch_entry(ctx.clone(), fx_rpc_state(&mm), fx_entry)
+			.await
+			.context("Should have a response")?;
+
+		// -- Check
+		assert_eq!(res["jsonrpc"], "2.0");
+		assert_eq!(res["id"], 1);
+		assert_eq!(res["result"]["title"], "test_rpc_batch_entry_ok");
+
+		// -- Clean
+		let id = res["result"]["id"].as_i64().context("Should have an id")?;
+		crate::model::task::TaskBmc::delete(&ctx, &mm, id).await?;
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_rpc_batch_entry_err_codes() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_cases = [
+			(
+				json!({"id": 1, "method": "no_such_method"}),
+				json!(1),
+				-32601,
+			),
+			(json!({"id": 2, "params": {}}), Value::Null, -32600),
+			(
+				json!({"jsonrpc": "1.0", "id": 3, "method": "list_tasks"}),
+				Value::Null,
+				-32600,
+			),
+			(json!({"id": 4, "method": "update_task"}), json!(4), -32602),
+			(
+				json!({"id": 5, "method": "delete_task", "params": {"id": 100}}),
+				json!(5),
+				-32000,
+			),
+		];
+
+		for (entry, expected_id, expected_code) in fx_cases {
+			// -- Exec
+			let res = rpc_batch_entry(ctx.clone(), fx_rpc_state(&mm), entry)
+				.await
+				.context("Should have a response")?;
+
+			// -- Check
+			assert_eq!(res["id"], expected_id);
+			assert_eq!(res["error"]["code"], expected_code, "for {res}");
+		}
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_rpc_batch_handler_err_too_many() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let fx_entries = (0..=RPC_BATCH_MAX_ENTRIES)
+			.map(|id| json!({"jsonrpc": "2.0", "id": id, "method": "list_tasks"}))
+			.collect();
+
+		// -- Exec
+		let res = rpc_batch_handler(ctx, fx_rpc_state(&mm), fx_entries).await;
+
+		// -- Check
+		let error = res.extensions().get::<Error>();
+		assert!(
+			matches!(error, Some(Error::RpcInvalidRequest)),
+			"Should have been RpcInvalidRequest, was {error:?}"
+		);
+		let (_, client_error) = Error::RpcInvalidRequest.client_status_and_error();
+		assert_eq!(client_error.rpc_code(), -32600);
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_rpc_batch_entry_notification() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+
+		// -- Exec & Check
+		// No response, even for an error.
+		let fx_entries = [
+			json!({"jsonrpc": "2.0", "method": "list_tasks"}),
+			json!({"jsonrpc": "2.0", "method": "no_such_method"}),
+		];
+		for entry in fx_entries {
+			let res = rpc_batch_entry(ctx.clone(), fx_rpc_state(&mm), entry).await;
+			assert!(res.is_none(), "notification should have no response");
+		}
+
+		// A null id is not a notification.
+		let res = rpc_batch_entry(
+			ctx,
+			fx_rpc_state(&mm),
+			json!({"jsonrpc": "2.0", "id": null, "method": "list_tasks"}),
+		)
+		.await
+		.context("Should have a response")?;
+		assert_eq!(res["id"], Value::Null);
+		assert!(res["result"].is_array());
+
+		Ok(())
+	}
 }
+// endregion: --- Tests
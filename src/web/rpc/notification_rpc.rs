@@ -0,0 +1,127 @@
+use crate::ctx::Ctx;
+use crate::model::notification::{Notification, NotificationBmc, NotificationPref};
+use crate::model::{ListOptions, ListPage, ModelManager};
+use crate::web::rpc::router::rpc_router;
+use crate::web::rpc::{IntoParams, ParamsIded, RpcRouter};
+use crate::web::Result;
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+pub fn rpc_router() -> RpcRouter {
+	rpc_router!(
+		list_notifications,
+		list_notifications_page,
+		count_unread_notifications,
+		mark_notification_read,
+		mark_all_notifications_read,
+		list_notification_prefs,
+		set_notification_pref
+	)
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ParamsForListNotifications {
+	#[serde(default)]
+	pub unread_only: bool,
+	pub list_options: Option<ListOptions>,
+}
+
+impl IntoParams for ParamsForListNotifications {}
+
+impl IntoParams for NotificationPref {}
+
+/// The notifications of the user, newest first.
+pub async fn list_notifications(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsForListNotifications,
+) -> Result<Vec<Notification>> {
+	let ParamsForListNotifications {
+		unread_only,
+		list_options,
+	} = params;
+
+	let notifications =
+		NotificationBmc::list(&ctx, &mm, unread_only, list_options).await?;
+
+	Ok(notifications)
+}
+
+/// A page of the notifications of the user, newest first
+/// (see `ListOptions::cursor`), with its next cursor.
+pub async fn list_notifications_page(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsForListNotifications,
+) -> Result<ListPage<Notification>> {
+	let ParamsForListNotifications {
+		unread_only,
+		list_options,
+	} = params;
+
+	let page =
+		NotificationBmc::list_page(&ctx, &mm, unread_only, list_options).await?;
+
+	Ok(page)
+}
+
+pub async fn count_unread_notifications(ctx: Ctx, mm: ModelManager) -> Result<i64> {
+	let count = NotificationBmc::count_unread(&ctx, &mm).await?;
+
+	Ok(count)
+}
+
+pub async fn mark_notification_read(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsIded,
+) -> Result<Notification> {
+	let ParamsIded { id } = params;
+
+	let ctx = &ctx;
+	let notification = mm
+		.txn(|mm| async move {
+			NotificationBmc::mark_read(ctx, &mm, id).await?;
+			NotificationBmc::get(ctx, &mm, id).await
+		})
+		.await?;
+
+	Ok(notification)
+}
+
+/// Returns the number of the notifications marked as read.
+pub async fn mark_all_notifications_read(ctx: Ctx, mm: ModelManager) -> Result<u64> {
+	let count = NotificationBmc::mark_all_read(&ctx, &mm).await?;
+
+	Ok(count)
+}
+
+/// The notification preferences of the user (for all the kinds).
+pub async fn list_notification_prefs(
+	ctx: Ctx,
+	mm: ModelManager,
+) -> Result<Vec<NotificationPref>> {
+	let prefs = NotificationBmc::list_prefs(&ctx, &mm).await?;
+
+	Ok(prefs)
+}
+
+/// Enable or disable a kind of notifications, returning all the preferences.
+pub async fn set_notification_pref(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: NotificationPref,
+) -> Result<Vec<NotificationPref>> {
+	let ctx = &ctx;
+	let prefs = mm
+		.txn(|mm| {
+			let params = params.clone();
+			async move {
+				NotificationBmc::set_pref(ctx, &mm, params).await?;
+				NotificationBmc::list_prefs(ctx, &mm).await
+			}
+		})
+		.await?;
+
+	Ok(prefs)
+}
@@ -5,20 +5,61 @@
 //! each rpc handler function to receive the exact desired type.
 //!
 
+use crate::web::rpc::IntoParams;
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
 use serde::Deserialize;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub struct ParamsForCreate<D> {
 	pub data: D,
 }
 
-#[derive(Deserialize)]
+impl<D> IntoParams for ParamsForCreate<D> where
+	D: DeserializeOwned + JsonSchema + Send + 'static
+{
+}
+
+#[derive(Deserialize, JsonSchema)]
 pub struct ParamsForUpdate<D> {
 	pub id: i64,
 	pub data: D,
+	/// The entity version the client based its update on (optimistic concurrency).
+	/// Only checked for the versioned entities, and not checked when absent.
+	#[serde(default)]
+	pub expected_version: Option<i64>,
+}
+
+impl<D> IntoParams for ParamsForUpdate<D> where
+	D: DeserializeOwned + JsonSchema + Send + 'static
+{
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub struct ParamsIded {
 	pub id: i64,
 }
+
+impl IntoParams for ParamsIded {}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ParamsIdeds {
+	pub ids: Vec<i64>,
+}
+
+impl IntoParams for ParamsIdeds {}
+
+/// Params of the bulk updates, either the same `data` for all the `ids`
+/// (e.g., `{"ids": [1000, 1001], "data": {"done": true}}`),
+/// or per id updates (e.g., `{"items": [{"id": 1000, "data": {..}}, ..]}`).
+#[derive(Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum ParamsForUpdateMany<D> {
+	Same { ids: Vec<i64>, data: D },
+	PerId { items: Vec<ParamsForUpdate<D>> },
+}
+
+impl<D> IntoParams for ParamsForUpdateMany<D> where
+	D: DeserializeOwned + JsonSchema + Send + 'static
+{
+}
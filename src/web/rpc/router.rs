@@ -0,0 +1,457 @@
+//! The `RpcRouter` routes a JSON-RPC method name to its handler function.
+//!
+//! Handlers are plain async functions (e.g., `task_rpc::create_task`) whose arguments
+//! are extracted from the `RpcResources` (`FromResources`, e.g., `Ctx`, `ModelManager`,
+//! `Resource<T>`), and optionally ending with the typed params (`IntoParams`).
+//!
+//! Each rpc module exposes its own `rpc_router()`, which are then combined
+//! with `RpcRouter::extend` (see `rpc::rpc_router`).
+//!
+//! The params and results being `JsonSchema`, the router can also describe its
+//! methods (see `RpcRouter::method_schemas` and `rpc.discover`).
+//!
+
+use crate::ctx::Ctx;
+use crate::model::ModelManager;
+use crate::web::{Error, Result};
+use schemars::{JsonSchema, Schema, SchemaGenerator};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{from_value, to_value, Value};
+use std::any::{type_name, Any, TypeId};
+use std::collections::HashMap;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::Arc;
+
+type PinFutureValue = Pin<Box<dyn Future<Output = Result<Value>> + Send>>;
+
+type ResourceMap = HashMap<TypeId, Arc<dyn Any + Send + Sync>>;
+
+// region:    --- RpcRouter
+
+/// Build an `RpcRouter` from handler functions, named after the functions.
+/// (e.g., `rpc_router!(create_task, list_tasks)`)
+macro_rules! rpc_router {
+	($($rpc_fn:ident),+ $(,)?) => {
+		$crate::web::rpc::RpcRouter::new()
+			$(.add(stringify!($rpc_fn), $rpc_fn))+
+	};
+}
+pub(crate) use rpc_router;
+
+#[derive(Default)]
+pub struct RpcRouter {
+	routes: HashMap<&'static str, Box<dyn RpcHandlerDyn>>,
+	resources: Arc<ResourceMap>,
+}
+
+impl RpcRouter {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Register a handler function for the `name` rpc method.
+	pub fn add<H, T, P, R>(mut self, name: &'static str, handler: H) -> Self
+	where
+		H: RpcHandler<T, P, R>,
+		T: 'static,
+		P: 'static,
+		R: 'static,
+	{
+		let handler = RpcHandlerWrapper {
+			handler,
+			_marker: PhantomData,
+		};
+		self.routes.insert(name, Box::new(handler));
+		self
+	}
+
+	/// Add the methods (and resources) of another router.
+	pub fn extend(mut self, other: RpcRouter) -> Self {
+		self.routes.extend(other.routes);
+		for (type_id, resource) in other.resources.iter() {
+			Arc::make_mut(&mut self.resources).insert(*type_id, resource.clone());
+		}
+		self
+	}
+
+	/// Add a resource for the handlers, extracted with `Resource<T>`.
+	pub fn with_resource<T>(mut self, resource: T) -> Self
+	where
+		T: Clone + Send + Sync + 'static,
+	{
+		Arc::make_mut(&mut self.resources)
+			.insert(TypeId::of::<T>(), Arc::new(resource));
+		self
+	}
+
+	/// The registered method names (sorted).
+	pub fn method_names(&self) -> Vec<&'static str> {
+		let mut names: Vec<&'static str> = self.routes.keys().copied().collect();
+		names.sort();
+		names
+	}
+
+	/// The params and result schemas of the registered methods (sorted by name).
+	/// (the named types being referenced in the `gen` definitions)
+	pub fn method_schemas(&self, gen: &mut SchemaGenerator) -> Vec<RpcMethodSchema> {
+		self.method_names()
+			.into_iter()
+			.map(|name| {
+				let handler = &self.routes[name];
+				RpcMethodSchema {
+					name,
+					params: handler.params_schema(gen),
+					result: handler.result_schema(gen),
+				}
+			})
+			.collect()
+	}
+
+	/// Call the `rpc_method` handler, returning its result as json.
+	pub async fn call(
+		&self,
+		rpc_method: &str,
+		ctx: Ctx,
+		mm: ModelManager,
+		params: Option<Value>,
+	) -> Result<Value> {
+		let handler = self
+			.routes
+			.get(rpc_method)
+			.ok_or_else(|| Error::RpcMethodUnknown(rpc_method.to_string()))?;
+
+		let resources = RpcResources {
+			ctx,
+			mm,
+			resources: self.resources.clone(),
+		};
+
+		handler.call(rpc_method, resources, params).await
+	}
+}
+
+/// The schemas of an rpc method (from `RpcRouter::method_schemas`).
+pub struct RpcMethodSchema {
+	pub name: &'static str,
+	/// None for the methods without params.
+	pub params: Option<Schema>,
+	pub result: Schema,
+}
+
+// endregion: --- RpcRouter
+
+// region:    --- Resources
+
+/// What the handler arguments (before the params) are extracted from.
+pub struct RpcResources {
+	ctx: Ctx,
+	mm: ModelManager,
+	resources: Arc<ResourceMap>,
+}
+
+/// A handler argument extracted from the `RpcResources`.
+pub trait FromResources: Sized {
+	fn from_resources(resources: &RpcResources) -> Result<Self>;
+}
+
+impl FromResources for Ctx {
+	fn from_resources(resources: &RpcResources) -> Result<Self> {
+		Ok(resources.ctx.clone())
+	}
+}
+
+impl FromResources for ModelManager {
+	fn from_resources(resources: &RpcResources) -> Result<Self> {
+		Ok(resources.mm.clone())
+	}
+}
+
+/// A resource added with `RpcRouter::with_resource`.
+pub struct Resource<T>(pub T);
+
+impl<T> FromResources for Resource<T>
+where
+	T: Clone + Send + Sync + 'static,
+{
+	fn from_resources(resources: &RpcResources) -> Result<Self> {
+		resources
+			.resources
+			.get(&TypeId::of::<T>())
+			.and_then(|resource| resource.downcast_ref::<T>())
+			.map(|resource| Resource(resource.clone()))
+			.ok_or(Error::RpcResourceNotFound(type_name::<T>()))
+	}
+}
+
+// endregion: --- Resources
+
+// region:    --- Params
+
+/// The typed params of a handler (its last argument).
+pub trait IntoParams: DeserializeOwned + JsonSchema + Send + 'static {
+	fn into_params(rpc_method: &str, params: Option<Value>) -> Result<Self> {
+		let params = params.ok_or_else(|| Error::RpcMissingParams {
+			rpc_method: rpc_method.to_string(),
+		})?;
+
+		from_value(params).map_err(|_| Error::RpcFailJsonParams {
+			rpc_method: rpc_method.to_string(),
+		})
+	}
+}
+
+// endregion: --- Params
+
+// region:    --- RpcHandler
+
+/// Implemented for the async functions taking up to 3 `FromResources` arguments,
+/// optionally followed by an `IntoParams` argument, and returning a serializable result.
+///
+/// Note: The params schema is the inline one (i.e., the params object itself,
+///       not a reference to it), as the params are described member by member.
+///
+/// Note: `T` (the resources tuple) and `P` (`(P,)` or `()` without params)
+///       only make the implementations distinct.
+pub trait RpcHandler<T, P, R>: Clone + Send + Sync + 'static {
+	fn call(
+		self,
+		rpc_method: &str,
+		resources: RpcResources,
+		params: Option<Value>,
+	) -> PinFutureValue;
+
+	fn params_schema(gen: &mut SchemaGenerator) -> Option<Schema>;
+
+	fn result_schema(gen: &mut SchemaGenerator) -> Schema;
+}
+
+macro_rules! impl_rpc_handler {
+	($($T:ident),+) => {
+		// -- With params.
+		impl<F, Fut, $($T,)+ P, R> RpcHandler<($($T,)+), (P,), R> for F
+		where
+			F: FnOnce($($T,)+ P) -> Fut + Clone + Send + Sync + 'static,
+			Fut: Future<Output = Result<R>> + Send,
+			$($T: FromResources + Send,)+
+			P: IntoParams,
+			R: Serialize + JsonSchema,
+		{
+			fn call(
+				self,
+				rpc_method: &str,
+				resources: RpcResources,
+				params: Option<Value>,
+			) -> PinFutureValue {
+				let params = P::into_params(rpc_method, params);
+
+				Box::pin(async move {
+					let params = params?;
+					let res = self(
+						$(<$T as FromResources>::from_resources(&resources)?,)+
+						params,
+					)
+					.await?;
+
+					Ok(to_value(res)?)
+				})
+			}
+
+			fn params_schema(gen: &mut SchemaGenerator) -> Option<Schema> {
+				Some(P::json_schema(gen))
+			}
+
+			fn result_schema(gen: &mut SchemaGenerator) -> Schema {
+				gen.subschema_for::<R>()
+			}
+		}
+
+		// -- Without params (the eventual request params are ignored).
+		impl<F, Fut, $($T,)+ R> RpcHandler<($($T,)+), (), R> for F
+		where
+			F: FnOnce($($T,)+) -> Fut + Clone + Send + Sync + 'static,
+			Fut: Future<Output = Result<R>> + Send,
+			$($T: FromResources + Send,)+
+			R: Serialize + JsonSchema,
+		{
+			fn call(
+				self,
+				_rpc_method: &str,
+				resources: RpcResources,
+				_params: Option<Value>,
+			) -> PinFutureValue {
+				Box::pin(async move {
+					let res =
+						self($(<$T as FromResources>::from_resources(&resources)?,)+)
+							.await?;
+
+					Ok(to_value(res)?)
+				})
+			}
+
+			fn params_schema(_gen: &mut SchemaGenerator) -> Option<Schema> {
+				None
+			}
+
+			fn result_schema(gen: &mut SchemaGenerator) -> Schema {
+				gen.subschema_for::<R>()
+			}
+		}
+	};
+}
+
+impl_rpc_handler!(T1);
+impl_rpc_handler!(T1, T2);
+impl_rpc_handler!(T1, T2, T3);
+
+/// Object safe `RpcHandler` (for the `RpcRouter` routes).
+trait RpcHandlerDyn: Send + Sync {
+	fn call(
+		&self,
+		rpc_method: &str,
+		resources: RpcResources,
+		params: Option<Value>,
+	) -> PinFutureValue;
+
+	fn params_schema(&self, gen: &mut SchemaGenerator) -> Option<Schema>;
+
+	fn result_schema(&self, gen: &mut SchemaGenerator) -> Schema;
+}
+
+/// Keeps the `RpcHandler` type params, without owning them.
+type HandlerMarker<T, P, R> = PhantomData<fn() -> (T, P, R)>;
+
+struct RpcHandlerWrapper<H, T, P, R> {
+	handler: H,
+	_marker: HandlerMarker<T, P, R>,
+}
+
+impl<H, T, P, R> RpcHandlerDyn for RpcHandlerWrapper<H, T, P, R>
+where
+	H: RpcHandler<T, P, R>,
+{
+	fn call(
+		&self,
+		rpc_method: &str,
+		resources: RpcResources,
+		params: Option<Value>,
+	) -> PinFutureValue {
+		self.handler.clone().call(rpc_method, resources, params)
+	}
+
+	fn params_schema(&self, gen: &mut SchemaGenerator) -> Option<Schema> {
+		H::params_schema(gen)
+	}
+
+	fn result_schema(&self, gen: &mut SchemaGenerator) -> Schema {
+		H::result_schema(gen)
+	}
+}
+
+// endregion: --- RpcHandler
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::_dev_utils;
+	use anyhow::Result;
+	use serde::Deserialize;
+	use serde_json::json;
+	use serial_test::serial;
+
+	#[derive(Clone)]
+	struct FxGreeting(&'static str);
+
+	#[derive(Deserialize, JsonSchema)]
+	struct FxParams {
+		name: String,
+	}
+	impl IntoParams for FxParams {}
+
+	async fn greet(
+		ctx: Ctx,
+		greeting: Resource<FxGreeting>,
+		params: FxParams,
+	) -> crate::web::Result<String> {
+		let Resource(FxGreeting(greeting)) = greeting;
+		Ok(format!("{greeting} {} ({})", params.name, ctx.user_id()))
+	}
+
+	async fn ping(_mm: ModelManager) -> crate::web::Result<&'static str> {
+		Ok("pong")
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_rpc_router_call_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		let rpc_router = rpc_router!(greet)
+			.extend(rpc_router!(ping))
+			.with_resource(FxGreeting("Hello"));
+
+		// -- Exec
+		let greet_res = rpc_router
+			.call(
+				"greet",
+				ctx.clone(),
+				mm.clone(),
+				Some(json!({"name": "Jen"})),
+			)
+			.await?;
+		let ping_res = rpc_router.call("ping", ctx, mm, None).await?;
+
+		// -- Check
+		assert_eq!(greet_res, json!("Hello Jen (0)"));
+		assert_eq!(ping_res, json!("pong"));
+		assert_eq!(rpc_router.method_names(), &["greet", "ping"]);
+
+		Ok(())
+	}
+
+	#[serial]
+	#[tokio::test]
+	async fn test_rpc_router_call_err() -> Result<()> {
+		// -- Setup & Fixtures
+		let mm = _dev_utils::init_test().await;
+		let ctx = Ctx::root_ctx();
+		// No FxGreeting resource.
+		let rpc_router = rpc_router!(greet);
+
+		// -- Exec & Check
+		let res = rpc_router
+			.call("greet", ctx.clone(), mm.clone(), None)
+			.await;
+		assert!(matches!(res, Err(Error::RpcMissingParams { .. })));
+
+		let res = rpc_router
+			.call(
+				"greet",
+				ctx.clone(),
+				mm.clone(),
+				Some(json!({"nom": "Jen"})),
+			)
+			.await;
+		assert!(matches!(res, Err(Error::RpcFailJsonParams { .. })));
+
+		let res = rpc_router
+			.call(
+				"greet",
+				ctx.clone(),
+				mm.clone(),
+				Some(json!({"name": "Jen"})),
+			)
+			.await;
+		assert!(matches!(res, Err(Error::RpcResourceNotFound(_))));
+
+		let res = rpc_router.call("no_such_method", ctx, mm, None).await;
+		assert!(matches!(res, Err(Error::RpcMethodUnknown(_))));
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
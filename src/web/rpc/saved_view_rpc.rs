@@ -0,0 +1,98 @@
+use crate::ctx::Ctx;
+use crate::model::saved_view::{
+	SavedView, SavedViewBmc, SavedViewForCreate, SavedViewForUpdate,
+};
+use crate::model::ModelManager;
+use crate::web::rpc::router::rpc_router;
+use crate::web::rpc::{ParamsForCreate, ParamsForUpdate, ParamsIded, RpcRouter};
+use crate::web::Result;
+
+pub fn rpc_router() -> RpcRouter {
+	rpc_router!(
+		create_saved_view,
+		get_saved_view,
+		list_saved_views,
+		update_saved_view,
+		delete_saved_view
+	)
+}
+
+pub async fn create_saved_view(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsForCreate<SavedViewForCreate>,
+) -> Result<SavedView> {
+	let ParamsForCreate { data } = params;
+
+	let ctx = &ctx;
+	let saved_view = mm
+		.txn(|mm| {
+			let data = data.clone();
+			async move {
+				let id = SavedViewBmc::create(ctx, &mm, data).await?;
+				SavedViewBmc::get(ctx, &mm, id).await
+			}
+		})
+		.await?;
+
+	Ok(saved_view)
+}
+
+pub async fn get_saved_view(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsIded,
+) -> Result<SavedView> {
+	let ParamsIded { id } = params;
+
+	let saved_view = SavedViewBmc::get(&ctx, &mm, id).await?;
+
+	Ok(saved_view)
+}
+
+/// The views of the user, and the shared ones, by name.
+pub async fn list_saved_views(ctx: Ctx, mm: ModelManager) -> Result<Vec<SavedView>> {
+	let saved_views = SavedViewBmc::list(&ctx, &mm).await?;
+
+	Ok(saved_views)
+}
+
+pub async fn update_saved_view(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsForUpdate<SavedViewForUpdate>,
+) -> Result<SavedView> {
+	let ParamsForUpdate { id, data, .. } = params;
+
+	let ctx = &ctx;
+	let saved_view = mm
+		.txn(|mm| {
+			let data = data.clone();
+			async move {
+				SavedViewBmc::update(ctx, &mm, id, data).await?;
+				SavedViewBmc::get(ctx, &mm, id).await
+			}
+		})
+		.await?;
+
+	Ok(saved_view)
+}
+
+pub async fn delete_saved_view(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsIded,
+) -> Result<SavedView> {
+	let ParamsIded { id } = params;
+
+	let ctx = &ctx;
+	let saved_view = mm
+		.txn(|mm| async move {
+			let saved_view = SavedViewBmc::get(ctx, &mm, id).await?;
+			SavedViewBmc::delete(ctx, &mm, id).await?;
+			Ok(saved_view)
+		})
+		.await?;
+
+	Ok(saved_view)
+}
@@ -0,0 +1,40 @@
+use crate::ctx::Ctx;
+use crate::model::search::{SearchBmc, SearchEntity, SearchHit};
+use crate::model::{ListOptions, ModelManager};
+use crate::web::rpc::router::rpc_router;
+use crate::web::rpc::{IntoParams, RpcRouter};
+use crate::web::Result;
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+pub fn rpc_router() -> RpcRouter {
+	rpc_router!(search)
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ParamsForSearch {
+	/// The words to search (the last one as a prefix, e.g., `buy mil`).
+	pub query: String,
+	/// The searched entities (all when absent).
+	pub entities: Option<Vec<SearchEntity>>,
+	pub list_options: Option<ListOptions>,
+}
+
+impl IntoParams for ParamsForSearch {}
+
+/// Full-text search of the tasks and comments, best ranked first.
+pub async fn search(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsForSearch,
+) -> Result<Vec<SearchHit>> {
+	let ParamsForSearch {
+		query,
+		entities,
+		list_options,
+	} = params;
+
+	let hits = SearchBmc::search(&ctx, &mm, &query, entities, list_options).await?;
+
+	Ok(hits)
+}
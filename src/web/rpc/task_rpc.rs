@@ -1,8 +1,96 @@
 use crate::ctx::Ctx;
-use crate::model::task::{Task, TaskBmc, TaskForCreate, TaskForUpdate};
-use crate::model::ModelManager;
-use crate::web::rpc::{ParamsForCreate, ParamsForUpdate, ParamsIded};
-use crate::web::Result;
+use crate::model::comment::CommentBmc;
+use crate::model::revision::{FieldDiff, Revision};
+use crate::model::saved_view::{SavedViewBmc, SavedViewQuery};
+use crate::model::task::{
+	Task, TaskBmc, TaskFilter, TaskForCreate, TaskForUpdate, TaskMove,
+};
+use crate::model::{ListOptions, ListPage, ModelManager};
+use crate::web::rpc::router::rpc_router;
+use crate::web::rpc::{
+	BulkItemResult, IntoParams, ParamsForCreate, ParamsForUpdate,
+	ParamsForUpdateMany, ParamsIded, ParamsIdeds, RpcRouter,
+};
+use crate::web::{Error, Result};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{from_value, Value};
+
+pub fn rpc_router() -> RpcRouter {
+	rpc_router!(
+		create_task,
+		list_tasks,
+		list_tasks_by_view,
+		list_tasks_page,
+		update_task,
+		create_tasks,
+		update_tasks,
+		delete_tasks,
+		move_task,
+		delete_task,
+		list_trash,
+		restore_task,
+		purge_task,
+		list_task_revisions,
+		diff_task_revisions,
+		revert_task,
+	)
+}
+
+/// The `list_tasks` params, which are optional (all the tasks when absent).
+#[derive(Default, Deserialize, JsonSchema)]
+pub struct ParamsForListTasks {
+	pub filter: Option<TaskFilter>,
+	pub list_options: Option<ListOptions>,
+}
+
+impl IntoParams for ParamsForListTasks {
+	fn into_params(rpc_method: &str, params: Option<Value>) -> Result<Self> {
+		let Some(params) = params else {
+			return Ok(Self::default());
+		};
+
+		from_value(params).map_err(|_| Error::RpcFailJsonParams {
+			rpc_method: rpc_method.to_string(),
+		})
+	}
+}
+
+/// The `list_tasks_by_view` params, the `list_options` fields overriding the
+/// view ones (e.g., to page through the view).
+#[derive(Deserialize, JsonSchema)]
+pub struct ParamsForListTasksByView {
+	pub view_id: i64,
+	pub list_options: Option<ListOptions>,
+}
+
+impl IntoParams for ParamsForListTasksByView {}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ParamsForRevisionDiff {
+	pub id: i64,
+	pub from_revision_id: i64,
+	/// None for the diff with the current task.
+	pub to_revision_id: Option<i64>,
+}
+
+impl IntoParams for ParamsForRevisionDiff {}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ParamsForRevert {
+	pub id: i64,
+	pub revision_id: i64,
+}
+
+impl IntoParams for ParamsForRevert {}
+
+/// A `Task` as returned by `list_tasks`, with its comment count.
+#[derive(Serialize, JsonSchema)]
+pub struct TaskListItem {
+	#[serde(flatten)]
+	pub task: Task,
+	pub comment_count: i64,
+}
 
 pub async fn create_task(
 	ctx: Ctx,
@@ -11,14 +99,121 @@ pub async fn create_task(
 ) -> Result<Task> {
 	let ParamsForCreate { data } = params;
 
-	let id = TaskBmc::create(&ctx, &mm, data).await?;
-	let task = TaskBmc::get(&ctx, &mm, id).await?;
+	let ctx = &ctx;
+	let task = mm
+		.txn(|mm| {
+			let data = data.clone();
+			async move {
+				let id = TaskBmc::create(ctx, &mm, data).await?;
+				TaskBmc::get(ctx, &mm, id).await
+			}
+		})
+		.await?;
 
 	Ok(task)
 }
 
-pub async fn list_tasks(ctx: Ctx, mm: ModelManager) -> Result<Vec<Task>> {
-	let tasks = TaskBmc::list(&ctx, &mm).await?;
+pub async fn list_tasks(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsForListTasks,
+) -> Result<Vec<TaskListItem>> {
+	let ParamsForListTasks {
+		filter,
+		list_options,
+	} = params;
+
+	list_task_items(&ctx, &mm, filter, list_options).await
+}
+
+/// A page of the tasks (see `ListOptions::cursor`), with its next cursor.
+pub async fn list_tasks_page(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsForListTasks,
+) -> Result<ListPage<TaskListItem>> {
+	let ParamsForListTasks {
+		filter,
+		list_options,
+	} = params;
+
+	let ListPage {
+		items,
+		next_cursor,
+		has_more,
+	} = TaskBmc::list_page(&ctx, &mm, filter.unwrap_or_default(), list_options).await?;
+
+	Ok(ListPage {
+		items: with_comment_counts(&ctx, &mm, items).await?,
+		next_cursor,
+		has_more,
+	})
+}
+
+/// The tasks of a saved view (see `SavedViewBmc`).
+pub async fn list_tasks_by_view(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsForListTasksByView,
+) -> Result<Vec<TaskListItem>> {
+	let ParamsForListTasksByView {
+		view_id,
+		list_options,
+	} = params;
+
+	let SavedViewQuery {
+		filter,
+		list_options: view_list_options,
+	} = SavedViewBmc::get(&ctx, &mm, view_id).await?.query;
+
+	// -- The given list options, field by field, then the view ones.
+	let list_options = match (list_options, view_list_options) {
+		(Some(list_options), Some(view_list_options)) => Some(ListOptions {
+			limit: list_options.limit.or(view_list_options.limit),
+			offset: list_options.offset.or(view_list_options.offset),
+			order_bys: list_options.order_bys.or(view_list_options.order_bys),
+			cursor: list_options.cursor,
+		}),
+		(list_options, view_list_options) => list_options.or(view_list_options),
+	};
+
+	list_task_items(&ctx, &mm, Some(filter.unwrap_or_default()), list_options).await
+}
+
+async fn list_task_items(
+	ctx: &Ctx,
+	mm: &ModelManager,
+	filter: Option<TaskFilter>,
+	list_options: Option<ListOptions>,
+) -> Result<Vec<TaskListItem>> {
+	// -- Without params, all the tasks (as before the filters).
+	let tasks = match (filter, list_options) {
+		(None, None) => TaskBmc::list(ctx, mm).await?,
+		(filter, list_options) => {
+			let filter = filter.unwrap_or_default();
+			TaskBmc::list_by_filter(ctx, mm, filter, list_options).await?
+		}
+	};
+
+	with_comment_counts(ctx, mm, tasks).await
+}
+
+/// The list items of the tasks, with all the comment counts got in one query.
+async fn with_comment_counts(
+	ctx: &Ctx,
+	mm: &ModelManager,
+	tasks: Vec<Task>,
+) -> Result<Vec<TaskListItem>> {
+	let task_ids: Vec<i64> = tasks.iter().map(|t| t.id).collect();
+	let comment_counts = CommentBmc::count_by_task_ids(ctx, mm, &task_ids).await?;
+
+	let tasks = tasks
+		.into_iter()
+		.map(|task| TaskListItem {
+			comment_count: comment_counts.get(&task.id).copied().unwrap_or(0),
+			task,
+		})
+		.collect();
 
 	Ok(tasks)
 }
@@ -28,11 +223,77 @@ pub async fn update_task(
 	mm: ModelManager,
 	params: ParamsForUpdate<TaskForUpdate>,
 ) -> Result<Task> {
-	let ParamsForUpdate { id, data } = params;
+	let ParamsForUpdate {
+		id,
+		data,
+		expected_version,
+	} = params;
 
-	TaskBmc::update(&ctx, &mm, id, data).await?;
+	let ctx = &ctx;
+	let task = mm
+		.txn(|mm| {
+			let data = data.clone();
+			async move {
+				TaskBmc::update(ctx, &mm, id, data, expected_version).await?;
+				TaskBmc::get(ctx, &mm, id).await
+			}
+		})
+		.await?;
 
-	let task = TaskBmc::get(&ctx, &mm, id).await?;
+	Ok(task)
+}
+
+pub async fn create_tasks(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsForCreate<Vec<TaskForCreate>>,
+) -> Result<Vec<BulkItemResult<i64>>> {
+	let ParamsForCreate { data } = params;
+
+	let results = TaskBmc::create_many(&ctx, &mm, data).await?;
+
+	Ok(results.into_iter().map(BulkItemResult::from).collect())
+}
+
+pub async fn update_tasks(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsForUpdateMany<TaskForUpdate>,
+) -> Result<Vec<BulkItemResult<i64>>> {
+	let results = match params {
+		ParamsForUpdateMany::Same { ids, data } => {
+			TaskBmc::update_many(&ctx, &mm, &ids, data).await?
+		}
+		ParamsForUpdateMany::PerId { items } => {
+			let updates = items
+				.into_iter()
+				.map(|item| (item.id, item.data, item.expected_version))
+				.collect();
+			TaskBmc::update_each(&ctx, &mm, updates).await?
+		}
+	};
+
+	Ok(results.into_iter().map(BulkItemResult::from).collect())
+}
+
+pub async fn move_task(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsForUpdate<TaskMove>,
+) -> Result<Task> {
+	let ParamsForUpdate {
+		id,
+		data,
+		expected_version,
+	} = params;
+
+	let ctx = &ctx;
+	let task = mm
+		.txn(|mm| async move {
+			TaskBmc::move_to(ctx, &mm, id, data, expected_version).await?;
+			TaskBmc::get(ctx, &mm, id).await
+		})
+		.await?;
 
 	Ok(task)
 }
@@ -44,8 +305,110 @@ pub async fn delete_task(
 ) -> Result<Task> {
 	let ParamsIded { id } = params;
 
-	let task = TaskBmc::get(&ctx, &mm, id).await?;
-	TaskBmc::delete(&ctx, &mm, id).await?;
+	let ctx = &ctx;
+	let task = mm
+		.txn(|mm| async move {
+			let task = TaskBmc::get(ctx, &mm, id).await?;
+			TaskBmc::delete(ctx, &mm, id).await?;
+			Ok(task)
+		})
+		.await?;
+
+	Ok(task)
+}
+
+pub async fn delete_tasks(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsIdeds,
+) -> Result<Vec<BulkItemResult<i64>>> {
+	let ParamsIdeds { ids } = params;
+
+	let results = TaskBmc::delete_many(&ctx, &mm, &ids).await?;
+
+	Ok(results.into_iter().map(BulkItemResult::from).collect())
+}
+
+pub async fn list_trash(ctx: Ctx, mm: ModelManager) -> Result<Vec<Task>> {
+	let tasks = TaskBmc::list_trash(&ctx, &mm).await?;
+
+	Ok(tasks)
+}
+
+pub async fn restore_task(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsIded,
+) -> Result<Task> {
+	let ParamsIded { id } = params;
+
+	let ctx = &ctx;
+	let task = mm
+		.txn(|mm| async move {
+			TaskBmc::restore(ctx, &mm, id).await?;
+			TaskBmc::get(ctx, &mm, id).await
+		})
+		.await?;
+
+	Ok(task)
+}
+
+pub async fn purge_task(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsIded,
+) -> Result<i64> {
+	let ParamsIded { id } = params;
+
+	TaskBmc::purge(&ctx, &mm, id).await?;
+
+	Ok(id)
+}
+
+pub async fn list_task_revisions(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsIded,
+) -> Result<Vec<Revision>> {
+	let ParamsIded { id } = params;
+
+	let revisions = TaskBmc::list_revisions(&ctx, &mm, id).await?;
+
+	Ok(revisions)
+}
+
+pub async fn diff_task_revisions(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsForRevisionDiff,
+) -> Result<Vec<FieldDiff>> {
+	let ParamsForRevisionDiff {
+		id,
+		from_revision_id,
+		to_revision_id,
+	} = params;
+
+	let diff =
+		TaskBmc::diff_revisions(&ctx, &mm, id, from_revision_id, to_revision_id)
+			.await?;
+
+	Ok(diff)
+}
+
+pub async fn revert_task(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsForRevert,
+) -> Result<Task> {
+	let ParamsForRevert { id, revision_id } = params;
+
+	let ctx = &ctx;
+	let task = mm
+		.txn(|mm| async move {
+			TaskBmc::revert(ctx, &mm, id, revision_id).await?;
+			TaskBmc::get(ctx, &mm, id).await
+		})
+		.await?;
 
 	Ok(task)
 }
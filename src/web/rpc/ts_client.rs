@@ -0,0 +1,412 @@
+//! TypeScript export of the rpc methods, for the web frontend: the types of all the
+//! params and results (e.g., `Task`, `TaskForCreate`), the `ClientError` union,
+//! and a typed client with one function per rpc method (e.g., `createTask`).
+//!
+//! Generated from the OpenRPC document (see `discover`), so from the same
+//! Rust definitions, and exported with `cargo run -- export-ts [file]`.
+//!
+//! Note: The default export (`TS_CLIENT_PATH`) is committed, and kept in sync by the
+//!       `test_ts_client_in_sync` test.
+//!
+
+use crate::web::rpc::discover::openrpc_doc;
+use crate::web::rpc::rpc_router;
+use serde_json::{Map, Value};
+use std::fmt::Write;
+use std::path::Path;
+
+/// Where `export-ts` writes the client by default (relative to the crate root).
+pub const TS_CLIENT_PATH: &str = "web-folder/ts/rpc-client.ts";
+
+const TS_HEADER: &str = "\
+// Generated from the Rust `web::rpc` definitions (`cargo run -- export-ts`).
+// Do not edit.
+";
+
+const TS_CLIENT_BASE: &str = r#"let rpcUrl = "/api/rpc";
+let rpcId = 0;
+
+/** Set the JSON-RPC endpoint (defaults to "/api/rpc"). */
+export function setRpcUrl(url: string) {
+	rpcUrl = url;
+}
+
+/** The error of a failed rpc call. */
+export class RpcError extends Error {
+	constructor(
+		public readonly code: number,
+		public readonly clientError: ClientError,
+		public readonly reqUuid: string | null,
+	) {
+		super(clientError.message);
+	}
+}
+
+async function rpcCall<R>(method: string, params?: unknown): Promise<R> {
+	const res = await fetch(rpcUrl, {
+		method: "POST",
+		headers: { "Content-Type": "application/json" },
+		credentials: "same-origin",
+		body: JSON.stringify({ jsonrpc: "2.0", id: ++rpcId, method, params }),
+	});
+	const body = await res.json();
+
+	if (body.error) {
+		const { code, message, data } = body.error;
+		const clientError = { message, detail: data?.detail } as ClientError;
+		throw new RpcError(code, clientError, data?.req_uuid ?? null);
+	}
+
+	return body.result as R;
+}
+"#;
+
+/// Write the TypeScript client of all the rpc methods to `path`.
+pub fn export_ts_client(path: impl AsRef<Path>) -> std::io::Result<()> {
+	let path = path.as_ref();
+	if let Some(dir) = path.parent() {
+		std::fs::create_dir_all(dir)?;
+	}
+
+	std::fs::write(path, ts_client(&openrpc_doc(&rpc_router())))
+}
+
+/// Returns the TypeScript client for the `doc` OpenRPC document.
+pub fn ts_client(doc: &Value) -> String {
+	let mut ts = String::from(TS_HEADER);
+
+	// -- Types (the named ones, e.g., `Task`)
+	ts.push_str("\n// region:    --- Types\n");
+	let empty_schemas = Map::new();
+	let schemas = doc["components"]["schemas"]
+		.as_object()
+		.unwrap_or(&empty_schemas);
+	for (name, schema) in schemas {
+		ts.push('\n');
+		ts.push_str(&ts_doc_comment(schema, 0));
+		ts.push_str(&ts_declaration(name, schema));
+	}
+	ts.push_str("\n// endregion: --- Types\n");
+
+	// -- Params (one type per method with params)
+	let methods = doc["methods"].as_array().map(Vec::as_slice).unwrap_or(&[]);
+	ts.push_str("\n// region:    --- Params\n");
+	for method in methods {
+		let Some(params) = method["params"].as_array().filter(|p| !p.is_empty())
+		else {
+			continue;
+		};
+		ts.push('\n');
+		ts.push_str(&ts_params_declaration(&method_params_name(method), params));
+	}
+	ts.push_str("\n// endregion: --- Params\n");
+
+	// -- Client
+	ts.push_str("\n// region:    --- Client\n\n");
+	ts.push_str(TS_CLIENT_BASE);
+	for method in methods {
+		ts.push('\n');
+		ts.push_str(&ts_client_fn(method));
+	}
+	ts.push_str("\n// endregion: --- Client\n");
+
+	ts
+}
+
+// region:    --- Declarations
+
+fn ts_declaration(name: &str, schema: &Value) -> String {
+	if schema.get("properties").is_some()
+		&& schema.get("type") == Some(&"object".into())
+	{
+		format!("export interface {name} {}\n", ts_type(schema, 0))
+	} else {
+		format!("export type {name} = {};\n", ts_type(schema, 0))
+	}
+}
+
+/// The params interface, from the method content descriptors
+/// (or the params type for a single `params` descriptor).
+fn ts_params_declaration(name: &str, params: &[Value]) -> String {
+	if let [param] = params {
+		if param["name"] == "params" {
+			return ts_declaration(name, &param["schema"]);
+		}
+	}
+
+	let mut ts = format!("export interface {name} {{\n");
+	for param in params {
+		ts.push_str(&ts_doc_comment(param, 1));
+		let _ = writeln!(
+			ts,
+			"\t{}{}: {};",
+			param["name"].as_str().unwrap_or_default(),
+			if param["required"] == true { "" } else { "?" },
+			ts_type(&param["schema"], 1)
+		);
+	}
+	ts.push_str("}\n");
+
+	ts
+}
+
+fn ts_client_fn(method: &Value) -> String {
+	let name = method["name"].as_str().unwrap_or_default();
+	let result = ts_type(&method["result"]["schema"], 1);
+	let has_params = method["params"].as_array().is_some_and(|p| !p.is_empty());
+
+	let (fn_params, call_params) = if has_params {
+		(
+			format!("params: {}", method_params_name(method)),
+			", params",
+		)
+	} else {
+		(String::new(), "")
+	};
+
+	format!(
+		"/** `{name}` */\nexport function {}({fn_params}): Promise<{result}> {{\n\
+		 \treturn rpcCall(\"{name}\"{call_params});\n}}\n",
+		camel_case(name)
+	)
+}
+
+/// The params type name of a method (e.g., `CreateTaskParams` for `create_task`).
+fn method_params_name(method: &Value) -> String {
+	let mut name = camel_case(method["name"].as_str().unwrap_or_default());
+	if let Some(first) = name.get_mut(0..1) {
+		first.make_ascii_uppercase();
+	}
+
+	format!("{name}Params")
+}
+
+/// e.g., `create_task` to `createTask`, and `rpc.discover` to `rpcDiscover`.
+fn camel_case(name: &str) -> String {
+	let mut parts = name.split(['_', '.']);
+	let mut camel = parts.next().unwrap_or_default().to_string();
+	for part in parts {
+		let mut chars = part.chars();
+		if let Some(first) = chars.next() {
+			camel.push(first.to_ascii_uppercase());
+			camel.extend(chars);
+		}
+	}
+
+	camel
+}
+
+fn ts_doc_comment(schema: &Value, indent: usize) -> String {
+	let Some(description) = schema.get("description").and_then(Value::as_str) else {
+		return String::new();
+	};
+
+	let tabs = "\t".repeat(indent);
+	let mut ts = format!("{tabs}/**\n");
+	for line in description.lines() {
+		let _ = writeln!(ts, "{tabs} * {line}");
+	}
+	let _ = writeln!(ts, "{tabs} */");
+
+	ts
+}
+
+// endregion: --- Declarations
+
+// region:    --- Schema to TS
+
+/// The TypeScript type of a (draft 07) JSON Schema, as generated by schemars.
+/// (`indent` being the indentation level of the object members)
+fn ts_type(schema: &Value, indent: usize) -> String {
+	let Some(schema) = schema.as_object() else {
+		// `true` (any value) or `false` (no value) schema.
+		return if schema == &Value::Bool(false) {
+			"never".to_string()
+		} else {
+			"unknown".to_string()
+		};
+	};
+
+	if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+		return reference
+			.rsplit('/')
+			.next()
+			.unwrap_or(reference)
+			.to_string();
+	}
+
+	if let Some(value) = schema.get("const") {
+		return value.to_string();
+	}
+
+	if let Some(values) = schema.get("enum").and_then(Value::as_array) {
+		return ts_union(values.iter().map(Value::to_string));
+	}
+
+	for (keyword, separator) in
+		[("oneOf", " | "), ("anyOf", " | "), ("allOf", " & ")]
+	{
+		if let Some(subschemas) = schema.get(keyword).and_then(Value::as_array) {
+			return subschemas
+				.iter()
+				.map(|subschema| ts_type_wrapped(subschema, indent))
+				.collect::<Vec<_>>()
+				.join(separator);
+		}
+	}
+
+	let types: Vec<&str> = match schema.get("type") {
+		Some(Value::String(ty)) => vec![ty.as_str()],
+		Some(Value::Array(types)) => {
+			types.iter().filter_map(Value::as_str).collect()
+		}
+		_ => return "unknown".to_string(),
+	};
+
+	ts_union(types.into_iter().map(|ty| match ty {
+		"string" => "string".to_string(),
+		"integer" | "number" => "number".to_string(),
+		"boolean" => "boolean".to_string(),
+		"null" => "null".to_string(),
+		"array" => match schema.get("items") {
+			Some(items) => format!("{}[]", ts_type_wrapped(items, indent)),
+			None => "unknown[]".to_string(),
+		},
+		"object" => ts_object(schema, indent),
+		_ => "unknown".to_string(),
+	}))
+}
+
+fn ts_object(schema: &Map<String, Value>, indent: usize) -> String {
+	let Some(properties) = schema.get("properties").and_then(Value::as_object)
+	else {
+		return match schema.get("additionalProperties") {
+			Some(Value::Object(values)) => {
+				format!(
+					"Record<string, {}>",
+					ts_type(&Value::Object(values.clone()), indent)
+				)
+			}
+			_ => "Record<string, unknown>".to_string(),
+		};
+	};
+
+	let required: Vec<&str> = schema
+		.get("required")
+		.and_then(Value::as_array)
+		.map(|names| names.iter().filter_map(Value::as_str).collect())
+		.unwrap_or_default();
+
+	let tabs = "\t".repeat(indent);
+	let mut ts = String::from("{\n");
+	for (name, property) in properties {
+		ts.push_str(&ts_doc_comment(property, indent + 1));
+		let _ = writeln!(
+			ts,
+			"{tabs}\t{name}{}: {};",
+			if required.contains(&name.as_str()) {
+				""
+			} else {
+				"?"
+			},
+			ts_type(property, indent + 1)
+		);
+	}
+	ts.push_str(&tabs);
+	ts.push('}');
+
+	ts
+}
+
+/// The type in parentheses when needed (i.e., a union or intersection),
+/// for it to be an array item or a union member.
+fn ts_type_wrapped(schema: &Value, indent: usize) -> String {
+	let ts = ts_type(schema, indent);
+	if !ts.starts_with('{') && (ts.contains(" | ") || ts.contains(" & ")) {
+		format!("({ts})")
+	} else {
+		ts
+	}
+}
+
+fn ts_union(types: impl Iterator<Item = String>) -> String {
+	let types: Vec<String> = types.collect();
+	if types.is_empty() {
+		"never".to_string()
+	} else {
+		types.join(" | ")
+	}
+}
+
+// endregion: --- Schema to TS
+
+// region:    --- Tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use anyhow::Result;
+	use serde_json::json;
+
+	#[test]
+	fn test_ts_type_ok() -> Result<()> {
+		// -- Setup & Fixtures
+		let fx_cases = [
+			(json!({"$ref": "#/components/schemas/Task"}), "Task"),
+			(json!({"type": ["string", "null"]}), "string | null"),
+			(
+				json!({"type": "array", "items": {"type": ["integer", "null"]}}),
+				"(number | null)[]",
+			),
+			(
+				json!({"enum": ["before", "after"]}),
+				r#""before" | "after""#,
+			),
+			(
+				json!({"oneOf": [
+					{"type": "object", "properties": {"message": {"const": "NO_AUTH"}},
+					 "required": ["message"]},
+					{"type": "null"}
+				]}),
+				"{\n\tmessage: \"NO_AUTH\";\n} | null",
+			),
+			(
+				json!({"type": "object", "properties": {"id": {"type": "integer"},
+					"title": {"type": "string"}}, "required": ["id"]}),
+				"{\n\tid: number;\n\ttitle?: string;\n}",
+			),
+			(json!(true), "unknown"),
+		];
+
+		// -- Exec & Check
+		for (schema, expected) in fx_cases {
+			assert_eq!(ts_type(&schema, 0), expected, "schema: {schema}");
+		}
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_ts_client_in_sync() -> Result<()> {
+		// -- Setup & Fixtures
+		let ts_path = Path::new(env!("CARGO_MANIFEST_DIR")).join(TS_CLIENT_PATH);
+
+		// -- Exec
+		let ts = ts_client(&openrpc_doc(&rpc_router()));
+
+		// -- Check
+		let committed_ts = std::fs::read_to_string(&ts_path)?;
+		assert!(
+			committed_ts == ts,
+			"{TS_CLIENT_PATH} is out of sync with the rpc methods \
+			 (run `cargo run -- export-ts` to update it)"
+		);
+		assert!(ts.contains("export interface Task {"));
+		assert!(ts.contains("export type ClientError ="));
+		assert!(ts.contains(
+			"export function createTask(params: CreateTaskParams): Promise<Task>"
+		));
+
+		Ok(())
+	}
+}
+// endregion: --- Tests
@@ -0,0 +1,160 @@
+use crate::ctx::Ctx;
+use crate::model::webhook::{
+	Webhook, WebhookBmc, WebhookDelivery, WebhookForCreate, WebhookForUpdate,
+};
+use crate::model::{ListOptions, ListPage, ModelManager};
+use crate::web::rpc::router::rpc_router;
+use crate::web::rpc::{
+	IntoParams, ParamsForCreate, ParamsForUpdate, ParamsIded, RpcRouter,
+};
+use crate::web::Result;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+pub fn rpc_router() -> RpcRouter {
+	rpc_router!(
+		create_webhook,
+		list_webhooks,
+		update_webhook,
+		delete_webhook,
+		list_webhook_deliveries,
+		list_webhook_deliveries_page,
+		send_test_webhook
+	)
+}
+
+/// A created webhook, with its signing secret (only returned on create).
+#[derive(Serialize, JsonSchema)]
+pub struct WebhookWithSecret {
+	#[serde(flatten)]
+	pub webhook: Webhook,
+	pub secret: String,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ParamsForListWebhookDeliveries {
+	pub webhook_id: i64,
+	pub list_options: Option<ListOptions>,
+}
+
+impl IntoParams for ParamsForListWebhookDeliveries {}
+
+pub async fn create_webhook(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsForCreate<WebhookForCreate>,
+) -> Result<WebhookWithSecret> {
+	let ParamsForCreate { data } = params;
+	// Note: Before the transaction, as resolving the url (see `validate`).
+	let data = data.validate().await?;
+
+	let ctx = &ctx;
+	let webhook = mm
+		.txn(|mm| {
+			let data = data.clone();
+			async move {
+				let id = WebhookBmc::create(ctx, &mm, data).await?;
+				let webhook = WebhookBmc::get(ctx, &mm, id).await?;
+				let secret = WebhookBmc::secret(ctx, &mm, id).await?;
+				Ok(WebhookWithSecret { webhook, secret })
+			}
+		})
+		.await?;
+
+	Ok(webhook)
+}
+
+pub async fn list_webhooks(ctx: Ctx, mm: ModelManager) -> Result<Vec<Webhook>> {
+	let webhooks = WebhookBmc::list(&ctx, &mm).await?;
+
+	Ok(webhooks)
+}
+
+pub async fn update_webhook(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsForUpdate<WebhookForUpdate>,
+) -> Result<Webhook> {
+	let ParamsForUpdate { id, data, .. } = params;
+	// Note: Before the transaction, as resolving the url (see `validate`).
+	let data = data.validate().await?;
+
+	let ctx = &ctx;
+	let webhook = mm
+		.txn(|mm| {
+			let data = data.clone();
+			async move {
+				WebhookBmc::update(ctx, &mm, id, data).await?;
+				WebhookBmc::get(ctx, &mm, id).await
+			}
+		})
+		.await?;
+
+	Ok(webhook)
+}
+
+pub async fn delete_webhook(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsIded,
+) -> Result<Webhook> {
+	let ParamsIded { id } = params;
+
+	let ctx = &ctx;
+	let webhook = mm
+		.txn(|mm| async move {
+			let webhook = WebhookBmc::get(ctx, &mm, id).await?;
+			WebhookBmc::delete(ctx, &mm, id).await?;
+			Ok(webhook)
+		})
+		.await?;
+
+	Ok(webhook)
+}
+
+pub async fn list_webhook_deliveries(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsForListWebhookDeliveries,
+) -> Result<Vec<WebhookDelivery>> {
+	let ParamsForListWebhookDeliveries {
+		webhook_id,
+		list_options,
+	} = params;
+
+	let deliveries =
+		WebhookBmc::list_deliveries(&ctx, &mm, webhook_id, list_options).await?;
+
+	Ok(deliveries)
+}
+
+/// A page of the delivery attempts of a webhook (see `ListOptions::cursor`),
+/// with its next cursor.
+pub async fn list_webhook_deliveries_page(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsForListWebhookDeliveries,
+) -> Result<ListPage<WebhookDelivery>> {
+	let ParamsForListWebhookDeliveries {
+		webhook_id,
+		list_options,
+	} = params;
+
+	let page = WebhookBmc::list_deliveries_page(&ctx, &mm, webhook_id, list_options)
+		.await?;
+
+	Ok(page)
+}
+
+/// Deliver a `webhook.test` event now, returning the delivery attempt.
+pub async fn send_test_webhook(
+	ctx: Ctx,
+	mm: ModelManager,
+	params: ParamsIded,
+) -> Result<WebhookDelivery> {
+	let ParamsIded { id } = params;
+
+	let delivery = WebhookBmc::send_test(&ctx, &mm, id).await?;
+
+	Ok(delivery)
+}
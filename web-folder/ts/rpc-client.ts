@@ -0,0 +1,867 @@
+// Generated from the Rust `web::rpc` definitions (`cargo run -- export-ts`).
+// Do not edit.
+
+// region:    --- Types
+
+/**
+ * The per item result of the bulk RPC methods (e.g., `create_tasks`),
+ * as `{"ok": ..}` or `{"error": {"message": .., "detail": ..}}`.
+ */
+export type BulkItemResult = {
+	ok: number;
+} | {
+	error: ClientError;
+};
+
+export type ClientError = {
+	message: "RPC_PARSE_ERROR";
+} | {
+	message: "RPC_INVALID_REQUEST";
+} | {
+	detail: {
+		rpc_method: string;
+	};
+	message: "RPC_METHOD_NOT_FOUND";
+} | {
+	detail: {
+		rpc_method: string;
+	};
+	message: "RPC_INVALID_PARAMS";
+} | {
+	detail: string;
+	message: "INVALID_INPUT";
+} | {
+	message: "LOGIN_FAIL";
+} | {
+	message: "NO_AUTH";
+} | {
+	detail: {
+		entity: string;
+		id: number;
+	};
+	message: "ENTITY_NOT_FOUND";
+} | {
+	detail: {
+		entity: string;
+		id: number;
+	};
+	message: "ACCESS_DENIED";
+} | {
+	message: "ADMIN_REQUIRED";
+} | {
+	detail: {
+		current_version: number;
+		entity: string;
+		id: number;
+	};
+	message: "VERSION_CONFLICT";
+} | {
+	detail: {
+		name: string;
+	};
+	message: "NAME_TAKEN";
+} | {
+	detail: string;
+	message: "INVALID_RRULE";
+} | {
+	message: "INVALID_RANK";
+} | {
+	message: "SERVICE_ERROR";
+};
+
+export interface Comment {
+	author_id: number;
+	body: string;
+	ctime: string;
+	id: number;
+	mtime?: string | null;
+	task_id: number;
+}
+
+export interface CommentForCreate {
+	body: string;
+	task_id: number;
+}
+
+export interface CommentForUpdate {
+	body: string;
+}
+
+/**
+ * The last run status of a cron.
+ */
+export interface CronRun {
+	last_finished_at?: string | null;
+	/**
+	 * The run summary, or its error.
+	 */
+	last_message?: string | null;
+	last_started_at?: string | null;
+	/**
+	 * None before the first run.
+	 */
+	last_status?: CronStatus | null;
+	name: string;
+	/**
+	 * None when the schedule never matches (anymore).
+	 */
+	next_run_at?: string | null;
+	schedule: string;
+}
+
+export type CronStatus = "running" | "ok" | "failed";
+
+export interface FieldDiff {
+	field: string;
+	from: unknown;
+	to: unknown;
+}
+
+/**
+ * Pagination and order options for the list functions.
+ */
+export interface ListOptions {
+	/**
+	 * The `next_cursor` of the previous page, for the lists paginated by keyset
+	 * (see `base::list_page`, the `offset` then being ignored).
+	 * Rejected by the other lists.
+	 */
+	cursor?: string | null;
+	limit?: number | null;
+	offset?: number | null;
+	/**
+	 * The order, by the orderable columns of the list (e.g., `["!due_date", "title"]`,
+	 * `!` for descending). The default order of the list when absent.
+	 */
+	order_bys?: string[] | null;
+}
+
+/**
+ * A page of a keyset paginated list (see `base::list_page`).
+ */
+export interface ListPage {
+	has_more: boolean;
+	items: Comment[];
+	/**
+	 * The `ListOptions::cursor` of the next page (None for the last page).
+	 */
+	next_cursor?: string | null;
+}
+
+/**
+ * A page of a keyset paginated list (see `base::list_page`).
+ */
+export interface ListPage2 {
+	has_more: boolean;
+	items: Notification[];
+	/**
+	 * The `ListOptions::cursor` of the next page (None for the last page).
+	 */
+	next_cursor?: string | null;
+}
+
+/**
+ * A page of a keyset paginated list (see `base::list_page`).
+ */
+export interface ListPage3 {
+	has_more: boolean;
+	items: TaskListItem[];
+	/**
+	 * The `ListOptions::cursor` of the next page (None for the last page).
+	 */
+	next_cursor?: string | null;
+}
+
+/**
+ * A page of a keyset paginated list (see `base::list_page`).
+ */
+export interface ListPage4 {
+	has_more: boolean;
+	items: WebhookDelivery[];
+	/**
+	 * The `ListOptions::cursor` of the next page (None for the last page).
+	 */
+	next_cursor?: string | null;
+}
+
+export interface Notification {
+	ctime: string;
+	id: number;
+	kind: NotificationKind;
+	payload: unknown;
+	read: boolean;
+	recipient_id: number;
+}
+
+export type NotificationKind = "task_assigned" | "task_commented" | "task_due_soon";
+
+export interface NotificationPref {
+	enabled: boolean;
+	kind: NotificationKind;
+}
+
+export interface ParamsForUpdate {
+	data: TaskForUpdate;
+	/**
+	 * The entity version the client based its update on (optimistic concurrency).
+	 * Only checked for the versioned entities, and not checked when absent.
+	 */
+	expected_version?: number | null;
+	id: number;
+}
+
+export interface Revision {
+	actor_id: number;
+	ctime: string;
+	/**
+	 * The entity row before the update.
+	 */
+	data: unknown;
+	entity: string;
+	entity_id: number;
+	id: number;
+}
+
+export interface SavedView {
+	ctime: string;
+	id: number;
+	mtime?: string | null;
+	name: string;
+	owner_id: number;
+	query: SavedViewQuery;
+	shared: boolean;
+}
+
+export interface SavedViewForCreate {
+	name: string;
+	query: SavedViewQuery;
+	shared?: boolean;
+}
+
+export interface SavedViewForUpdate {
+	name?: string | null;
+	query?: SavedViewQuery | null;
+	shared?: boolean | null;
+}
+
+/**
+ * The tasks list of a view (the same shape as the `list_tasks` params).
+ */
+export interface SavedViewQuery {
+	filter?: TaskFilter | null;
+	list_options?: ListOptions | null;
+}
+
+export type SearchEntity = "task" | "comment";
+
+export interface SearchHit {
+	entity: SearchEntity;
+	id: number;
+	rank: number;
+	/**
+	 * HTML escaped excerpt, with the matches in `<mark>` elements.
+	 */
+	snippet: string;
+	/**
+	 * The task of the hit (i.e., the `id` for a task).
+	 */
+	task_id: number;
+	task_title: string;
+}
+
+export interface Task {
+	assignee_id?: number | null;
+	done: boolean;
+	due_date?: string | null;
+	id: number;
+	rank: string;
+	rrule?: string | null;
+	title: string;
+	version: number;
+}
+
+/**
+ * The filter of `TaskBmc::list_by_filter` (all the given conditions apply).
+ */
+export interface TaskFilter {
+	done?: boolean | null;
+	/**
+	 * Tasks due at or after this time.
+	 */
+	due_after?: string | null;
+	/**
+	 * Tasks due strictly before this time.
+	 */
+	due_before?: string | null;
+	/**
+	 * Case insensitive substring of the title.
+	 */
+	title_contains?: string | null;
+}
+
+export interface TaskForCreate {
+	/**
+	 * The assigned user (notified, see `model::notification`).
+	 */
+	assignee_id?: number | null;
+	due_date?: string | null;
+	rrule?: string | null;
+	title: string;
+}
+
+export interface TaskForUpdate {
+	assignee_id?: number | null;
+	done?: boolean | null;
+	due_date?: string | null;
+	rrule?: string | null;
+	title?: string | null;
+}
+
+/**
+ * A `Task` as returned by `list_tasks`, with its comment count.
+ */
+export interface TaskListItem {
+	assignee_id?: number | null;
+	comment_count: number;
+	done: boolean;
+	due_date?: string | null;
+	id: number;
+	rank: string;
+	rrule?: string | null;
+	title: string;
+	version: number;
+}
+
+/**
+ * Where to move a task, relative to another task of the list.
+ * (e.g., `{"before": 1001}`)
+ */
+export type TaskMove = {
+	before: number;
+} | {
+	after: number;
+};
+
+export interface Webhook {
+	active: boolean;
+	ctime: string;
+	/**
+	 * The delivered event types (e.g., `task.created`, `task.*`, or `*` for all).
+	 */
+	events: string[];
+	id: number;
+	mtime?: string | null;
+	owner_id: number;
+	url: string;
+}
+
+/**
+ * A delivery attempt (of the log).
+ */
+export interface WebhookDelivery {
+	attempt: number;
+	ctime: string;
+	/**
+	 * Same for all the attempts of a delivery (sent as `X-Webhook-Id`).
+	 */
+	delivery_id: string;
+	duration_ms: number;
+	error?: string | null;
+	event: string;
+	id: number;
+	/**
+	 * None when the endpoint did not respond (see `error`).
+	 */
+	status_code?: number | null;
+	webhook_id: number;
+}
+
+export interface WebhookForCreate {
+	events: string[];
+	/**
+	 * The `http(s)` url the events are posted to.
+	 */
+	url: string;
+}
+
+export interface WebhookForUpdate {
+	active?: boolean | null;
+	events?: string[] | null;
+	url?: string | null;
+}
+
+/**
+ * A created webhook, with its signing secret (only returned on create).
+ */
+export interface WebhookWithSecret {
+	active: boolean;
+	ctime: string;
+	/**
+	 * The delivered event types (e.g., `task.created`, `task.*`, or `*` for all).
+	 */
+	events: string[];
+	id: number;
+	mtime?: string | null;
+	owner_id: number;
+	secret: string;
+	url: string;
+}
+
+// endregion: --- Types
+
+// region:    --- Params
+
+export interface CreateCommentParams {
+	data: CommentForCreate;
+}
+
+export interface CreateSavedViewParams {
+	data: SavedViewForCreate;
+}
+
+export interface CreateTaskParams {
+	data: TaskForCreate;
+}
+
+export interface CreateTasksParams {
+	data: TaskForCreate[];
+}
+
+export interface CreateWebhookParams {
+	data: WebhookForCreate;
+}
+
+export interface DeleteCommentParams {
+	id: number;
+}
+
+export interface DeleteSavedViewParams {
+	id: number;
+}
+
+export interface DeleteTaskParams {
+	id: number;
+}
+
+export interface DeleteTasksParams {
+	ids: number[];
+}
+
+export interface DeleteWebhookParams {
+	id: number;
+}
+
+export interface DiffTaskRevisionsParams {
+	from_revision_id: number;
+	id: number;
+	/**
+	 * None for the diff with the current task.
+	 */
+	to_revision_id?: number | null;
+}
+
+export interface GetSavedViewParams {
+	id: number;
+}
+
+export interface ListCommentsParams {
+	list_options?: ListOptions | null;
+	task_id: number;
+}
+
+export interface ListCommentsPageParams {
+	list_options?: ListOptions | null;
+	task_id: number;
+}
+
+export interface ListNotificationsParams {
+	list_options?: ListOptions | null;
+	unread_only?: boolean;
+}
+
+export interface ListNotificationsPageParams {
+	list_options?: ListOptions | null;
+	unread_only?: boolean;
+}
+
+export interface ListTaskRevisionsParams {
+	id: number;
+}
+
+export interface ListTasksParams {
+	filter?: TaskFilter | null;
+	list_options?: ListOptions | null;
+}
+
+export interface ListTasksByViewParams {
+	list_options?: ListOptions | null;
+	view_id: number;
+}
+
+export interface ListTasksPageParams {
+	filter?: TaskFilter | null;
+	list_options?: ListOptions | null;
+}
+
+export interface ListWebhookDeliveriesParams {
+	list_options?: ListOptions | null;
+	webhook_id: number;
+}
+
+export interface ListWebhookDeliveriesPageParams {
+	list_options?: ListOptions | null;
+	webhook_id: number;
+}
+
+export interface MarkNotificationReadParams {
+	id: number;
+}
+
+export interface MoveTaskParams {
+	data: TaskMove;
+	/**
+	 * The entity version the client based its update on (optimistic concurrency).
+	 * Only checked for the versioned entities, and not checked when absent.
+	 */
+	expected_version?: number | null;
+	id: number;
+}
+
+export interface PurgeTaskParams {
+	id: number;
+}
+
+export interface RestoreTaskParams {
+	id: number;
+}
+
+export interface RevertTaskParams {
+	id: number;
+	revision_id: number;
+}
+
+export interface SearchParams {
+	/**
+	 * The searched entities (all when absent).
+	 */
+	entities?: SearchEntity[] | null;
+	list_options?: ListOptions | null;
+	/**
+	 * The words to search (the last one as a prefix, e.g., `buy mil`).
+	 */
+	query: string;
+}
+
+export interface SendTestWebhookParams {
+	id: number;
+}
+
+export interface SetNotificationPrefParams {
+	enabled: boolean;
+	kind: NotificationKind;
+}
+
+export interface UpdateCommentParams {
+	data: CommentForUpdate;
+	/**
+	 * The entity version the client based its update on (optimistic concurrency).
+	 * Only checked for the versioned entities, and not checked when absent.
+	 */
+	expected_version?: number | null;
+	id: number;
+}
+
+export interface UpdateSavedViewParams {
+	data: SavedViewForUpdate;
+	/**
+	 * The entity version the client based its update on (optimistic concurrency).
+	 * Only checked for the versioned entities, and not checked when absent.
+	 */
+	expected_version?: number | null;
+	id: number;
+}
+
+export interface UpdateTaskParams {
+	data: TaskForUpdate;
+	/**
+	 * The entity version the client based its update on (optimistic concurrency).
+	 * Only checked for the versioned entities, and not checked when absent.
+	 */
+	expected_version?: number | null;
+	id: number;
+}
+
+export type UpdateTasksParams = {
+	data: TaskForUpdate;
+	ids: number[];
+} | {
+	items: ParamsForUpdate[];
+};
+
+export interface UpdateWebhookParams {
+	data: WebhookForUpdate;
+	/**
+	 * The entity version the client based its update on (optimistic concurrency).
+	 * Only checked for the versioned entities, and not checked when absent.
+	 */
+	expected_version?: number | null;
+	id: number;
+}
+
+// endregion: --- Params
+
+// region:    --- Client
+
+let rpcUrl = "/api/rpc";
+let rpcId = 0;
+
+/** Set the JSON-RPC endpoint (defaults to "/api/rpc"). */
+export function setRpcUrl(url: string) {
+	rpcUrl = url;
+}
+
+/** The error of a failed rpc call. */
+export class RpcError extends Error {
+	constructor(
+		public readonly code: number,
+		public readonly clientError: ClientError,
+		public readonly reqUuid: string | null,
+	) {
+		super(clientError.message);
+	}
+}
+
+async function rpcCall<R>(method: string, params?: unknown): Promise<R> {
+	const res = await fetch(rpcUrl, {
+		method: "POST",
+		headers: { "Content-Type": "application/json" },
+		credentials: "same-origin",
+		body: JSON.stringify({ jsonrpc: "2.0", id: ++rpcId, method, params }),
+	});
+	const body = await res.json();
+
+	if (body.error) {
+		const { code, message, data } = body.error;
+		const clientError = { message, detail: data?.detail } as ClientError;
+		throw new RpcError(code, clientError, data?.req_uuid ?? null);
+	}
+
+	return body.result as R;
+}
+
+/** `count_unread_notifications` */
+export function countUnreadNotifications(): Promise<number> {
+	return rpcCall("count_unread_notifications");
+}
+
+/** `create_comment` */
+export function createComment(params: CreateCommentParams): Promise<Comment> {
+	return rpcCall("create_comment", params);
+}
+
+/** `create_saved_view` */
+export function createSavedView(params: CreateSavedViewParams): Promise<SavedView> {
+	return rpcCall("create_saved_view", params);
+}
+
+/** `create_task` */
+export function createTask(params: CreateTaskParams): Promise<Task> {
+	return rpcCall("create_task", params);
+}
+
+/** `create_tasks` */
+export function createTasks(params: CreateTasksParams): Promise<BulkItemResult[]> {
+	return rpcCall("create_tasks", params);
+}
+
+/** `create_webhook` */
+export function createWebhook(params: CreateWebhookParams): Promise<WebhookWithSecret> {
+	return rpcCall("create_webhook", params);
+}
+
+/** `delete_comment` */
+export function deleteComment(params: DeleteCommentParams): Promise<Comment> {
+	return rpcCall("delete_comment", params);
+}
+
+/** `delete_saved_view` */
+export function deleteSavedView(params: DeleteSavedViewParams): Promise<SavedView> {
+	return rpcCall("delete_saved_view", params);
+}
+
+/** `delete_task` */
+export function deleteTask(params: DeleteTaskParams): Promise<Task> {
+	return rpcCall("delete_task", params);
+}
+
+/** `delete_tasks` */
+export function deleteTasks(params: DeleteTasksParams): Promise<BulkItemResult[]> {
+	return rpcCall("delete_tasks", params);
+}
+
+/** `delete_webhook` */
+export function deleteWebhook(params: DeleteWebhookParams): Promise<Webhook> {
+	return rpcCall("delete_webhook", params);
+}
+
+/** `diff_task_revisions` */
+export function diffTaskRevisions(params: DiffTaskRevisionsParams): Promise<FieldDiff[]> {
+	return rpcCall("diff_task_revisions", params);
+}
+
+/** `get_saved_view` */
+export function getSavedView(params: GetSavedViewParams): Promise<SavedView> {
+	return rpcCall("get_saved_view", params);
+}
+
+/** `list_comments` */
+export function listComments(params: ListCommentsParams): Promise<Comment[]> {
+	return rpcCall("list_comments", params);
+}
+
+/** `list_comments_page` */
+export function listCommentsPage(params: ListCommentsPageParams): Promise<ListPage> {
+	return rpcCall("list_comments_page", params);
+}
+
+/** `list_cron_runs` */
+export function listCronRuns(): Promise<CronRun[]> {
+	return rpcCall("list_cron_runs");
+}
+
+/** `list_notification_prefs` */
+export function listNotificationPrefs(): Promise<NotificationPref[]> {
+	return rpcCall("list_notification_prefs");
+}
+
+/** `list_notifications` */
+export function listNotifications(params: ListNotificationsParams): Promise<Notification[]> {
+	return rpcCall("list_notifications", params);
+}
+
+/** `list_notifications_page` */
+export function listNotificationsPage(params: ListNotificationsPageParams): Promise<ListPage2> {
+	return rpcCall("list_notifications_page", params);
+}
+
+/** `list_saved_views` */
+export function listSavedViews(): Promise<SavedView[]> {
+	return rpcCall("list_saved_views");
+}
+
+/** `list_task_revisions` */
+export function listTaskRevisions(params: ListTaskRevisionsParams): Promise<Revision[]> {
+	return rpcCall("list_task_revisions", params);
+}
+
+/** `list_tasks` */
+export function listTasks(params: ListTasksParams): Promise<TaskListItem[]> {
+	return rpcCall("list_tasks", params);
+}
+
+/** `list_tasks_by_view` */
+export function listTasksByView(params: ListTasksByViewParams): Promise<TaskListItem[]> {
+	return rpcCall("list_tasks_by_view", params);
+}
+
+/** `list_tasks_page` */
+export function listTasksPage(params: ListTasksPageParams): Promise<ListPage3> {
+	return rpcCall("list_tasks_page", params);
+}
+
+/** `list_trash` */
+export function listTrash(): Promise<Task[]> {
+	return rpcCall("list_trash");
+}
+
+/** `list_webhook_deliveries` */
+export function listWebhookDeliveries(params: ListWebhookDeliveriesParams): Promise<WebhookDelivery[]> {
+	return rpcCall("list_webhook_deliveries", params);
+}
+
+/** `list_webhook_deliveries_page` */
+export function listWebhookDeliveriesPage(params: ListWebhookDeliveriesPageParams): Promise<ListPage4> {
+	return rpcCall("list_webhook_deliveries_page", params);
+}
+
+/** `list_webhooks` */
+export function listWebhooks(): Promise<Webhook[]> {
+	return rpcCall("list_webhooks");
+}
+
+/** `mark_all_notifications_read` */
+export function markAllNotificationsRead(): Promise<number> {
+	return rpcCall("mark_all_notifications_read");
+}
+
+/** `mark_notification_read` */
+export function markNotificationRead(params: MarkNotificationReadParams): Promise<Notification> {
+	return rpcCall("mark_notification_read", params);
+}
+
+/** `move_task` */
+export function moveTask(params: MoveTaskParams): Promise<Task> {
+	return rpcCall("move_task", params);
+}
+
+/** `purge_task` */
+export function purgeTask(params: PurgeTaskParams): Promise<number> {
+	return rpcCall("purge_task", params);
+}
+
+/** `restore_task` */
+export function restoreTask(params: RestoreTaskParams): Promise<Task> {
+	return rpcCall("restore_task", params);
+}
+
+/** `revert_task` */
+export function revertTask(params: RevertTaskParams): Promise<Task> {
+	return rpcCall("revert_task", params);
+}
+
+/** `rpc.discover` */
+export function rpcDiscover(): Promise<unknown> {
+	return rpcCall("rpc.discover");
+}
+
+/** `search` */
+export function search(params: SearchParams): Promise<SearchHit[]> {
+	return rpcCall("search", params);
+}
+
+/** `send_test_webhook` */
+export function sendTestWebhook(params: SendTestWebhookParams): Promise<WebhookDelivery> {
+	return rpcCall("send_test_webhook", params);
+}
+
+/** `set_notification_pref` */
+export function setNotificationPref(params: SetNotificationPrefParams): Promise<NotificationPref[]> {
+	return rpcCall("set_notification_pref", params);
+}
+
+/** `update_comment` */
+export function updateComment(params: UpdateCommentParams): Promise<Comment> {
+	return rpcCall("update_comment", params);
+}
+
+/** `update_saved_view` */
+export function updateSavedView(params: UpdateSavedViewParams): Promise<SavedView> {
+	return rpcCall("update_saved_view", params);
+}
+
+/** `update_task` */
+export function updateTask(params: UpdateTaskParams): Promise<Task> {
+	return rpcCall("update_task", params);
+}
+
+/** `update_tasks` */
+export function updateTasks(params: UpdateTasksParams): Promise<BulkItemResult[]> {
+	return rpcCall("update_tasks", params);
+}
+
+/** `update_webhook` */
+export function updateWebhook(params: UpdateWebhookParams): Promise<Webhook> {
+	return rpcCall("update_webhook", params);
+}
+
+// endregion: --- Client